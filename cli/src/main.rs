@@ -1,18 +1,91 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::Value;
 
 use shugo::{
     scan_antivirus, 
+    scan_defender,
     scan_updates, 
     scan_firewall,
     scan_uac,
-    scan_uas
+    scan_uac_with,
+    scan_uas,
+    scan_os_with,
+    scan_smb,
+    scan_smb_with,
+    scan_rdp,
+    scan_rdp_with,
+    scan_bitlocker,
+    scan_browsers_with,
+    scan_extensions,
+    scan_extensions_with,
+    scan_shares,
+    scan_startup_with,
+    scan_powershell,
+    scan_powershell_with,
+    run_audit,
+    run_audit_with,
+    save_snapshot,
+    load_snapshot,
+    diff_audits,
+    audit_facts,
+    evaluate_policy,
+    bundled_baseline,
+    Policy,
+    BASELINES
 };
+use shugo::{LifecycleTable, VersionTable, BrowserPaths, StartupPaths, ProfileLocation, OfflineSource, system_source};
 use shugo::{
     display_antivirus, 
+    display_defender,
     display_updates,
     display_firewalls,
     display_uac,
-    display_uas
+    display_uas,
+    display_os,
+    display_smb,
+    display_rdp,
+    display_bitlocker,
+    display_browsers,
+    display_extensions,
+    display_shares,
+    display_startup,
+    display_powershell,
+    display_audit,
+    display_diff,
+    display_policy
+};
+use shugo::{
+    json_report,
+    html_report,
+    html_audit,
+    sarif_log,
+    sarif_audit,
+    sarif_policy,
+    finding_cases,
+    junit_xml,
+    junit_audit,
+    junit_policy,
+    Finding,
+    json_antivirus,
+    json_defender,
+    json_updates,
+    json_firewall,
+    json_uac,
+    json_uas,
+    json_os,
+    json_smb,
+    json_rdp,
+    json_bitlocker,
+    json_browsers,
+    json_extensions,
+    json_shares,
+    json_startup,
+    json_powershell,
+    json_audit,
+    json_diff,
+    json_policy
 };
 
 /// Shuhari-CyberForge: Experimental security tools for educational purposes
@@ -23,7 +96,25 @@ struct Cli {
 
     /// Gives a more detailed output
     #[arg(short, long, global = true)]
-    verbose: bool
+    verbose: bool,
+
+    /// How results are printed
+    #[arg(long, value_enum, global = true, default_value_t = Format::Text)]
+    format: Format
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// Human readable report
+    Text,
+    /// Versioned JSON document for scripts and pipelines
+    Json,
+    /// Self-contained HTML report with explanations, for sharing
+    Html,
+    /// SARIF 2.1.0 log for code scanning dashboards, one result per finding
+    Sarif,
+    /// JUnit XML for CI test reports, one test case per finding
+    Junit
 }
 
 // This is where tools can be added to the CLI and be given subcommands
@@ -39,27 +130,365 @@ enum Command {
 enum ShugoCommand {
     /// Shows current and third-party antivirus's and their states
     Antivirus,
+    /// Shows Windows Defender protection, signatures, scans, exclusions, and antispyware products
+    Defender,
     /// Shows pending updates, sizes, product, classification, and description
     Updates,
     /// Shows Windows Defender profiles, third-party firewalls, and their states
     Firewall,
     /// Shows UAC (User Access Control) settings
-    Uac,
+    Uac {
+        #[command(flatten)]
+        offline: OfflineArgs
+    },
     /// Shows UAS (User Access Security) settings
-    Uas
+    Uas,
+    /// Shows the Windows version, build, and how long it's supported
+    Os {
+        /// Uses this lifecycle table instead of the bundled one, for newer support dates
+        #[arg(long, value_name = "PATH")]
+        lifecycle: Option<PathBuf>,
+
+        #[command(flatten)]
+        offline: OfflineArgs
+    },
+    /// Shows SMBv1, SMB signing, and SMB encryption settings
+    Smb {
+        #[command(flatten)]
+        offline: OfflineArgs
+    },
+    /// Shows Remote Desktop settings and whether the firewall lets it in on public networks
+    Rdp {
+        #[command(flatten)]
+        offline: OfflineArgs
+    },
+    /// Shows PowerShell execution policies, logging, and whether PowerShell 2.0 is installed
+    Powershell {
+        #[command(flatten)]
+        offline: OfflineArgs
+    },
+    /// Shows BitLocker protection, encryption method, and key protectors for every volume
+    Bitlocker,
+    /// Shows installed browsers, whether they're up to date, Safe Browsing, the password manager, and extension policy
+    Browser {
+        /// Uses this minimum version table instead of the bundled one, for newer browser releases
+        #[arg(long, value_name = "PATH")]
+        versions: Option<PathBuf>,
+
+        #[command(flatten)]
+        offline: OfflineArgs
+    },
+    /// Shows every browser extension, the permissions it asked for, and how risky they are
+    Extensions {
+        /// Reads extensions from this profile folder instead of this user's profiles, like one copied from another machine
+        #[arg(long = "profile", value_name = "PATH")]
+        profiles: Vec<PathBuf>
+    },
+    /// Shows every network share, its path, and its share permissions
+    Shares,
+    /// Shows programs that start on their own, from Run keys, Startup folders, tasks, services, Winlogon, and IFEO
+    Startup {
+        #[command(flatten)]
+        offline: OfflineArgs
+    },
+    /// Runs every module and gives an overall security score
+    #[command(alias = "all")]
+    Audit {
+        #[command(flatten)]
+        offline: OfflineArgs,
+
+        /// Saves the audit as a JSON snapshot for `shugo diff`
+        #[arg(long, value_name = "PATH")]
+        save: Option<PathBuf>
+    },
+    /// Compares two saved audit snapshots and shows what changed
+    Diff {
+        /// The earlier snapshot
+        old: PathBuf,
+        /// The later snapshot
+        new: PathBuf
+    },
+    /// Checks an audit against a policy file or bundled baseline, exits with 1 on violations and 3 when rules couldn't be evaluated
+    Check {
+        /// The policy file (.toml, .yaml, or .yml)
+        #[arg(long, value_name = "PATH", required_unless_present = "baseline")]
+        policy: Option<PathBuf>,
+
+        /// Uses a bundled baseline instead of a policy file
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with = "policy",
+            value_parser = clap::builder::PossibleValuesParser::new(BASELINES.iter().map(|baseline| baseline.name))
+        )]
+        baseline: Option<String>,
+
+        /// Checks a saved audit snapshot instead of scanning
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["hives", "reg_files"])]
+        snapshot: Option<PathBuf>,
+
+        #[command(flatten)]
+        offline: OfflineArgs
+    }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli: Cli = Cli::parse();
+// Registry files collected off another machine, for commands that only read the registry
+#[derive(Args)]
+struct OfflineArgs {
+    /// Reads from an exported registry hive file (SOFTWARE, SYSTEM, SAM) instead of this machine
+    #[arg(long = "hive", value_name = "PATH")]
+    hives: Vec<PathBuf>,
+
+    /// Reads from a regedit .reg export instead of this machine
+    #[arg(long = "reg", value_name = "PATH")]
+    reg_files: Vec<PathBuf>
+}
+
+impl OfflineArgs {
+    fn is_empty(&self) -> bool {
+        self.hives.is_empty() && self.reg_files.is_empty()
+    }
+
+    /// Loads every registry file given on the command line into one offline source
+    fn source(&self) -> Result<OfflineSource, Box<dyn std::error::Error + Send + Sync>> {
+        let mut source = OfflineSource::new();
+        for hive in &self.hives {
+            source = source.open_hive(hive)?;
+        }
+        for reg_file in &self.reg_files {
+            source = source.open_reg(reg_file)?;
+        }
+        Ok(source)
+    }
+}
+
+fn main() {
+    // Errors are printed with their message (which module and query failed) instead of their debug form
+    if let Err(error) = run(Cli::parse()) {
+        eprintln!("Error: {}", error);
+        std::process::exit(2);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     match cli.command {
         Command::Shugo(wcmd) => match wcmd {
-            ShugoCommand::Antivirus => display_antivirus(&scan_antivirus()?, cli.verbose),
-            ShugoCommand::Updates => display_updates(scan_updates()?, cli.verbose),
-            ShugoCommand::Firewall => display_firewalls(scan_firewall()?, cli.verbose),
-            ShugoCommand::Uac => display_uac(scan_uac()?, cli.verbose),
-            ShugoCommand::Uas => display_uas(scan_uas()?, cli.verbose),
+            ShugoCommand::Antivirus => {
+                let products = scan_antivirus()?;
+                if !print_structured(cli.format, "antivirus", || json_antivirus(&products))? {
+                    display_antivirus(&products, cli.verbose)
+                }
+            },
+            ShugoCommand::Defender => {
+                let status = scan_defender()?;
+                if !print_structured(cli.format, "defender", || json_defender(&status))? {
+                    display_defender(&status, cli.verbose)
+                }
+            },
+            ShugoCommand::Updates => {
+                let summary = scan_updates()?;
+                if !print_structured(cli.format, "updates", || json_updates(&summary))? {
+                    display_updates(summary, cli.verbose)
+                }
+            },
+            ShugoCommand::Firewall => {
+                let summary = scan_firewall()?;
+                if !print_structured(cli.format, "firewall", || json_firewall(&summary))? {
+                    display_firewalls(summary, cli.verbose)
+                }
+            },
+            ShugoCommand::Uac { offline } => {
+                let info = if offline.is_empty() {
+                    scan_uac()?
+                } else {
+                    scan_uac_with(&offline.source()?)?
+                };
+                if !print_structured(cli.format, "uac", || json_uac(&info))? {
+                    display_uac(info, cli.verbose)
+                }
+            },
+            ShugoCommand::Uas => {
+                let summary = scan_uas()?;
+                if !print_structured(cli.format, "uas", || json_uas(&summary))? {
+                    display_uas(summary, cli.verbose)
+                }
+            },
+            ShugoCommand::Os { lifecycle, offline } => {
+                let table = match lifecycle {
+                    Some(path) => LifecycleTable::open(&path)?,
+                    None => LifecycleTable::bundled()?
+                };
+                let info = if offline.is_empty() {
+                    scan_os_with(system_source()?.as_ref(), &table)?
+                } else {
+                    scan_os_with(&offline.source()?, &table)?
+                };
+                if !print_structured(cli.format, "os", || json_os(&info))? {
+                    display_os(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Smb { offline } => {
+                let info = if offline.is_empty() {
+                    scan_smb()?
+                } else {
+                    scan_smb_with(&offline.source()?)?
+                };
+                if !print_structured(cli.format, "smb", || json_smb(&info))? {
+                    display_smb(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Rdp { offline } => {
+                let info = if offline.is_empty() {
+                    scan_rdp()?
+                } else {
+                    scan_rdp_with(&offline.source()?)?
+                };
+                if !print_structured(cli.format, "rdp", || json_rdp(&info))? {
+                    display_rdp(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Powershell { offline } => {
+                let info = if offline.is_empty() {
+                    scan_powershell()?
+                } else {
+                    scan_powershell_with(&offline.source()?)?
+                };
+                if !print_structured(cli.format, "powershell", || json_powershell(&info))? {
+                    display_powershell(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Bitlocker => {
+                let info = scan_bitlocker()?;
+                if !print_structured(cli.format, "bitlocker", || json_bitlocker(&info))? {
+                    display_bitlocker(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Browser { versions, offline } => {
+                let table = match versions {
+                    Some(path) => VersionTable::open(&path)?,
+                    None => VersionTable::bundled()?
+                };
+                // Browser files are only read on a live machine, an offline scan checks the policies
+                let info = if offline.is_empty() {
+                    scan_browsers_with(system_source()?.as_ref(), Some(&BrowserPaths::from_env()), &table)?
+                } else {
+                    scan_browsers_with(&offline.source()?, None, &table)?
+                };
+                if !print_structured(cli.format, "browser", || json_browsers(&info))? {
+                    display_browsers(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Extensions { profiles } => {
+                let info = if profiles.is_empty() {
+                    scan_extensions()?
+                } else {
+                    let mut locations = Vec::with_capacity(profiles.len());
+                    for path in profiles.iter() {
+                        match ProfileLocation::detect(path) {
+                            Some(location) => locations.push(location),
+                            None => return Err(format!("{} isn't a Chromium or Firefox profile folder", path.display()).into())
+                        }
+                    }
+                    scan_extensions_with(&locations)?
+                };
+                if !print_structured(cli.format, "extensions", || json_extensions(&info))? {
+                    display_extensions(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Shares => {
+                let info = scan_shares()?;
+                if !print_structured(cli.format, "shares", || json_shares(&info))? {
+                    display_shares(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Startup { offline } => {
+                // Startup folders and task files are only read on a live machine, an offline scan checks the registry
+                let info = if offline.is_empty() {
+                    scan_startup_with(system_source()?.as_ref(), Some(&StartupPaths::from_env()))?
+                } else {
+                    scan_startup_with(&offline.source()?, None)?
+                };
+                if !print_structured(cli.format, "startup", || json_startup(&info))? {
+                    display_startup(&info, cli.verbose)
+                }
+            },
+            ShugoCommand::Audit { offline, save } => {
+                let report = if offline.is_empty() {
+                    run_audit()?
+                } else {
+                    run_audit_with(&offline.source()?)
+                };
+                if let Some(path) = save {
+                    save_snapshot(&report, &path)?;
+                    eprintln!("Saved audit snapshot to {}", path.display());
+                }
+                match cli.format {
+                    Format::Html => println!("{}", html_audit(&report)),
+                    Format::Sarif => println!("{}", serde_json::to_string_pretty(&sarif_audit(&report))?),
+                    Format::Junit => print!("{}", junit_audit(&report)),
+                    _ => if !print_structured(cli.format, "audit", || json_audit(&report))? {
+                        display_audit(&report, cli.verbose)
+                    }
+                }
+            },
+            ShugoCommand::Diff { old, new } => {
+                let diff = diff_audits(&load_snapshot(&old)?, &load_snapshot(&new)?);
+                if !print_structured(cli.format, "diff", || json_diff(&diff))? {
+                    display_diff(&diff, cli.verbose)
+                }
+            },
+            ShugoCommand::Check { policy, baseline, snapshot, offline } => {
+                // Load the policy first so a broken file fails before a slow scan
+                let policy = match (policy, baseline) {
+                    (Some(path), _) => Policy::open(&path)?,
+                    (None, Some(name)) => bundled_baseline(&name)?,
+                    (None, None) => unreachable!("clap requires --policy or --baseline")
+                };
+                let audit = match snapshot {
+                    Some(path) => load_snapshot(&path)?,
+                    None if offline.is_empty() => run_audit()?,
+                    None => run_audit_with(&offline.source()?)
+                };
+                let report = evaluate_policy(&policy, &audit_facts(&audit));
+                match cli.format {
+                    Format::Sarif => println!("{}", serde_json::to_string_pretty(&sarif_policy(&report))?),
+                    Format::Junit => print!("{}", junit_policy(&report)),
+                    _ => if !print_structured(cli.format, "check", || json_policy(&report))? {
+                        display_policy(&report, cli.verbose)
+                    }
+                }
+                if report.exit_code() != 0 {
+                    std::process::exit(report.exit_code());
+                }
+            }
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Prints a module's result as JSON, HTML, SARIF, or JUnit XML. Returns false for the text format so the caller
+/// can use the module's own display instead
+fn print_structured(
+    format: Format,
+    module: &str,
+    result: impl FnOnce() -> Value
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    match format {
+        Format::Text => return Ok(false),
+        // Wraps the result in the versioned report envelope
+        Format::Json => println!("{}", serde_json::to_string_pretty(&json_report(module, result()))?),
+        Format::Html => println!("{}", html_report(module, &result())),
+        Format::Sarif => println!("{}", serde_json::to_string_pretty(&sarif_log(&findings_in(module, result())?))?),
+        Format::Junit => print!("{}", junit_xml("Shugo", &finding_cases(module, &findings_in(module, result())?)))
+    }
+    Ok(true)
+}
+
+/// The findings a module's JSON result carries, for the finding based formats
+fn findings_in(module: &str, mut result: Value) -> Result<Vec<Finding>, Box<dyn std::error::Error + Send + Sync>> {
+    match result.get_mut("findings") {
+        Some(findings) => Ok(serde_json::from_value(findings.take())?),
+        None => Err(format!("{} doesn't produce findings, use --format text, json, or html", module).into())
+    }
+}
@@ -0,0 +1,69 @@
+//! Assessment for the Antivirus Module, turns the products found into findings.
+use super::scanner::ProductInfo;
+use crate::common::finding::{Finding, Severity};
+use crate::common::product_state::{DefinitionsState, ScannerState};
+
+const MODULE: &str = "antivirus";
+
+/// Assessing antivirus products
+pub fn assess_antivirus(products: &[ProductInfo]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let active: Vec<&ProductInfo> = products.iter().filter(|p| p.product_state.scanner == ScannerState::On).collect();
+
+    if active.is_empty() {
+        findings.push(
+            Finding::new("AV-001", MODULE, Severity::Critical, "No active antivirus protection")
+                .explanation("Nothing is scanning files as they are downloaded or run, so malware can execute unchallenged")
+                .remediation("Turn on Microsoft Defender Antivirus or install and enable a third-party antivirus")
+                .evidence(format!("products_found={}", products.len()))
+        );
+    }
+
+    if active.len() > 1 {
+        let mut finding = Finding::new("AV-002", MODULE, Severity::Low, "More than one antivirus product is active")
+            .explanation("Two real-time scanners can fight over the same files, slowing the machine and sometimes missing threats")
+            .remediation("Keep one antivirus product active and disable or uninstall the others");
+        for prod in &active {
+            finding = finding.evidence(prod.name.clone());
+        }
+        findings.push(finding);
+    }
+
+    for prod in active {
+        if prod.product_state.definitions == DefinitionsState::OutOfDate {
+            findings.push(
+                Finding::new("AV-003", MODULE, Severity::High, format!("{} definitions are out-of-date", prod.name))
+                    .explanation("Antivirus can only recognise threats its definitions know about")
+                    .remediation("Run a definition update and check the product can reach its update servers")
+                    .evidence(format!("productState=0x{:X}", prod.state))
+            );
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(name: &str, state: i32) -> ProductInfo {
+        ProductInfo::new(name, state)
+    }
+
+    #[test]
+    fn flags_missing_protection() {
+        let findings = assess_antivirus(&[product("Windows Defender", 0x60100)]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "AV-001");
+        assert_eq!(findings[0].severity, Severity::Critical);
+    }
+
+    #[test]
+    fn flags_stale_definitions_and_duplicates() {
+        let findings = assess_antivirus(&[product("Windows Defender", 0x61100), product("Other AV", 0x41010)]);
+        let ids: Vec<&str> = findings.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["AV-002", "AV-003"]);
+        assert!(findings[1].title.starts_with("Other AV"));
+    }
+}
@@ -1,4 +1,10 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_antivirus;
 use super::scanner::ProductInfo;
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::product_state::ScannerState;
 use crate::common::time::get_time;
 
 /// Display for Antivirus Module
@@ -12,16 +18,34 @@ pub fn display_antivirus(products: &[ProductInfo], verbose: bool) {
     if products.is_empty() {
         println!(" - No Antivirus Products Found!")
     } else {
-        display_summary(&products, verbose);
+        display_summary(products, verbose);
 
-        product_display(&products, verbose);
+        product_display(products, verbose);
     }
 
-    display_assessment(&products);
+    display_findings(&assess_antivirus(products), verbose);
 
     if verbose {display_technical();}
 }
 
+/// JSON for Antivirus Module
+pub fn json_antivirus(products: &[ProductInfo]) -> Value {
+    let product_list: Vec<Value> = products
+        .iter()
+        .map(|prod| with_decoded(prod, json!({
+            "status": prod.product_state.scanner.to_string(),
+            "owner": prod.product_state.owner.to_string(),
+            "definitions": prod.product_state.definitions.to_string(),
+            "providers": prod.product_state.providers.names()
+        })))
+        .collect();
+
+    json!({
+        "products": product_list,
+        "findings": assess_antivirus(products)
+    })
+}
+
 fn display_scan_details() {
     let (h, m, s) = get_time();
 
@@ -36,18 +60,13 @@ fn display_summary(products: &[ProductInfo], verbose: bool) {
     println!("Summary:");
     println!(" - Products Found: {}", products.len());
 
-    let inactive_count = products.iter().filter(|p| p.product_status == 0).count();
-    println!("   - Products Inactive: {}", inactive_count);
-
-    let active_count = products.iter().filter(|p| p.product_status == 1).count();
-    println!("   - Products Active: {}", active_count);
+    let count = |scanner: ScannerState| products.iter().filter(|p| p.product_state.scanner == scanner).count();
+    println!("   - Products Inactive: {}", count(ScannerState::Off));
+    println!("   - Products Active: {}", count(ScannerState::On));
 
     if verbose {
-        let snoozed_count = products.iter().filter(|p| p.product_status == 2).count();
-        println!("   - Products Snoozed: {}", snoozed_count);
-
-        let expired_count = products.iter().filter(|p| p.product_status == 3).count();
-        println!("   - Products Expired: {}", expired_count);
+        println!("   - Products Snoozed: {}", count(ScannerState::Snoozed));
+        println!("   - Products Expired: {}", count(ScannerState::Expired));
     }
 
     println!();
@@ -59,16 +78,18 @@ fn product_display(products: &[ProductInfo], verbose: bool) {
     for (i, prod) in products.iter().enumerate() {
         println!("{}. {}", i+1, prod.name);
 
-        println!(" - Status: {}", state_status_decode(prod.product_status));
-        if verbose {println!("   - Hex Value (0x0F000): {}", prod.product_status);}
+        let state = &prod.product_state;
+        println!(" - Status: {}", state.scanner);
+        if verbose {println!("   - Hex Value (0x0F000): {}", (prod.state >> 12) & 0xF);}
 
-        println!(" - Third-Party: {}", state_owner_decode(prod.product_owner));
-        if verbose {println!("   - Hex Value (0x00F00): {}", prod.product_owner);}
+        println!(" - Owner: {}", state.owner);
+        if verbose {println!("   - Hex Value (0x00F00): {}", (prod.state >> 8) & 0xF);}
 
-        println!(" - Definitions: {}", state_definition_decode(prod.definition_status));
-        if verbose {println!("   - Hex Value (0x000F0): {}", prod.definition_status);}
+        println!(" - Definitions: {}", state.definitions);
+        if verbose {println!("   - Hex Value (0x000FF): {}", prod.state & 0xFF);}
 
         if verbose {
+            println!(" - Registered As: {}", state.providers);
             println!(" - Product State: {}", prod.state);
             println!(" - Hexadecimal State: 0x{:X}", prod.state);
         }
@@ -76,71 +97,9 @@ fn product_display(products: &[ProductInfo], verbose: bool) {
     }
 }
 
-fn display_assessment(products: &[ProductInfo]) {
-    println!("Security Assessment:");
-
-    println!(" - Antivirus Protection:");
-    let active_count = products.iter().filter(|p| p.product_status == 1).count();
-    if active_count == 0 {
-        println!("   - Antivirus Protection Not Found!");
-    }
-    if active_count == 1 {
-        println!("   - Antivirus Protection Is Active");
-    }
-    if active_count > 1 {
-        println!("   - More than one antivirus product is active.");
-        println!("   - It's recommend having only one antivirus active at a time.");
-    }
-    println!();
-
-    println!(" - Active Products:");
-    if active_count == 0 {
-        println!("   - No active products");
-        println!();
-    } else {
-        for prod in products {
-            if prod.product_status == 1 {
-                println!("   - {}", prod.name);
-                if prod.definition_status == 1 {
-                    println!("     - Definitions: Out-of-date");
-                } else {
-                    println!("     - Definitions: Up-to-date");
-                }
-            }
-            println!();
-        }
-    }
-}
-
 fn display_technical() {
     println!("Technical Information:");
     println!(" - COM Apartment: MTA (Multi-threaded)");
     println!(" - WMI Context: CLSCTX_INPROC_SERVER");
     println!();
 }
-
-fn state_status_decode(state: i32) -> String{
-    match state {
-        0 => "Off".to_string(),
-        1 => "On".to_string(),
-        2 => "Snoozed".to_string(),
-        3 => "Expired".to_string(),
-        _ => "Unknown".to_string()
-    }
-}
-
-fn state_definition_decode(state: i32) -> String {
-    match state {
-        0 => "Up-to-date".to_string(),
-        1 => "Out-of-date".to_string(),
-        _ => "Unknown".to_string()
-    }
-}
-
-fn state_owner_decode(state: i32) -> String {
-    match state {
-        0 => "Yes".to_string(),
-        1 => "No".to_string(),
-        _ => "Unknown".to_string()
-    }
-}
\ No newline at end of file
@@ -0,0 +1,118 @@
+//! Scoring for the Full Audit. Every module starts at 100 and loses points for each finding,
+//! then the module scores are combined using the weights below into one 0-100 score.
+use serde::{Deserialize, Serialize};
+
+use crate::common::finding::{Finding, Severity};
+
+/// How much each module counts towards the overall score, adding up to 100
+pub const MODULE_WEIGHTS: [(&str, u32); 15] = [
+    ("antivirus", 12),
+    ("firewall", 12),
+    ("updates", 12),
+    ("uac", 8),
+    ("uas", 8),
+    ("defender", 8),
+    ("os", 8),
+    ("smb", 6),
+    ("rdp", 6),
+    ("bitlocker", 6),
+    ("powershell", 4),
+    ("browser", 4),
+    ("extensions", 2),
+    ("shares", 2),
+    ("startup", 2)
+];
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleScore {
+    pub module: String,
+    pub weight: u32,
+    pub score: u32
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AuditScore {
+    /// Weighted score from 0 (worst) to 100 (best), over the modules that ran
+    pub overall: u32,
+    pub modules: Vec<ModuleScore>
+}
+
+/// Points a module loses for one finding of this severity
+pub fn severity_penalty(severity: Severity) -> u32 {
+    match severity {
+        Severity::Info => 0,
+        Severity::Low => 5,
+        Severity::Medium => 15,
+        Severity::High => 35,
+        Severity::Critical => 100
+    }
+}
+
+/// Scoring the modules that completed
+pub fn score_audit(completed: &[&str], findings: &[Finding]) -> AuditScore {
+    /*
+        Shugo: Weighted Score
+
+        A module's score is 100 minus the penalty for each of its findings, never going below 0.
+        Modules that failed to run are left out and the weights of the ones that did run are scaled
+        back up to 100, so a failed scan doesn't count as either a pass or a fail:
+
+        overall = sum(score * weight) / sum(weight)
+    */
+    let mut modules = Vec::new();
+    for (module, weight) in MODULE_WEIGHTS {
+        if !completed.contains(&module) {
+            continue;
+        }
+        let penalty: u32 = findings
+            .iter()
+            .filter(|finding| finding.module == module)
+            .map(|finding| severity_penalty(finding.severity))
+            .sum();
+        modules.push(ModuleScore {
+            module: module.to_string(),
+            weight,
+            score: 100u32.saturating_sub(penalty)
+        });
+    }
+
+    let total_weight: u32 = modules.iter().map(|module| module.weight).sum();
+    let overall = if total_weight == 0 {
+        0
+    } else {
+        let weighted: u32 = modules.iter().map(|module| module.score * module.weight).sum();
+        (weighted as f64 / total_weight as f64).round() as u32
+    };
+
+    AuditScore { overall, modules }
+}
+
+/// A word for the overall score
+pub fn score_rating(score: u32) -> &'static str {
+    match score {
+        90..=100 => "Strong",
+        70..=89 => "Fair",
+        40..=69 => "Weak",
+        _ => "Critical"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_failed_modules_out() {
+        let findings = vec![
+            Finding::new("UAC-003", "uac", Severity::Medium, "secure desktop"),
+            Finding::new("AV-001", "antivirus", Severity::Critical, "no antivirus")
+        ];
+        let score = score_audit(&["antivirus", "uac"], &findings);
+        assert_eq!(score.modules[0].score, 0);
+        assert_eq!(score.modules[1].score, 85);
+        // (0 * 12 + 85 * 8) / 20
+        assert_eq!(score.overall, 34);
+        assert_eq!(score_audit(&[], &findings).overall, 0);
+        assert_eq!(score_audit(&["firewall"], &findings).overall, 100);
+    }
+}
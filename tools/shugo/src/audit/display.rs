@@ -0,0 +1,134 @@
+use serde_json::{Value, json};
+
+use super::assessment::{MODULE_WEIGHTS, score_rating};
+use super::scanner::AuditReport;
+use crate::antivirus::display::json_antivirus;
+use crate::bitlocker::display::json_bitlocker;
+use crate::browser::display::json_browsers;
+use crate::common::finding::{Severity, count_at_least, display_findings};
+use crate::common::html::{HtmlSection, html_document, module_title};
+use crate::common::junit::{TestCase, TestOutcome, finding_cases, junit_xml};
+use crate::common::sarif::sarif_log;
+use crate::common::time::get_time;
+use crate::defender::display::json_defender;
+use crate::extensions::display::json_extensions;
+use crate::firewall::display::json_firewall;
+use crate::os::display::json_os;
+use crate::powershell::display::json_powershell;
+use crate::rdp::display::json_rdp;
+use crate::shares::display::json_shares;
+use crate::smb::display::json_smb;
+use crate::startup::display::json_startup;
+use crate::uac::display::json_uac;
+use crate::uas::display::json_uas;
+use crate::updates::display::json_updates;
+
+/// Display for the Full Audit
+pub fn display_audit(report: &AuditReport, verbose: bool) {
+    println!();
+    println!("FULL SECURITY AUDIT");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details();}
+
+    display_summary(report);
+
+    display_modules(report);
+
+    display_findings(&report.findings, verbose);
+}
+
+/// JSON for the Full Audit
+pub fn json_audit(report: &AuditReport) -> Value {
+    serde_json::to_value(report).unwrap_or(Value::Null)
+}
+
+/// HTML for the Full Audit, one section per module
+pub fn html_audit(report: &AuditReport) -> String {
+    let summary = vec![
+        ("Security Score".to_string(), format!("{}/100 ({})", report.score.overall, score_rating(report.score.overall))),
+        ("Findings".to_string(), report.findings.len().to_string()),
+        ("High or above".to_string(), count_at_least(&report.findings, Severity::High).to_string())
+    ];
+
+    let mut sections = vec![HtmlSection::new("Module Scores", json!({"modules": report.score.modules}))];
+    let modules = [
+        ("antivirus", report.antivirus.as_deref().map(json_antivirus)),
+        ("firewall", report.firewall.as_ref().map(json_firewall)),
+        ("updates", report.updates.as_ref().map(json_updates)),
+        ("uac", report.uac.as_ref().map(json_uac)),
+        ("uas", report.uas.as_ref().map(json_uas)),
+        ("defender", report.defender.as_ref().map(json_defender)),
+        ("os", report.os.as_ref().map(json_os)),
+        ("smb", report.smb.as_ref().map(json_smb)),
+        ("rdp", report.rdp.as_ref().map(json_rdp)),
+        ("bitlocker", report.bitlocker.as_ref().map(json_bitlocker)),
+        ("powershell", report.powershell.as_ref().map(json_powershell)),
+        ("browser", report.browser.as_ref().map(json_browsers)),
+        ("extensions", report.extensions.as_ref().map(json_extensions)),
+        ("shares", report.shares.as_ref().map(json_shares)),
+        ("startup", report.startup.as_ref().map(json_startup))
+    ];
+    for (module, content) in modules {
+        let content = content.unwrap_or_else(|| {
+            let error = report.errors.iter().find(|error| error.module == module).map(|error| error.error.as_str());
+            json!({"status": "Not scanned", "reason": error.unwrap_or("Unknown")})
+        });
+        sections.push(HtmlSection::new(&module_title(module), content));
+    }
+
+    html_document("Shugo Security Audit", &summary, &sections)
+}
+
+/// SARIF for the Full Audit
+pub fn sarif_audit(report: &AuditReport) -> Value {
+    sarif_log(&report.findings)
+}
+
+/// JUnit XML for the Full Audit, one test suite per module. Modules that failed to scan are skipped
+pub fn junit_audit(report: &AuditReport) -> String {
+    let mut cases = Vec::new();
+    for (module, _) in MODULE_WEIGHTS {
+        match report.errors.iter().find(|error| error.module == module) {
+            Some(error) => cases.push(TestCase {
+                classname: module.to_string(),
+                name: format!("{}: scan", module),
+                outcome: TestOutcome::Skipped(error.error.clone())
+            }),
+            None => cases.extend(finding_cases(module, &report.findings))
+        }
+    }
+    junit_xml("Shugo Security Audit", &cases)
+}
+
+fn display_scan_details() {
+    let (h, m, s) = get_time();
+
+    println!("Scan Details:");
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    let modules: Vec<&str> = MODULE_WEIGHTS.iter().map(|(module, _)| *module).collect();
+    println!(" - Modules: {}", modules.join(", "));
+    println!();
+}
+
+fn display_summary(report: &AuditReport) {
+    println!("Summary:");
+    println!(" - Security Score: {}/100 ({})", report.score.overall, score_rating(report.score.overall));
+    println!(" - Findings: {}", report.findings.len());
+    println!("   - Critical: {}", report.findings.iter().filter(|f| f.severity == Severity::Critical).count());
+    println!("   - High or above: {}", count_at_least(&report.findings, Severity::High));
+    println!("   - Medium or above: {}", count_at_least(&report.findings, Severity::Medium));
+    println!();
+}
+
+fn display_modules(report: &AuditReport) {
+    println!("Modules:");
+    for module in &report.score.modules {
+        println!(" - {}: {}/100 (weight {})", module.module, module.score, module.weight);
+    }
+    for error in &report.errors {
+        let status = if error.kind == "unreadable" {"Not scored"} else {"Not scanned"};
+        println!(" - {}: {} ({})", error.module, status, error.error);
+    }
+    println!();
+}
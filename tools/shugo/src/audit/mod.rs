@@ -0,0 +1,4 @@
+pub mod scanner;
+pub mod assessment;
+pub mod snapshot;
+pub mod display;
\ No newline at end of file
@@ -0,0 +1,247 @@
+//! This is the Full Audit for Shugo. It runs every module against one source:
+//!
+//! - Antivirus and Microsoft Defender
+//! - Firewall
+//! - Windows Updates and the OS support lifecycle
+//! - UAC (User Account Control) and UAS (User Account Security)
+//! - SMB, RDP, and network shares
+//! - PowerShell
+//! - BitLocker
+//! - Browsers and browser extensions
+//! - Startup programs
+//!
+//! A module that fails (updates timing out, no WMI on an offline source) is recorded and
+//! skipped, the rest of the audit still runs. So is a module that ran but couldn't read the
+//! settings it's scored on, its findings are kept but it doesn't count towards the score. The findings from each of them are gathered
+//! into one list and scored in `tools\shugo\src\audit\assessment.rs`.
+use serde::{Deserialize, Serialize};
+
+use super::assessment::{AuditScore, score_audit};
+use crate::antivirus::{assessment::assess_antivirus, scanner::{ProductInfo, scan_antivirus_with}};
+use crate::bitlocker::{assessment::assess_bitlocker, scanner::{BitLockerInfo, scan_bitlocker_with}};
+use crate::browser::{assessment::assess_browsers, scanner::{BrowserInfo, BrowserPaths, scan_browsers_with}, versions::VersionTable};
+use crate::common::error::ShugoError;
+use crate::common::finding::Finding;
+use crate::common::source::{SecuritySource, SourceResult, system_source};
+use crate::defender::{assessment::assess_defender, scanner::{DefenderStatus, scan_defender_with}};
+use crate::extensions::{assessment::assess_extensions, scanner::{ExtensionInfo, local_profiles, scan_extensions_with}};
+use crate::firewall::{assessment::assess_firewall, scanner::{FirewallSummary, scan_firewall_with}};
+use crate::os::{assessment::assess_os, lifecycle::LifecycleTable, scanner::{OsInfo, scan_os_with}};
+use crate::powershell::{assessment::assess_powershell, scanner::{PowerShellInfo, scan_powershell_with}};
+use crate::rdp::{assessment::assess_rdp, scanner::{RdpInfo, scan_rdp_with}};
+use crate::shares::{assessment::assess_shares, scanner::{ShareInfo, scan_shares_with}};
+use crate::smb::{assessment::assess_smb, scanner::{SmbInfo, scan_smb_with}};
+use crate::startup::{assessment::assess_startup, scanner::{StartupInfo, StartupPaths, scan_startup_with}};
+use crate::uac::{assessment::assess_uac, scanner::{UacInfo, scan_uac_with}};
+use crate::uas::{assessment::assess_uas, scanner::{UserAccountSummary, scan_uas_with}};
+use crate::updates::{assessment::assess_updates, scanner::{UpdateSummary, scan_updates_with}};
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleError {
+    pub module: String,
+    /// The `ShugoError` variant, like `access_denied` or `unavailable`, or `unreadable` for a module that ran
+    /// but couldn't read what it's scored on
+    #[serde(default)]
+    pub kind: String,
+    pub error: String
+}
+
+/// Modules added to the audit after the first snapshots were saved default to `None`, so older snapshots
+/// load with those modules shown as not scanned
+#[derive(Serialize, Deserialize)]
+pub struct AuditReport {
+    pub antivirus: Option<Vec<ProductInfo>>,
+    pub firewall: Option<FirewallSummary>,
+    pub updates: Option<UpdateSummary>,
+    pub uac: Option<UacInfo>,
+    pub uas: Option<UserAccountSummary>,
+    #[serde(default)]
+    pub defender: Option<DefenderStatus>,
+    #[serde(default)]
+    pub os: Option<OsInfo>,
+    #[serde(default)]
+    pub smb: Option<SmbInfo>,
+    #[serde(default)]
+    pub rdp: Option<RdpInfo>,
+    #[serde(default)]
+    pub powershell: Option<PowerShellInfo>,
+    #[serde(default)]
+    pub bitlocker: Option<BitLockerInfo>,
+    #[serde(default)]
+    pub browser: Option<BrowserInfo>,
+    #[serde(default)]
+    pub extensions: Option<ExtensionInfo>,
+    #[serde(default)]
+    pub shares: Option<ShareInfo>,
+    #[serde(default)]
+    pub startup: Option<StartupInfo>,
+    pub errors: Vec<ModuleError>,
+    pub findings: Vec<Finding>,
+    pub score: AuditScore
+}
+
+/// Folders the browser, extensions, and startup modules read files from on a live machine
+struct AuditPaths {
+    browser: BrowserPaths,
+    startup: StartupPaths
+}
+
+impl AuditPaths {
+    /// The folders of the user Shugo is running as
+    fn from_env() -> AuditPaths {
+        AuditPaths { browser: BrowserPaths::from_env(), startup: StartupPaths::from_env() }
+    }
+}
+
+/// Running the audit modules against Windows
+pub fn run_audit() -> SourceResult<AuditReport> {
+    Ok(audit(system_source()?.as_ref(), Some(&AuditPaths::from_env())))
+}
+
+/// Running the audit modules against any source. Startup folders, scheduled tasks, and browser profiles
+/// are files on a live machine, so they aren't read here
+pub fn run_audit_with(source: &dyn SecuritySource) -> AuditReport {
+    audit(source, None)
+}
+
+fn audit(source: &dyn SecuritySource, paths: Option<&AuditPaths>) -> AuditReport {
+    let mut progress = AuditProgress::default();
+
+    let antivirus = progress.record("antivirus", scan_antivirus_with(source), |products: &Vec<ProductInfo>| assess_antivirus(products));
+    let firewall = progress.record("firewall", scan_firewall_with(source), assess_firewall);
+    let updates = progress.record("updates", scan_updates_with(source), assess_updates);
+    let uac = progress.record("uac", scan_uac_with(source), assess_uac);
+    let uas = progress.record("uas", scan_uas_with(source), assess_uas);
+    let defender = progress.record("defender", scan_defender_with(source), assess_defender);
+    let os = progress.record("os", LifecycleTable::bundled().and_then(|table| scan_os_with(source, &table)), assess_os);
+    let smb = progress.record_read("smb", scan_smb_with(source), assess_smb, SmbInfo::nothing_read);
+    let rdp = progress.record_read("rdp", scan_rdp_with(source), assess_rdp, RdpInfo::nothing_read);
+    let powershell = progress.record_read("powershell", scan_powershell_with(source), assess_powershell, PowerShellInfo::nothing_read);
+    let bitlocker = progress.record("bitlocker", scan_bitlocker_with(source), assess_bitlocker);
+    let browser_paths = paths.map(|paths| &paths.browser);
+    let browser = progress.record_read("browser", VersionTable::bundled().and_then(|table| scan_browsers_with(source, browser_paths, &table)), assess_browsers, BrowserInfo::nothing_read);
+    // Extensions only live in profile folders, there's nothing to read from a registry hive
+    let extensions = match browser_paths {
+        Some(browser_paths) => {
+            let mut unreadable = Default::default();
+            let profiles = local_profiles(browser_paths, &mut unreadable);
+            let scan = scan_extensions_with(&profiles).map(|mut info| {
+                info.module_info.unreadable.extend(unreadable);
+                info
+            });
+            progress.record("extensions", scan, assess_extensions)
+        },
+        None => progress.record("extensions", Err(ShugoError::unavailable("Browser profiles", "extensions are only read on a live machine")), assess_extensions)
+    };
+    let shares = progress.record_read("shares", scan_shares_with(source), assess_shares, ShareInfo::nothing_read);
+    let startup = progress.record_read("startup", scan_startup_with(source, paths.map(|paths| &paths.startup)), assess_startup, StartupInfo::nothing_read);
+
+    let score = score_audit(&progress.completed, &progress.findings);
+
+    AuditReport {
+        antivirus,
+        firewall,
+        updates,
+        uac,
+        uas,
+        defender,
+        os,
+        smb,
+        rdp,
+        powershell,
+        bitlocker,
+        browser,
+        extensions,
+        shares,
+        startup,
+        errors: progress.errors,
+        findings: progress.findings,
+        score
+    }
+}
+
+/// What the audit has gathered so far
+#[derive(Default)]
+struct AuditProgress {
+    completed: Vec<&'static str>,
+    errors: Vec<ModuleError>,
+    findings: Vec<Finding>
+}
+
+impl AuditProgress {
+    /*
+        Shugo: Tolerating Failures
+
+        Each scanner returns a `Result`. Instead of using `?` and giving up on the first error like the single
+        module commands do, we keep the error next to the module name and move on. The `Option` we hand back
+        holds whatever did succeed so the report can still show it.
+    */
+    fn record<T>(&mut self, module: &'static str, result: SourceResult<T>, assess: impl Fn(&T) -> Vec<Finding>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.findings.extend(assess(&value));
+                self.completed.push(module);
+                Some(value)
+            },
+            Err(error) => {
+                self.errors.push(ModuleError { module: module.to_string(), kind: error.kind().to_string(), error: error.detail() });
+                None
+            }
+        }
+    }
+
+    /// Like `record`, but a module that couldn't read any of the settings it's scored on would score 100 with
+    /// nothing to go on, so it's kept out of the score and listed with the errors. Its findings still count
+    fn record_read<T>(&mut self, module: &'static str, result: SourceResult<T>, assess: impl Fn(&T) -> Vec<Finding>, nothing_read: fn(&T) -> bool) -> Option<T> {
+        let value = self.record(module, result, assess)?;
+        if nothing_read(&value) {
+            self.completed.retain(|completed| *completed != module);
+            self.errors.push(ModuleError {
+                module: module.to_string(),
+                kind: "unreadable".to_string(),
+                error: "none of the settings it's scored on could be read".to_string()
+            });
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::assessment::MODULE_WEIGHTS;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::offline::OfflineSource;
+    use crate::common::source::{RegistryHive, RegistryValue};
+    use crate::uac::scanner::UAC_KEY;
+
+    #[test]
+    fn keeps_going_when_modules_fail() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, UAC_KEY, "EnableLUA", RegistryValue::Dword(0))
+            .with_users(vec![]);
+
+        let report = run_audit_with(&source);
+        assert!(report.uac.is_some());
+        assert!(report.uas.is_some());
+        assert!(report.antivirus.is_none());
+        let failed: Vec<&str> = report.errors.iter().filter(|e| e.kind != "unreadable").map(|e| e.module.as_str()).collect();
+        assert_eq!(failed, vec!["antivirus", "firewall", "updates", "os", "extensions"]);
+        assert_eq!(report.errors.iter().find(|e| e.module == "extensions").unwrap().kind, "unavailable");
+        assert!(report.findings.iter().any(|finding| finding.id == "UAC-001"));
+        // The SMB, RDP, and share fixtures are empty, so those ran without anything to score
+        assert_eq!(report.score.modules.len(), MODULE_WEIGHTS.len() - report.errors.len());
+        assert!(report.score.modules.iter().any(|module| module.module == "uac"));
+    }
+
+    #[test]
+    fn unread_modules_are_not_scored() {
+        // Nothing loaded, so every module either fails or reads nothing. None of them can pass
+        let report = run_audit_with(&OfflineSource::new());
+        assert!(report.score.modules.is_empty(), "{:?}", report.score.modules.iter().map(|module| &module.module).collect::<Vec<_>>());
+        assert!(report.score.overall < 50);
+        let unread: Vec<&str> = report.errors.iter().filter(|error| error.kind == "unreadable").map(|error| error.module.as_str()).collect();
+        assert_eq!(unread, vec!["smb", "rdp", "powershell", "browser", "shares", "startup"]);
+        assert!(report.smb.is_some());
+    }
+}
@@ -0,0 +1,80 @@
+//! Saving and loading audit snapshots. A snapshot is the same versioned JSON document
+//! `shugo audit --format json` prints, written to a file so two audits can be compared later.
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::scanner::AuditReport;
+use crate::common::error::ShugoError;
+use crate::common::output::{SCHEMA_VERSION, json_report};
+use crate::common::source::SourceResult;
+
+/// Name the audit is stored under in the report envelope
+pub const AUDIT_MODULE: &str = "audit";
+
+/// Writes an audit report to `path`
+pub fn save_snapshot(report: &AuditReport, path: &Path) -> SourceResult<()> {
+    let result = serde_json::to_value(report).map_err(|error| ShugoError::parse("snapshot", error.to_string()))?;
+    let text = serde_json::to_string_pretty(&json_report(AUDIT_MODULE, result))
+        .map_err(|error| ShugoError::parse("snapshot", error.to_string()))?;
+    fs::write(path, text).map_err(|error| ShugoError::io(path.display().to_string(), &error))
+}
+
+/// Reads an audit report saved by `save_snapshot`
+pub fn load_snapshot(path: &Path) -> SourceResult<AuditReport> {
+    let text = fs::read_to_string(path).map_err(|error| ShugoError::io(path.display().to_string(), &error))?;
+    parse_snapshot(&text).map_err(|error| error.for_query(path.display().to_string()))
+}
+
+/// Checks the envelope and pulls the audit report out of a snapshot document
+pub fn parse_snapshot(text: &str) -> SourceResult<AuditReport> {
+    let invalid = |reason: String| ShugoError::parse("snapshot", reason);
+    let document: Value = serde_json::from_str(text).map_err(|error| invalid(error.to_string()))?;
+    if document["tool"] != "shugo" || document["module"] != AUDIT_MODULE {
+        return Err(invalid("not a shugo audit snapshot, save one with `shugo audit --save`".to_string()));
+    }
+    let version = document["schema_version"].as_u64().unwrap_or(0);
+    if version == 0 || version > SCHEMA_VERSION as u64 {
+        return Err(invalid(format!("unsupported snapshot schema version {}", version)));
+    }
+    serde_json::from_value(document["result"].clone()).map_err(|error| invalid(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::scanner::run_audit_with;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::product_state::ScannerState;
+
+    #[test]
+    fn round_trips_through_json() {
+        let report = run_audit_with(&FixtureSource::new().with_users(vec![]));
+        let document = json_report(AUDIT_MODULE, serde_json::to_value(&report).unwrap());
+
+        let loaded = parse_snapshot(&document.to_string()).unwrap();
+        assert!(loaded.uas.is_some());
+        assert_eq!(loaded.errors.len(), report.errors.len());
+        assert_eq!(loaded.score.overall, report.score.overall);
+    }
+
+    #[test]
+    fn loads_version_one_products() {
+        let report = run_audit_with(&FixtureSource::new().with_users(vec![]));
+        let mut document = json_report(AUDIT_MODULE, serde_json::to_value(&report).unwrap());
+        document["schema_version"] = 1.into();
+        document["result"]["antivirus"] = serde_json::json!([
+            {"name": "Windows Defender", "state": 397568, "product_status": 1, "definition_status": 0, "product_owner": 1}
+        ]);
+
+        let loaded = parse_snapshot(&document.to_string()).unwrap();
+        assert_eq!(loaded.antivirus.unwrap()[0].product_state.scanner, ScannerState::On);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(parse_snapshot("{\"tool\": \"shugo\", \"module\": \"uac\", \"schema_version\": 1}").is_err());
+        assert!(parse_snapshot("not json").is_err());
+    }
+}
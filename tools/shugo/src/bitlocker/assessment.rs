@@ -0,0 +1,253 @@
+//! Assessment for the BitLocker Module, turns each volume's protection, cipher, and key protectors into findings.
+use super::scanner::{BitLockerInfo, EncryptableVolume, encryption_method_text, conversion_status_text, key_protector_text};
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "bitlocker";
+
+/// Assessing BitLocker
+pub fn assess_bitlocker(info: &BitLockerInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if info.available == Some(false) {
+        findings.push(
+            Finding::new("BL-010", MODULE, Severity::High, "BitLocker isn't available on this edition of Windows")
+                .explanation("Windows has no BitLocker here, which usually means a Home edition, so its drives aren't encrypted by Windows. Anyone who takes the disk out can read every file on it. Encryption from other tools, like VeraCrypt, isn't detected")
+                .remediation("Turn on Device encryption under Settings > Privacy & security if it's offered, upgrade to Windows Pro for BitLocker, or encrypt the drive with another tool")
+                .evidence("namespace=not found")
+        );
+    }
+
+    for volume in info.volumes.iter().flatten() {
+        let unprotected = volume.protection_status == Some(0);
+        // Without a conversion status there's no telling an unencrypted drive from one Windows didn't describe,
+        // so the encryption checks are skipped and BL-008 says what couldn't be read
+        let encrypted = volume.is_encrypted();
+
+        if volume.is_os_volume() && unprotected && encrypted == Some(false) {
+            findings.push(
+                Finding::new("BL-001", MODULE, Severity::Critical, format!("The operating system drive {} isn't encrypted", volume.name()))
+                    .explanation("Anyone who takes the disk out, or boots the machine from a USB stick, can read every file on it and pull saved credentials from the registry")
+                    .remediation(format!("Turn on BitLocker with `manage-bde -on {} -RecoveryPassword`, or from Control Panel > BitLocker Drive Encryption", volume.name()))
+                    .evidence(volume_evidence(volume))
+                    .evidence(format!("conversion={}", conversion_status_text(volume.conversion_status)))
+            );
+        } else if volume.is_os_volume() && unprotected && volume.conversion_status == Some(1) {
+            findings.push(
+                Finding::new("BL-002", MODULE, Severity::High, format!("BitLocker protection is suspended on {}", volume.name()))
+                    .explanation("While protection is suspended the volume key is written to the disk in the clear, so the encryption doesn't stop anyone who has the disk. Windows suspends it for updates and firmware changes, but it should come back on after the next restart")
+                    .remediation(format!("Run `manage-bde -protectors -enable {0}` or `Resume-BitLocker -MountPoint {0}`", volume.name()))
+                    .evidence(volume_evidence(volume))
+            );
+        } else if unprotected && encrypted == Some(false) && volume.volume_type == Some(1) {
+            findings.push(
+                Finding::new("BL-003", MODULE, Severity::Medium, format!("The fixed data drive {} isn't encrypted", volume.name()))
+                    .explanation("Data drives often hold the same documents and backups as the OS drive, and are just as readable once removed")
+                    .remediation("Turn on BitLocker for the drive and let it unlock automatically with the OS drive")
+                    .evidence(volume_evidence(volume))
+            );
+        }
+
+        if encrypted == Some(true) && volume.weak_method() {
+            findings.push(
+                Finding::new("BL-004", MODULE, Severity::Low, format!("{} is encrypted with a 128-bit key", volume.name()))
+                    .explanation("AES-128 is still Windows' default and isn't broken, but most security baselines require XTS-AES 256. The method can't be changed without decrypting the volume first")
+                    .remediation("Set \"Choose drive encryption method and cipher strength\" to XTS-AES 256 in Group Policy, then decrypt and re-encrypt the volume")
+                    .evidence(format!("method={}", encryption_method_text(volume.encryption_method)))
+            );
+        }
+
+        if volume.is_os_volume() && encrypted == Some(true) && volume.tpm_only() {
+            findings.push(
+                Finding::new("BL-005", MODULE, Severity::Medium, format!("{} unlocks with the TPM alone", volume.name()))
+                    .explanation("The TPM releases the key to anyone who turns the machine on, so a stolen laptop boots to the logon screen with the disk already unlocked. Attacks on the TPM bus or on memory can read the key from there")
+                    .remediation(format!("Add a startup PIN with `manage-bde -protectors -add {} -TPMAndPIN` after allowing it in \"Require additional authentication at startup\"", volume.name()))
+                    .evidence(protector_evidence(volume))
+            );
+        }
+
+        if encrypted == Some(true) && volume.has_recovery_password() == Some(false) {
+            findings.push(
+                Finding::new("BL-006", MODULE, Severity::Medium, format!("{} has no recovery password", volume.name()))
+                    .explanation("If the TPM is cleared or the startup PIN is forgotten, a recovery password is the only way back into the volume without its other protectors")
+                    .remediation(format!("Run `manage-bde -protectors -add {} -RecoveryPassword` and back the password up to Active Directory, Entra ID, or somewhere off the machine", volume.name()))
+                    .evidence(protector_evidence(volume))
+            );
+        }
+
+        let percentage = volume.encryption_percentage.map(|percentage| format!("{}%", percentage)).unwrap_or_else(|| "unknown".to_string());
+        if matches!(volume.conversion_status, Some(2) | Some(4)) {
+            findings.push(
+                Finding::new("BL-007", MODULE, Severity::Low, format!("{} is {}", volume.name(), conversion_status_text(volume.conversion_status).to_lowercase()))
+                    .explanation("Only part of the volume is encrypted until the conversion finishes, and a paused conversion won't finish on its own")
+                    .remediation(format!("Check `manage-bde -status {0}`, and run `manage-bde -resume {0}` if the conversion is paused", volume.name()))
+                    .evidence(format!("conversion={}", conversion_status_text(volume.conversion_status)))
+                    .evidence(format!("encrypted={}", percentage))
+            );
+        } else if matches!(volume.conversion_status, Some(3) | Some(5)) {
+            let severity = if volume.is_os_volume() {Severity::High} else {Severity::Medium};
+            findings.push(
+                Finding::new("BL-009", MODULE, severity, format!("BitLocker is being turned off on {}", volume.name()))
+                    .explanation("The volume is being decrypted, which only happens when someone turns BitLocker off. The part already decrypted is readable by anyone who has the disk, and all of it will be once decryption finishes")
+                    .remediation(format!("If nobody meant to turn BitLocker off, stop decryption with `manage-bde -pause {0}` and turn it back on with `manage-bde -on {0}`", volume.name()))
+                    .evidence(format!("conversion={}", conversion_status_text(volume.conversion_status)))
+                    .evidence(format!("encrypted={}", percentage))
+            );
+        }
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        findings.push(
+            Finding::new("BL-008", MODULE, Severity::Info, "Some BitLocker information couldn't be read")
+                .explanation("Shugo couldn't read every volume's conversion status or key protectors, so some drives may not be reported")
+                .remediation("Run Shugo as administrator on a live machine, BitLocker can't be checked from offline registry files")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+    }
+
+    findings
+}
+
+fn volume_evidence(volume: &EncryptableVolume) -> String {
+    format!("volume={}", volume.name())
+}
+
+fn protector_evidence(volume: &EncryptableVolume) -> String {
+    let protectors = volume.key_protectors.iter().flatten().map(|protector| key_protector_text(*protector)).collect::<Vec<_>>();
+    format!("protectors={}", protectors.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitlocker::scanner::{object_path, scan_bitlocker_with, BITLOCKER_NAMESPACE, VOLUME_OS};
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::{WmiRow, WmiValue};
+
+    const DEVICE_ID: &str = "\\\\?\\Volume{1}\\";
+
+    /// A Win32_EncryptableVolume row. Without any method fixtures the percentage and key protectors can't be read
+    fn volume(letter: &str, volume_type: u32, protection_status: u32, conversion_status: Option<u32>) -> WmiRow {
+        let row = WmiRow::new()
+            .with("DeviceID", WmiValue::String(DEVICE_ID.to_string()))
+            .with("DriveLetter", WmiValue::String(letter.to_string()))
+            .with("ProtectionStatus", WmiValue::U32(protection_status))
+            .with("EncryptionMethod", WmiValue::U32(7))
+            .with("VolumeType", WmiValue::U32(volume_type));
+        match conversion_status {
+            Some(status) => row.with("ConversionStatus", WmiValue::U32(status)),
+            None => row
+        }
+    }
+
+    /// Answers GetKeyProtectors and GetKeyProtectorType for one volume, protectors given as (ID, type)
+    fn with_protectors(source: FixtureSource, protectors: &[(&str, u32)]) -> FixtureSource {
+        let path = object_path(DEVICE_ID);
+        let ids = protectors.iter().map(|(id, _)| WmiValue::String(id.to_string())).collect();
+        let source = source.with_wmi_method(BITLOCKER_NAMESPACE, &path, "GetKeyProtectors", &[("KeyProtectorType", WmiValue::U32(0))], WmiRow::new()
+            .with("ReturnValue", WmiValue::U32(0))
+            .with("VolumeKeyProtectorID", WmiValue::Array(ids)));
+        protectors.iter().fold(source, |source, (id, protector)| {
+            source.with_wmi_method(BITLOCKER_NAMESPACE, &path, "GetKeyProtectorType", &[("VolumeKeyProtectorID", WmiValue::String(id.to_string()))], WmiRow::new()
+                .with("ReturnValue", WmiValue::U32(0))
+                .with("KeyProtectorType", WmiValue::U32(*protector)))
+        })
+    }
+
+    /// Answers GetConversionStatus for one volume
+    fn with_conversion(source: FixtureSource, conversion_status: u32, percentage: u32) -> FixtureSource {
+        source.with_wmi_method(BITLOCKER_NAMESPACE, &object_path(DEVICE_ID), "GetConversionStatus", &[], WmiRow::new()
+            .with("ReturnValue", WmiValue::U32(0))
+            .with("ConversionStatus", WmiValue::U32(conversion_status))
+            .with("EncryptionPercentage", WmiValue::U32(percentage)))
+    }
+
+    fn assess(source: &FixtureSource) -> Vec<Finding> {
+        assess_bitlocker(&scan_bitlocker_with(source).unwrap())
+    }
+
+    #[test]
+    fn hardened_volume_has_no_findings() {
+        let source = FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("C:", VOLUME_OS, 1, Some(1))]);
+        let source = with_protectors(with_conversion(source, 1, 100), &[("{tpm-pin}", 4), ("{recovery}", 3)]);
+        assert!(assess(&source).is_empty());
+    }
+
+    #[test]
+    fn unencrypted_os_drive_is_critical() {
+        let findings = assess(&FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("C:", VOLUME_OS, 0, Some(0))]));
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("BL-001", Severity::Critical));
+        assert_eq!(findings[0].evidence, vec!["volume=C:", "conversion=Fully Decrypted"]);
+    }
+
+    #[test]
+    fn only_a_fully_encrypted_drive_is_suspended() {
+        let cases = [(1, "BL-002", Severity::High), (2, "BL-007", Severity::Low), (3, "BL-009", Severity::High), (5, "BL-009", Severity::High)];
+        for (conversion_status, id, severity) in cases {
+            let source = FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("C:", VOLUME_OS, 0, Some(conversion_status))]);
+            let findings = assess(&source);
+            let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+            // The method calls have no fixtures, so BL-008 is always last
+            assert_eq!(ids, [id, "BL-008"], "ConversionStatus={}", conversion_status);
+            assert_eq!(findings[0].severity, severity);
+        }
+    }
+
+    #[test]
+    fn decryption_isnt_resumed_like_a_suspension() {
+        let source = FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("E:", 1, 0, Some(3))]);
+        let findings = assess(&source);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("BL-009", Severity::Medium));
+        assert!(!findings[0].remediation.contains("-protectors -enable"));
+    }
+
+    #[test]
+    fn reports_paused_conversion_with_percentage() {
+        let source = FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("C:", VOLUME_OS, 1, Some(4))]);
+        let source = with_conversion(source, 4, 37);
+        let findings = assess(&with_protectors(source, &[("{pin}", 4), ("{password}", 3)]));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].evidence, vec!["conversion=Encryption Paused", "encrypted=37%"]);
+    }
+
+    #[test]
+    fn unencrypted_data_drive_is_medium() {
+        let findings = assess(&FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("D:", 1, 0, Some(0))]));
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("BL-003", Severity::Medium));
+    }
+
+    #[test]
+    fn unknown_conversion_status_skips_encryption_checks() {
+        let source = FixtureSource::new()
+            .with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("C:", VOLUME_OS, 0, None)])
+            .with_wmi_method(BITLOCKER_NAMESPACE, &object_path(DEVICE_ID), "GetConversionStatus", &[], WmiRow::new().with("ReturnValue", WmiValue::U32(0)));
+        let findings = assess(&with_protectors(source, &[]));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "BL-008");
+        assert_eq!(findings[0].evidence, vec!["unreadable=C:.ConversionStatus"]);
+    }
+
+    #[test]
+    fn unread_key_protectors_are_not_missing() {
+        let findings = assess(&FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("C:", VOLUME_OS, 1, Some(1))]));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].evidence, vec!["unreadable=C:.GetConversionStatus,C:.GetKeyProtectors"]);
+    }
+
+    #[test]
+    fn tpm_only_without_recovery_password_names_the_volume() {
+        let source = FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume("D:", VOLUME_OS, 1, Some(1))]);
+        let findings = assess(&with_protectors(source, &[("{tpm}", 1)]));
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["BL-005", "BL-006", "BL-008"]);
+        for finding in &findings[..2] {
+            assert!(finding.remediation.contains("D:") && !finding.remediation.contains("C:"), "{}", finding.remediation);
+        }
+    }
+
+    #[test]
+    fn missing_namespace_is_a_finding_not_a_pass() {
+        let findings = assess(&FixtureSource::new());
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("BL-010", Severity::High));
+    }
+}
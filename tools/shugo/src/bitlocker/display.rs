@@ -0,0 +1,96 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_bitlocker;
+use super::scanner::{BitLockerInfo, encryption_method_text, protection_status_text, conversion_status_text, volume_type_text, key_protector_text};
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::get_time;
+
+/// Display for BitLocker Module
+pub fn display_bitlocker(info: &BitLockerInfo, verbose: bool) {
+    println!();
+    println!("BITLOCKER ENCRYPTION STATUS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_volumes(info, verbose);
+
+    display_findings(&assess_bitlocker(info), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for BitLocker Module
+pub fn json_bitlocker(info: &BitLockerInfo) -> Value {
+    let volumes: Vec<Value> = info.volumes.iter().flatten().map(|volume| json!({
+        "volume": volume.name(),
+        "volume_type": volume_type_text(volume.volume_type),
+        "protection": protection_status_text(volume.protection_status),
+        "conversion": conversion_status_text(volume.conversion_status),
+        "encryption_method": encryption_method_text(volume.encryption_method),
+        "key_protectors": volume.key_protectors.as_ref().map(|protectors| protectors.iter().map(|protector| key_protector_text(*protector)).collect::<Vec<_>>())
+    })).collect();
+    let decoded = json!({ "volumes": volumes });
+
+    json!({
+        "bitlocker": with_decoded(info, decoded),
+        "findings": assess_bitlocker(info)
+    })
+}
+
+fn display_scan_details(info: &BitLockerInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Query:");
+    for query in info.module_info.queries.iter() {
+        println!("   - {}", query);
+    }
+    for (location, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", location, reason);
+    }
+    println!();
+}
+
+fn display_volumes(info: &BitLockerInfo, verbose: bool) {
+    println!("Volumes:");
+    let Some(volumes) = &info.volumes else {
+        match info.available {
+            Some(false) => println!(" - BitLocker isn't available on this edition of Windows"),
+            _ => println!(" - Volumes couldn't be read (BitLocker needs administrator rights)")
+        }
+        println!();
+        return;
+    };
+    if volumes.is_empty() {
+        println!(" - No encryptable volumes found");
+    }
+    for volume in volumes.iter() {
+        println!(" - {} ({})", volume.name(), volume_type_text(volume.volume_type));
+        println!("   - Protection: {}", protection_status_text(volume.protection_status));
+        let percentage = volume.encryption_percentage.map(|percentage| format!(" ({}%)", percentage)).unwrap_or_default();
+        println!("   - Conversion: {}{}", conversion_status_text(volume.conversion_status), percentage);
+        println!("   - Encryption Method: {}", encryption_method_text(volume.encryption_method));
+        match &volume.key_protectors {
+            Some(protectors) if !protectors.is_empty() => {
+                let names = protectors.iter().map(|protector| key_protector_text(*protector)).collect::<Vec<_>>();
+                println!("   - Key Protectors: {}", names.join(", "));
+            },
+            Some(_) => println!("   - Key Protectors: None"),
+            None => println!("   - Key Protectors: Unknown")
+        }
+        if verbose && volume.drive_letter.is_some() {
+            println!("   - Device ID: {}", volume.device_id);
+        }
+    }
+    println!();
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - WMI Namespace: ROOT\\CIMV2\\Security\\MicrosoftVolumeEncryption");
+    println!(" - WMI Methods: GetConversionStatus, GetKeyProtectors, GetKeyProtectorType");
+    println!(" - Requires: Administrator");
+    println!();
+}
@@ -0,0 +1,407 @@
+//! This is the BitLocker Module for Shugo. Here we can see:
+//!
+//! - Protection Status for Each Volume
+//! - Encryption Method (AES-128/AES-256, CBC/XTS)
+//! - Conversion Status and Percentage
+//! - Key Protector Types (TPM, TPM+PIN, Recovery Password, ...)
+//!
+//! Everything comes from the `Win32_EncryptableVolume` WMI class, which only answers administrators.
+//! Offline registry files don't have any of it, so an offline scan only reports that BitLocker couldn't be read.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::ShugoError;
+use crate::common::source::{SecuritySource, SourceResult, WmiRow, WmiValue, system_source};
+use crate::common::wmi::{query, optional, FromWmiObject, WmiObject};
+
+pub const BITLOCKER_NAMESPACE: &str = "ROOT\\CIMV2\\Security\\MicrosoftVolumeEncryption";
+pub const VOLUME_QUERY: &str = "SELECT DeviceID, DriveLetter, ProtectionStatus, ConversionStatus, EncryptionMethod, VolumeType FROM Win32_EncryptableVolume";
+
+/// Win32_EncryptableVolume.VolumeType for the drive Windows boots from
+pub const VOLUME_OS: u32 = 0;
+
+/// Win32_EncryptableVolume.ProtectionStatus when BitLocker is protecting the volume
+pub const PROTECTION_ON: u32 = 1;
+
+/// One volume BitLocker can encrypt. Each value is `None` when Windows didn't report it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EncryptableVolume {
+    pub device_id: String,
+    pub drive_letter: Option<String>,
+    /// 0 = Off, 1 = On, 2 = Unknown (locked)
+    pub protection_status: Option<u32>,
+    /// 0 = Fully Decrypted, 1 = Fully Encrypted, 2 = Encrypting, 3 = Decrypting, 4 = Encryption Paused, 5 = Decryption Paused
+    pub conversion_status: Option<u32>,
+    pub encryption_method: Option<u32>,
+    /// 0 = Operating System, 1 = Fixed Data, 2 = Removable Data
+    pub volume_type: Option<u32>,
+    /// From `GetConversionStatus`, `None` when the call failed
+    pub encryption_percentage: Option<u32>,
+    /// Key protector types from `GetKeyProtectors`, `None` when the call failed
+    pub key_protectors: Option<Vec<u32>>
+}
+
+impl FromWmiObject for EncryptableVolume {
+    fn from_wmi_object(object: &dyn WmiObject) -> SourceResult<Self> {
+        Ok(EncryptableVolume {
+            device_id: object.get_string("DeviceID")?,
+            drive_letter: optional(object.get_string("DriveLetter"))?,
+            protection_status: optional(object.get_u32("ProtectionStatus"))?,
+            conversion_status: optional(object.get_u32("ConversionStatus"))?,
+            encryption_method: optional(object.get_u32("EncryptionMethod"))?,
+            volume_type: optional(object.get_u32("VolumeType"))?,
+            encryption_percentage: None,
+            key_protectors: None
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BitLockerInfo {
+    /// `Some(false)` when Windows has no BitLocker at all, like Windows Home. `None` in snapshots saved before this was kept
+    #[serde(default)]
+    pub available: Option<bool>,
+    /// `None` when BitLocker isn't available, or in older snapshots when the volumes couldn't be listed
+    pub volumes: Option<Vec<EncryptableVolume>>,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub registry_keys: Vec<String>,
+    pub queries: Vec<String>,
+    /// Queries and method calls that couldn't be made, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+/// Scanning BitLocker for Windows
+pub fn scan_bitlocker() -> SourceResult<BitLockerInfo> {
+    scan_bitlocker_with(system_source()?.as_ref())
+}
+
+/// Scanning BitLocker from any source
+pub fn scan_bitlocker_with(source: &dyn SecuritySource) -> SourceResult<BitLockerInfo> {
+    /*
+        Shugo: BitLocker Through WMI
+
+        BitLocker has its own WMI namespace, ROOT\CIMV2\Security\MicrosoftVolumeEncryption, with one
+        `Win32_EncryptableVolume` object per volume. The properties cover the basics:
+
+        - ProtectionStatus: whether the volume is actually protected. A volume can be fully encrypted with
+          protection suspended, which leaves the key in the clear on the disk
+        - EncryptionMethod: which cipher and key size was used
+        - VolumeType: whether this is the OS drive, a fixed data drive, or a removable drive
+
+        How far along encryption is and which key protectors unlock the volume aren't properties, so we call
+        the `GetConversionStatus` and `GetKeyProtectors` methods on each volume as well.
+
+        The namespace only allows administrators in, and it doesn't exist on Windows editions without BitLocker.
+        Being refused fails the module since nothing about encryption can be said, while a missing namespace
+        means Windows can't encrypt the drives, which is a finding of its own.
+
+        For more information on Win32_EncryptableVolume:
+        (https://learn.microsoft.com/en-us/windows/win32/secprov/win32-encryptablevolume)
+    */
+    let mut queries: Vec<String> = vec![VOLUME_QUERY.to_string()];
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+
+    let volumes = match query::<EncryptableVolume>(source, BITLOCKER_NAMESPACE, VOLUME_QUERY) {
+        Ok(volumes) => Some(volumes),
+        Err(ShugoError::NotFound { .. }) => None,
+        Err(error) => return Err(error.in_module("bitlocker"))
+    };
+    let available = Some(volumes.is_some());
+
+    let volumes = match volumes {
+        Some(volumes) => {
+            let mut scanned = Vec::with_capacity(volumes.len());
+            for mut volume in volumes.into_iter() {
+                let path = object_path(&volume.device_id);
+                let name = volume.drive_letter.clone().unwrap_or_else(|| volume.device_id.clone());
+
+                queries.push(format!("{}.GetConversionStatus", name));
+                match conversion_status(source, &path) {
+                    Ok((status, percentage)) => {
+                        volume.encryption_percentage = percentage;
+                        if status.is_some() {
+                            volume.conversion_status = status;
+                        }
+                    },
+                    Err(error) => {unreadable.insert(format!("{}.GetConversionStatus", name), error.detail());}
+                }
+                if volume.conversion_status.is_none() && !unreadable.contains_key(&format!("{}.GetConversionStatus", name)) {
+                    unreadable.insert(format!("{}.ConversionStatus", name), "Windows didn't report the conversion status".to_string());
+                }
+
+                queries.push(format!("{}.GetKeyProtectors", name));
+                match key_protectors(source, &path) {
+                    Ok(protectors) => volume.key_protectors = Some(protectors),
+                    Err(error) => {unreadable.insert(format!("{}.GetKeyProtectors", name), error.detail());}
+                }
+
+                scanned.push(volume);
+            }
+            Some(scanned)
+        },
+        None => None
+    };
+
+    Ok(BitLockerInfo {
+        available,
+        volumes,
+        module_info: ModuleInfo {
+            registry_keys: vec![],
+            queries,
+            unreadable
+        }
+    })
+}
+
+/// The WMI path of one volume, like `Win32_EncryptableVolume.DeviceID="\\\\?\\Volume{...}\\"`
+pub fn object_path(device_id: &str) -> String {
+    format!("Win32_EncryptableVolume.DeviceID=\"{}\"", device_id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Calls one `Win32_EncryptableVolume` method and checks its `ReturnValue`
+fn call(source: &dyn SecuritySource, path: &str, method: &str, inputs: &[(&str, WmiValue)]) -> SourceResult<WmiRow> {
+    let outputs = source.wmi_method(BITLOCKER_NAMESPACE, path, method, inputs)?;
+    match optional(outputs.get_u32("ReturnValue"))? {
+        Some(0) | None => Ok(outputs),
+        Some(code) => Err(ShugoError::from_hresult(method, code as i32, format!("{} returned 0x{:08X}", method, code)))
+    }
+}
+
+/// The conversion status and encryption percentage from `GetConversionStatus`, each `None` when it wasn't returned
+fn conversion_status(source: &dyn SecuritySource, path: &str) -> SourceResult<(Option<u32>, Option<u32>)> {
+    let outputs = call(source, path, "GetConversionStatus", &[])?;
+    Ok((optional(outputs.get_u32("ConversionStatus"))?, optional(outputs.get_u32("EncryptionPercentage"))?))
+}
+
+/// The type of every key protector on a volume
+fn key_protectors(source: &dyn SecuritySource, path: &str) -> SourceResult<Vec<u32>> {
+    /*
+        Shugo: Key Protectors
+
+        A key protector is one way of unlocking the volume key. `GetKeyProtectors` with a type of 0 lists the IDs
+        of all of them, then `GetKeyProtectorType` says what each one is:
+
+        1 = TPM, 2 = External Key (USB), 3 = Numerical Password (the 48 digit recovery password),
+        4 = TPM And PIN, 5 = TPM And Startup Key, 6 = TPM And PIN And Startup Key, 7 = Public Key,
+        8 = Passphrase, 9 = TPM Certificate, 10 = Active Directory Account (SID)
+    */
+    let outputs = call(source, path, "GetKeyProtectors", &[("KeyProtectorType", WmiValue::U32(0))])?;
+    let ids = optional(outputs.get_strings("VolumeKeyProtectorID"))?.unwrap_or_default();
+    let mut protectors = Vec::with_capacity(ids.len());
+    for id in ids.into_iter() {
+        let outputs = call(source, path, "GetKeyProtectorType", &[("VolumeKeyProtectorID", WmiValue::String(id))])?;
+        protectors.push(outputs.get_u32("KeyProtectorType")?);
+    }
+    Ok(protectors)
+}
+
+impl EncryptableVolume {
+    /// The drive letter, or the volume GUID path for volumes without one
+    pub fn name(&self) -> &str {
+        self.drive_letter.as_deref().unwrap_or(&self.device_id)
+    }
+
+    pub fn is_os_volume(&self) -> bool {
+        self.volume_type == Some(VOLUME_OS)
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.protection_status == Some(PROTECTION_ON)
+    }
+
+    /// Whether any data on the volume is encrypted, even if protection is suspended. `None` when the
+    /// conversion status wasn't reported, which doesn't say either way
+    pub fn is_encrypted(&self) -> Option<bool> {
+        self.conversion_status.map(|status| status != 0)
+    }
+
+    /// AES-128 in any mode. Microsoft's default, but below the AES-256 most baselines ask for
+    pub fn weak_method(&self) -> bool {
+        matches!(self.encryption_method, Some(1) | Some(3) | Some(6))
+    }
+
+    /// Only a TPM protects the key, so the machine unlocks itself at boot for anyone holding it
+    pub fn tpm_only(&self) -> bool {
+        let Some(protectors) = &self.key_protectors else {return false};
+        protectors.contains(&1) && !protectors.iter().any(|protector| matches!(protector, 4..=6))
+    }
+
+    pub fn has_recovery_password(&self) -> Option<bool> {
+        self.key_protectors.as_ref().map(|protectors| protectors.contains(&3))
+    }
+}
+
+pub fn encryption_method_text(method: Option<u32>) -> &'static str {
+    match method {
+        Some(0) => "None",
+        Some(1) => "AES-128 with Diffuser",
+        Some(2) => "AES-256 with Diffuser",
+        Some(3) => "AES-CBC 128",
+        Some(4) => "AES-CBC 256",
+        Some(5) => "Hardware Encryption",
+        Some(6) => "XTS-AES 128",
+        Some(7) => "XTS-AES 256",
+        Some(_) | None => "Unknown"
+    }
+}
+
+pub fn protection_status_text(status: Option<u32>) -> &'static str {
+    match status {
+        Some(0) => "Off",
+        Some(1) => "On",
+        Some(2) => "Locked",
+        Some(_) | None => "Unknown"
+    }
+}
+
+pub fn conversion_status_text(status: Option<u32>) -> &'static str {
+    match status {
+        Some(0) => "Fully Decrypted",
+        Some(1) => "Fully Encrypted",
+        Some(2) => "Encrypting",
+        Some(3) => "Decrypting",
+        Some(4) => "Encryption Paused",
+        Some(5) => "Decryption Paused",
+        Some(_) | None => "Unknown"
+    }
+}
+
+pub fn volume_type_text(volume_type: Option<u32>) -> &'static str {
+    match volume_type {
+        Some(0) => "Operating System",
+        Some(1) => "Fixed Data",
+        Some(2) => "Removable Data",
+        Some(_) | None => "Unknown"
+    }
+}
+
+pub fn key_protector_text(protector: u32) -> &'static str {
+    match protector {
+        1 => "TPM",
+        2 => "External Key",
+        3 => "Recovery Password",
+        4 => "TPM And PIN",
+        5 => "TPM And Startup Key",
+        6 => "TPM And PIN And Startup Key",
+        7 => "Public Key",
+        8 => "Passphrase",
+        9 => "TPM Certificate",
+        10 => "Active Directory Account",
+        _ => "Unknown"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::offline::OfflineSource;
+
+    const DEVICE_ID: &str = "\\\\?\\Volume{1b2c3d4e-0000-0000-0000-100000000000}\\";
+
+    fn volume_row(conversion_status: u32, encryption_method: u32) -> WmiRow {
+        WmiRow::new()
+            .with("DeviceID", WmiValue::String(DEVICE_ID.to_string()))
+            .with("DriveLetter", WmiValue::String("C:".to_string()))
+            .with("ProtectionStatus", WmiValue::U32(1))
+            .with("ConversionStatus", WmiValue::U32(conversion_status))
+            .with("EncryptionMethod", WmiValue::U32(encryption_method))
+            .with("VolumeType", WmiValue::U32(VOLUME_OS))
+    }
+
+    fn returned(row: WmiRow) -> WmiRow {
+        row.with("ReturnValue", WmiValue::U32(0))
+    }
+
+    #[test]
+    fn escapes_object_paths() {
+        assert_eq!(object_path("\\\\?\\Volume{a}\\"), "Win32_EncryptableVolume.DeviceID=\"\\\\\\\\?\\\\Volume{a}\\\\\"");
+    }
+
+    #[test]
+    fn reads_volumes_and_calls_methods() {
+        let path = object_path(DEVICE_ID);
+        let source = FixtureSource::new()
+            .with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume_row(2, 6)])
+            .with_wmi_method(BITLOCKER_NAMESPACE, &path, "GetConversionStatus", &[], returned(WmiRow::new()
+                .with("ConversionStatus", WmiValue::U32(2))
+                .with("EncryptionPercentage", WmiValue::U32(42))))
+            .with_wmi_method(BITLOCKER_NAMESPACE, &path, "GetKeyProtectors", &[("KeyProtectorType", WmiValue::U32(0))], returned(WmiRow::new()
+                .with("VolumeKeyProtectorID", WmiValue::Array(vec![WmiValue::String("{tpm}".to_string()), WmiValue::String("{password}".to_string())]))))
+            .with_wmi_method(BITLOCKER_NAMESPACE, &path, "GetKeyProtectorType", &[("VolumeKeyProtectorID", WmiValue::String("{tpm}".to_string()))], returned(WmiRow::new()
+                .with("KeyProtectorType", WmiValue::U32(1))))
+            .with_wmi_method(BITLOCKER_NAMESPACE, &path, "GetKeyProtectorType", &[("VolumeKeyProtectorID", WmiValue::String("{password}".to_string()))], returned(WmiRow::new()
+                .with("KeyProtectorType", WmiValue::U32(3))));
+
+        let info = scan_bitlocker_with(&source).unwrap();
+        let volume = &info.volumes.as_ref().unwrap()[0];
+        assert_eq!(volume.encryption_percentage, Some(42));
+        assert_eq!(volume.key_protectors, Some(vec![1, 3]));
+        assert!(volume.is_os_volume() && volume.is_encrypted() == Some(true) && volume.weak_method() && volume.tpm_only());
+        assert_eq!(volume.has_recovery_password(), Some(true));
+        assert!(info.module_info.unreadable.is_empty());
+    }
+
+    #[test]
+    fn failed_method_calls_are_unreadable() {
+        let source = FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume_row(1, 7)]);
+
+        let info = scan_bitlocker_with(&source).unwrap();
+        let volume = &info.volumes.as_ref().unwrap()[0];
+        assert_eq!(volume.encryption_percentage, None);
+        assert_eq!(volume.key_protectors, None);
+        assert!(!volume.tpm_only());
+        assert!(info.module_info.unreadable.contains_key("C:.GetKeyProtectors"));
+    }
+
+    #[test]
+    fn mistyped_conversion_status_is_unreadable() {
+        let path = object_path(DEVICE_ID);
+        let source = FixtureSource::new()
+            .with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume_row(1, 7)])
+            .with_wmi_method(BITLOCKER_NAMESPACE, &path, "GetConversionStatus", &[], returned(WmiRow::new()
+                .with("EncryptionPercentage", WmiValue::String("100".to_string()))));
+
+        let info = scan_bitlocker_with(&source).unwrap();
+        let volume = &info.volumes.as_ref().unwrap()[0];
+        assert_eq!((volume.conversion_status, volume.encryption_percentage), (Some(1), None));
+        assert!(info.module_info.unreadable.contains_key("C:.GetConversionStatus"));
+    }
+
+    #[test]
+    fn unreported_conversion_status_is_unreadable() {
+        let row = WmiRow::new()
+            .with("DeviceID", WmiValue::String(DEVICE_ID.to_string()))
+            .with("DriveLetter", WmiValue::String("C:".to_string()))
+            .with("VolumeType", WmiValue::U32(VOLUME_OS));
+        let source = FixtureSource::new()
+            .with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![row])
+            .with_wmi_method(BITLOCKER_NAMESPACE, &object_path(DEVICE_ID), "GetConversionStatus", &[], returned(WmiRow::new()));
+
+        let info = scan_bitlocker_with(&source).unwrap();
+        assert_eq!(info.volumes.as_ref().unwrap()[0].is_encrypted(), None);
+        assert!(info.module_info.unreadable.contains_key("C:.ConversionStatus"));
+        assert!(!info.module_info.unreadable.contains_key("C:.GetConversionStatus"));
+    }
+
+    #[test]
+    fn missing_namespace_is_unavailable() {
+        let info = scan_bitlocker_with(&FixtureSource::new()).unwrap();
+        assert_eq!(info.available, Some(false));
+        assert!(info.volumes.is_none());
+        assert!(info.module_info.unreadable.is_empty());
+    }
+
+    #[test]
+    fn unreadable_namespace_fails_the_scan() {
+        let error = scan_bitlocker_with(&OfflineSource::new()).err().unwrap();
+        assert_eq!(error.kind(), "unavailable");
+        assert_eq!(error.context().module.as_deref(), Some("bitlocker"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Assessment for the Browser Module, turns browser versions, Safe Browsing, the password manager, and extension policy into findings.
+use super::scanner::{BrowserInfo, BrowserStatus};
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "browser";
+
+/// Assessing browser security basics
+pub fn assess_browsers(info: &BrowserInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for browser in info.browsers.iter() {
+        if browser.outdated() == Some(true) {
+            findings.push(
+                Finding::new("BRW-001", MODULE, Severity::High, format!("{} is out of date", browser.kind))
+                    .explanation("Browsers are the program most exposed to the internet, and every release fixes security holes that are already public. An old version can be exploited just by visiting a page")
+                    .remediation(format!("Open {} and let it update from its About page, or check why automatic updates aren't running", browser.kind))
+                    .evidence(format!("version={}", version(browser)))
+                    .evidence(format!("minimum={}", browser.minimum_version.as_deref().unwrap_or("unknown")))
+                    .evidence(format!("table_updated={}", info.versions_updated))
+            );
+        }
+
+        let disabled = browser.safe_browsing_disabled();
+        if !disabled.is_empty() {
+            let by_policy = browser.policies.safe_browsing == Some(false);
+            findings.push(
+                Finding::new("BRW-002", MODULE, if by_policy {Severity::High} else {Severity::Medium}, format!("{} {} is turned off", browser.kind, browser.kind.safe_browsing_name()))
+                    .explanation(format!(
+                        "{} warns before opening known phishing and malware sites and downloads. Without it the browser opens them without a word",
+                        browser.kind.safe_browsing_name()
+                    ))
+                    .remediation(if by_policy {
+                        "Remove the policy turning it off, or set it to 1 under HKLM\\SOFTWARE\\Policies"
+                    } else {
+                        "Turn it back on in the browser's privacy and security settings, or enforce it with Group Policy"
+                    })
+                    .evidence(format!("disabled_in={}", disabled.join(",")))
+            );
+        }
+
+        if browser.installation.is_some() && browser.password_manager_enabled() && browser.policies.password_manager.is_none() {
+            findings.push(
+                Finding::new("BRW-003", MODULE, Severity::Low, format!("The {} password manager isn't managed", browser.kind))
+                    .explanation("Saved browser passwords are one of the first things infostealer malware takes, since anything running as the user can decrypt them. Organisations with a dedicated password manager usually turn the browser's off")
+                    .remediation("Set PasswordManagerEnabled to 0 in the browser's Group Policy if another password manager is used")
+                    .evidence(format!("profiles={}", browser.profiles.len()))
+            );
+        }
+
+        if browser.installation.is_some() && !browser.extensions_restricted() {
+            findings.push(
+                Finding::new("BRW-004", MODULE, Severity::Low, format!("{} allows any extension to be installed", browser.kind))
+                    .explanation("Extensions can read and change every page the user visits. Without an allow-list, one malicious or sold-off extension can take session cookies and passwords")
+                    .remediation("Block every extension with `*` in ExtensionInstallBlocklist (ExtensionSettings for Firefox) and list the approved ones in ExtensionInstallAllowlist")
+                    .evidence(format!("allowlist={}", browser.policies.extension_allowlist.len()))
+                    .evidence(format!("blocklist={}", browser.policies.extension_blocklist.join(",")))
+            );
+        }
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        findings.push(
+            Finding::new("BRW-005", MODULE, Severity::Info, "Some browser settings couldn't be read")
+                .explanation("Shugo couldn't read every browser file or policy, so an outdated browser or a turned off setting may be missing")
+                .remediation("Run Shugo on a live machine as the user whose browsers you're checking, or include NTUSER.DAT when scanning offline")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+    }
+
+    findings
+}
+
+fn version(browser: &BrowserStatus) -> &str {
+    browser.installation.as_ref().map(|installation| installation.version.as_str()).unwrap_or("unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::scanner::{BrowserKind, BrowserPolicies, Installation, ModuleInfo, ProfileSettings};
+
+    fn info(browsers: Vec<BrowserStatus>) -> BrowserInfo {
+        BrowserInfo {
+            browsers,
+            files_checked: true,
+            versions_updated: "2026-10-01".to_string(),
+            module_info: ModuleInfo { registry_keys: vec![], queries: vec![], unreadable: Default::default() }
+        }
+    }
+
+    fn chrome(version: &str, policies: BrowserPolicies, profiles: Vec<ProfileSettings>) -> BrowserStatus {
+        BrowserStatus {
+            kind: BrowserKind::Chrome,
+            installation: Some(Installation { version: version.to_string(), location: "C:\\Program Files\\Google\\Chrome\\Application".to_string(), channel: None }),
+            minimum_version: Some("150.0.0.0".to_string()),
+            profiles,
+            policies
+        }
+    }
+
+    fn managed() -> BrowserPolicies {
+        BrowserPolicies { safe_browsing: Some(true), password_manager: Some(false), extension_allowlist: vec![], extension_blocklist: vec!["*".to_string()] }
+    }
+
+    fn ids(info: &BrowserInfo) -> Vec<String> {
+        assess_browsers(info).into_iter().map(|finding| finding.id).collect()
+    }
+
+    #[test]
+    fn managed_current_browser_has_no_findings() {
+        assert!(ids(&info(vec![chrome("151.0.1.0", managed(), vec![])])).is_empty());
+    }
+
+    #[test]
+    fn flags_outdated_unmanaged_browsers() {
+        let profile = ProfileSettings { name: "Default".to_string(), safe_browsing: Some(false), password_manager: None };
+        let findings = assess_browsers(&info(vec![chrome("140.0.1.0", BrowserPolicies::default(), vec![profile])]));
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["BRW-001", "BRW-002", "BRW-003", "BRW-004"]);
+        assert_eq!(findings[1].severity, Severity::Medium);
+    }
+
+    #[test]
+    fn policy_turning_safe_browsing_off_is_high() {
+        let policies = BrowserPolicies { safe_browsing: Some(false), ..managed() };
+        let findings = assess_browsers(&info(vec![chrome("151.0.1.0", policies, vec![])]));
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("BRW-002", Severity::High));
+    }
+}
@@ -0,0 +1,105 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_browsers;
+use super::scanner::BrowserInfo;
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::get_time;
+
+/// Display for Browser Module
+pub fn display_browsers(info: &BrowserInfo, verbose: bool) {
+    println!();
+    println!("BROWSER SECURITY STATUS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_browser_list(info, verbose);
+
+    display_findings(&assess_browsers(info), verbose);
+
+    if verbose {display_technical(info);}
+}
+
+/// JSON for Browser Module
+pub fn json_browsers(info: &BrowserInfo) -> Value {
+    let browsers: Vec<Value> = info.browsers.iter().filter(|browser| browser.present()).map(|browser| json!({
+        "browser": browser.kind,
+        "version": browser.installation.as_ref().map(|installation| &installation.version),
+        "outdated": browser.outdated(),
+        "safe_browsing_disabled_in": browser.safe_browsing_disabled(),
+        "password_manager_enabled": browser.password_manager_enabled(),
+        "extensions_restricted": browser.extensions_restricted()
+    })).collect();
+    let decoded = json!({ "browsers": browsers });
+
+    json!({
+        "browser": with_decoded(info, decoded),
+        "findings": assess_browsers(info)
+    })
+}
+
+fn display_scan_details(info: &BrowserInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Registry Keys:");
+    for key in info.module_info.registry_keys.iter() {
+        println!("   - {}", key);
+    }
+    println!(" - Query:");
+    for query in info.module_info.queries.iter() {
+        println!("   - {}", query);
+    }
+    for (value, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", value, reason);
+    }
+    println!();
+}
+
+fn display_browser_list(info: &BrowserInfo, verbose: bool) {
+    println!("Browsers:");
+    let present: Vec<_> = info.browsers.iter().filter(|browser| browser.present()).collect();
+    if present.is_empty() {
+        println!(" - {}", if info.files_checked {"No supported browsers found"} else {"No browser policies set"});
+    }
+    for browser in present {
+        let version = match (&browser.installation, browser.outdated()) {
+            (Some(installation), Some(true)) => format!("{} (out of date)", installation.version),
+            (Some(installation), _) => installation.version.clone(),
+            (None, _) => "Not installed".to_string()
+        };
+        println!(" - {}: {}", browser.kind, version);
+        let disabled = browser.safe_browsing_disabled();
+        println!("   - {}: {}", browser.kind.safe_browsing_name(), if disabled.is_empty() {"On".to_string()} else {format!("Off ({})", disabled.join(", "))});
+        println!("   - Password Manager: {}", if browser.password_manager_enabled() {"On"} else {"Off"});
+        println!("   - Extension Installs: {}", if browser.extensions_restricted() {"Allow-list only"} else {"Any"});
+        if verbose {
+            if let Some(installation) = &browser.installation {
+                println!("     - Location: {}", installation.location);
+                if let Some(channel) = &installation.channel {
+                    println!("     - Channel: {}", channel);
+                }
+            }
+            if let Some(minimum) = &browser.minimum_version {
+                println!("     - Minimum Version: {}", minimum);
+            }
+            for profile in browser.profiles.iter() {
+                println!("     - Profile: {}", profile.name);
+            }
+            for id in browser.policies.extension_allowlist.iter() {
+                println!("     - Allowed Extension: {}", id);
+            }
+        }
+    }
+    println!();
+}
+
+fn display_technical(info: &BrowserInfo) {
+    println!("Technical Information:");
+    println!(" - Access Rights: KEY_READ");
+    println!(" - Registry Hives: HKEY_LOCAL_MACHINE, HKEY_CURRENT_USER");
+    println!(" - Files: Program Files, %LOCALAPPDATA%, %APPDATA% (live scans only)");
+    println!(" - Version Table: browser/versions.toml, updated {} (or --versions)", info.versions_updated);
+    println!();
+}
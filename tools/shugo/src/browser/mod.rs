@@ -0,0 +1,5 @@
+pub mod scanner;
+pub mod versions;
+pub mod prefs;
+pub mod assessment;
+pub mod display;
@@ -0,0 +1,311 @@
+//! Parsers for the files browsers keep their settings in: Chromium's `Preferences` (JSON), Firefox's
+//! `prefs.js`, and Firefox's `application.ini`. They only take text, so they're tested with sample
+//! profiles on any OS.
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::scanner::ProfileSettings;
+use crate::common::error::ShugoError;
+use crate::common::source::SourceResult;
+
+/// One value from `prefs.js`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefValue {
+    Bool(bool),
+    Integer(i64),
+    String(String)
+}
+
+impl PrefValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PrefValue::Bool(flag) => Some(*flag),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PrefValue::String(text) => Some(text),
+            _ => None
+        }
+    }
+}
+
+/// Parses a Chromium `Preferences` file, `what` names the file in errors
+pub fn parse_json(text: &str, what: &str) -> SourceResult<Value> {
+    serde_json::from_str(text).map_err(|error| ShugoError::parse(what, error.to_string()))
+}
+
+/// A boolean at a dotted path like `safebrowsing.enabled`, `None` when it isn't there
+pub fn json_bool(preferences: &Value, path: &str) -> Option<bool> {
+    preferences.pointer(&format!("/{}", path.replace('.', "/")))?.as_bool()
+}
+
+/// The settings Shugo checks from one Chromium profile (Chrome, Edge, Brave)
+pub fn chromium_profile(name: &str, text: &str) -> SourceResult<ProfileSettings> {
+    /*
+        Shugo: Chromium Preferences
+
+        Chrome, Edge, and Brave keep each profile's settings in a JSON file called `Preferences`, in the profile
+        folder under `User Data` (`Default`, `Profile 1`, ...). A setting is only written once it's changed from
+        its default, so a missing value means the default:
+
+        - safebrowsing.enabled: Safe Browsing checks every page and download against Google's list of known
+          malicious sites. Defaults to true
+        - safebrowsing.enhanced: the stronger "Enhanced protection" mode
+        - credentials_enable_service: whether the browser offers to save passwords. Defaults to true
+
+        Group Policy (under HKLM\SOFTWARE\Policies) always wins over these, so they only matter when no policy is set.
+    */
+    let preferences = parse_json(text, name)?;
+    Ok(ProfileSettings {
+        name: name.to_string(),
+        safe_browsing: json_bool(&preferences, "safebrowsing.enabled"),
+        password_manager: json_bool(&preferences, "credentials_enable_service")
+    })
+}
+
+/// The settings Shugo checks from one Firefox profile
+pub fn firefox_profile(name: &str, text: &str) -> SourceResult<ProfileSettings> {
+    /*
+        Shugo: Firefox prefs.js
+
+        Firefox writes every setting changed from its default into `prefs.js` in the profile folder, one
+        `user_pref("name", value);` call per line. The ones we care about:
+
+        - browser.safebrowsing.malware.enabled / browser.safebrowsing.phishing.enabled: Firefox's version of
+          Safe Browsing, both default to true
+        - signon.rememberSignons: whether Firefox offers to save passwords, defaults to true
+
+        For more information on prefs.js:
+        (https://support.mozilla.org/en-US/kb/profiles-where-firefox-stores-user-data)
+    */
+    let prefs = parse_prefs_js(text).map_err(|error| error.for_query(name))?;
+    let flag = |pref: &str| prefs.get(pref).and_then(PrefValue::as_bool);
+    let safe_browsing = match (flag("browser.safebrowsing.malware.enabled"), flag("browser.safebrowsing.phishing.enabled")) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None
+    };
+    Ok(ProfileSettings {
+        name: name.to_string(),
+        safe_browsing,
+        password_manager: flag("signon.rememberSignons")
+    })
+}
+
+/// Reads every `user_pref(...)`, `pref(...)`, and `sticky_pref(...)` call from a Firefox prefs file
+pub fn parse_prefs_js(text: &str) -> SourceResult<BTreeMap<String, PrefValue>> {
+    let mut parser = PrefsParser { chars: text.chars().collect(), position: 0 };
+    let mut prefs = BTreeMap::new();
+    loop {
+        parser.skip_blank();
+        if parser.position >= parser.chars.len() {
+            break;
+        }
+        let function = parser.word();
+        if !matches!(function.as_str(), "user_pref" | "pref" | "sticky_pref") {
+            return Err(parser.error(format!("expected user_pref, found {:?}", function)));
+        }
+        parser.expect('(')?;
+        let name = parser.string()?;
+        parser.expect(',')?;
+        let value = parser.value()?;
+        parser.expect(')')?;
+        parser.expect(';')?;
+        prefs.insert(name, value);
+    }
+    Ok(prefs)
+}
+
+/// The `Version` from the `[App]` section of Firefox's `application.ini`
+pub fn application_ini_version(text: &str) -> Option<String> {
+    let mut in_app = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_app = line.eq_ignore_ascii_case("[App]");
+        } else if in_app && let Some((key, value)) = line.split_once('=') && key.trim() == "Version" {
+            return Some(value.trim().to_string());
+        }
+    }
+    None
+}
+
+/// The extension IDs Firefox's `ExtensionSettings` policy allows and blocks, `*` stands for every extension
+pub fn firefox_extension_settings(text: &str) -> SourceResult<(Vec<String>, Vec<String>)> {
+    let settings = parse_json(text, "ExtensionSettings")?;
+    let Some(settings) = settings.as_object() else {
+        return Err(ShugoError::parse("ExtensionSettings", "expected a JSON object"));
+    };
+    let mut allowed = Vec::new();
+    let mut blocked = Vec::new();
+    for (id, setting) in settings.iter() {
+        match setting.get("installation_mode").and_then(Value::as_str) {
+            Some("blocked") => blocked.push(id.clone()),
+            Some("allowed" | "force_installed" | "normal_installed") if id != "*" => allowed.push(id.clone()),
+            _ => {}
+        }
+    }
+    Ok((allowed, blocked))
+}
+
+/// A small reader for the JavaScript subset prefs.js is written in
+struct PrefsParser {
+    chars: Vec<char>,
+    position: usize
+}
+
+impl PrefsParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, reason: String) -> ShugoError {
+        let line = self.chars[..self.position.min(self.chars.len())].iter().filter(|c| **c == '\n').count() + 1;
+        ShugoError::parse("prefs.js", format!("line {}: {}", line, reason))
+    }
+
+    /// Skips whitespace and `//`, `#`, and `/* */` comments
+    fn skip_blank(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.position += 1;
+            } else if c == '#' || (c == '/' && self.chars.get(self.position + 1) == Some(&'/')) {
+                while self.peek().is_some_and(|c| c != '\n') {self.position += 1;}
+            } else if c == '/' && self.chars.get(self.position + 1) == Some(&'*') {
+                self.position += 2;
+                while self.position < self.chars.len() && !(self.chars[self.position] == '*' && self.chars.get(self.position + 1) == Some(&'/')) {
+                    self.position += 1;
+                }
+                self.position = (self.position + 2).min(self.chars.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {self.position += 1;}
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn expect(&mut self, expected: char) -> SourceResult<()> {
+        self.skip_blank();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            },
+            found => Err(self.error(format!("expected {:?}, found {:?}", expected, found)))
+        }
+    }
+
+    fn string(&mut self) -> SourceResult<String> {
+        self.skip_blank();
+        let Some(quote) = self.peek().filter(|c| *c == '"' || *c == '\'') else {
+            return Err(self.error("expected a string".to_string()));
+        };
+        self.position += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string".to_string())),
+                Some(c) if c == quote => {
+                    self.position += 1;
+                    return Ok(text);
+                },
+                Some('\\') => {
+                    self.position += 1;
+                    match self.peek() {
+                        Some('n') => text.push('\n'),
+                        Some('t') => text.push('\t'),
+                        Some(c) => text.push(c),
+                        None => return Err(self.error("unterminated string".to_string()))
+                    }
+                    self.position += 1;
+                },
+                Some(c) => {
+                    text.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    fn value(&mut self) -> SourceResult<PrefValue> {
+        self.skip_blank();
+        match self.peek() {
+            Some('"' | '\'') => Ok(PrefValue::String(self.string()?)),
+            _ => {
+                let word = self.word();
+                match word.as_str() {
+                    "true" => Ok(PrefValue::Bool(true)),
+                    "false" => Ok(PrefValue::Bool(false)),
+                    number => number.parse::<i64>().map(PrefValue::Integer).map_err(|_| self.error(format!("{:?} is not a pref value", number)))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREFS_JS: &str = r#"// Mozilla User Preferences
+
+// DO NOT EDIT THIS FILE.
+/* Comments like this one
+   show up in older profiles */
+user_pref("app.update.lastUpdateTime.addon-background-update-timer", 1760000000);
+user_pref("browser.safebrowsing.malware.enabled", false);
+user_pref("browser.startup.homepage", "https://example.com/?q=\"shugo\"");
+user_pref("signon.rememberSignons", true);
+"#;
+
+    #[test]
+    fn parses_prefs_js() {
+        let prefs = parse_prefs_js(PREFS_JS).unwrap();
+        assert_eq!(prefs["app.update.lastUpdateTime.addon-background-update-timer"], PrefValue::Integer(1760000000));
+        assert_eq!(prefs["browser.startup.homepage"], PrefValue::String("https://example.com/?q=\"shugo\"".to_string()));
+
+        let profile = firefox_profile("abcd1234.default-release", PREFS_JS).unwrap();
+        assert_eq!(profile.safe_browsing, Some(false));
+        assert_eq!(profile.password_manager, Some(true));
+    }
+
+    #[test]
+    fn reports_the_line_of_bad_prefs() {
+        let error = parse_prefs_js("user_pref(\"a\", true);\nuser_pref(\"b\" true);").unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{}", error);
+        assert!(parse_prefs_js("user_pref(\"a\", maybe);").is_err());
+    }
+
+    #[test]
+    fn reads_chromium_preferences() {
+        let text = r#"{"credentials_enable_service": false, "safebrowsing": {"enabled": true, "enhanced": false}, "profile": {"name": "Work"}}"#;
+        let profile = chromium_profile("Default", text).unwrap();
+        assert_eq!(profile.safe_browsing, Some(true));
+        assert_eq!(profile.password_manager, Some(false));
+
+        // Nothing changed from the defaults
+        let profile = chromium_profile("Profile 1", "{}").unwrap();
+        assert_eq!((profile.safe_browsing, profile.password_manager), (None, None));
+        assert!(chromium_profile("Profile 2", "{\"safebrowsing\":").is_err());
+    }
+
+    #[test]
+    fn reads_firefox_metadata() {
+        let ini = "[App]\nVendor=Mozilla\nName=Firefox\nVersion=150.0.1\nBuildID=20261001000000\n\n[Gecko]\nVersion=150.0.1\n";
+        assert_eq!(application_ini_version(ini), Some("150.0.1".to_string()));
+        assert_eq!(application_ini_version("[Gecko]\nVersion=1.0\n"), None);
+
+        let settings = r#"{"*": {"installation_mode": "blocked"}, "uBlock0@raymondhill.net": {"installation_mode": "force_installed"}}"#;
+        let (allowed, blocked) = firefox_extension_settings(settings).unwrap();
+        assert_eq!(allowed, ["uBlock0@raymondhill.net"]);
+        assert_eq!(blocked, ["*"]);
+    }
+}
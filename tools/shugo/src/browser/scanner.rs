@@ -0,0 +1,560 @@
+//! This is the Browser Module for Shugo. Here we can see:
+//!
+//! - Installed Browsers (Edge, Chrome, Firefox, Brave) and their Versions
+//! - Whether each Version is still getting Security Fixes
+//! - SmartScreen / Safe Browsing and the Password Manager, from Policy and each Profile
+//! - Extension Install Allow and Block Lists
+//!
+//! Versions and profile settings come from files on disk, and policies from the registry. An offline scan
+//! only has the registry, so it checks the policies and skips the files.
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::prefs::{chromium_profile, firefox_profile, parse_prefs_js, application_ini_version, firefox_extension_settings, PrefValue};
+use super::versions::{VersionTable, parse_version, compare_versions, is_older};
+use crate::common::error::ShugoError;
+use crate::common::path::join;
+use crate::common::source::{SecuritySource, SourceResult, RegistryHive, RegistryValue, system_source};
+
+/// The browsers Shugo knows how to find
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrowserKind {
+    Edge,
+    Chrome,
+    Firefox,
+    Brave
+}
+
+/// Where to look for browser files, every path is optional so tests can point at a sample folder
+#[derive(Debug, Clone, Default)]
+pub struct BrowserPaths {
+    /// %ProgramFiles% and %ProgramFiles(x86)%
+    pub program_files: Vec<PathBuf>,
+    /// %LOCALAPPDATA%, for per-user installs and Chromium profiles
+    pub local_app_data: Option<PathBuf>,
+    /// %APPDATA%, for Firefox profiles
+    pub app_data: Option<PathBuf>
+}
+
+/// Where a browser is installed and which version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Installation {
+    pub version: String,
+    pub location: String,
+    /// The update channel when the browser says, like "esr" for Firefox ESR
+    pub channel: Option<String>
+}
+
+/// Settings from one browser profile, each `None` when it's left at the default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileSettings {
+    pub name: String,
+    pub safe_browsing: Option<bool>,
+    pub password_manager: Option<bool>
+}
+
+/// Enterprise policies for one browser, each `None` when no policy is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BrowserPolicies {
+    /// SmartScreenEnabled for Edge, SafeBrowsingProtectionLevel or SafeBrowsingEnabled for Chrome and Brave
+    pub safe_browsing: Option<bool>,
+    pub password_manager: Option<bool>,
+    pub extension_allowlist: Vec<String>,
+    /// `*` blocks every extension that isn't on the allowlist
+    pub extension_blocklist: Vec<String>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrowserStatus {
+    pub kind: BrowserKind,
+    /// `None` when the browser isn't installed or files weren't checked
+    pub installation: Option<Installation>,
+    /// The oldest supported version from the version table
+    pub minimum_version: Option<String>,
+    pub profiles: Vec<ProfileSettings>,
+    pub policies: BrowserPolicies
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BrowserInfo {
+    pub browsers: Vec<BrowserStatus>,
+    /// Whether browser files were read, false for offline scans
+    pub files_checked: bool,
+    /// The `updated` date of the version table used
+    pub versions_updated: String,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub registry_keys: Vec<String>,
+    pub queries: Vec<String>,
+    /// Keys, values, and files that couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+impl BrowserKind {
+    pub const ALL: [BrowserKind; 4] = [BrowserKind::Edge, BrowserKind::Chrome, BrowserKind::Firefox, BrowserKind::Brave];
+
+    pub fn is_chromium(&self) -> bool {
+        *self != BrowserKind::Firefox
+    }
+
+    /// The install folder under Program Files (or %LOCALAPPDATA% for per-user installs)
+    pub fn install_folder(&self) -> &'static str {
+        match self {
+            BrowserKind::Edge => "Microsoft\\Edge\\Application",
+            BrowserKind::Chrome => "Google\\Chrome\\Application",
+            BrowserKind::Firefox => "Mozilla Firefox",
+            BrowserKind::Brave => "BraveSoftware\\Brave-Browser\\Application"
+        }
+    }
+
+    /// The folder holding every profile, under %LOCALAPPDATA% for Chromium and %APPDATA% for Firefox
+    pub fn profiles_folder(&self) -> &'static str {
+        match self {
+            BrowserKind::Edge => "Microsoft\\Edge\\User Data",
+            BrowserKind::Chrome => "Google\\Chrome\\User Data",
+            BrowserKind::Firefox => "Mozilla\\Firefox\\Profiles",
+            BrowserKind::Brave => "BraveSoftware\\Brave-Browser\\User Data"
+        }
+    }
+
+    pub fn policy_key(&self) -> &'static str {
+        match self {
+            BrowserKind::Edge => "SOFTWARE\\Policies\\Microsoft\\Edge",
+            BrowserKind::Chrome => "SOFTWARE\\Policies\\Google\\Chrome",
+            BrowserKind::Firefox => "SOFTWARE\\Policies\\Mozilla\\Firefox",
+            BrowserKind::Brave => "SOFTWARE\\Policies\\BraveSoftware\\Brave"
+        }
+    }
+
+    /// What the browser calls its malicious site filter
+    pub fn safe_browsing_name(&self) -> &'static str {
+        if *self == BrowserKind::Edge {"SmartScreen"} else {"Safe Browsing"}
+    }
+}
+
+impl fmt::Display for BrowserKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BrowserKind::Edge => "Microsoft Edge",
+            BrowserKind::Chrome => "Google Chrome",
+            BrowserKind::Firefox => "Mozilla Firefox",
+            BrowserKind::Brave => "Brave"
+        })
+    }
+}
+
+impl BrowserPaths {
+    /// The folders for the user Shugo is running as
+    pub fn from_env() -> BrowserPaths {
+        let folder = |name: &str| env::var_os(name).map(PathBuf::from);
+        let mut program_files: Vec<PathBuf> = ["ProgramFiles", "ProgramFiles(x86)"].into_iter().filter_map(folder).collect();
+        program_files.dedup();
+        BrowserPaths {
+            program_files,
+            local_app_data: folder("LOCALAPPDATA"),
+            app_data: folder("APPDATA")
+        }
+    }
+}
+
+/// Scanning browsers on this machine against the bundled version table
+pub fn scan_browsers() -> SourceResult<BrowserInfo> {
+    scan_browsers_with(system_source()?.as_ref(), Some(&BrowserPaths::from_env()), &VersionTable::bundled()?)
+}
+
+/// Scanning browser policies from any source, and browser files from `paths` when given
+pub fn scan_browsers_with(source: &dyn SecuritySource, paths: Option<&BrowserPaths>, table: &VersionTable) -> SourceResult<BrowserInfo> {
+    let mut queries: Vec<String> = Vec::new();
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+
+    let mut browsers = Vec::with_capacity(BrowserKind::ALL.len());
+    for kind in BrowserKind::ALL {
+        let (installation, profiles) = match paths {
+            Some(paths) => (find_installation(kind, paths, &mut unreadable), read_profiles(kind, paths, &mut unreadable)),
+            None => (None, Vec::new())
+        };
+        let policies = read_policies(source, kind, &mut queries, &mut unreadable)?;
+        let channel = installation.as_ref().and_then(|installation| installation.channel.as_deref());
+        browsers.push(BrowserStatus {
+            kind,
+            minimum_version: table.minimum(kind, channel).map(str::to_string),
+            installation,
+            profiles,
+            policies
+        });
+    }
+
+    if paths.is_none() {
+        unreadable.insert("Browser files".to_string(), "installed versions and profiles are only checked on a live machine".to_string());
+    }
+
+    Ok(BrowserInfo {
+        browsers,
+        files_checked: paths.is_some(),
+        versions_updated: table.updated.clone(),
+        module_info: ModuleInfo {
+            registry_keys: BrowserKind::ALL.iter().map(|kind| kind.policy_key().to_string()).collect(),
+            queries,
+            unreadable
+        }
+    })
+}
+
+/// Reads a text file, `None` when it doesn't exist. Other failures are recorded as unreadable
+pub(crate) fn read_file(path: &Path, unreadable: &mut BTreeMap<String, String>) -> Option<String> {
+    match fs::read(path) {
+        // Browsers write UTF-8, but a stray bad byte shouldn't hide the whole file
+        Ok(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => {
+            unreadable.insert(path.display().to_string(), ShugoError::io(path.display().to_string(), &error).detail());
+            None
+        }
+    }
+}
+
+/// The folders directly inside `path`, empty when it doesn't exist
+pub(crate) fn folders(path: &Path, unreadable: &mut BTreeMap<String, String>) -> Vec<(String, PathBuf)> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            unreadable.insert(path.display().to_string(), ShugoError::io(path.display().to_string(), &error).detail());
+            return Vec::new();
+        }
+    };
+    let mut folders: Vec<(String, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| (entry.file_name().to_string_lossy().into_owned(), entry.path()))
+        .collect();
+    folders.sort();
+    folders
+}
+
+fn find_installation(kind: BrowserKind, paths: &BrowserPaths, unreadable: &mut BTreeMap<String, String>) -> Option<Installation> {
+    /*
+        Shugo: Finding Browser Versions On Disk
+
+        Every browser leaves its version somewhere in its install folder, no need to run it:
+
+        Chrome, Edge, and Brave: the `Application` folder holds a subfolder named after each version, like
+        `Application\150.0.7390.65\`. An old version's folder can stay behind until the browser restarts after
+        an update, so the newest one is the version that runs next.
+        Firefox: `application.ini` has `Version=` under `[App]`, and `defaults\pref\channel-prefs.js` sets
+        `app.update.channel` to "release" or "esr".
+
+        Machine-wide installs are under Program Files, per-user installs of Chrome and Brave are under %LOCALAPPDATA%.
+    */
+    let roots = paths.program_files.iter().chain(paths.local_app_data.iter());
+    for root in roots {
+        let folder = join(root, kind.install_folder());
+        if kind.is_chromium() {
+            let newest = folders(&folder, unreadable)
+                .into_iter()
+                .filter_map(|(name, _)| parse_version(&name).map(|parts| (parts, name)))
+                .max_by(|(left, _), (right, _)| compare_versions(left, right));
+            if let Some((_, version)) = newest {
+                return Some(Installation { version, location: folder.display().to_string(), channel: None });
+            }
+        } else if let Some(version) = read_file(&folder.join("application.ini"), unreadable).as_deref().and_then(application_ini_version) {
+            let channel = read_file(&join(&folder, "defaults\\pref\\channel-prefs.js"), unreadable)
+                .and_then(|text| parse_prefs_js(&text).ok())
+                .and_then(|prefs| prefs.get("app.update.channel").and_then(PrefValue::as_str).map(str::to_string));
+            return Some(Installation { version, location: folder.display().to_string(), channel });
+        }
+    }
+    None
+}
+
+/// Every profile folder of one browser, with its folder name
+pub fn profile_folders(kind: BrowserKind, paths: &BrowserPaths, unreadable: &mut BTreeMap<String, String>) -> Vec<(String, PathBuf)> {
+    let root = if kind.is_chromium() {&paths.local_app_data} else {&paths.app_data};
+    let Some(root) = root else {return Vec::new()};
+    folders(&join(root, kind.profiles_folder()), unreadable)
+        .into_iter()
+        // Chromium keeps other folders (like `System Profile` and `Crashpad`) next to the real profiles
+        .filter(|(name, _)| !kind.is_chromium() || name == "Default" || name.starts_with("Profile "))
+        .collect()
+}
+
+fn read_profiles(kind: BrowserKind, paths: &BrowserPaths, unreadable: &mut BTreeMap<String, String>) -> Vec<ProfileSettings> {
+    let mut profiles = Vec::new();
+    for (name, folder) in profile_folders(kind, paths, unreadable) {
+        let file = folder.join(if kind.is_chromium() {"Preferences"} else {"prefs.js"});
+        let Some(text) = read_file(&file, unreadable) else {continue};
+        let parsed = if kind.is_chromium() {chromium_profile(&name, &text)} else {firefox_profile(&name, &text)};
+        match parsed {
+            Ok(mut profile) => {
+                // Edge's SmartScreen isn't the Chromium Safe Browsing preference, so only its policy says anything
+                if kind == BrowserKind::Edge {profile.safe_browsing = None;}
+                profiles.push(profile);
+            },
+            Err(error) => {unreadable.insert(file.display().to_string(), error.detail());}
+        }
+    }
+    profiles
+}
+
+fn read_policies(source: &dyn SecuritySource, kind: BrowserKind, queries: &mut Vec<String>, unreadable: &mut BTreeMap<String, String>) -> SourceResult<BrowserPolicies> {
+    /*
+        Shugo: Browser Group Policy
+
+        Every browser here reads enterprise policy from the registry, machine policy first (HKLM) and then user
+        policy (HKCU), under its own key in SOFTWARE\Policies. A policy always beats what the user picked in settings.
+
+        - SmartScreenEnabled (Edge) / SafeBrowsingProtectionLevel (Chrome, Brave; 0 = off, 1 = standard, 2 = enhanced)
+        - PasswordManagerEnabled: 0 stops the browser offering to save passwords
+        - ExtensionInstallAllowlist / ExtensionInstallBlocklist: subkeys with one extension ID per value, named
+          "1", "2", and so on. A blocklist of `*` blocks everything that isn't allowed
+
+        Firefox has the same PasswordManagerEnabled, but puts its extension rules in one JSON value, ExtensionSettings.
+
+        For more information on the policies:
+        (https://learn.microsoft.com/en-us/deployedge/microsoft-edge-policies)
+        (https://chromeenterprise.google/policies/)
+        (https://mozilla.github.io/policy-templates/)
+    */
+    let key = kind.policy_key();
+    let mut read = |value: &str| -> SourceResult<Option<RegistryValue>> {
+        for hive in [RegistryHive::LocalMachine, RegistryHive::CurrentUser] {
+            if let Some(data) = policy_value(source, hive, key, value, unreadable)? {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    };
+    let mut flag = |value: &str| -> SourceResult<Option<u32>> {
+        queries.push(format!("{}\\{}", key, value));
+        Ok(read(value)?.and_then(|data| data.as_u32()))
+    };
+
+    let safe_browsing = match kind {
+        BrowserKind::Edge => flag("SmartScreenEnabled")?.map(|enabled| enabled != 0),
+        BrowserKind::Chrome | BrowserKind::Brave => match flag("SafeBrowsingProtectionLevel")? {
+            Some(level) => Some(level != 0),
+            None => flag("SafeBrowsingEnabled")?.map(|enabled| enabled != 0)
+        },
+        BrowserKind::Firefox => None
+    };
+    let password_manager = flag("PasswordManagerEnabled")?.map(|enabled| enabled != 0);
+
+    let (extension_allowlist, extension_blocklist) = if kind.is_chromium() {
+        let mut list = |name: &str| -> SourceResult<Vec<String>> {
+            let list_key = format!("{}\\{}", key, name);
+            queries.push(list_key.clone());
+            for hive in [RegistryHive::LocalMachine, RegistryHive::CurrentUser] {
+                let mut ids = Vec::new();
+                // The values are numbered from 1 with no gaps, so the first missing number ends the list
+                for index in 1..=1000 {
+                    match policy_value(source, hive, &list_key, &index.to_string(), unreadable)? {
+                        Some(RegistryValue::String(id)) => ids.push(id),
+                        _ => break
+                    }
+                }
+                if !ids.is_empty() {
+                    return Ok(ids);
+                }
+            }
+            Ok(Vec::new())
+        };
+        (list("ExtensionInstallAllowlist")?, list("ExtensionInstallBlocklist")?)
+    } else {
+        queries.push(format!("{}\\ExtensionSettings", key));
+        let text = match read("ExtensionSettings")? {
+            Some(RegistryValue::String(text) | RegistryValue::ExpandString(text)) => Some(text),
+            Some(RegistryValue::MultiString(lines)) => Some(lines.join("\n")),
+            _ => None
+        };
+        match text.map(|text| firefox_extension_settings(&text)) {
+            Some(Ok(lists)) => lists,
+            Some(Err(error)) => {
+                unreadable.insert(format!("{}\\ExtensionSettings", key), error.detail());
+                (Vec::new(), Vec::new())
+            },
+            None => (Vec::new(), Vec::new())
+        }
+    };
+
+    Ok(BrowserPolicies { safe_browsing, password_manager, extension_allowlist, extension_blocklist })
+}
+
+/// Reads one policy value, treating a missing key as no policy and recording anything that can't be read
+fn policy_value(source: &dyn SecuritySource, hive: RegistryHive, key: &str, value: &str, unreadable: &mut BTreeMap<String, String>) -> SourceResult<Option<RegistryValue>> {
+    match source.registry_value(hive, key, value) {
+        Ok(data) => Ok(data),
+        // Policy keys only exist once someone sets a policy
+        Err(ShugoError::ValueMissing { .. } | ShugoError::NotFound { .. }) => Ok(None),
+        // An offline scan without NTUSER.DAT or SOFTWARE can't answer for that hive
+        Err(error @ ShugoError::Unavailable { .. }) => {
+            unreadable.entry(hive.root_name().to_string()).or_insert_with(|| error.detail());
+            Ok(None)
+        },
+        Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::ParseError { .. })) => {
+            unreadable.insert(hive.value_path(key, value), error.to_string());
+            Ok(None)
+        },
+        Err(error) => Err(error.in_module("browser"))
+    }
+}
+
+impl BrowserInfo {
+    /// Without browser files or HKLM nothing says which browsers are installed or how they're set up, so the
+    /// audit leaves browsers out of the score
+    pub fn nothing_read(&self) -> bool {
+        !self.files_checked && self.module_info.unreadable.contains_key(RegistryHive::LocalMachine.root_name())
+    }
+}
+
+impl BrowserStatus {
+    /// Installed, or at least configured through a profile or policy
+    pub fn present(&self) -> bool {
+        self.installation.is_some() || !self.profiles.is_empty() || self.policies != BrowserPolicies::default()
+    }
+
+    /// Whether the installed version is older than the table's minimum, `None` when either is unknown
+    pub fn outdated(&self) -> Option<bool> {
+        is_older(&self.installation.as_ref()?.version, self.minimum_version.as_deref()?)
+    }
+
+    /// Where SmartScreen or Safe Browsing is turned off: "policy", or each profile that turned it off
+    pub fn safe_browsing_disabled(&self) -> Vec<String> {
+        match self.policies.safe_browsing {
+            Some(false) => vec!["policy".to_string()],
+            Some(true) => Vec::new(),
+            None => self.profiles.iter().filter(|profile| profile.safe_browsing == Some(false)).map(|profile| profile.name.clone()).collect()
+        }
+    }
+
+    /// Whether the built-in password manager can save passwords. It's on by default, so only a policy or
+    /// every profile turning it off counts as off
+    pub fn password_manager_enabled(&self) -> bool {
+        match self.policies.password_manager {
+            Some(enabled) => enabled,
+            None => self.profiles.is_empty() || self.profiles.iter().any(|profile| profile.password_manager != Some(false))
+        }
+    }
+
+    /// Whether extensions have to be on the allowlist to be installed
+    pub fn extensions_restricted(&self) -> bool {
+        self.policies.extension_blocklist.iter().any(|id| id == "*")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+
+    /// A throwaway folder laid out like a Windows machine's Program Files, LOCALAPPDATA, and APPDATA
+    struct SampleMachine {
+        root: PathBuf
+    }
+
+    impl SampleMachine {
+        fn new(name: &str) -> SampleMachine {
+            let root = env::temp_dir().join(format!("shugo-browser-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            SampleMachine { root }
+        }
+
+        fn write(&self, path: &str, text: &str) -> &Self {
+            let path = join(&self.root, path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+            self
+        }
+
+        fn paths(&self) -> BrowserPaths {
+            BrowserPaths {
+                program_files: vec![self.root.join("Program Files")],
+                local_app_data: Some(join(&self.root, "AppData\\Local")),
+                app_data: Some(join(&self.root, "AppData\\Roaming"))
+            }
+        }
+    }
+
+    impl Drop for SampleMachine {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn browser(info: &BrowserInfo, kind: BrowserKind) -> &BrowserStatus {
+        info.browsers.iter().find(|browser| browser.kind == kind).unwrap()
+    }
+
+    #[test]
+    fn finds_browsers_and_profiles_on_disk() {
+        let machine = SampleMachine::new("disk");
+        machine
+            .write("Program Files\\Google\\Chrome\\Application\\149.0.7000.10\\chrome.dll", "")
+            .write("Program Files\\Google\\Chrome\\Application\\151.0.7100.2\\chrome.dll", "")
+            .write("AppData\\Local\\Google\\Chrome\\User Data\\Default\\Preferences", r#"{"safebrowsing": {"enabled": false}}"#)
+            .write("AppData\\Local\\Google\\Chrome\\User Data\\System Profile\\Preferences", r#"{"safebrowsing": {"enabled": false}}"#)
+            .write("Program Files\\Mozilla Firefox\\application.ini", "[App]\nVersion=140.4.0\n")
+            .write("Program Files\\Mozilla Firefox\\defaults\\pref\\channel-prefs.js", "pref(\"app.update.channel\", \"esr\");\n")
+            .write("AppData\\Roaming\\Mozilla\\Firefox\\Profiles\\abcd.default-esr\\prefs.js", "user_pref(\"signon.rememberSignons\", false);\n");
+
+        let info = scan_browsers_with(&FixtureSource::new(), Some(&machine.paths()), &VersionTable::bundled().unwrap()).unwrap();
+        let chrome = browser(&info, BrowserKind::Chrome);
+        assert_eq!(chrome.installation.as_ref().unwrap().version, "151.0.7100.2");
+        assert_eq!(chrome.outdated(), Some(false));
+        assert_eq!(chrome.safe_browsing_disabled(), ["Default"]);
+
+        let firefox = browser(&info, BrowserKind::Firefox);
+        assert_eq!(firefox.installation.as_ref().unwrap().channel.as_deref(), Some("esr"));
+        assert_eq!(firefox.minimum_version.as_deref(), VersionTable::bundled().unwrap().minimum(BrowserKind::Firefox, Some("esr")));
+        assert!(!firefox.password_manager_enabled());
+
+        assert!(!browser(&info, BrowserKind::Edge).present());
+        assert!(info.module_info.unreadable.is_empty(), "{:?}", info.module_info.unreadable);
+    }
+
+    #[test]
+    fn broken_profiles_are_unreadable() {
+        let machine = SampleMachine::new("broken");
+        machine.write("AppData\\Local\\BraveSoftware\\Brave-Browser\\User Data\\Profile 1\\Preferences", "{\"safebrowsing\":");
+
+        let info = scan_browsers_with(&FixtureSource::new(), Some(&machine.paths()), &VersionTable::bundled().unwrap()).unwrap();
+        assert!(browser(&info, BrowserKind::Brave).profiles.is_empty());
+        assert!(info.module_info.unreadable.keys().any(|path| path.ends_with("Preferences")));
+    }
+
+    #[test]
+    fn reads_policies_from_the_registry() {
+        let edge = BrowserKind::Edge.policy_key();
+        let allowlist = format!("{}\\ExtensionInstallAllowlist", edge);
+        let blocklist = format!("{}\\ExtensionInstallBlocklist", edge);
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, edge, "SmartScreenEnabled", RegistryValue::Dword(0))
+            .with_registry(RegistryHive::CurrentUser, edge, "PasswordManagerEnabled", RegistryValue::Dword(0))
+            .with_registry(RegistryHive::LocalMachine, &allowlist, "1", RegistryValue::String("odfafepnkmbhccpbejgmiehpchacaeak".to_string()))
+            .with_registry(RegistryHive::LocalMachine, &allowlist, "2", RegistryValue::String("ghbmnnjooekpmoecnnnilnnbdlolhkhi".to_string()))
+            .with_registry(RegistryHive::LocalMachine, &blocklist, "1", RegistryValue::String("*".to_string()))
+            .with_registry(RegistryHive::LocalMachine, BrowserKind::Firefox.policy_key(), "ExtensionSettings", RegistryValue::MultiString(vec![
+                "{\"*\": {\"installation_mode\": \"blocked\"},".to_string(),
+                "\"uBlock0@raymondhill.net\": {\"installation_mode\": \"allowed\"}}".to_string()
+            ]));
+
+        let info = scan_browsers_with(&source, None, &VersionTable::bundled().unwrap()).unwrap();
+        let edge = browser(&info, BrowserKind::Edge);
+        assert_eq!(edge.safe_browsing_disabled(), ["policy"]);
+        assert!(!edge.password_manager_enabled());
+        assert_eq!(edge.policies.extension_allowlist.len(), 2);
+        assert!(edge.extensions_restricted());
+        assert!(browser(&info, BrowserKind::Firefox).extensions_restricted());
+        assert!(!info.files_checked);
+        assert!(info.module_info.unreadable.contains_key("Browser files"));
+    }
+}
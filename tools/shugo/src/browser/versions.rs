@@ -0,0 +1,134 @@
+//! The minimum browser version table. The bundled copy lives in `browser/versions.toml` and a newer one
+//! can be loaded from a file, like the OS lifecycle table. Comparing versions is plain Rust, so it's
+//! tested on any OS.
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::scanner::BrowserKind;
+use crate::common::error::ShugoError;
+use crate::common::source::SourceResult;
+use crate::common::time::parse_date;
+
+pub const BUNDLED_VERSIONS: &str = include_str!("versions.toml");
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VersionTable {
+    /// When the versions were last checked, shown so readers know how fresh the answer is
+    pub updated: String,
+    #[serde(rename = "browser")]
+    pub browsers: Vec<MinimumVersion>
+}
+
+/// The oldest supported version of one browser on one release channel
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MinimumVersion {
+    pub name: BrowserKind,
+    /// `None` for the main (stable) channel
+    pub channel: Option<String>,
+    pub minimum: String
+}
+
+impl VersionTable {
+    /// The table compiled into Shugo
+    pub fn bundled() -> SourceResult<VersionTable> {
+        VersionTable::from_toml(BUNDLED_VERSIONS)
+    }
+
+    /// Loads a table from a TOML file laid out like `browser/versions.toml`
+    pub fn open(path: &Path) -> SourceResult<VersionTable> {
+        let text = fs::read_to_string(path).map_err(|error| ShugoError::io(path.display().to_string(), &error))?;
+        VersionTable::from_toml(&text).map_err(|error| error.for_query(path.display().to_string()))
+    }
+
+    pub fn from_toml(text: &str) -> SourceResult<VersionTable> {
+        let table: VersionTable = toml::from_str(text).map_err(|error| ShugoError::parse("versions", error.to_string()))?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Catches typos in versions and browsers listed twice
+    fn validate(&self) -> SourceResult<()> {
+        let invalid = |reason: String| Err(ShugoError::parse("versions", reason));
+        if parse_date(&self.updated).is_none() {
+            return invalid(format!("updated {:?} is not a YYYY-MM-DD date", self.updated));
+        }
+        for (index, entry) in self.browsers.iter().enumerate() {
+            if parse_version(&entry.minimum).is_none() {
+                return invalid(format!("{} has an invalid minimum {:?}", entry.name, entry.minimum));
+            }
+            if self.browsers[..index].iter().any(|other| other.name == entry.name && other.channel == entry.channel) {
+                return invalid(format!("{} {} is listed twice", entry.name, entry.channel.as_deref().unwrap_or("stable")));
+            }
+        }
+        Ok(())
+    }
+
+    /// The minimum for a browser's channel, falling back to the main channel when the channel isn't listed
+    pub fn minimum(&self, kind: BrowserKind, channel: Option<&str>) -> Option<&str> {
+        let find = |channel: Option<&str>| self.browsers.iter().find(|entry| {
+            entry.name == kind && entry.channel.as_deref().map(str::to_lowercase).as_deref() == channel.map(str::to_lowercase).as_deref()
+        });
+        channel.and_then(|channel| find(Some(channel))).or_else(|| find(None)).map(|entry| entry.minimum.as_str())
+    }
+}
+
+/// Splits "150.0.7390.65" into its numbers, `None` when any part isn't a number
+pub fn parse_version(text: &str) -> Option<Vec<u32>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    text.split('.').map(|part| part.parse::<u32>().ok()).collect()
+}
+
+/// Compares two versions number by number, missing numbers count as 0 so "150" and "150.0.0" are equal
+pub fn compare_versions(left: &[u32], right: &[u32]) -> Ordering {
+    let length = left.len().max(right.len());
+    (0..length)
+        .map(|index| left.get(index).unwrap_or(&0).cmp(right.get(index).unwrap_or(&0)))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Whether `version` is older than `minimum`, `None` when either isn't a version
+pub fn is_older(version: &str, minimum: &str) -> Option<bool> {
+    Some(compare_versions(&parse_version(version)?, &parse_version(minimum)?) == Ordering::Less)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_table_loads() {
+        let table = VersionTable::bundled().unwrap();
+        assert!(table.minimum(BrowserKind::Chrome, None).is_some());
+        assert_ne!(table.minimum(BrowserKind::Firefox, Some("esr")), table.minimum(BrowserKind::Firefox, None));
+        // Channels the table doesn't list use the stable minimum
+        assert_eq!(table.minimum(BrowserKind::Firefox, Some("release")), table.minimum(BrowserKind::Firefox, None));
+    }
+
+    #[test]
+    fn compares_versions_number_by_number() {
+        assert_eq!(is_older("149.0.7000.1", "150.0"), Some(true));
+        assert_eq!(is_older("150.0.10.0", "150.0.9.0"), Some(false));
+        assert_eq!(is_older("150", "150.0.0.0"), Some(false));
+        assert_eq!(is_older("150.0b1", "150.0"), None);
+    }
+
+    #[test]
+    fn rejects_bad_tables() {
+        assert!(VersionTable::from_toml("updated = \"soon\"\nbrowser = []").is_err());
+        let version = "updated = \"2026-01-01\"\n[[browser]]\nname = \"chrome\"\nminimum = \"latest\"";
+        assert!(VersionTable::from_toml(version).is_err());
+        let duplicate = "updated = \"2026-01-01\"\n[[browser]]\nname = \"edge\"\nminimum = \"1.0\"\n[[browser]]\nname = \"edge\"\nminimum = \"2.0\"";
+        assert!(VersionTable::from_toml(duplicate).is_err());
+        let unknown = "updated = \"2026-01-01\"\n[[browser]]\nname = \"netscape\"\nminimum = \"9.0\"";
+        assert!(VersionTable::from_toml(unknown).is_err());
+    }
+}
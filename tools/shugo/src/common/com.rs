@@ -0,0 +1,72 @@
+//! The COM apartment guard. Every COM call (WMI, the firewall policy, Windows Update) needs COM
+//! started on the calling thread and closed again afterwards, even when a call fails halfway.
+use windows::Win32::Foundation::{RPC_E_CHANGED_MODE, S_FALSE, S_OK};
+use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED};
+use windows::core::HRESULT;
+
+use super::error::{ErrorContext, ShugoError};
+use super::source::SourceResult;
+
+/// Keeps COM initialized for as long as it's alive and closes it when dropped
+pub struct ComGuard {
+    /// False when someone else started COM on this thread, so it isn't ours to close
+    owned: bool
+}
+
+impl ComGuard {
+    /// Initializing COM for the calling thread. `query` is only used to say what failed
+    pub fn new(query: &str) -> SourceResult<ComGuard> {
+        /*
+            Shugo: COM Library
+
+            Before we do anything with COM, we need to initialize the COM library for use by the
+            calling thread. This is usually only called once for each thread that uses
+            the COM library.
+
+            For more information on `CoInitializeEx`:
+            (https://learn.microsoft.com/en-us/windows/win32/api/combaseapi/nf-combaseapi-coinitializeex) - C++
+            (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Com/fn.CoInitializeEx.html) - Rust
+        */
+        let _com: HRESULT = unsafe {
+            CoInitializeEx(
+                None, // This has to be NULL as its already reserved
+                COINIT_MULTITHREADED // This specifies the concurrency model and initialization options for the thread
+            )
+        };
+
+        /*
+            Shugo: Already Initialized
+
+            S_FALSE means COM was already running on this thread. It still counts, so it still needs a matching
+            `CoUninitialize`. RPC_E_CHANGED_MODE means someone started COM with a different threading model. COM
+            is still usable, but that call failed, so we must NOT call `CoUninitialize` for it.
+        */
+        match _com {
+            S_OK | S_FALSE => Ok(ComGuard { owned: true }),
+            RPC_E_CHANGED_MODE => Ok(ComGuard { owned: false }),
+            _ => Err(ShugoError::ComInit { context: ErrorContext::new(query), code: _com.0 })
+        }
+    }
+}
+
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        /*
+            Shugo: Closing The Thread
+
+            When we open a COM connection through a thread, we must close that thread when we're done. To do that
+            we use `CoUninitialize`. Doing it in `Drop` means it happens however we leave the scope, including an
+            early return from `?`.
+
+            Rust drops values in the reverse order they were created, so any COM object made after the guard
+            is released before COM is closed.
+
+            For more information on `CoUninitialize`:
+            (https://learn.microsoft.com/en-us/windows/win32/api/combaseapi/nf-combaseapi-couninitialize) - C++
+            (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Com/fn.CoUninitialize.html) - Rust
+        */
+        if self.owned {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
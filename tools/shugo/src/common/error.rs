@@ -0,0 +1,212 @@
+//! The error every Shugo source and scanner returns. Each variant says what kind of failure it was
+//! and carries the module and query it happened in, so "access denied" can be told apart from "not
+//! installed" and a report can say exactly which lookup failed.
+use std::error::Error;
+use std::fmt;
+
+/// Where an error happened
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorContext {
+    /// The Shugo module that was scanning, filled in once the error reaches a scanner
+    pub module: Option<String>,
+    /// What was being asked for, for example a WQL query, a registry value, or a file path
+    pub query: String
+}
+
+impl ErrorContext {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self { module: None, query: query.into() }
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.module {
+            Some(module) => write!(f, "{} ({})", module, self.query),
+            None => f.write_str(&self.query)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShugoError {
+    /// COM couldn't be started on this thread, so no WMI, firewall, or update queries can run
+    ComInit { context: ErrorContext, code: i32 },
+    /// Windows refused the request, usually because Shugo isn't running as administrator
+    AccessDenied { context: ErrorContext },
+    /// The key, class, namespace, or file doesn't exist
+    NotFound { context: ErrorContext },
+    /// The query took too long to answer
+    Timeout { context: ErrorContext },
+    /// The key or object exists but the value asked for isn't set
+    ValueMissing { context: ErrorContext },
+    /// Data was read but couldn't be understood
+    ParseError { context: ErrorContext, reason: String },
+    /// The source can't provide this kind of data at all, like WMI from an offline registry file
+    Unavailable { context: ErrorContext, reason: String },
+    /// Any other Windows API failure, with its HRESULT
+    Windows { context: ErrorContext, code: i32, message: String },
+    /// Any other file system failure
+    Io { context: ErrorContext, reason: String }
+}
+
+impl ShugoError {
+    pub fn not_found(query: impl Into<String>) -> Self {
+        ShugoError::NotFound { context: ErrorContext::new(query) }
+    }
+
+    pub fn value_missing(query: impl Into<String>) -> Self {
+        ShugoError::ValueMissing { context: ErrorContext::new(query) }
+    }
+
+    pub fn parse(query: impl Into<String>, reason: impl Into<String>) -> Self {
+        ShugoError::ParseError { context: ErrorContext::new(query), reason: reason.into() }
+    }
+
+    pub fn unavailable(query: impl Into<String>, reason: impl Into<String>) -> Self {
+        ShugoError::Unavailable { context: ErrorContext::new(query), reason: reason.into() }
+    }
+
+    /// Sorts a file system error into the matching variant
+    pub fn io(path: impl Into<String>, error: &std::io::Error) -> Self {
+        let context = ErrorContext::new(path);
+        match error.kind() {
+            std::io::ErrorKind::NotFound => ShugoError::NotFound { context },
+            std::io::ErrorKind::PermissionDenied => ShugoError::AccessDenied { context },
+            std::io::ErrorKind::TimedOut => ShugoError::Timeout { context },
+            _ => ShugoError::Io { context, reason: error.to_string() }
+        }
+    }
+
+    /// Sorts a failed Windows call into the matching variant by its HRESULT
+    pub fn from_hresult(query: impl Into<String>, code: i32, message: impl Into<String>) -> Self {
+        /*
+            Shugo: Reading HRESULTs
+
+            Windows APIs report failures as a 32-bit HRESULT. The top bit means failure, the middle bits say which
+            part of Windows it came from (7 is plain Win32, 4 is COM/WMI), and the low 16 bits are the error code.
+            A Win32 error like ERROR_ACCESS_DENIED (5) becomes 0x80070005 once it's wrapped as an HRESULT, and WMI
+            has its own codes in the 0x8004xxxx range.
+
+            For more information on HRESULT values:
+            (https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-erref/0642cb2f-2075-4469-918c-4441e69c548a)
+            (https://learn.microsoft.com/en-us/windows/win32/wmisdk/wmi-error-constants)
+        */
+        let context = ErrorContext::new(query);
+        match code as u32 {
+            0x80070005 | 0x80041003 => ShugoError::AccessDenied { context }, // E_ACCESSDENIED, WBEM_E_ACCESS_DENIED
+            // WBEM_E_ENCRYPTED_CONNECTION_REQUIRED, running as administrator doesn't help with this one
+            0x80041087 => ShugoError::Unavailable { context, reason: "this WMI namespace only accepts encrypted connections".to_string() },
+            0x80070002 | 0x80070003 | 0x80041002 | 0x8004100E | 0x80041010 => ShugoError::NotFound { context }, // file/path not found, WBEM_E_NOT_FOUND, WBEM_E_INVALID_NAMESPACE, WBEM_E_INVALID_CLASS
+            0x800705B4 | 0x8001011F | 0x80041069 | 0x80043001 => ShugoError::Timeout { context }, // ERROR_TIMEOUT, RPC_E_TIMEOUT, WBEM_E_TIMED_OUT, WBEM_E_RETRY_LATER
+            _ => ShugoError::Windows { context, code, message: message.into() }
+        }
+    }
+
+    /// Short name for the variant, kept in reports so scripts can tell failures apart
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ShugoError::ComInit { .. } => "com_init",
+            ShugoError::AccessDenied { .. } => "access_denied",
+            ShugoError::NotFound { .. } => "not_found",
+            ShugoError::Timeout { .. } => "timeout",
+            ShugoError::ValueMissing { .. } => "value_missing",
+            ShugoError::ParseError { .. } => "parse_error",
+            ShugoError::Unavailable { .. } => "unavailable",
+            ShugoError::Windows { .. } => "windows",
+            ShugoError::Io { .. } => "io"
+        }
+    }
+
+    /// The message without the module name, for places that already show which module failed
+    pub fn detail(&self) -> String {
+        let mut error = self.clone();
+        error.context_mut().module = None;
+        error.to_string()
+    }
+
+    pub fn context(&self) -> &ErrorContext {
+        match self {
+            ShugoError::ComInit { context, .. }
+            | ShugoError::AccessDenied { context }
+            | ShugoError::NotFound { context }
+            | ShugoError::Timeout { context }
+            | ShugoError::ValueMissing { context }
+            | ShugoError::ParseError { context, .. }
+            | ShugoError::Unavailable { context, .. }
+            | ShugoError::Windows { context, .. }
+            | ShugoError::Io { context, .. } => context
+        }
+    }
+
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        match self {
+            ShugoError::ComInit { context, .. }
+            | ShugoError::AccessDenied { context }
+            | ShugoError::NotFound { context }
+            | ShugoError::Timeout { context }
+            | ShugoError::ValueMissing { context }
+            | ShugoError::ParseError { context, .. }
+            | ShugoError::Unavailable { context, .. }
+            | ShugoError::Windows { context, .. }
+            | ShugoError::Io { context, .. } => context
+        }
+    }
+
+    /// Replaces what the error says was being asked for, for example a file name instead of "policy"
+    pub fn for_query(mut self, query: impl Into<String>) -> Self {
+        self.context_mut().query = query.into();
+        self
+    }
+
+    /// Records which module the error happened in, keeping the first module if one is already set
+    pub fn in_module(mut self, module: &str) -> Self {
+        let context = self.context_mut();
+        if context.module.is_none() {
+            context.module = Some(module.to_string());
+        }
+        self
+    }
+}
+
+impl fmt::Display for ShugoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShugoError::ComInit { context, code } => write!(f, "{}: COM initialization failed (HRESULT 0x{:08X})", context, *code as u32),
+            ShugoError::AccessDenied { context } => write!(f, "{}: access denied, try running as administrator", context),
+            ShugoError::NotFound { context } => write!(f, "{}: not found", context),
+            ShugoError::Timeout { context } => write!(f, "{}: timed out", context),
+            ShugoError::ValueMissing { context } => write!(f, "{}: value is not set", context),
+            ShugoError::ParseError { context, reason } => write!(f, "{}: {}", context, reason),
+            ShugoError::Unavailable { context, reason } => write!(f, "{}: {}", context, reason),
+            ShugoError::Windows { context, code, message } => write!(f, "{}: {} (HRESULT 0x{:08X})", context, message, *code as u32),
+            ShugoError::Io { context, reason } => write!(f, "{}: {}", context, reason)
+        }
+    }
+}
+
+impl Error for ShugoError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_hresults() {
+        assert!(matches!(ShugoError::from_hresult("q", 0x80070005u32 as i32, ""), ShugoError::AccessDenied { .. }));
+        assert!(matches!(ShugoError::from_hresult("q", 0x80041010u32 as i32, ""), ShugoError::NotFound { .. }));
+        assert!(matches!(ShugoError::from_hresult("q", 0x80041087u32 as i32, ""), ShugoError::Unavailable { .. }));
+        assert!(matches!(ShugoError::from_hresult("q", 0x80041069u32 as i32, ""), ShugoError::Timeout { .. }));
+        assert!(matches!(ShugoError::from_hresult("q", 0x80043001u32 as i32, ""), ShugoError::Timeout { .. }));
+        assert!(matches!(ShugoError::from_hresult("q", 0x80004005u32 as i32, "Unspecified"), ShugoError::Windows { .. }));
+    }
+
+    #[test]
+    fn keeps_the_first_module() {
+        let error = ShugoError::not_found("HKLM\\SOFTWARE\\Missing").in_module("uac").in_module("audit");
+        assert_eq!(error.context().module.as_deref(), Some("uac"));
+        assert_eq!(error.to_string(), "uac (HKLM\\SOFTWARE\\Missing): not found");
+        assert_eq!(error.detail(), "HKLM\\SOFTWARE\\Missing: not found");
+        assert_eq!(error.kind(), "not_found");
+    }
+}
@@ -0,0 +1,48 @@
+//! Windows optional features from `Win32_OptionalFeature`. Modules that check for legacy components
+//! (SMBv1, PowerShell 2.0) query the features they care about and look them up here.
+use serde::{Deserialize, Serialize};
+
+use super::source::SourceResult;
+use super::wmi::{FromWmiObject, WmiObject};
+
+pub const FEATURE_NAMESPACE: &str = "ROOT\\CIMV2";
+
+/// Win32_OptionalFeature.InstallState for an installed feature
+pub const FEATURE_ENABLED: u32 = 1;
+
+/// One optional feature from `Win32_OptionalFeature`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptionalFeature {
+    pub name: String,
+    /// 1 = Enabled, 2 = Disabled, 3 = Absent, 4 = Unknown
+    pub install_state: u32
+}
+
+impl FromWmiObject for OptionalFeature {
+    fn from_wmi_object(object: &dyn WmiObject) -> SourceResult<Self> {
+        Ok(OptionalFeature {
+            name: object.get_string("Name")?,
+            install_state: object.get_u32("InstallState")?
+        })
+    }
+}
+
+impl OptionalFeature {
+    pub fn is_enabled(&self) -> bool {
+        self.install_state == FEATURE_ENABLED
+    }
+
+    pub fn install_state_text(&self) -> &'static str {
+        match self.install_state {
+            1 => "Enabled",
+            2 => "Disabled",
+            3 => "Absent",
+            _ => "Unknown"
+        }
+    }
+}
+
+/// Finds a feature by name, ignoring case like Windows does
+pub fn find_feature<'a>(features: &'a [OptionalFeature], name: &str) -> Option<&'a OptionalFeature> {
+    features.iter().find(|feature| feature.name.eq_ignore_ascii_case(name))
+}
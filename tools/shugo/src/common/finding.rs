@@ -0,0 +1,134 @@
+//! The `Finding` every module's assessment produces. Assessment code only decides what is wrong,
+//! the display files decide how to print it, so findings can be counted, filtered, exported, and
+//! tested without going through the terminal.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// How bad a finding is, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL"
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    /// Stable identifier, for example `UAC-001`
+    pub id: String,
+    pub module: String,
+    pub severity: Severity,
+    pub title: String,
+    /// Why this matters
+    pub explanation: String,
+    /// What to change to fix it
+    pub remediation: String,
+    /// The raw values the finding was decided from, for example `EnableLUA=0`
+    pub evidence: Vec<String>
+}
+
+impl Finding {
+    pub fn new(id: &str, module: &str, severity: Severity, title: impl Into<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            module: module.to_string(),
+            severity,
+            title: title.into(),
+            explanation: String::new(),
+            remediation: String::new(),
+            evidence: Vec::new()
+        }
+    }
+
+    pub fn explanation(mut self, explanation: impl Into<String>) -> Self {
+        self.explanation = explanation.into();
+        self
+    }
+
+    pub fn remediation(mut self, remediation: impl Into<String>) -> Self {
+        self.remediation = remediation.into();
+        self
+    }
+
+    pub fn evidence(mut self, evidence: impl Into<String>) -> Self {
+        self.evidence.push(evidence.into());
+        self
+    }
+}
+
+/// Counts findings at or above `severity`
+pub fn count_at_least(findings: &[Finding], severity: Severity) -> usize {
+    findings.iter().filter(|finding| finding.severity >= severity).count()
+}
+
+/// The shared "Security Assessment" section every module prints
+pub fn display_findings(findings: &[Finding], verbose: bool) {
+    println!("Security Assessment:");
+    if findings.is_empty() {
+        println!(" - No significant weaknesses detected");
+        println!();
+        return;
+    }
+
+    // Worst first, keeping each module's own order for the same severity
+    let mut sorted: Vec<&Finding> = findings.iter().collect();
+    sorted.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+
+    for (i, finding) in sorted.iter().enumerate() {
+        println!("{}. [{}] {}", i + 1, finding.severity, finding.title);
+        if !finding.explanation.is_empty() {
+            println!("   - Why: {}", finding.explanation);
+        }
+        if !finding.remediation.is_empty() {
+            println!("   - Fix: {}", finding.remediation);
+        }
+        if verbose {
+            println!("   - ID: {}", finding.id);
+            for evidence in &finding.evidence {
+                println!("   - Evidence: {}", evidence);
+            }
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn severities_are_ordered() {
+        assert!(Severity::Critical > Severity::High);
+        assert!(Severity::Low > Severity::Info);
+
+        let findings = vec![
+            Finding::new("T-001", "test", Severity::Low, "low"),
+            Finding::new("T-002", "test", Severity::High, "high"),
+            Finding::new("T-003", "test", Severity::Critical, "critical")
+        ];
+        assert_eq!(count_at_least(&findings, Severity::High), 2);
+        assert_eq!(serde_json::to_value(Severity::Medium).unwrap(), "medium");
+    }
+}
@@ -0,0 +1,189 @@
+//! An in-memory `SecuritySource`. Fixtures are filled in with builder methods and then handed to
+//! any `scan_*_with` function, which lets us run Shugo's scanners on any OS.
+use std::collections::BTreeMap;
+
+use super::error::ShugoError;
+use super::source::*;
+use crate::firewall::scanner::{FirewallRule, WindowsFirewallProfile};
+
+#[derive(Default)]
+pub struct FixtureSource {
+    wmi: BTreeMap<(String, String), Vec<WmiRow>>,
+    wmi_methods: BTreeMap<String, WmiRow>,
+    registry: StoredKeys,
+    firewall: Option<WindowsFirewallProfile>,
+    firewall_rules: Option<Vec<FirewallRule>>,
+    updates: Option<Vec<UpdateRecord>>,
+    users: Option<Vec<UserRecord>>,
+    shares: Option<Vec<ShareRecord>>
+}
+
+impl FixtureSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rows returned for any query against `class` in `namespace`
+    pub fn with_wmi(mut self, namespace: &str, class: &str, rows: Vec<WmiRow>) -> Self {
+        self.wmi.insert((namespace.to_lowercase(), class.to_lowercase()), rows);
+        self
+    }
+
+    /// Out parameters returned when `method` is called on `object_path` with exactly these inputs
+    pub fn with_wmi_method(mut self, namespace: &str, object_path: &str, method: &str, inputs: &[(&str, WmiValue)], outputs: WmiRow) -> Self {
+        self.wmi_methods.insert(method_key(namespace, object_path, method, inputs), outputs);
+        self
+    }
+
+    /// Creates the key if needed and sets one of its values
+    pub fn with_registry(mut self, hive: RegistryHive, key: &str, value: &str, data: RegistryValue) -> Self {
+        stored_key(&mut self.registry, hive, key).set(value, data);
+        self
+    }
+
+    /// Creates the key and sets each of the given values in it
+    pub fn with_registry_values(self, hive: RegistryHive, key: &str, values: &[(&str, RegistryValue)]) -> Self {
+        values.iter().fold(self.with_registry_key(hive, key), |source, (value, data)| source.with_registry(hive, key, value, data.clone()))
+    }
+
+    /// Sets several DWORD values, each given as (key, value, data)
+    pub fn with_dwords(self, hive: RegistryHive, values: &[(&str, &str, u32)]) -> Self {
+        values.iter().fold(self, |source, (key, value, data)| source.with_registry(hive, key, value, RegistryValue::Dword(*data)))
+    }
+
+    /// Creates an empty registry key
+    pub fn with_registry_key(mut self, hive: RegistryHive, key: &str) -> Self {
+        stored_key(&mut self.registry, hive, key);
+        self
+    }
+
+    pub fn with_firewall(mut self, profile: WindowsFirewallProfile) -> Self {
+        self.firewall = Some(profile);
+        self
+    }
+
+    pub fn with_firewall_rules(mut self, rules: Vec<FirewallRule>) -> Self {
+        self.firewall_rules = Some(rules);
+        self
+    }
+
+    pub fn with_updates(mut self, updates: Vec<UpdateRecord>) -> Self {
+        self.updates = Some(updates);
+        self
+    }
+
+    pub fn with_users(mut self, users: Vec<UserRecord>) -> Self {
+        self.users = Some(users);
+        self
+    }
+
+    pub fn with_shares(mut self, shares: Vec<ShareRecord>) -> Self {
+        self.shares = Some(shares);
+        self
+    }
+}
+
+impl SecuritySource for FixtureSource {
+    fn wmi_query(&self, namespace: &str, query: &str) -> SourceResult<Vec<WmiRow>> {
+        let class = query_class(query).ok_or_else(|| ShugoError::parse(query, "could not find a class in the query"))?;
+        self.wmi
+            .get(&(namespace.to_lowercase(), class.to_lowercase()))
+            .cloned()
+            .ok_or_else(|| ShugoError::not_found(format!("{} in {}", class, namespace)))
+    }
+
+    fn wmi_method(&self, namespace: &str, object_path: &str, method: &str, inputs: &[(&str, WmiValue)]) -> SourceResult<WmiRow> {
+        self.wmi_methods
+            .get(&method_key(namespace, object_path, method, inputs))
+            .cloned()
+            .ok_or_else(|| ShugoError::not_found(format!("{}.{} in {}", object_path, method, namespace)))
+    }
+
+    fn registry_value(&self, hive: RegistryHive, key: &str, value: &str) -> SourceResult<Option<RegistryValue>> {
+        let stored = self.registry
+            .get(&(hive, stored_path(key)))
+            .ok_or_else(|| ShugoError::not_found(key))?;
+        Ok(stored.get(value).cloned())
+    }
+
+    fn registry_values(&self, hive: RegistryHive, key: &str) -> SourceResult<Vec<(String, RegistryValue)>> {
+        let stored = self.registry
+            .get(&(hive, stored_path(key)))
+            .ok_or_else(|| ShugoError::not_found(key))?;
+        Ok(stored.values())
+    }
+
+    fn registry_subkeys(&self, hive: RegistryHive, key: &str) -> SourceResult<Vec<String>> {
+        stored_subkeys(&self.registry, hive, key).ok_or_else(|| ShugoError::not_found(key))
+    }
+
+    fn firewall_policy(&self) -> SourceResult<WindowsFirewallProfile> {
+        self.firewall.clone().ok_or_else(|| ShugoError::unavailable("firewall policy", "no firewall fixture"))
+    }
+
+    fn firewall_rules(&self) -> SourceResult<Vec<FirewallRule>> {
+        self.firewall_rules.clone().ok_or_else(|| ShugoError::unavailable("firewall rules", "no firewall rule fixture"))
+    }
+
+    fn update_search(&self, _criteria: &str) -> SourceResult<Vec<UpdateRecord>> {
+        self.updates.clone().ok_or_else(|| ShugoError::unavailable("update search", "no update fixture"))
+    }
+
+    fn local_users(&self) -> SourceResult<Vec<UserRecord>> {
+        self.users.clone().ok_or_else(|| ShugoError::unavailable("local accounts", "no user fixture"))
+    }
+
+    fn network_shares(&self) -> SourceResult<Vec<ShareRecord>> {
+        self.shares.clone().ok_or_else(|| ShugoError::unavailable("network shares", "no share fixture"))
+    }
+}
+
+/// One lookup key for a method call, object paths and names ignore case like WMI does
+fn method_key(namespace: &str, object_path: &str, method: &str, inputs: &[(&str, WmiValue)]) -> String {
+    format!("{}|{}|{}|{:?}", namespace.to_lowercase(), object_path.to_lowercase(), method.to_lowercase(), inputs)
+}
+
+/// Pulls the class name out of a WQL query (the word after FROM)
+fn query_class(query: &str) -> Option<&str> {
+    let mut words = query.split_whitespace();
+    words.find(|word| word.eq_ignore_ascii_case("from"))?;
+    words.next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_class_in_query() {
+        assert_eq!(query_class("Select displayName, productState FROM AntiVirusProduct"), Some("AntiVirusProduct"));
+        assert_eq!(query_class("select * from Win32_OperatingSystem where x = 1"), Some("Win32_OperatingSystem"));
+        assert_eq!(query_class("Select displayName"), None);
+    }
+
+    #[test]
+    fn registry_lookups_ignore_case() {
+        let source = FixtureSource::new().with_registry(
+            RegistryHive::LocalMachine,
+            "SOFTWARE\\Test",
+            "EnableLUA",
+            RegistryValue::Dword(1)
+        );
+        let value = source.registry_value(RegistryHive::LocalMachine, "software\\test", "enablelua").unwrap();
+        assert_eq!(value, Some(RegistryValue::Dword(1)));
+        assert!(source.registry_value(RegistryHive::LocalMachine, "software\\test", "Missing").unwrap().is_none());
+        assert!(source.registry_value(RegistryHive::CurrentUser, "software\\test", "EnableLUA").is_err());
+    }
+
+    #[test]
+    fn listings_keep_written_names() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, "SOFTWARE\\Test", "EnableLUA", RegistryValue::Dword(1))
+            .with_registry_key(RegistryHive::LocalMachine, "SOFTWARE\\Test\\Sub\\Deeper");
+        let values = source.registry_values(RegistryHive::LocalMachine, "software\\test").unwrap();
+        assert_eq!(values, [("EnableLUA".to_string(), RegistryValue::Dword(1))]);
+        assert_eq!(source.registry_subkeys(RegistryHive::LocalMachine, "SOFTWARE").unwrap(), ["Test"]);
+        assert_eq!(source.registry_subkeys(RegistryHive::LocalMachine, "software\\test").unwrap(), ["Sub"]);
+        assert!(source.registry_subkeys(RegistryHive::LocalMachine, "software\\other").is_err());
+    }
+}
@@ -0,0 +1,514 @@
+//! A reader for raw registry hive files (the `regf` format), written in pure Rust so exported
+//! `SOFTWARE`, `SYSTEM`, `SAM`, and `NTUSER.DAT` files can be audited on any machine.
+//!
+//! Shugo: How A Hive File Is Laid Out
+//!
+//! - The first 4096 bytes are the base block. It starts with "regf" and tells us where the root key is.
+//! - Everything after that is split into "hbin" blocks full of cells. Every cell starts with a
+//!   signed size (negative means the cell is in use) and every offset in the file points at a
+//!   cell, counted from the start of the first hbin.
+//! - "nk" cells are keys, "vk" cells are values, and "lf"/"lh"/"li"/"ri" cells are lists of subkeys.
+//!
+//! For more information on the format:
+//! (https://github.com/msuhanov/regf/blob/master/Windows%20registry%20file%20format%20specification.md)
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use super::error::ShugoError;
+use super::source::{RegistryValue, SourceResult};
+
+const BASE_BLOCK_SIZE: usize = 4096;
+const KEY_COMP_NAME: u16 = 0x0020;
+const VALUE_COMP_NAME: u16 = 0x0001;
+const DATA_IS_RESIDENT: u32 = 0x8000_0000;
+const BIG_DATA_SEGMENT: usize = 16344;
+/// More subkeys than any real key has, so a corrupt list can't make us collect forever
+const MAX_SUBKEYS: usize = 1 << 16;
+
+/// A registry hive file loaded into memory
+pub struct HiveFile {
+    data: Vec<u8>,
+    root: u32
+}
+
+impl HiveFile {
+    /// Reads and checks a hive file from disk
+    pub fn open(path: &Path) -> SourceResult<HiveFile> {
+        let data = fs::read(path).map_err(|error| ShugoError::io(path.display().to_string(), &error))?;
+        HiveFile::from_bytes(data)
+    }
+
+    /// Checks the base block and finds the root key
+    pub fn from_bytes(data: Vec<u8>) -> SourceResult<HiveFile> {
+        if data.len() < BASE_BLOCK_SIZE || &data[0..4] != b"regf" {
+            return Err(corrupt("not a registry hive (missing regf signature)"));
+        }
+        let root = u32_at(&data, 0x24)?;
+        let hive = HiveFile { data, root };
+        hive.key_cell(root)?;
+        Ok(hive)
+    }
+
+    /// Reads one value from a key path like `Microsoft\Windows\CurrentVersion`
+    pub fn value(&self, path: &str, name: &str) -> SourceResult<Option<RegistryValue>> {
+        let key = self.find_key(path)?;
+        for offset in self.value_offsets(key)? {
+            let cell = self.cell(offset)?;
+            if self.value_name(cell)?.eq_ignore_ascii_case(name) {
+                return Ok(Some(self.value_data(cell)?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Lists every value in a key as (name, value)
+    pub fn values(&self, path: &str) -> SourceResult<Vec<(String, RegistryValue)>> {
+        let key = self.find_key(path)?;
+        self.value_offsets(key)?
+            .into_iter()
+            .map(|offset| {
+                let cell = self.cell(offset)?;
+                Ok((self.value_name(cell)?, self.value_data(cell)?))
+            })
+            .collect()
+    }
+
+    /// Lists the names of every subkey in a key
+    pub fn subkeys(&self, path: &str) -> SourceResult<Vec<String>> {
+        let key = self.find_key(path)?;
+        self.subkey_offsets(key)?
+            .into_iter()
+            .map(|offset| self.key_name(self.key_cell(offset)?))
+            .collect()
+    }
+
+    /// Walks down from the root key one name at a time, names are not case sensitive
+    fn find_key(&self, path: &str) -> SourceResult<&[u8]> {
+        let mut key = self.key_cell(self.root)?;
+        for part in path.split('\\').filter(|part| !part.is_empty()) {
+            let mut next = None;
+            for offset in self.subkey_offsets(key)? {
+                let child = self.key_cell(offset)?;
+                if self.key_name(child)?.eq_ignore_ascii_case(part) {
+                    next = Some(child);
+                    break;
+                }
+            }
+            key = next.ok_or_else(|| ShugoError::not_found(path))?;
+        }
+        Ok(key)
+    }
+
+    /// Gives the data of the cell at `offset` (without its size field)
+    fn cell(&self, offset: u32) -> SourceResult<&[u8]> {
+        let start = BASE_BLOCK_SIZE + offset as usize;
+        let size = i32_at(&self.data, start)?;
+        let length = size.unsigned_abs() as usize;
+        if length < 4 || start + length > self.data.len() {
+            return Err(corrupt(format!("bad cell at offset 0x{:X}", offset)));
+        }
+        Ok(&self.data[start + 4..start + length])
+    }
+
+    fn key_cell(&self, offset: u32) -> SourceResult<&[u8]> {
+        let cell = self.cell(offset)?;
+        if cell.len() < 76 || &cell[0..2] != b"nk" {
+            return Err(corrupt(format!("expected a key at offset 0x{:X}", offset)));
+        }
+        Ok(cell)
+    }
+
+    fn key_name(&self, key: &[u8]) -> SourceResult<String> {
+        let flags = u16_at(key, 2)?;
+        let length = u16_at(key, 72)? as usize;
+        let name = bytes_at(key, 76, length)?;
+        Ok(decode_name(name, flags & KEY_COMP_NAME != 0))
+    }
+
+    /// Follows a key's subkey list (and any index roots) to every child key offset
+    fn subkey_offsets(&self, key: &[u8]) -> SourceResult<Vec<u32>> {
+        if u32_at(key, 20)? == 0 {
+            return Ok(Vec::new());
+        }
+        let mut offsets = Vec::new();
+        self.collect_list(u32_at(key, 28)?, &mut offsets, &mut BTreeSet::new(), 0)?;
+        Ok(offsets)
+    }
+
+    /// Hives can come from anywhere, so lists that point back at themselves or grow without end are corrupt
+    fn collect_list(&self, offset: u32, offsets: &mut Vec<u32>, visited: &mut BTreeSet<u32>, depth: usize) -> SourceResult<()> {
+        if depth > 8 {
+            return Err(corrupt("subkey lists nested too deep"));
+        }
+        if !visited.insert(offset) {
+            return Err(corrupt(format!("subkey list at offset 0x{:X} is listed twice", offset)));
+        }
+        let list = self.cell(offset)?;
+        let count = u16_at(list, 2)? as usize;
+        if offsets.len() + count > MAX_SUBKEYS {
+            return Err(corrupt(format!("more than {} subkeys under one key", MAX_SUBKEYS)));
+        }
+        match &list[0..2] {
+            b"lf" | b"lh" => {
+                for i in 0..count {
+                    offsets.push(u32_at(list, 4 + i * 8)?);
+                }
+            },
+            b"li" => {
+                for i in 0..count {
+                    offsets.push(u32_at(list, 4 + i * 4)?);
+                }
+            },
+            b"ri" => {
+                for i in 0..count {
+                    self.collect_list(u32_at(list, 4 + i * 4)?, offsets, visited, depth + 1)?;
+                }
+            },
+            _ => return Err(corrupt(format!("unknown subkey list at offset 0x{:X}", offset)))
+        }
+        Ok(())
+    }
+
+    fn value_offsets(&self, key: &[u8]) -> SourceResult<Vec<u32>> {
+        let count = u32_at(key, 36)? as usize;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let list = self.cell(u32_at(key, 40)?)?;
+        (0..count).map(|i| u32_at(list, i * 4)).collect()
+    }
+
+    fn value_name(&self, value: &[u8]) -> SourceResult<String> {
+        if value.len() < 20 || &value[0..2] != b"vk" {
+            return Err(corrupt("expected a value cell"));
+        }
+        let length = u16_at(value, 2)? as usize;
+        if length == 0 {
+            return Ok(String::new()); // The (Default) value has no name
+        }
+        let flags = u16_at(value, 16)?;
+        Ok(decode_name(bytes_at(value, 20, length)?, flags & VALUE_COMP_NAME != 0))
+    }
+
+    fn value_data(&self, value: &[u8]) -> SourceResult<RegistryValue> {
+        let size = u32_at(value, 4)?;
+        let value_type = u32_at(value, 12)?;
+
+        // Small values (4 bytes or less) are stored right inside the offset field
+        if size & DATA_IS_RESIDENT != 0 {
+            let length = ((size & !DATA_IS_RESIDENT) as usize).min(4);
+            return Ok(RegistryValue::from_raw(value_type, bytes_at(value, 8, length)?));
+        }
+
+        let length = size as usize;
+        if length == 0 {
+            return Ok(RegistryValue::from_raw(value_type, &[]));
+        }
+        let cell = self.cell(u32_at(value, 8)?)?;
+
+        // Anything bigger than one segment is split up and listed by a "db" cell
+        if length > BIG_DATA_SEGMENT && cell.len() >= 8 && &cell[0..2] == b"db" {
+            let segments = u16_at(cell, 2)? as usize;
+            let list = self.cell(u32_at(cell, 4)?)?;
+            // The length comes from the file, so only trust it as far as the segments can hold
+            let mut data = Vec::with_capacity(length.min(segments * BIG_DATA_SEGMENT));
+            for i in 0..segments {
+                let segment = self.cell(u32_at(list, i * 4)?)?;
+                let take = (length - data.len()).min(BIG_DATA_SEGMENT).min(segment.len());
+                data.extend_from_slice(&segment[..take]);
+            }
+            if data.len() < length {
+                return Err(corrupt(format!("big data value says {} bytes but its segments hold {}", length, data.len())));
+            }
+            return Ok(RegistryValue::from_raw(value_type, &data));
+        }
+
+        Ok(RegistryValue::from_raw(value_type, bytes_at(cell, 0, length)?))
+    }
+}
+
+/// Compressed names are one byte per character (Latin-1), everything else is UTF-16LE
+fn decode_name(bytes: &[u8], compressed: bool) -> String {
+    if compressed {
+        bytes.iter().map(|&byte| byte as char).collect()
+    } else {
+        let wide: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&wide)
+    }
+}
+
+/// A hive file that doesn't hold together
+fn corrupt(reason: impl Into<String>) -> ShugoError {
+    ShugoError::parse("registry hive", reason)
+}
+
+fn bytes_at(data: &[u8], start: usize, length: usize) -> SourceResult<&[u8]> {
+    data.get(start..start + length).ok_or_else(|| corrupt("read past the end of a cell"))
+}
+
+fn u16_at(data: &[u8], start: usize) -> SourceResult<u16> {
+    let bytes = bytes_at(data, start, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], start: usize) -> SourceResult<u32> {
+    let bytes = bytes_at(data, start, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn i32_at(data: &[u8], start: usize) -> SourceResult<i32> {
+    Ok(u32_at(data, start)? as i32)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::common::source::{REG_BINARY, REG_DWORD, REG_SZ};
+
+    /// Builds a small but valid hive in memory for tests
+    pub(crate) struct HiveBuilder {
+        cells: Vec<u8>,
+        list: ListKind
+    }
+
+    /// Which kind of subkey list the builder writes
+    #[derive(Clone, Copy)]
+    pub(crate) enum ListKind {
+        Lf,
+        Li,
+        /// An index root over two `li` lists, each holding half the subkeys
+        Ri
+    }
+
+    pub(crate) struct TestKey<'a> {
+        pub name: &'a str,
+        pub values: Vec<(&'a str, u32, Vec<u8>)>,
+        pub children: Vec<TestKey<'a>>
+    }
+
+    impl HiveBuilder {
+        pub(crate) fn build(root: &TestKey) -> Vec<u8> {
+            HiveBuilder::build_with(root, ListKind::Lf)
+        }
+
+        pub(crate) fn build_with(root: &TestKey, list: ListKind) -> Vec<u8> {
+            let mut builder = HiveBuilder { cells: Vec::new(), list };
+            let root_offset = builder.key(root, 0);
+
+            let mut hbin = b"hbin".to_vec();
+            hbin.extend_from_slice(&0u32.to_le_bytes());
+            let size = ((builder.cells.len() + 32).div_ceil(4096) * 4096) as u32;
+            hbin.extend_from_slice(&size.to_le_bytes());
+            hbin.resize(32, 0);
+            let mut bins = hbin;
+            bins.extend_from_slice(&builder.cells);
+            bins.resize(size as usize, 0);
+
+            let mut data = vec![0u8; BASE_BLOCK_SIZE];
+            data[0..4].copy_from_slice(b"regf");
+            data[0x24..0x28].copy_from_slice(&(root_offset).to_le_bytes());
+            data[0x28..0x2C].copy_from_slice(&size.to_le_bytes());
+            data.extend_from_slice(&bins);
+            data
+        }
+
+        /// Adds a cell and returns its offset (counted from the start of the hbin)
+        fn cell(&mut self, body: &[u8]) -> u32 {
+            let offset = (32 + self.cells.len()) as u32;
+            let size = (body.len() + 4).div_ceil(8) * 8;
+            self.cells.extend_from_slice(&(-(size as i32)).to_le_bytes());
+            self.cells.extend_from_slice(body);
+            self.cells.resize(self.cells.len() + size - 4 - body.len(), 0);
+            offset
+        }
+
+        fn key(&mut self, key: &TestKey, parent: u32) -> u32 {
+            let children: Vec<u32> = key.children.iter().map(|child| self.key(child, parent)).collect();
+            let list = if children.is_empty() {
+                u32::MAX
+            } else {
+                match self.list {
+                    ListKind::Lf => {
+                        let mut body = b"lf".to_vec();
+                        body.extend_from_slice(&(children.len() as u16).to_le_bytes());
+                        for child in &children {
+                            body.extend_from_slice(&child.to_le_bytes());
+                            body.extend_from_slice(&0u32.to_le_bytes());
+                        }
+                        self.cell(&body)
+                    },
+                    ListKind::Li => self.offset_list(b"li", &children),
+                    ListKind::Ri => {
+                        let (first, second) = children.split_at(children.len() / 2);
+                        let lists = [self.offset_list(b"li", first), self.offset_list(b"li", second)];
+                        self.offset_list(b"ri", &lists)
+                    }
+                }
+            };
+
+            let values: Vec<u32> = key.values.iter().map(|(name, value_type, data)| {
+                let data_offset = if data.len() <= 4 {
+                    let mut inline = [0u8; 4];
+                    inline[..data.len()].copy_from_slice(data);
+                    (u32::from_le_bytes(inline), data.len() as u32 | DATA_IS_RESIDENT)
+                } else if data.len() > BIG_DATA_SEGMENT {
+                    // Big data: a "db" cell pointing at a list of segment cells
+                    let segments: Vec<u32> = data.chunks(BIG_DATA_SEGMENT).map(|segment| self.cell(segment)).collect();
+                    let list: Vec<u8> = segments.iter().flat_map(|offset| offset.to_le_bytes()).collect();
+                    let list = self.cell(&list);
+                    let mut body = b"db".to_vec();
+                    body.extend_from_slice(&(segments.len() as u16).to_le_bytes());
+                    body.extend_from_slice(&list.to_le_bytes());
+                    (self.cell(&body), data.len() as u32)
+                } else {
+                    (self.cell(data), data.len() as u32)
+                };
+                let mut body = b"vk".to_vec();
+                body.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                body.extend_from_slice(&data_offset.1.to_le_bytes());
+                body.extend_from_slice(&data_offset.0.to_le_bytes());
+                body.extend_from_slice(&value_type.to_le_bytes());
+                body.extend_from_slice(&VALUE_COMP_NAME.to_le_bytes());
+                body.extend_from_slice(&0u16.to_le_bytes());
+                body.extend_from_slice(name.as_bytes());
+                self.cell(&body)
+            }).collect();
+            let value_list = if values.is_empty() {
+                u32::MAX
+            } else {
+                let body: Vec<u8> = values.iter().flat_map(|offset| offset.to_le_bytes()).collect();
+                self.cell(&body)
+            };
+
+            let mut body = vec![0u8; 76];
+            body[0..2].copy_from_slice(b"nk");
+            body[2..4].copy_from_slice(&KEY_COMP_NAME.to_le_bytes());
+            body[16..20].copy_from_slice(&parent.to_le_bytes());
+            body[20..24].copy_from_slice(&(children.len() as u32).to_le_bytes());
+            body[28..32].copy_from_slice(&list.to_le_bytes());
+            body[36..40].copy_from_slice(&(values.len() as u32).to_le_bytes());
+            body[40..44].copy_from_slice(&value_list.to_le_bytes());
+            body[72..74].copy_from_slice(&(key.name.len() as u16).to_le_bytes());
+            body.extend_from_slice(key.name.as_bytes());
+            self.cell(&body)
+        }
+    }
+
+    impl HiveBuilder {
+        /// An "li" or "ri" list, which is just a count and offsets
+        fn offset_list(&mut self, signature: &[u8; 2], offsets: &[u32]) -> u32 {
+            let mut body = signature.to_vec();
+            body.extend_from_slice(&(offsets.len() as u16).to_le_bytes());
+            for offset in offsets {
+                body.extend_from_slice(&offset.to_le_bytes());
+            }
+            self.cell(&body)
+        }
+    }
+
+    pub(crate) fn dword(value: u32) -> Vec<u8> {
+        value.to_le_bytes().to_vec()
+    }
+
+    pub(crate) fn wide(text: &str) -> Vec<u8> {
+        text.encode_utf16().chain([0]).flat_map(|unit| unit.to_le_bytes()).collect()
+    }
+
+    fn sample() -> HiveFile {
+        HiveFile::from_bytes(HiveBuilder::build(&sample_root())).unwrap()
+    }
+
+    fn sample_root() -> TestKey<'static> {
+        TestKey {
+            name: "ROOT",
+            values: vec![],
+            children: vec![TestKey {
+                name: "Microsoft",
+                values: vec![("Owner", REG_SZ, wide("Contoso Incident Response"))],
+                children: vec![TestKey {
+                    name: "Policies",
+                    values: vec![("EnableLUA", REG_DWORD, dword(1)), ("ConsentPromptBehaviorAdmin", REG_DWORD, dword(0))],
+                    children: vec![]
+                }]
+            }]
+        }
+    }
+
+    fn children(names: &[&'static str]) -> TestKey<'static> {
+        TestKey {
+            name: "ROOT",
+            values: vec![],
+            children: names.iter().map(|name| TestKey { name, values: vec![], children: vec![] }).collect()
+        }
+    }
+
+    #[test]
+    fn reads_values_by_path() {
+        let hive = sample();
+        assert_eq!(hive.value("Microsoft\\Policies", "EnableLUA").unwrap(), Some(RegistryValue::Dword(1)));
+        assert_eq!(hive.value("microsoft\\POLICIES", "consentpromptbehavioradmin").unwrap(), Some(RegistryValue::Dword(0)));
+        assert_eq!(
+            hive.value("Microsoft", "Owner").unwrap(),
+            Some(RegistryValue::String("Contoso Incident Response".to_string()))
+        );
+        assert_eq!(hive.value("Microsoft\\Policies", "Missing").unwrap(), None);
+        assert!(hive.value("Microsoft\\Nope", "EnableLUA").is_err());
+    }
+
+    #[test]
+    fn enumerates_keys_and_values() {
+        let hive = sample();
+        assert_eq!(hive.subkeys("").unwrap(), vec!["Microsoft".to_string()]);
+        assert_eq!(hive.subkeys("Microsoft").unwrap(), vec!["Policies".to_string()]);
+        assert_eq!(hive.values("Microsoft\\Policies").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn follows_li_and_ri_lists() {
+        let names = ["Alpha", "Beta", "Gamma"];
+        for kind in [ListKind::Li, ListKind::Ri] {
+            let hive = HiveFile::from_bytes(HiveBuilder::build_with(&children(&names), kind)).unwrap();
+            assert_eq!(hive.subkeys("").unwrap(), names);
+        }
+        let hive = HiveFile::from_bytes(HiveBuilder::build_with(&sample_root(), ListKind::Ri)).unwrap();
+        assert_eq!(hive.value("Microsoft\\Policies", "EnableLUA").unwrap(), Some(RegistryValue::Dword(1)));
+    }
+
+    #[test]
+    fn rejects_looping_index_roots() {
+        let mut data = HiveBuilder::build_with(&children(&["Alpha", "Beta"]), ListKind::Ri);
+        // Point the index root's first entry back at the index root itself
+        let position = data.windows(4).position(|window| window == b"ri\x02\x00").unwrap();
+        let offset = (position - 4 - BASE_BLOCK_SIZE) as u32;
+        data[position + 4..position + 8].copy_from_slice(&offset.to_le_bytes());
+        let error = HiveFile::from_bytes(data).unwrap().subkeys("").unwrap_err();
+        assert!(error.to_string().contains("listed twice"));
+    }
+
+    #[test]
+    fn reads_big_data_values() {
+        let big: Vec<u8> = (0..BIG_DATA_SEGMENT * 2 + 100).map(|i| (i % 251) as u8).collect();
+        let root = TestKey { name: "ROOT", values: vec![("Blob", REG_BINARY, big.clone())], children: vec![] };
+        let hive = HiveFile::from_bytes(HiveBuilder::build(&root)).unwrap();
+        assert_eq!(hive.value("", "Blob").unwrap(), Some(RegistryValue::Binary(big)));
+    }
+
+    #[test]
+    fn rejects_big_data_longer_than_its_segments() {
+        let big = vec![7u8; BIG_DATA_SEGMENT * 2];
+        let root = TestKey { name: "ROOT", values: vec![("Blob", REG_BINARY, big)], children: vec![] };
+        let mut data = HiveBuilder::build(&root);
+        // Claim almost 2 GB in the value's size field
+        let position = data.windows(4).position(|window| window == b"vk\x04\x00").unwrap();
+        data[position + 4..position + 8].copy_from_slice(&0x7FFF_FFF0u32.to_le_bytes());
+        let error = HiveFile::from_bytes(data).unwrap().value("", "Blob").unwrap_err();
+        assert!(error.to_string().contains("segments hold"));
+    }
+
+    #[test]
+    fn rejects_non_hives() {
+        assert!(HiveFile::from_bytes(vec![0u8; 10]).is_err());
+        assert!(HiveFile::from_bytes(vec![0u8; BASE_BLOCK_SIZE]).is_err());
+    }
+}
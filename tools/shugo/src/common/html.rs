@@ -0,0 +1,309 @@
+//! The single-file HTML report. It's built from the same JSON the `--format json` output uses, so
+//! every module gets a section without writing its own HTML: findings become cards with a severity
+//! badge and an expandable "why this matters / how to fix", `decoded` values become a table, and
+//! everything else is laid out as tables and lists.
+use serde_json::{Map, Value};
+
+use super::time::{format_unix_time, unix_time};
+
+/// One module's part of the report
+pub struct HtmlSection {
+    pub title: String,
+    pub content: Value
+}
+
+impl HtmlSection {
+    pub fn new(title: &str, content: Value) -> Self {
+        Self { title: title.to_string(), content }
+    }
+}
+
+/// Builds a report for a single module
+pub fn html_report(module: &str, result: &Value) -> String {
+    html_document(&format!("Shugo {} Report", module_title(module)), &[], &[HtmlSection::new(&module_title(module), result.clone())])
+}
+
+/// Builds a complete HTML page. `summary` rows are shown at the top before the sections
+pub fn html_document(title: &str, summary: &[(String, String)], sections: &[HtmlSection]) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    out.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", escape(title), STYLE));
+    out.push_str(&format!("<header><h1>{}</h1><p class=\"meta\">Generated {}</p></header>\n", escape(title), format_unix_time(unix_time())));
+
+    if !summary.is_empty() {
+        out.push_str("<section class=\"summary\"><table>");
+        for (label, value) in summary {
+            out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>", escape(label), escape(value)));
+        }
+        out.push_str("</table></section>\n");
+    }
+
+    if sections.len() > 1 {
+        out.push_str("<nav><ul>");
+        for (i, section) in sections.iter().enumerate() {
+            out.push_str(&format!("<li><a href=\"#section-{}\">{}</a></li>", i, escape(&section.title)));
+        }
+        out.push_str("</ul></nav>\n");
+    }
+
+    for (i, section) in sections.iter().enumerate() {
+        out.push_str(&format!("<section id=\"section-{}\"><h2>{}</h2>\n", i, escape(&section.title)));
+        render_section(&section.content, &mut out);
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("<footer>Shugo is an educational tool, findings are a starting point and not a full security review.</footer>\n");
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Renders a module's JSON, findings first since they're what a reader came for
+fn render_section(content: &Value, out: &mut String) {
+    let Value::Object(map) = content else {
+        render_value(content, out);
+        return;
+    };
+
+    if let Some(Value::Array(findings)) = map.get("findings") {
+        render_findings(findings, out);
+    }
+
+    let rest: Map<String, Value> = map
+        .iter()
+        .filter(|(key, _)| key.as_str() != "findings")
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    render_object(&rest, out);
+}
+
+fn render_findings(findings: &[Value], out: &mut String) {
+    out.push_str("<h3>Security Assessment</h3>\n");
+    if findings.is_empty() {
+        out.push_str("<p><span class=\"badge pass\">PASS</span> No significant weaknesses detected</p>\n");
+        return;
+    }
+
+    for finding in findings {
+        let text = |key: &str| finding.get(key).and_then(Value::as_str).unwrap_or_default();
+        let severity = text("severity");
+
+        out.push_str(&format!("<div class=\"finding {}\">", escape(severity)));
+        out.push_str(&format!(
+            "<p><span class=\"badge {}\">{}</span> <strong>{}</strong> <code>{}</code></p>",
+            escape(severity),
+            escape(&severity.to_uppercase()),
+            escape(text("title")),
+            escape(text("id"))
+        ));
+        out.push_str("<details><summary>Why this matters / how to fix</summary>");
+        if !text("explanation").is_empty() {
+            out.push_str(&format!("<p><b>Why this matters:</b> {}</p>", escape(text("explanation"))));
+        }
+        if !text("remediation").is_empty() {
+            out.push_str(&format!("<p><b>How to fix:</b> {}</p>", escape(text("remediation"))));
+        }
+        if let Some(Value::Array(evidence)) = finding.get("evidence")
+            && !evidence.is_empty() {
+            out.push_str("<p><b>Evidence:</b></p><ul>");
+            for item in evidence {
+                out.push_str(&format!("<li><code>{}</code></li>", escape(&scalar(item))));
+            }
+            out.push_str("</ul>");
+        }
+        out.push_str("</details></div>\n");
+    }
+}
+
+/// Scalars go in one key/value table, nested values get their own heading underneath
+fn render_object(map: &Map<String, Value>, out: &mut String) {
+    let scalars: Vec<(&String, &Value)> = map.iter().filter(|(_, value)| !is_nested(value)).collect();
+    if !scalars.is_empty() {
+        out.push_str("<table>");
+        for (key, value) in scalars {
+            out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>", escape(&humanize(key)), escape(&scalar(value))));
+        }
+        out.push_str("</table>\n");
+    }
+
+    for (key, value) in map.iter().filter(|(_, value)| is_nested(value)) {
+        // Technical details are collapsed so they don't crowd out the results
+        if key == "module_info" {
+            out.push_str("<details><summary>Technical details</summary>");
+            render_value(value, out);
+            out.push_str("</details>\n");
+            continue;
+        }
+        let heading = if key == "decoded" {"What These Values Mean".to_string()} else {module_title(key)};
+        out.push_str(&format!("<h3>{}</h3>\n", escape(&heading)));
+        render_value(value, out);
+    }
+}
+
+fn render_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => render_object(map, out),
+        Value::Array(items) if items.is_empty() => out.push_str("<p class=\"meta\">None</p>\n"),
+        Value::Array(items) if items.iter().all(Value::is_object) => render_table(items, out),
+        Value::Array(items) => {
+            out.push_str("<ul>");
+            for item in items {
+                out.push_str(&format!("<li>{}</li>", escape(&scalar(item))));
+            }
+            out.push_str("</ul>\n");
+        },
+        _ => out.push_str(&format!("<p>{}</p>\n", escape(&scalar(value))))
+    }
+}
+
+/// Lists of objects (products, accounts, updates) become one table, with `decoded` values as extra columns
+fn render_table(items: &[Value], out: &mut String) {
+    let rows: Vec<Vec<(String, String)>> = items
+        .iter()
+        .filter_map(Value::as_object)
+        .map(|item| {
+            let mut cells: Vec<(String, String)> = item
+                .iter()
+                .filter(|(key, _)| key.as_str() != "decoded")
+                .map(|(key, value)| (humanize(key), scalar(value)))
+                .collect();
+            if let Some(Value::Object(decoded)) = item.get("decoded") {
+                for (name, meaning) in decoded {
+                    cells.push((format!("{} (meaning)", humanize(name)), scalar(meaning)));
+                }
+            }
+            cells
+        })
+        .collect();
+
+    let mut columns: Vec<String> = Vec::new();
+    for row in &rows {
+        for (column, _) in row {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+    }
+
+    out.push_str("<div class=\"scroll\"><table class=\"list\"><tr>");
+    for column in &columns {
+        out.push_str(&format!("<th>{}</th>", escape(column)));
+    }
+    out.push_str("</tr>");
+    for row in &rows {
+        out.push_str("<tr>");
+        for column in &columns {
+            let cell = row.iter().find(|(name, _)| name == column).map(|(_, value)| value.as_str()).unwrap_or("");
+            out.push_str(&format!("<td>{}</td>", escape(cell)));
+        }
+        out.push_str("</tr>");
+    }
+    out.push_str("</table></div>\n");
+}
+
+fn is_nested(value: &Value) -> bool {
+    matches!(value, Value::Object(_)) || matches!(value, Value::Array(items) if items.iter().any(|item| item.is_object()))
+}
+
+/// A value as plain text, nested values are summarized
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "Unknown".to_string(),
+        Value::Bool(true) => "Yes".to_string(),
+        Value::Bool(false) => "No".to_string(),
+        Value::String(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+        Value::Array(items) => items.iter().map(scalar).collect::<Vec<_>>().join(", "),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| format!("{}: {}", humanize(key), scalar(value)))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// `profile_enabled` -> `Profile enabled`
+fn humanize(key: &str) -> String {
+    let text = key.replace('_', " ");
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text
+    }
+}
+
+/// `uac` -> `UAC`, `antivirus` -> `Antivirus`
+pub fn module_title(module: &str) -> String {
+    match module {
+        "uac" | "uas" | "rdp" | "smb" => module.to_uppercase(),
+        _ => humanize(module)
+    }
+}
+
+/// Escapes text for use inside HTML elements and attributes
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+const STYLE: &str = "
+body{font-family:Segoe UI,Helvetica,Arial,sans-serif;max-width:960px;margin:0 auto;padding:1.5rem;color:#1f2328;background:#fafbfc;line-height:1.45}
+header h1{margin-bottom:0}.meta{color:#656d76;margin-top:.25rem}
+section{background:#fff;border:1px solid #d0d7de;border-radius:8px;padding:1rem 1.25rem;margin:1rem 0}
+h2{margin-top:0;border-bottom:1px solid #d0d7de;padding-bottom:.4rem}h3{margin-bottom:.4rem}
+table{border-collapse:collapse;margin:.5rem 0}th,td{text-align:left;padding:.3rem .7rem;border-bottom:1px solid #eaeef2;vertical-align:top}
+th{color:#424a53;font-weight:600}.list th{background:#f6f8fa}.scroll{overflow-x:auto}
+nav ul{list-style:none;padding:0;display:flex;flex-wrap:wrap;gap:.5rem}nav a{color:#0969da}
+.finding{border-left:4px solid #8c959f;padding:.1rem .8rem;margin:.6rem 0;background:#f6f8fa;border-radius:0 6px 6px 0}
+.finding.critical{border-color:#a40e26}.finding.high{border-color:#cf222e}.finding.medium{border-color:#bf8700}.finding.low{border-color:#0969da}
+.badge{display:inline-block;padding:.05rem .5rem;border-radius:1rem;font-size:.75rem;font-weight:700;color:#fff;background:#8c959f}
+.badge.critical{background:#a40e26}.badge.high{background:#cf222e}.badge.medium{background:#bf8700}.badge.low{background:#0969da}.badge.pass{background:#1a7f37}
+details{margin:.3rem 0 .6rem}summary{cursor:pointer;color:#0969da}
+footer{color:#656d76;font-size:.85rem;margin-top:2rem}
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_findings_and_escapes() {
+        let html = html_report("uac", &json!({
+            "uac": {"lua_value": 0, "decoded": {"uac_status": "Disabled"}},
+            "findings": [{
+                "id": "UAC-001",
+                "severity": "critical",
+                "title": "UAC <is> disabled",
+                "explanation": "Everything runs as admin",
+                "remediation": "Set EnableLUA to 1",
+                "evidence": ["EnableLUA=0"]
+            }]
+        }));
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<span class=\"badge critical\">CRITICAL</span>"));
+        assert!(html.contains("UAC &lt;is&gt; disabled"));
+        assert!(html.contains("<summary>Why this matters / how to fix</summary>"));
+        assert!(html.contains("What These Values Mean"));
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn turns_object_lists_into_tables() {
+        let mut out = String::new();
+        render_value(&json!([{"name": "Defender", "decoded": {"status": "On"}}, {"name": "Other"}]), &mut out);
+        assert!(out.contains("<th>Status (meaning)</th>"));
+        assert!(out.contains("<td>Other</td><td></td>"));
+    }
+}
@@ -0,0 +1,146 @@
+//! JUnit XML output. CI systems that don't know SARIF nearly all know JUnit, so each check becomes a
+//! test case: a finding is a failed test, a module with no findings is a passed test, and a module that
+//! couldn't run is a skipped test.
+use super::finding::{Finding, Severity};
+use super::html;
+
+pub enum TestOutcome {
+    Passed,
+    Failed { message: String, details: String },
+    Skipped(String)
+}
+
+pub struct TestCase {
+    /// Groups test cases into suites, Shugo uses the module name
+    pub classname: String,
+    pub name: String,
+    pub outcome: TestOutcome
+}
+
+/// Test cases for one module's findings. Info findings are reported as passing
+pub fn finding_cases(module: &str, findings: &[Finding]) -> Vec<TestCase> {
+    let mut cases: Vec<TestCase> = findings
+        .iter()
+        .filter(|finding| finding.module == module)
+        .map(|finding| TestCase {
+            classname: module.to_string(),
+            name: format!("{}: {}", finding.id, finding.title),
+            outcome: if finding.severity == Severity::Info {
+                TestOutcome::Passed
+            } else {
+                TestOutcome::Failed {
+                    message: format!("[{}] {}", finding.severity, finding.title),
+                    details: finding_details(finding)
+                }
+            }
+        })
+        .collect();
+
+    if cases.is_empty() {
+        cases.push(TestCase {
+            classname: module.to_string(),
+            name: format!("{}: no findings", module),
+            outcome: TestOutcome::Passed
+        });
+    }
+    cases
+}
+
+fn finding_details(finding: &Finding) -> String {
+    let mut details = Vec::new();
+    if !finding.explanation.is_empty() {
+        details.push(format!("Why: {}", finding.explanation));
+    }
+    if !finding.remediation.is_empty() {
+        details.push(format!("Fix: {}", finding.remediation));
+    }
+    for evidence in &finding.evidence {
+        details.push(format!("Evidence: {}", evidence));
+    }
+    details.join("\n")
+}
+
+/// Escapes text for XML 1.0. Registry strings and command lines in evidence can hold control characters
+/// XML forbids even as `&#..;` references, and CI parsers reject the whole file over one, so those become U+FFFD
+fn escape(text: &str) -> String {
+    let allowed = |c: char| matches!(c, '\t' | '\n' | '\r') || (c >= ' ' && !matches!(c, '\u{FFFE}' | '\u{FFFF}'));
+    let cleaned: String = text.chars().map(|c| if allowed(c) {c} else {char::REPLACEMENT_CHARACTER}).collect();
+    html::escape(&cleaned)
+}
+
+/// Writes test cases as a JUnit XML document, one `testsuite` per classname
+pub fn junit_xml(name: &str, cases: &[TestCase]) -> String {
+    let count = |cases: &[&TestCase], check: fn(&TestOutcome) -> bool| cases.iter().filter(|case| check(&case.outcome)).count();
+    let failed = |outcome: &TestOutcome| matches!(outcome, TestOutcome::Failed { .. });
+    let skipped = |outcome: &TestOutcome| matches!(outcome, TestOutcome::Skipped(_));
+
+    let mut suites: Vec<&str> = Vec::new();
+    for case in cases {
+        if !suites.contains(&case.classname.as_str()) {
+            suites.push(&case.classname);
+        }
+    }
+
+    let all: Vec<&TestCase> = cases.iter().collect();
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" errors=\"0\">\n",
+        escape(name), all.len(), count(&all, failed), count(&all, skipped)
+    ));
+
+    for suite in suites {
+        let suite_cases: Vec<&TestCase> = cases.iter().filter(|case| case.classname == suite).collect();
+        out.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" errors=\"0\">\n",
+            escape(suite), suite_cases.len(), count(&suite_cases, failed), count(&suite_cases, skipped)
+        ));
+        for case in suite_cases {
+            let open = format!("    <testcase classname=\"{}\" name=\"{}\"", escape(&case.classname), escape(&case.name));
+            match &case.outcome {
+                TestOutcome::Passed => out.push_str(&format!("{}/>\n", open)),
+                TestOutcome::Failed { message, details } => out.push_str(&format!(
+                    "{}>\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                    open, escape(message), escape(details)
+                )),
+                TestOutcome::Skipped(reason) => out.push_str(&format!(
+                    "{}>\n      <skipped message=\"{}\"/>\n    </testcase>\n",
+                    open, escape(reason)
+                ))
+            }
+        }
+        out.push_str("  </testsuite>\n");
+    }
+
+    out.push_str("</testsuites>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_suites_per_module() {
+        let findings = vec![Finding::new("UAC-001", "uac", Severity::Critical, "UAC is disabled").evidence("EnableLUA=0")];
+        let mut cases = finding_cases("uac", &findings);
+        cases.extend(finding_cases("uas", &findings));
+        cases.push(TestCase { classname: "updates".to_string(), name: "updates: scan".to_string(), outcome: TestOutcome::Skipped("no <WUA>".to_string()) });
+
+        let xml = junit_xml("Shugo", &cases);
+        assert!(xml.contains("<testsuites name=\"Shugo\" tests=\"3\" failures=\"1\" skipped=\"1\" errors=\"0\">"));
+        assert!(xml.contains("<failure message=\"[CRITICAL] UAC is disabled\">Evidence: EnableLUA=0</failure>"));
+        assert!(xml.contains("<testcase classname=\"uas\" name=\"uas: no findings\"/>"));
+        assert!(xml.contains("<skipped message=\"no &lt;WUA&gt;\"/>"));
+    }
+
+    #[test]
+    fn replaces_characters_xml_forbids() {
+        assert_eq!(escape("a\u{0}b\u{1b}[0m\u{FFFF}"), "a\u{FFFD}b\u{FFFD}[0m\u{FFFD}");
+        assert_eq!(escape("tab\tline\r\n<\u{e9}>"), "tab\tline\r\n&lt;\u{e9}&gt;");
+
+        let findings = vec![Finding::new("STU-001", "startup", Severity::High, "Run Key x").evidence("command=evil.exe\u{7}")];
+        let xml = junit_xml("Shugo", &finding_cases("startup", &findings));
+        assert!(xml.contains("Evidence: command=evil.exe\u{FFFD}</failure>"));
+        assert!(!xml.chars().any(|c| c < ' ' && !matches!(c, '\t' | '\n' | '\r')));
+    }
+}
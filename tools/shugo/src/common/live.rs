@@ -0,0 +1,895 @@
+//! This is the live Windows source for Shugo. Every COM, WMI, Registry, and NetAPI call the
+//! scanners need lives in here, and the scanners only see the plain Rust data we hand back.
+//!
+//! To grab this information, we will have to know how to use COM, WMI, and Memory Management.
+//! That may seem like a lot but once you see how it works, it should click. Lets get started:
+use windows::core::*;
+use windows::Win32::Foundation::*;
+use windows::Win32::System::Com::*;
+use windows::Win32::System::Wmi::*;
+use windows::Win32::System::Registry::*;
+use windows::Win32::System::Ole::IEnumVARIANT;
+use windows::Win32::System::Variant::{VARIANT, VT_DISPATCH, VariantClear};
+use windows::Win32::System::UpdateAgent::*;
+use windows::Win32::NetworkManagement::WindowsFirewall::*;
+use windows::Win32::NetworkManagement::NetManagement::*;
+use windows::Win32::Storage::FileSystem::{NetShareEnum, SHARE_INFO_502};
+use windows::Win32::Security::{GetSecurityDescriptorLength, IsValidSecurityDescriptor};
+
+use super::com::ComGuard;
+use super::error::ShugoError;
+use super::source::*;
+use super::wmi_helpers::{object_properties, decimal_to_u128, to_variant};
+use crate::firewall::scanner::{FirewallRule, WindowsFirewallProfile, FirewallProfileDetails};
+
+/// Reads straight from the Windows machine Shugo is running on
+#[derive(Default)]
+pub struct LiveSource;
+
+impl LiveSource {
+    pub fn new() -> Self {
+        LiveSource
+    }
+}
+
+impl SecuritySource for LiveSource {
+    fn wmi_query(&self, namespace: &str, query: &str) -> SourceResult<Vec<WmiRow>> {
+        // We'll be using the unsafe method a lot because were using foreign functions that
+        // the Rust compiler can't check. This is our way of saying to Rust "Don't worry, we -
+        // made sure this is handled safely" at least I'm sure it's safe.
+        unsafe {
+            let _com = ComGuard::new(query)?; // Closes COM when this block ends, even on an early return
+
+            // This vector will be used to hold every object our query returns
+            let mut rows: Vec<WmiRow> = Vec::new();
+
+            let result: Result<()> = (|| {
+                /*
+                    Shugo: Object Management
+
+                    You might be wondering why were using a closure here. This scope will manage our objects we call using
+                    `CoCreateInstance` and drop them when they leave this scope.
+
+                    "Why do we need to drop them?":
+                    If we don't drop these objects, we can't close our thread. This turns into a memory leak which we don't want.
+
+                    "Shouldn't Rust be able to destroy these objects?":
+                    Rust will handle these objects by itself but it causes problems. When Rust destroys the objects, Windows still
+                    thinks their active, making us unable to close the thread. So we drop them before `CoUninitialize` is called.
+
+                    For more information on `CoCreateInstance`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/combaseapi/nf-combaseapi-cocreateinstance) - C++
+                    (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Com/fn.CoCreateInstance.html) - Rust
+                */
+                let locator: IWbemLocator = CoCreateInstance(
+                    &WbemLocator, // The CLSID associated with the data and code that will be used to create the object
+                    None, // Leaving NULL because object is not being created as part of an aggregate
+                    CLSCTX_INPROC_SERVER // The context in which the code that manages the newly created object will run
+                )?;
+
+                /*
+                    Shugo: WMI Connection
+
+                    We'll be using the `ConnectServer` method to make a connection through DCOM to a WMI namespace on the computer.
+                    The namespace needs to be in `BSTR` format otherwise it won't work. Most of the arguments in `ConnectServer`
+                    must be in `BSTR` format besides two.
+
+                    For more information on `ConnectServer`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/wbemcli/nf-wbemcli-iwbemlocator-connectserver) - C++
+                    (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Wmi/trait.IWbemLocator_Impl.html#tymethod.ConnectServer) - Rust
+                */
+                let services: IWbemServices = locator.ConnectServer(
+                    &BSTR::from(namespace), // This is the pointer to the specified namespace
+                    &BSTR::default(), // user name for the connection, we'll use '&BSTR::default()' as NULL for this pointer
+                    &BSTR::default(), // Password for the connection
+                    &BSTR::default(), // locale for connection
+                    0, // This is for flags. we'll use '0' for this value because it will return the call from `ConnectServer` only after its established
+                    &BSTR::default(), // This can contain the name of the domain of the user to authenticate
+                    None // This is usually NULL
+                )?;
+
+                /*
+                    Shugo: Connection Security
+
+                    Some namespaces, like BitLocker's ROOT\CIMV2\Security\MicrosoftVolumeEncryption, refuse any connection
+                    that isn't encrypted and answer with WBEM_E_ENCRYPTED_CONNECTION_REQUIRED. `CoSetProxyBlanket` sets the
+                    security on our `services` proxy, and RPC_C_AUTHN_LEVEL_PKT_PRIVACY encrypts every call made through it.
+                    Namespaces that don't need it accept it too, so every connection gets the same settings.
+
+                    For more information on `CoSetProxyBlanket`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/combaseapi/nf-combaseapi-cosetproxyblanket) - C++
+                    (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Com/fn.CoSetProxyBlanket.html) - Rust
+                */
+                set_proxy_security(&services)?;
+
+                /*
+                    Shugo: Querying Objects
+
+                    We can now look for the objects we want using the `ExecQuery` method. For example, the Antivirus Module wants
+                    the Antivirus Products names and state so it asks for "Select displayName, productState FROM AntiVirusProduct".
+
+                    For more information on `ExecQuery`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/wbemcli/nf-wbemcli-iwbemservices-execquery) - C++
+                    (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Wmi/struct.IWbemServices.html#method.ExecQuery) - Rust
+                */
+                let enum_object: IEnumWbemClassObject = services.ExecQuery(
+                    &BSTR::from("WQL"), // This specifies the query language to use supported by Windows and it MUST be "WQL", the acronym for WMI Query Language.
+                    &BSTR::from(query), // This is where the query search will go. It cannot be NULL
+                    WBEM_FLAG_RETURN_IMMEDIATELY | WBEM_FLAG_FORWARD_ONLY, // This is where flags go and they affect the behavior of this method
+                    None // This is usually NULL
+                )?;
+
+                loop {
+                    /*
+                        Shugo: Grabing Object Information
+
+                        We finally have the information we need from our query, now we need to extract it so we can use it.
+                        Were using the `Next` method for `IWbemClassObject` to grab our objects. We'll also use a helper function
+                        that you can find in `tools\shugo\common\wmi_helpers`. Go check it out to see how we convert `VARIANT` and `BSTR`.
+
+                        For more information on `Next`:
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wbemcli/nf-wbemcli-ienumwbemclassobject-next) - C++
+                        (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Wmi/struct.IEnumWbemClassObject.html) - Rust
+                    */
+                    let mut objects: [Option<IWbemClassObject>; 1] = [None; 1];
+                    let mut returned = 0;
+                    // WBEM_S_FALSE (fewer objects than asked for) still counts as success, anything failing would otherwise look like the end of the results
+                    enum_object.Next(
+                        WBEM_INFINITE, // This specifies the maximum amount of time in milliseconds that the call blocks before returning. I stole this line from the page
+                        &mut objects, // This should point to a storage to hold the number of IWbemClassObject interface pointers specified by uCount
+                        &mut returned // This receives the number of objects returned.
+                    ).ok()?;
+                    if returned == 0 {
+                        break;
+                    }
+                    if let Some(class_object) = &objects[0] {
+                        rows.push(object_properties(class_object)?);
+                    }
+                }
+                Ok(())
+            })();
+
+            result.map_err(|error| api_error(query, error))?;
+            Ok(rows)
+        } // End of unsafe block
+    }
+
+    fn wmi_method(&self, namespace: &str, object_path: &str, method: &str, inputs: &[(&str, WmiValue)]) -> SourceResult<WmiRow> {
+        unsafe {
+            let _com = ComGuard::new(method)?;
+
+            let result: Result<WmiRow> = (|| {
+                // The same connection `wmi_query` makes
+                let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)?;
+                let services: IWbemServices = locator.ConnectServer(
+                    &BSTR::from(namespace),
+                    &BSTR::default(),
+                    &BSTR::default(),
+                    &BSTR::default(),
+                    0,
+                    &BSTR::default(),
+                    None
+                )?;
+                set_proxy_security(&services)?;
+
+                /*
+                    Shugo: Calling A WMI Method
+
+                    Some information isn't a property at all, like how far along BitLocker encryption is, and can only be
+                    read by calling a method on the object. That takes three steps:
+
+                    1. Get the class with `GetObject` and ask it for the method's in-parameter signature with `GetMethod`.
+                    2. `SpawnInstance` an empty copy of that signature and `Put` each input into it.
+                    3. `ExecMethod` on the object path, which hands back an object holding the out-parameters.
+
+                    The out-parameters are a normal `IWbemClassObject`, so `object_properties` reads them like any query
+                    result, including `ReturnValue`.
+
+                    For more information on `ExecMethod`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/wbemcli/nf-wbemcli-iwbemservices-execmethod) - C++
+                */
+                let class_name = object_path.split('.').next().unwrap_or(object_path);
+                let mut class: Option<IWbemClassObject> = None;
+                services.GetObject(&BSTR::from(class_name), WBEM_FLAG_RETURN_WBEM_COMPLETE, None, Some(&mut class), None)?;
+                let class = class.ok_or_else(|| Error::from_hresult(HRESULT(WBEM_E_NOT_FOUND.0)))?;
+
+                let mut signature: Option<IWbemClassObject> = None;
+                class.GetMethod(&HSTRING::from(method), 0, &mut signature, std::ptr::null_mut())?;
+                let in_params = match signature {
+                    Some(signature) => {
+                        let in_params = signature.SpawnInstance(0)?;
+                        for (name, value) in inputs {
+                            let Some(mut variant) = to_variant(value) else {continue};
+                            let put = in_params.Put(&HSTRING::from(*name), 0, &variant, 0);
+                            VariantClear(&mut variant)?;
+                            put?;
+                        }
+                        Some(in_params)
+                    },
+                    None => None
+                };
+
+                let mut out_params: Option<IWbemClassObject> = None;
+                services.ExecMethod(
+                    &BSTR::from(object_path),
+                    &BSTR::from(method),
+                    WBEM_FLAG_RETURN_WBEM_COMPLETE,
+                    None,
+                    in_params.as_ref(),
+                    Some(&mut out_params),
+                    None
+                )?;
+
+                match out_params {
+                    Some(out_params) => object_properties(&out_params),
+                    None => Ok(WmiRow::new())
+                }
+            })();
+
+            result.map_err(|error| api_error(&format!("{}.{}", object_path, method), error))
+        }
+    }
+
+    fn registry_value(&self, hive: RegistryHive, key: &str, value: &str) -> SourceResult<Option<RegistryValue>> {
+        /*
+            Shugo: Using Windows Registry
+
+            The Registry is Windows heirarchical database for system configuration.
+            Modules like UAC read their settings straight from here instead of using COM/WMI.
+        */
+        unsafe {
+            /*
+                Shugo: Opening a Registry Key
+
+                We need to open the registry key that contains our settings before we can read
+                values from it. For example, the UAC Module opens:
+                HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System
+
+                Note: Registry key names are NOT case sensitive
+
+                Every registry read opens its key the same way, so that lives in `open_key` at the bottom of this file.
+
+                For more information on `RegOpenKeyExW`:
+                (https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regopenkeyexw) - C++
+                (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Registry/fn.RegOpenKeyExW.html) - Rust
+            */
+            let handle = open_key(hive, key)?;
+            let location = hive.value_path(key, value); // Used to say which value failed
+
+            /*
+                Shugo: Querying Registry Values
+
+                Now that we have the key open, we can query specific values within it. We ask `RegQueryValueExW` twice,
+                the first time with no buffer so it tells us the type and size of the data, and the second time with a
+                buffer big enough to hold it.
+
+                For more information on `RegQueryValueExW`:
+                (https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regqueryvalueexw) - C++
+                (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Registry/fn.RegQueryValueExW.html) - Rust
+            */
+            let name: HSTRING = HSTRING::from(value);
+            let mut value_type: REG_VALUE_TYPE = REG_VALUE_TYPE::default();
+            let mut size: u32 = 0;
+            let _size_query: WIN32_ERROR = RegQueryValueExW(
+                handle, // Our handle to the open registry key
+                &name, // Our registry value we want to grab
+                None, // This must be NULL
+                Some(&mut value_type), // Receives the type of data stored in the value
+                None, // No buffer yet, we only want the size
+                Some(&mut size) // Receives the size of the data in bytes
+            );
+
+            let result: SourceResult<Option<RegistryValue>> = if _size_query == ERROR_FILE_NOT_FOUND {
+                Ok(None)
+            } else if _size_query != ERROR_SUCCESS {
+                Err(api_error(&location, Error::from(_size_query)))
+            } else {
+                let mut data: Vec<u8> = vec![0; size as usize];
+                let _data_query: WIN32_ERROR = RegQueryValueExW(
+                    handle,
+                    &name,
+                    None,
+                    Some(&mut value_type),
+                    Some(data.as_mut_ptr()), // A pointer to a buffer to recieve the values data
+                    Some(&mut size) // A pointer to a variable that specifies the size of a buffer in bytes
+                );
+                if _data_query == ERROR_SUCCESS {
+                    data.truncate(size as usize);
+                    Ok(Some(RegistryValue::from_raw(value_type.0, &data)))
+                } else {
+                    Err(api_error(&location, Error::from(_data_query)))
+                }
+            };
+
+            /*
+                Shugo: Closing the Registry Key
+
+                Always close the registry keys when you're done with them. Leaving keys
+                open can cause resource leaks.
+
+                For more information on `RegCloseKey`:
+                (https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regclosekey) - C++
+                (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Registry/fn.RegCloseKey.html) - Rust
+            */
+            let _ = RegCloseKey(handle);
+            result
+        }
+    }
+
+    fn registry_values(&self, hive: RegistryHive, key: &str) -> SourceResult<Vec<(String, RegistryValue)>> {
+        /*
+            Shugo: Enumerating Registry Values
+
+            Some keys, like the Run keys, don't have fixed value names: every program adds its own. `RegEnumValueW`
+            walks the values by index until it returns ERROR_NO_MORE_ITEMS. Value names can be up to 16,383
+            characters, and if the data doesn't fit our buffer it returns ERROR_MORE_DATA with the size it needs.
+
+            For more information on `RegEnumValueW`:
+            (https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regenumvaluew) - C++
+        */
+        unsafe {
+            let handle = open_key(hive, key)?;
+            let mut values: Vec<(String, RegistryValue)> = Vec::new();
+            let mut name: Vec<u16> = vec![0; 16384];
+            let mut data: Vec<u8> = vec![0; 4096];
+            let mut index: u32 = 0;
+            let result: SourceResult<Vec<(String, RegistryValue)>> = loop {
+                let mut name_length: u32 = name.len() as u32;
+                let mut data_length: u32 = data.len() as u32;
+                let mut value_type: u32 = 0;
+                let status: WIN32_ERROR = RegEnumValueW(
+                    handle,
+                    index, // Which value to read, counting from 0
+                    Some(PWSTR(name.as_mut_ptr())), // Receives the value name
+                    &mut name_length, // In: the buffer size in characters, Out: the name length
+                    None, // Reserved
+                    Some(&mut value_type), // Receives the REG_* type
+                    Some(data.as_mut_ptr()), // Receives the data
+                    Some(&mut data_length) // In: the buffer size in bytes, Out: the data size
+                );
+                if status == ERROR_NO_MORE_ITEMS {
+                    break Ok(values);
+                } else if status == ERROR_MORE_DATA {
+                    data.resize(data_length as usize, 0);
+                    continue;
+                } else if status != ERROR_SUCCESS {
+                    break Err(api_error(&format!("{}\\{}", hive.root_name(), key), Error::from(status)));
+                }
+                let value_name = String::from_utf16_lossy(&name[..name_length as usize]);
+                values.push((value_name, RegistryValue::from_raw(value_type, &data[..data_length as usize])));
+                index += 1;
+            };
+            let _ = RegCloseKey(handle);
+            result
+        }
+    }
+
+    fn registry_subkeys(&self, hive: RegistryHive, key: &str) -> SourceResult<Vec<String>> {
+        /*
+            Shugo: Enumerating Registry Subkeys
+
+            `RegEnumKeyExW` works like `RegEnumValueW` but for subkeys, which is how we walk keys like
+            SYSTEM\CurrentControlSet\Services where every service has its own subkey. Key names are at most
+            255 characters.
+
+            For more information on `RegEnumKeyExW`:
+            (https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regenumkeyexw) - C++
+        */
+        unsafe {
+            let handle = open_key(hive, key)?;
+            let mut subkeys: Vec<String> = Vec::new();
+            let mut name: Vec<u16> = vec![0; 256];
+            let mut index: u32 = 0;
+            let result: SourceResult<Vec<String>> = loop {
+                let mut name_length: u32 = name.len() as u32;
+                let status: WIN32_ERROR = RegEnumKeyExW(
+                    handle,
+                    index,
+                    Some(PWSTR(name.as_mut_ptr())), // Receives the subkey name
+                    &mut name_length, // In: the buffer size in characters, Out: the name length
+                    None, // Reserved
+                    None, // We don't need the class
+                    None,
+                    None // Or the last write time
+                );
+                if status == ERROR_NO_MORE_ITEMS {
+                    break Ok(subkeys);
+                } else if status != ERROR_SUCCESS {
+                    break Err(api_error(&format!("{}\\{}", hive.root_name(), key), Error::from(status)));
+                }
+                subkeys.push(String::from_utf16_lossy(&name[..name_length as usize]));
+                index += 1;
+            };
+            let _ = RegCloseKey(handle);
+            result
+        }
+    }
+
+    fn firewall_policy(&self) -> SourceResult<WindowsFirewallProfile> {
+        unsafe {
+            let _com = ComGuard::new("firewall policy")?;
+
+            let result: Result<WindowsFirewallProfile> = (|| {
+                /*
+                    Shugo: Windows Firewall Policy Interface
+
+                    Unlike the Antivirus and Update modules which use WMI or WUA, we can access
+                    Windows Firewall settings directly through the INetFwPolicy2 interface.
+
+                    We use CLSCTX_ALL here because the firewall service may run in a different
+                    process, similar to the Windows Update service.
+
+                    For more information on `INetFwPolicy2`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/netfw/nn-netfw-inetfwpolicy2) - C++
+                */
+                let policy: INetFwPolicy2 = CoCreateInstance(&NetFwPolicy2, None, CLSCTX_ALL)?;
+
+                /*
+                    Shugo: Checking Firewall Profile States
+
+                    Windows Firewall has three network profiles:
+                    - Public: Used for untrusted networks (coffee shops, airports)
+                    - Private: Used for trusted home/work networks
+                    - Domain: Used when connected to a corporate domain
+
+                    Each profile can be independently enabled or disabled. We check each one using
+                    `get_FirewallEnabled` with the appropriate profile type constant.
+
+                    We then check to see the state of inbound and outbound traffic from the profiles
+                    using `get_DefaultInboundAction` and `get_DefaultOutboundAction`.
+
+                    After that, we see if notifications are disabled using `get_NotificationsDisabled`
+
+                    IMPORTANT: These states only reflect Windows Defender Firewall. Third-party
+                    firewalls (Norton, McAfee) are tracked separately through WMI.
+                */
+                let details = |profiletype: NET_FW_PROFILE_TYPE2| -> Result<FirewallProfileDetails> {
+                    Ok(FirewallProfileDetails {
+                        profile_enabled: policy.get_FirewallEnabled(profiletype)? == VARIANT_TRUE,
+                        inbound_blocked: policy.get_DefaultInboundAction(profiletype)? == NET_FW_ACTION_BLOCK,
+                        outbound_blocked: policy.get_DefaultOutboundAction(profiletype)? == NET_FW_ACTION_BLOCK,
+                        notifications_disabled: policy.get_NotificationsDisabled(profiletype)? == VARIANT_TRUE
+                    })
+                };
+
+                Ok(WindowsFirewallProfile {
+                    public: details(NET_FW_PROFILE2_PUBLIC)?,
+                    private: details(NET_FW_PROFILE2_PRIVATE)?,
+                    domain: details(NET_FW_PROFILE2_DOMAIN)?
+                })
+            })();
+
+            result.map_err(|error| api_error("firewall policy", error))
+        }
+    }
+
+    fn firewall_rules(&self) -> SourceResult<Vec<FirewallRule>> {
+        unsafe {
+            let _com = ComGuard::new("firewall rules")?;
+
+            let result: Result<Vec<FirewallRule>> = (|| {
+                /*
+                    Shugo: Walking Through The Firewall Rules
+
+                    `INetFwPolicy2::Rules` gives us an `INetFwRules` collection. COM collections don't hand out a Rust
+                    iterator, instead `_NewEnum` gives an `IEnumVARIANT` we call `Next` on until it stops filling in
+                    VARIANTs. Each VARIANT holds an `IDispatch` that we cast to `INetFwRule` to read the rule.
+
+                    Just like the WMI VARIANTs, every one we get back has to be cleared with `VariantClear`, otherwise
+                    the rule object it holds is never released.
+
+                    For more information on `INetFwRule`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/netfw/nn-netfw-inetfwrule) - C++
+                */
+                let policy: INetFwPolicy2 = CoCreateInstance(&NetFwPolicy2, None, CLSCTX_ALL)?;
+                let enumerator: IEnumVARIANT = policy.Rules()?._NewEnum()?.cast()?;
+                let text = |value: BSTR| Some(value.to_string()).filter(|value| !value.is_empty());
+
+                let mut rules: Vec<FirewallRule> = Vec::new();
+                loop {
+                    let mut variant = [VARIANT::default()];
+                    let mut fetched: u32 = 0;
+                    enumerator.Next(&mut variant, &mut fetched).ok()?;
+                    if fetched == 0 {break;}
+
+                    // `pdispVal` only means something when the VARIANT says it holds an `IDispatch`
+                    let rule: Result<Option<INetFwRule>> = if variant[0].Anonymous.Anonymous.vt == VT_DISPATCH {
+                        (*variant[0].Anonymous.Anonymous.Anonymous.pdispVal).as_ref().map(|dispatch| dispatch.cast()).transpose()
+                    } else {
+                        Ok(None)
+                    };
+                    VariantClear(&mut variant[0])?;
+                    let Some(rule) = rule? else {continue};
+
+                    rules.push(FirewallRule {
+                        name: rule.Name()?.to_string(),
+                        enabled: rule.Enabled()? == VARIANT_TRUE,
+                        inbound: rule.Direction()? == NET_FW_RULE_DIR_IN,
+                        allow: rule.Action()? == NET_FW_ACTION_ALLOW,
+                        profiles: rule.Profiles()?,
+                        protocol: rule.Protocol()?,
+                        local_ports: rule.LocalPorts()?.to_string(),
+                        application: text(rule.ApplicationName()?),
+                        service: text(rule.ServiceName()?)
+                    });
+                }
+                Ok(rules)
+            })();
+
+            result.map_err(|error| api_error("firewall rules", error))
+        }
+    }
+
+    fn update_search(&self, criteria: &str) -> SourceResult<Vec<UpdateRecord>> {
+        unsafe {
+            let _com = ComGuard::new(criteria)?;
+
+            let result: Result<Vec<UpdateRecord>> = (|| {
+                /*
+                    Shugo: Creating an Update Session
+
+                    Before we can grab updates, we need to make a session for the Windows Update Agent. Now as you can see we're
+                    using `CLSCTX_ALL` instead of `CLSCTX_INPROC_SERVER` like we did for WMI. Why do you think that is?
+
+                    The Windows Update service may run in a different process or even as a system service and `CLSCTX_ALL` allows COM
+                    to find it regardless of where it's running.
+
+                    For more information on `IUpdateSession`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nn-wuapi-iupdatesession) - C++
+                    (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/UpdateAgent/struct.IUpdateSession.html) - Rust
+                */
+                let session: IUpdateSession = CoCreateInstance(
+                    &UpdateSession,
+                    None,
+                    CLSCTX_ALL
+                )?;
+
+                /*
+                    Shugo: Creating an Update Searcher
+
+                    We need an interface to search for our updates, to do that we use the `CreateUpdateSearcher` method from our `IUpdateSession`
+                    interface. This gives us an `IUpdateSearcher` interface which can search for updates on Windows servers.
+
+                    For more information on `IUpdateSearcher`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nn-wuapi-iupdatesearcher) - C++
+                    (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/UpdateAgent/struct.IUpdateSearcher.html) - Rust
+                */
+                let searcher: IUpdateSearcher = session.CreateUpdateSearcher()?;
+
+                /*
+                    Shugo: Searching for Updates
+
+                    Using our `IUpdateSeacher` interface, we now have multiple methods we can use to get updates. We'll be using the method
+                    `Search` which performs a synchronous search for updates using a criteria in `BSTR` format. The Update Module uses the
+                    criteria "IsInstalled=0". This tells the search to look for updates we haven't installed yet.
+
+                    Note: This is why the Update Module takes so long to grab information. We have to grab the updates from Windows servers
+                    which can take 5-30 seconds.
+
+                    For more information on `Search`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nf-wuapi-iupdatesearcher-search) - C++
+                    (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/UpdateAgent/struct.IUpdateSearcher.html#method.Search) - Rust
+                */
+                eprintln!("Grabbing Updates, this may take 5-30 seconds..."); // stderr so it stays out of JSON output
+                let data: ISearchResult = searcher.Search(&BSTR::from(criteria))?;
+
+                /*
+                    Shugo: Grabbing Updates
+
+                    Once the search is complete, we then need to get our updates from that search. To do this we use the `Updates` method
+                    which will grab those updates and give us an `IUpdateCollection` interface. This allows us to work with our collection
+                    of updates with multiple different methods.
+
+                    For example, we'll use the `Count` method to grab the total count of updates we have in our collection.
+
+                    For more information on `IUpdateCollection`:
+                    (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nn-wuapi-iupdatecollection) - C++
+                    (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/UpdateAgent/struct.IUpdateCollection.html) - Rust
+                */
+                let updates: IUpdateCollection = data.Updates()?;
+                let mut records: Vec<UpdateRecord> = Vec::new();
+
+                for i in 0..updates.Count()? { // We get updates at index i (COM collections are 0-indexed like Rust)
+                    /*
+                        Shugo: Setting Up IUpdate Interface
+
+                        We'll use the `get_Item` method which gets or sets an `IUpdate` interface in a collection. Their are multiple
+                        different `IUpdate` interfaces that we can use but this one gives us all the options we need.
+
+                        For more information on `IUpdate`:
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nn-wuapi-iupdate) - C++
+                        (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/UpdateAgent/struct.IUpdate.html) - Rust
+                    */
+                    let update: IUpdate = updates.get_Item(i)?;
+
+                    /*
+                        Shugo: Update Size
+
+                        To get our update size, we can use the methods `MaxDownloadSize` or `MinDownloadSize`. This will give us either a
+                        maximum size for a download or a minimum size. These sizes can vary massively as using `MaxDownloadSize` will
+                        give us a size for worst case scenerio. Meaning a simple definitions update can say 1.5GB but will most likely
+                        only be less than 1MB. Keep that in mind when using these methods.
+
+                        Now we need to convert our DECIMAL type to a u128 type. For further information on how this works go to the helper
+                        function in: `tools\shugo\src\common\wmi_helpers.rs`
+
+                        For more information on `MaxDownloadSize` and MinDownloadSize:
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nf-wuapi-iupdate-get_maxdownloadsize) - MaxDownloadSize
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nf-wuapi-iupdate-get_mindownloadsize) - MinDownloadSize
+                    */
+                    let min_size: DECIMAL = update.MinDownloadSize()?;
+                    let max_size: DECIMAL = update.MaxDownloadSize()?;
+
+                    /*
+                        Shugo: Update Categories
+
+                        By using the `Categories` method, we can get the categories of the update and put them in a
+                        `ICategoryCollection` interface. You get two categories for each update, the update classification
+                        (UpdateClassification) and the product receiving it (Product). We hand both back along with their
+                        `CategoryID` so the Update Module can sort them.
+
+                        For more information on `Type`:
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nf-wuapi-icategory-get_type) - C++
+                        For more information on `CategoryID`:
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nf-wuapi-icategory-get_categoryid) - C++
+                    */
+                    let categories: ICategoryCollection = update.Categories()?;
+                    let mut category_list: Vec<UpdateCategory> = Vec::new();
+                    for j in 0..categories.Count()? {
+                        let category: ICategory = categories.get_Item(j)?;
+                        category_list.push(UpdateCategory {
+                            category_type: category.Type()?.to_string(),
+                            name: category.Name()?.to_string(),
+                            id: category.CategoryID()?.to_string()
+                        });
+                    }
+
+                    /*
+                        Shugo: Update Title and Description
+
+                        Titles of updates can be grabbed easily using the `Title` method and converting it to a `String` type.
+                        Using the `Description` method we can grab the description of the update from Windows Update Agent.
+                        This should always be filled and should never return empty.
+
+                        For more information on `Title` and `Description`:
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nf-wuapi-iupdate-get_title) - C++
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nf-wuapi-iupdate-get_description) - C++
+                    */
+                    /*
+                        Shugo: Update Release Date
+
+                        `LastDeploymentChangeTime` tells us when the update was published (or last changed) on the update
+                        service. It comes back as an OLE Automation DATE, a `f64` counting days since 30 December 1899, with
+                        the time of day as the fraction. There are 25569 days between that date and the Unix epoch
+                        (1 January 1970), so we subtract those and multiply by the seconds in a day.
+
+                        For more information on `LastDeploymentChangeTime`:
+                        (https://learn.microsoft.com/en-us/windows/win32/api/wuapi/nf-wuapi-iupdate-get_lastdeploymentchangetime) - C++
+                    */
+                    let released = update
+                        .LastDeploymentChangeTime()
+                        .ok()
+                        .filter(|date| *date > 25569.0)
+                        .map(|date| ((date - 25569.0) * 86400.0) as u64);
+
+                    records.push(UpdateRecord {
+                        title: update.Title()?.to_string(),
+                        description: update.Description()?.to_string(),
+                        min_download_bytes: decimal_to_u128(min_size),
+                        max_download_bytes: decimal_to_u128(max_size),
+                        categories: category_list,
+                        released
+                    });
+                }
+                Ok(records)
+            })();
+
+            result.map_err(|error| api_error(criteria, error))
+        }
+    }
+
+    fn local_users(&self) -> SourceResult<Vec<UserRecord>> {
+        /*
+            Shugo: User Account Enumeration
+
+            We'll be using the NetUserEnum function to retrieve information about all local user
+            accounts on the system. This is different from our other calls becuase we're using
+            the Network Management API instead of COM/WMI/Registry.
+
+            For more information on `NetUserEnum`:
+            (https://learn.microsoft.com/en-us/windows/win32/api/lmaccess/nf-lmaccess-netuserenum) - C++
+            (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/NetworkManagement/NetManagement/fn.NetUseEnum.html) - Rust
+        */
+        let mut buffer: *mut u8 = std::ptr::null_mut();
+        let mut entries_read: u32 = 0;
+        let mut total_entries: u32 = 0;
+        let mut resume_handle: u32 = 0;
+
+        unsafe {
+            /*
+                Shugo: Enumerating User Accounts
+
+                `NetUserEnum` retrieves information about all user accounts and allocates memory for
+                the results. We must free this memory later using `NetApiBufferFree`.
+
+                Level 1 gives us basic info: username, privilege level, and flags.
+                FILTER_NORMAL_ACCOUNT excludes system accounts and focuses on regular users.
+            */
+            let result: u32 = NetUserEnum(
+                None, // A pointer that secifies the DNS or NetBIOS name of a remote server on which to execute. We'll leave it Null for local computer
+                1, // This specifies the information level of the data, go check out the above link to see more options
+                FILTER_NORMAL_ACCOUNT, // Specifies the user account types to be included in the enumeration
+                &mut buffer, // This is where we use our buffer variable to recieve our data
+                u32::MAX, // We can set the maximum length in bytes of the returned data, well use MAX to return all
+                &mut entries_read, // reads the count of entries actually enumerated
+                &mut total_entries, // total entries available
+                Some(&mut resume_handle) // Resume handle to continue existing search
+            );
+            if result != NERR_Success {
+                return Err(api_error("local accounts", Error::from_hresult(HRESULT::from_win32(result))));
+            }
+
+            /*
+                Shugo: Processing User Account Data
+
+                We cast our buffer to a USER_INFO_1 array and copy out the name, privilege level,
+                and flags of each user. The User Account Security Module decides what they mean.
+            */
+            let users: &[USER_INFO_1] = std::slice::from_raw_parts(
+                buffer as *const USER_INFO_1,
+                entries_read as usize
+            );
+
+            let mut records: Vec<UserRecord> = Vec::new();
+            let mut name_error: Option<Error> = None;
+            for user in users {
+                match user.usri1_name.to_string() {
+                    Ok(username) => records.push(UserRecord {
+                        username,
+                        privilege: user.usri1_priv.0,
+                        flags: user.usri1_flags.0
+                    }),
+                    Err(error) => {
+                        name_error = Some(Error::from(error));
+                        break;
+                    }
+                }
+            }
+
+            /*
+                Shugo: Cleaning Up Memory
+
+                NetUserEnum allocates memory that we must free using NetApiBufferFree.
+                Failing to do this causes memory leaks.
+
+                For more information on `NetApiBufferFree`:
+                (https://learn.microsoft.com/en-us/windows/win32/api/lmapibuf/nf-lmapibuf-netapibufferfree) - C++
+                (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/NetworkManagement/NetManagement/fn.NetApiBufferFree.html) - Rust
+            */
+            NetApiBufferFree(Some(buffer as *const _));
+
+            match name_error {
+                Some(error) => Err(api_error("local accounts", error)),
+                None => Ok(records)
+            }
+        }
+    }
+
+    fn network_shares(&self) -> SourceResult<Vec<ShareRecord>> {
+        /*
+            Shugo: Share Enumeration
+
+            `NetShareEnum` lists the shares this machine offers, the same list `net share` prints. It's another
+            Network Management call, so it works like `NetUserEnum`: Windows allocates the buffer and we free it.
+
+            Level 502 is the only level that includes the share's security descriptor, the permissions set on the
+            Sharing tab. It needs administrator rights, everyone else gets ERROR_ACCESS_DENIED.
+
+            For more information on `NetShareEnum`:
+            (https://learn.microsoft.com/en-us/windows/win32/api/lmshare/nf-lmshare-netshareenum) - C++
+        */
+        let mut buffer: *mut u8 = std::ptr::null_mut();
+        let mut entries_read: u32 = 0;
+        let mut total_entries: u32 = 0;
+        let mut resume_handle: u32 = 0;
+
+        unsafe {
+            let result: u32 = NetShareEnum(
+                None, // The local computer
+                502, // SHARE_INFO_502, with the security descriptor
+                &mut buffer,
+                u32::MAX, // Return every share at once
+                &mut entries_read,
+                &mut total_entries,
+                Some(&mut resume_handle)
+            );
+            if result != NERR_Success {
+                return Err(api_error("network shares", Error::from_hresult(HRESULT::from_win32(result))));
+            }
+
+            let shares: &[SHARE_INFO_502] = if buffer.is_null() {
+                &[]
+            } else {
+                std::slice::from_raw_parts(buffer as *const SHARE_INFO_502, entries_read as usize)
+            };
+
+            let text = |value: PWSTR| if value.is_null() {String::new()} else {value.to_string().unwrap_or_default()};
+            let mut records: Vec<ShareRecord> = Vec::new();
+            for share in shares {
+                /*
+                    Shugo: Copying The Security Descriptor
+
+                    The descriptor is in self-relative form: one block of memory with the owner, group, and ACLs
+                    at offsets from its start. `GetSecurityDescriptorLength` says how long the block is, so we can
+                    copy the bytes out before the buffer is freed and let the pure Rust parser in `common/sddl.rs`
+                    read them.
+                */
+                let name = text(share.shi502_netname);
+                let descriptor = share.shi502_security_descriptor;
+                let security_descriptor = if descriptor.0.is_null() {
+                    Ok(None)
+                } else if !IsValidSecurityDescriptor(descriptor).as_bool() {
+                    // Its length can't be trusted either, so there's nothing safe to copy
+                    Err(ShugoError::parse(format!("{} security descriptor", name), "IsValidSecurityDescriptor rejected it"))
+                } else {
+                    let length = GetSecurityDescriptorLength(descriptor) as usize;
+                    Ok(Some(std::slice::from_raw_parts(descriptor.0 as *const u8, length).to_vec()))
+                };
+                records.push(ShareRecord {
+                    name,
+                    share_type: share.shi502_type.0,
+                    remark: text(share.shi502_remark),
+                    path: text(share.shi502_path),
+                    current_uses: share.shi502_current_uses,
+                    security_descriptor
+                });
+            }
+
+            NetApiBufferFree(Some(buffer as *const _));
+            Ok(records)
+        }
+    }
+}
+
+/// Opens a key for reading, the caller closes it with `RegCloseKey`
+unsafe fn open_key(hive: RegistryHive, key: &str) -> SourceResult<HKEY> {
+    let root: HKEY = match hive {
+        RegistryHive::LocalMachine => HKEY_LOCAL_MACHINE,
+        RegistryHive::CurrentUser => HKEY_CURRENT_USER
+    };
+    let mut handle: HKEY = HKEY::default();
+    let status: WIN32_ERROR = unsafe {
+        RegOpenKeyExW(
+            root, // This is a handle to open the specified registry path (e.g. HKEY_CLASSES_ROOT, HKEY_CURRENT_CONFIG, HKEY_CURRENT_USER, HKEY_USERS)
+            &HSTRING::from(key), // The name of the registry subkey to be opened
+            Some(0), // Specifies the option to apply when opening the key. Were gonna set it to 0
+            KEY_READ, // This mask specifies the directed access rights to the key being opened. for info: https://learn.microsoft.com/en-us/windows/win32/sysinfo/registry-key-security-and-access-rights
+            &mut handle // This is a pointer to a variable that receives a handle to the opened key
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return Err(api_error(&format!("{}\\{}", hive.root_name(), key), Error::from(status)));
+    }
+    Ok(handle)
+}
+
+/// Encrypting and authenticating every call made through a WMI connection
+fn set_proxy_security(services: &IWbemServices) -> Result<()> {
+    unsafe {
+        CoSetProxyBlanket(
+            services,
+            10, // RPC_C_AUTHN_WINNT, the default Windows authentication
+            0, // RPC_C_AUTHZ_NONE, the server doesn't need anything more to authorize us
+            PCWSTR::null(), // No server principal name for a local connection
+            RPC_C_AUTHN_LEVEL_PKT_PRIVACY, // Sign and encrypt every call
+            RPC_C_IMP_LEVEL_IMPERSONATE, // WMI acts as us, so it sees the same rights we have
+            None, // Use the identity we're already running as
+            EOAC_NONE
+        )
+    }
+}
+
+/// Turning a `windows::core::Error` into a `ShugoError` that says what we were asking for
+fn api_error(query: &str, error: Error) -> ShugoError {
+    ShugoError::from_hresult(query, error.code().0, error.message())
+}
@@ -1,2 +1,23 @@
+pub mod error;
+pub mod source;
+pub mod fixture;
+pub mod hive;
+pub mod offline;
+pub mod regfile;
+pub mod wmi;
+pub mod product_state;
+pub mod feature;
+pub mod sddl;
+pub mod path;
+#[cfg(windows)]
+pub mod com;
+#[cfg(windows)]
+pub mod live;
+#[cfg(windows)]
 pub mod wmi_helpers;
-pub mod time;
\ No newline at end of file
+pub mod time;
+pub mod output;
+pub mod finding;
+pub mod html;
+pub mod sarif;
+pub mod junit;
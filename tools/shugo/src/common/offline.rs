@@ -0,0 +1,243 @@
+//! An offline `SecuritySource` built from registry files collected off another machine, either raw
+//! hive files or `.reg` exports. Only the
+//! registry backed checks can run against it, everything else (WMI, firewall policy, updates,
+//! accounts, shares) needs a live machine.
+use std::path::Path;
+
+use super::error::ShugoError;
+use super::hive::HiveFile;
+use super::regfile::RegFile;
+use super::source::*;
+use crate::firewall::scanner::{FirewallRule, WindowsFirewallProfile};
+
+/// A hive file and where it would normally be loaded in the registry
+struct MountedHive {
+    root: RegistryHive,
+    mount: String,
+    hive: HiveFile
+}
+
+#[derive(Default)]
+pub struct OfflineSource {
+    hives: Vec<MountedHive>,
+    reg_files: Vec<RegFile>
+}
+
+impl OfflineSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a hive file, working out where it belongs from its file name
+    /// (`SOFTWARE`, `SYSTEM`, `SAM`, `SECURITY`, or `NTUSER.DAT`)
+    pub fn open_hive(self, path: &Path) -> SourceResult<Self> {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_uppercase())
+            .unwrap_or_default();
+        let (root, mount) = match file_name.as_str() {
+            "SOFTWARE" | "SYSTEM" | "SAM" | "SECURITY" => (RegistryHive::LocalMachine, file_name.as_str()),
+            "NTUSER.DAT" => (RegistryHive::CurrentUser, ""),
+            _ => return Err(ShugoError::parse(
+                path.display().to_string(),
+                "can't tell which hive this is, name it SOFTWARE, SYSTEM, SAM, SECURITY, or NTUSER.DAT"
+            ))
+        };
+        let mount = mount.to_string();
+        Ok(self.with_hive(root, &mount, HiveFile::open(path)?))
+    }
+
+    /// Mounts a hive at `root\mount`, for example `SOFTWARE` under HKEY_LOCAL_MACHINE
+    pub fn with_hive(mut self, root: RegistryHive, mount: &str, hive: HiveFile) -> Self {
+        self.hives.push(MountedHive { root, mount: mount.to_string(), hive });
+        self
+    }
+
+    /// Loads a `.reg` export
+    pub fn open_reg(self, path: &Path) -> SourceResult<Self> {
+        Ok(self.with_reg_file(RegFile::open(path)?))
+    }
+
+    pub fn with_reg_file(mut self, file: RegFile) -> Self {
+        self.reg_files.push(file);
+        self
+    }
+
+    /// Finds the hive a full key path lives in and gives back the path inside that hive
+    fn resolve(&self, root: RegistryHive, key: &str) -> SourceResult<(&HiveFile, String)> {
+        for mounted in self.hives.iter().filter(|mounted| mounted.root == root) {
+            let Some(inner) = strip_key_prefix(key, &mounted.mount) else {
+                continue;
+            };
+
+            /*
+                Shugo: CurrentControlSet
+
+                A live machine shows `SYSTEM\CurrentControlSet`, but that key doesn't exist in the SYSTEM hive file.
+                Windows builds it at boot from one of the numbered `ControlSet00X` keys, and `Select\Current`
+                tells us which one.
+            */
+            if mounted.mount.eq_ignore_ascii_case("SYSTEM")
+                && let Some(rest) = strip_key_prefix(inner, "CurrentControlSet") {
+                let current = mounted.hive
+                    .value("Select", "Current")?
+                    .and_then(|value| value.as_u32())
+                    .unwrap_or(1);
+                return Ok((&mounted.hive, format!("ControlSet{:03}\\{}", current, rest)));
+            }
+            return Ok((&mounted.hive, inner.to_string()));
+        }
+        Err(ShugoError::unavailable(format!("{}\\{}", root.root_name(), key), "no offline hive loaded for this key"))
+    }
+}
+
+impl SecuritySource for OfflineSource {
+    fn wmi_query(&self, _namespace: &str, query: &str) -> SourceResult<Vec<WmiRow>> {
+        Err(ShugoError::unavailable(query, "WMI is not available from offline registry files"))
+    }
+
+    fn wmi_method(&self, _namespace: &str, _object_path: &str, method: &str, _inputs: &[(&str, WmiValue)]) -> SourceResult<WmiRow> {
+        Err(ShugoError::unavailable(method, "WMI is not available from offline registry files"))
+    }
+
+    fn registry_value(&self, hive: RegistryHive, key: &str, value: &str) -> SourceResult<Option<RegistryValue>> {
+        // .reg exports are usually the smaller, more specific input so they're checked first. A partial export can
+        // have the key without the value though, so only a value that's actually there stops the search
+        let mut key_exported = false;
+        for file in self.reg_files.iter() {
+            match file.value(hive, key, value) {
+                Some(Some(found)) => return Ok(Some(found)),
+                Some(None) => key_exported = true,
+                None => {}
+            }
+        }
+        match self.resolve(hive, key).and_then(|(file, path)| file.value(&path, value)) {
+            Err(ShugoError::NotFound { .. } | ShugoError::Unavailable { .. }) if key_exported => Ok(None),
+            result => result
+        }
+    }
+
+    fn registry_values(&self, hive: RegistryHive, key: &str) -> SourceResult<Vec<(String, RegistryValue)>> {
+        if let Some(found) = self.reg_files.iter().find_map(|file| file.values(hive, key)) {
+            return Ok(found);
+        }
+        let (file, path) = self.resolve(hive, key)?;
+        file.values(&path)
+    }
+
+    fn registry_subkeys(&self, hive: RegistryHive, key: &str) -> SourceResult<Vec<String>> {
+        if let Some(found) = self.reg_files.iter().find_map(|file| file.subkeys(hive, key)) {
+            return Ok(found);
+        }
+        let (file, path) = self.resolve(hive, key)?;
+        file.subkeys(&path)
+    }
+
+    fn firewall_policy(&self) -> SourceResult<WindowsFirewallProfile> {
+        Err(ShugoError::unavailable("firewall policy", "firewall policy is not available from offline registry files"))
+    }
+
+    fn firewall_rules(&self) -> SourceResult<Vec<FirewallRule>> {
+        Err(ShugoError::unavailable("firewall rules", "firewall rules are not available from offline registry files"))
+    }
+
+    fn update_search(&self, criteria: &str) -> SourceResult<Vec<UpdateRecord>> {
+        Err(ShugoError::unavailable(criteria, "Windows Update is not available from offline registry files"))
+    }
+
+    fn local_users(&self) -> SourceResult<Vec<UserRecord>> {
+        Err(ShugoError::unavailable("local accounts", "local accounts are not available from offline registry files"))
+    }
+
+    fn network_shares(&self) -> SourceResult<Vec<ShareRecord>> {
+        Err(ShugoError::unavailable("network shares", "network shares are not available from offline registry files"))
+    }
+}
+
+/// Strips `prefix` off the front of a key path if it matches a whole key name
+fn strip_key_prefix<'a>(key: &'a str, prefix: &str) -> Option<&'a str> {
+    let key = key.trim_start_matches('\\');
+    if prefix.is_empty() {
+        return Some(key);
+    }
+    let head = key.get(..prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    match &key[prefix.len()..] {
+        "" => Some(""),
+        rest if rest.starts_with('\\') => Some(&rest[1..]),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::hive::tests::{HiveBuilder, TestKey, dword};
+
+    fn system_hive() -> HiveFile {
+        let root = TestKey {
+            name: "ROOT",
+            values: vec![],
+            children: vec![
+                TestKey { name: "Select", values: vec![("Current", REG_DWORD, dword(2))], children: vec![] },
+                TestKey {
+                    name: "ControlSet002",
+                    values: vec![],
+                    children: vec![TestKey { name: "Services", values: vec![("Marker", REG_DWORD, dword(7))], children: vec![] }]
+                }
+            ]
+        };
+        HiveFile::from_bytes(HiveBuilder::build(&root)).unwrap()
+    }
+
+    #[test]
+    fn strips_whole_key_names_only() {
+        assert_eq!(strip_key_prefix("SOFTWARE\\Microsoft", "software"), Some("Microsoft"));
+        assert_eq!(strip_key_prefix("SOFTWARE", "SOFTWARE"), Some(""));
+        assert_eq!(strip_key_prefix("SOFTWAREX\\Microsoft", "SOFTWARE"), None);
+        assert_eq!(strip_key_prefix("Software\\Test", ""), Some("Software\\Test"));
+    }
+
+    #[test]
+    fn resolves_current_control_set() {
+        let source = OfflineSource::new().with_hive(RegistryHive::LocalMachine, "SYSTEM", system_hive());
+        let value = source
+            .registry_value(RegistryHive::LocalMachine, "SYSTEM\\CurrentControlSet\\Services", "Marker")
+            .unwrap();
+        assert_eq!(value, Some(RegistryValue::Dword(7)));
+        assert!(source.registry_value(RegistryHive::LocalMachine, "SOFTWARE\\Microsoft", "Marker").is_err());
+        assert!(source.local_users().is_err());
+    }
+
+    #[test]
+    fn reads_from_reg_files_first() {
+        let reg = RegFile::parse(
+            "Windows Registry Editor Version 5.00\n[HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Services]\n\"Marker\"=dword:00000009\n"
+        ).unwrap();
+        let source = OfflineSource::new()
+            .with_hive(RegistryHive::LocalMachine, "SYSTEM", system_hive())
+            .with_reg_file(reg);
+        let value = source
+            .registry_value(RegistryHive::LocalMachine, "SYSTEM\\CurrentControlSet\\Services", "Marker")
+            .unwrap();
+        assert_eq!(value, Some(RegistryValue::Dword(9)));
+    }
+
+    #[test]
+    fn falls_through_to_hives_for_values_reg_files_lack() {
+        let export = "Windows Registry Editor Version 5.00\n[HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Services]\n\"Other\"=dword:00000001\n";
+        let source = OfflineSource::new()
+            .with_hive(RegistryHive::LocalMachine, "SYSTEM", system_hive())
+            .with_reg_file(RegFile::parse(export).unwrap());
+        let value = source
+            .registry_value(RegistryHive::LocalMachine, "SYSTEM\\CurrentControlSet\\Services", "Marker")
+            .unwrap();
+        assert_eq!(value, Some(RegistryValue::Dword(7)));
+
+        // Without a hive, the key being in the export is enough to say the value isn't set
+        let source = OfflineSource::new().with_reg_file(RegFile::parse(export).unwrap());
+        assert_eq!(source.registry_value(RegistryHive::LocalMachine, "SYSTEM\\CurrentControlSet\\Services", "Marker").unwrap(), None);
+    }
+}
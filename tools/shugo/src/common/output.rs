@@ -0,0 +1,55 @@
+//! Machine-readable output shared by every module. Each module's display file builds the JSON for
+//! its own results and this wraps it in the same versioned envelope so dashboards can rely on it.
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use super::time::unix_time;
+
+/// Bumped whenever a field is renamed, removed, or can newly be `null`. Adding fields doesn't change it
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// Wraps one module's results in the report envelope
+pub fn json_report(module: &str, result: Value) -> Value {
+    json!({
+        "tool": "shugo",
+        "schema_version": SCHEMA_VERSION,
+        "module": module,
+        "generated_at": unix_time(),
+        "result": result
+    })
+}
+
+/// Serializes `item` and adds a `decoded` object with the human readable meaning of its raw values
+pub fn with_decoded<T: Serialize>(item: &T, decoded: Value) -> Value {
+    let mut value = serde_json::to_value(item).unwrap_or(Value::Null);
+    if let Value::Object(map) = &mut value {
+        map.insert("decoded".to_string(), decoded);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Sample {
+        state: u32
+    }
+
+    #[test]
+    fn wraps_results_in_envelope() {
+        let report = json_report("uac", json!({"ok": true}));
+        assert_eq!(report["tool"], "shugo");
+        assert_eq!(report["schema_version"], SCHEMA_VERSION);
+        assert_eq!(report["module"], "uac");
+        assert_eq!(report["result"]["ok"], true);
+    }
+
+    #[test]
+    fn keeps_raw_values_next_to_decoded() {
+        let value = with_decoded(&Sample { state: 1 }, json!({"state": "Enabled"}));
+        assert_eq!(value["state"], 1);
+        assert_eq!(value["decoded"]["state"], "Enabled");
+    }
+}
@@ -0,0 +1,18 @@
+//! Helpers for the Windows paths modules read files from, kept OS independent so tests run anywhere.
+use std::path::{Path, PathBuf};
+
+/// Joins a Windows-style relative folder onto a path, one part at a time so it also works in tests on Linux
+pub(crate) fn join(base: &Path, relative: &str) -> PathBuf {
+    relative.split('\\').fold(base.to_path_buf(), |path, part| path.join(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_windows_parts() {
+        let joined = join(Path::new("root"), "AppData\\Local\\Google");
+        assert_eq!(joined, Path::new("root").join("AppData").join("Local").join("Google"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Decoding the `productState` number Security Center keeps for every antivirus, antispyware and
+//! firewall product. The antivirus and firewall scanners decode it once while scanning and keep the
+//! typed result on their product structs, so assessments and displays never touch the raw bits.
+use std::fmt;
+
+use serde::Serialize;
+
+/// Everything packed into a `productState`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProductState {
+    /// Which kinds of protection the product registered for
+    pub providers: SecurityProviders,
+    pub scanner: ScannerState,
+    pub owner: ProductOwner,
+    pub definitions: DefinitionsState
+}
+
+/// The WSC_SECURITY_PROVIDER flags from the top byte
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SecurityProviders {
+    pub firewall: bool,
+    pub autoupdate_settings: bool,
+    pub antivirus: bool,
+    pub antispyware: bool,
+    pub internet_settings: bool,
+    pub user_account_control: bool,
+    pub service: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScannerState {
+    Off,
+    On,
+    Snoozed,
+    Expired,
+    Unknown
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductOwner {
+    Windows,
+    ThirdParty,
+    Unknown
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefinitionsState {
+    UpToDate,
+    OutOfDate,
+    Unknown
+}
+
+impl ProductState {
+    /// Splits a raw `productState` into its fields. Negative or oversized values decode as all unknown
+    pub fn decode(state: i32) -> ProductState {
+        /*
+            Shugo: Bit Logic
+
+            Security Center products carry a unique set of numbers called a product state in Decimal format. We can use the product
+            state to see what state the product is in. Before we go further, we must understand what were looking at. Microsoft never
+            documented this number, so information here must be taken with a grain of salt as some of it could be wrong.
+            Here we go:
+
+            Let's say our product state is `397568`
+
+            To turn our product state into a hex digit value we need to do some math.
+            Specifically division, take the product state and divide it by 16:
+
+            397568 / 16 = 24848, Remainder: 0
+            24848 / 16 = 1553,   Remainder: 0
+            1553 / 16 = 97,      Remainder: 1
+            97 / 16 = 6,         Remainder: 1
+            6 / 16 = 0,          Remainder: 6
+
+            Sweet, now we have our 8 hex digit number: 0x00061100
+            The top byte is never used, so were left with a 6 hex digit number: 0x061100
+
+            Now we could stop here but to make sure we know what information were looking at,
+            we'll go one step further into making it binary:
+
+            Binary:  00000110   00010001   00000000
+                     [23-16]    [15 - 8]    [7 - 0]
+
+            Every 8 bits is a byte, we'll use these bytes to find our information more clearly. We'll go from right to left
+            and to represent the hex digit were looking at, I'll put `F` in their place:
+
+            Bits 0-7:
+            Signature Status (0x0611FF): 00 = UpToDate signatures, 10 = OutOfDate signatures
+
+            Bits 8-11:
+            Product Owner (0x061F00): 1 = Windows, 0 = ThirdParty
+
+            Bits 12-15:
+            Scanner State (0x06F100): 0 = Off, 1 = On, 2 = Snoozed, 3 = Expired
+
+            Bits 16-23:
+            Security Providers (0xFF1100): one bit for each kind of protection the product registered for, the same values as
+            the WSC_SECURITY_PROVIDER enum. 0x06 above is 0x04 (antivirus) + 0x02 (auto-update settings).
+                0x01 = Firewall, 0x02 = Auto-update settings, 0x04 = Antivirus, 0x08 = Antispyware,
+                0x10 = Internet settings, 0x20 = User Account Control, 0x40 = Service
+
+            Now remember, some third party products don't always follow this format.
+
+            For more information on WSC_SECURITY_PROVIDER:
+            (https://learn.microsoft.com/en-us/windows/win32/api/wscapi/ne-wscapi-wsc_security_provider)
+        */
+        if !(0..=0xFFFFFF).contains(&state) {
+            return ProductState::unknown();
+        }
+        let providers = (state >> 16) & 0xFF;
+        let flag = |bit: i32| providers & bit != 0;
+
+        ProductState {
+            providers: SecurityProviders {
+                firewall: flag(0x01),
+                autoupdate_settings: flag(0x02),
+                antivirus: flag(0x04),
+                antispyware: flag(0x08),
+                internet_settings: flag(0x10),
+                user_account_control: flag(0x20),
+                service: flag(0x40)
+            },
+            scanner: match (state >> 12) & 0xF {
+                0 => ScannerState::Off,
+                1 => ScannerState::On,
+                2 => ScannerState::Snoozed,
+                3 => ScannerState::Expired,
+                _ => ScannerState::Unknown
+            },
+            owner: match (state >> 8) & 0xF {
+                0 => ProductOwner::ThirdParty,
+                1 => ProductOwner::Windows,
+                _ => ProductOwner::Unknown
+            },
+            definitions: match state & 0xFF {
+                0x00 => DefinitionsState::UpToDate,
+                0x10 => DefinitionsState::OutOfDate,
+                _ => DefinitionsState::Unknown
+            }
+        }
+    }
+
+    /// Used when the product didn't report a state at all
+    pub fn unknown() -> ProductState {
+        ProductState {
+            providers: SecurityProviders::default(),
+            scanner: ScannerState::Unknown,
+            owner: ProductOwner::Unknown,
+            definitions: DefinitionsState::Unknown
+        }
+    }
+}
+
+impl SecurityProviders {
+    /// Display names of the flags that are set
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.firewall, "Firewall"),
+            (self.autoupdate_settings, "Auto-update settings"),
+            (self.antivirus, "Antivirus"),
+            (self.antispyware, "Antispyware"),
+            (self.internet_settings, "Internet settings"),
+            (self.user_account_control, "User Account Control"),
+            (self.service, "Service")
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+impl fmt::Display for SecurityProviders {
+    /// "Auto-update settings, Antivirus", or "None" when no flags are set
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.names();
+        if names.is_empty() {f.write_str("None")} else {f.write_str(&names.join(", "))}
+    }
+}
+
+impl fmt::Display for ScannerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScannerState::Off => "Off",
+            ScannerState::On => "On",
+            ScannerState::Snoozed => "Snoozed",
+            ScannerState::Expired => "Expired",
+            ScannerState::Unknown => "Unknown"
+        })
+    }
+}
+
+impl fmt::Display for ProductOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProductOwner::Windows => "Windows",
+            ProductOwner::ThirdParty => "Third-party",
+            ProductOwner::Unknown => "Unknown"
+        })
+    }
+}
+
+impl fmt::Display for DefinitionsState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DefinitionsState::UpToDate => "Up-to-date",
+            DefinitionsState::OutOfDate => "Out-of-date",
+            DefinitionsState::Unknown => "Unknown"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_windows_defender() {
+        let state = ProductState::decode(397568); // 0x061100
+        assert!(state.providers.antivirus && state.providers.autoupdate_settings);
+        assert!(!state.providers.firewall && !state.providers.antispyware);
+        assert_eq!(state.scanner, ScannerState::On);
+        assert_eq!(state.owner, ProductOwner::Windows);
+        assert_eq!(state.definitions, DefinitionsState::UpToDate);
+        assert_eq!(state.providers.to_string(), "Auto-update settings, Antivirus");
+        assert_eq!(SecurityProviders::default().to_string(), "None");
+    }
+
+    #[test]
+    fn decodes_third_party_products() {
+        let firewall = ProductState::decode(0x211000);
+        assert!(firewall.providers.firewall && firewall.providers.user_account_control);
+        assert_eq!(firewall.owner, ProductOwner::ThirdParty);
+
+        let stale = ProductState::decode(0x0C0010);
+        assert!(stale.providers.antivirus && stale.providers.antispyware);
+        assert_eq!(stale.scanner, ScannerState::Off);
+        assert_eq!(stale.definitions, DefinitionsState::OutOfDate);
+
+        assert_eq!(ProductState::decode(0x63100).scanner, ScannerState::Expired);
+        assert_eq!(ProductState::decode(0x62100).scanner, ScannerState::Snoozed);
+    }
+
+    #[test]
+    fn odd_values_are_unknown() {
+        assert_eq!(ProductState::decode(-1), ProductState::unknown());
+        assert_eq!(ProductState::decode(0x1000000), ProductState::unknown());
+
+        let state = ProductState::decode(0x69205);
+        assert_eq!(state.scanner, ScannerState::Unknown);
+        assert_eq!(state.owner, ProductOwner::Unknown);
+        assert_eq!(state.definitions, DefinitionsState::Unknown);
+        assert_eq!(serde_json::to_value(state.scanner).unwrap(), "unknown");
+    }
+}
@@ -0,0 +1,381 @@
+//! A parser for `.reg` files, the text exports made by `regedit` and `reg export`. Once parsed,
+//! the keys can be handed to an `OfflineSource` so registry checks run against them.
+//!
+//! Shugo: What A .reg File Looks Like
+//!
+//! ```text
+//! Windows Registry Editor Version 5.00
+//!
+//! [HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System]
+//! "EnableLUA"=dword:00000001
+//! "Shell"="explorer.exe"
+//! "Path"=hex(2):25,00,53,00,00,00
+//! ```
+//!
+//! - Key paths go in square brackets, a `-` in front (`[-HKEY_...]`) deletes the key
+//! - Values are `"name"=data`, `@` is the (Default) value, and `=-` deletes the value
+//! - `hex(n):` is raw bytes for registry type `n`, long lines are continued with a trailing `\`
+//! - `regedit` saves version 5 files as UTF-16LE with a byte order mark, older `REGEDIT4` files are ANSI
+//! - `;` starts a comment line, a trailing `\` on a comment doesn't continue it
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::error::ShugoError;
+use super::source::*;
+
+/// A line in a .reg file we couldn't understand
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegParseError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for RegParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RegParseError {}
+
+/// The keys and values from a parsed .reg file
+#[derive(Debug, Default)]
+pub struct RegFile {
+    keys: StoredKeys
+}
+
+impl RegFile {
+    /// Reads a .reg file from disk, UTF-16LE (with BOM), UTF-8 and ANSI REGEDIT4 files are all fine
+    pub fn open(path: &Path) -> SourceResult<RegFile> {
+        let bytes = fs::read(path).map_err(|error| ShugoError::io(path.display().to_string(), &error))?;
+        let text = decode_text(&bytes);
+        RegFile::parse(&text).map_err(|error| ShugoError::parse(path.display().to_string(), error.to_string()))
+    }
+
+    /// Parses the text of a .reg file
+    pub fn parse(text: &str) -> Result<RegFile, RegParseError> {
+        let mut file = RegFile::default();
+        let mut lines = text.lines().enumerate().peekable();
+
+        let (_, header) = lines.next().ok_or(RegParseError { line: 1, message: "file is empty".to_string() })?;
+        let header = header.trim();
+        if header != "Windows Registry Editor Version 5.00" && header != "REGEDIT4" {
+            return Err(RegParseError { line: 1, message: format!("unknown header {:?}", header) });
+        }
+        // REGEDIT4 files come from older tools that stored strings as ANSI, one byte per character
+        let ansi = header == "REGEDIT4";
+
+        // Values belong to the last key we saw. `None` means that key is being skipped
+        let mut current: Option<(RegistryHive, String)> = None;
+        let mut in_key = false;
+
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let mut line = line.trim().to_string();
+            if line.starts_with(';') {
+                continue;
+            }
+
+            // A trailing backslash means the value keeps going on the next line
+            while line.ends_with('\\') && !line.starts_with('[') {
+                line.pop();
+                match lines.next() {
+                    Some((_, next)) => line.push_str(next.trim()),
+                    None => break
+                }
+            }
+
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| RegParseError { line: line_number, message };
+
+            if line.starts_with('[') {
+                let path = line
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .ok_or_else(|| error("key line is missing its closing ']'".to_string()))?;
+                in_key = true;
+
+                if let Some(deleted) = path.strip_prefix('-') {
+                    if let Some((hive, key)) = split_root(deleted) {
+                        let key = stored_path(&key);
+                        let prefix = format!("{}\\", key);
+                        file.keys.retain(|(entry_hive, entry_key), _| {
+                            *entry_hive != hive || (*entry_key != key && !entry_key.starts_with(&prefix))
+                        });
+                    }
+                    current = None;
+                    continue;
+                }
+
+                // Keys under roots Shugo doesn't read (like HKEY_CLASSES_ROOT) are skipped
+                current = split_root(path);
+                if let Some((hive, key)) = &current {
+                    stored_key(&mut file.keys, *hive, key);
+                }
+                continue;
+            }
+
+            if !in_key {
+                return Err(error("value found before any [key] line".to_string()));
+            }
+
+            let (name, data) = split_value_line(&line).map_err(error)?;
+            let Some((hive, key)) = &current else {
+                continue;
+            };
+            let stored = stored_key(&mut file.keys, *hive, key);
+            if data == "-" {
+                stored.remove(&name);
+            } else {
+                stored.set(&name, parse_data(data, ansi).map_err(error)?);
+            }
+        }
+        Ok(file)
+    }
+
+    /// Reads one value. `Ok(None)` means the key exists but the value doesn't
+    pub fn value(&self, hive: RegistryHive, key: &str, name: &str) -> Option<Option<RegistryValue>> {
+        let stored = self.keys.get(&(hive, stored_path(key)))?;
+        Some(stored.get(name).cloned())
+    }
+
+    /// Every value in a key, `None` when the file doesn't have the key
+    pub fn values(&self, hive: RegistryHive, key: &str) -> Option<Vec<(String, RegistryValue)>> {
+        Some(self.keys.get(&(hive, stored_path(key)))?.values())
+    }
+
+    /// The subkeys the file has under a key, `None` when it doesn't have the key or anything under it
+    pub fn subkeys(&self, hive: RegistryHive, key: &str) -> Option<Vec<String>> {
+        stored_subkeys(&self.keys, hive, key)
+    }
+}
+
+/// Turns the raw bytes of the file into text, looking at the byte order mark
+pub(crate) fn decode_text(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let wide: Vec<u16> = rest.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        return String::from_utf16_lossy(&wide);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(rest).into_owned();
+    }
+    // REGEDIT4 files are ANSI, which on Western systems is Windows-1252 and never UTF-8
+    if bytes.starts_with(b"REGEDIT4") {
+        return bytes.iter().map(|byte| windows_1252(*byte)).collect();
+    }
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Windows-1252 matches Latin-1 except for 0x80-0x9F, where it has punctuation and a few letters
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+        '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+        '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+        '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}'
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[(byte - 0x80) as usize],
+        _ => byte as char
+    }
+}
+
+/// Splits `HKEY_LOCAL_MACHINE\SOFTWARE\...` into the root and the rest of the path
+fn split_root(path: &str) -> Option<(RegistryHive, String)> {
+    let (root, rest) = path.split_once('\\').unwrap_or((path, ""));
+    let hive = match root.to_uppercase().as_str() {
+        "HKEY_LOCAL_MACHINE" | "HKLM" => RegistryHive::LocalMachine,
+        "HKEY_CURRENT_USER" | "HKCU" => RegistryHive::CurrentUser,
+        _ => return None
+    };
+    Some((hive, rest.trim_matches('\\').to_string()))
+}
+
+/// Splits `"name"=data` (or `@=data`) into the unescaped name and the data text
+fn split_value_line(line: &str) -> Result<(String, &str), String> {
+    if let Some(data) = line.strip_prefix("@=") {
+        return Ok((String::new(), data.trim()));
+    }
+    if !line.starts_with('"') {
+        return Err(format!("expected a quoted value name or @, found {:?}", line));
+    }
+    let (name, rest) = read_quoted(&line[1..])?;
+    let data = rest
+        .trim_start()
+        .strip_prefix('=')
+        .ok_or_else(|| format!("missing '=' after value name \"{}\"", name))?;
+    Ok((name, data.trim()))
+}
+
+/// Reads a quoted string (after the opening quote), giving back the text and what's left after the closing quote
+fn read_quoted(text: &str) -> Result<(String, &str), String> {
+    let mut result = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped)) => result.push(escaped),
+                None => return Err("string ends with a lone '\\'".to_string())
+            },
+            '"' => return Ok((result, &text[i + 1..])),
+            _ => result.push(c)
+        }
+    }
+    Err("string is missing its closing quote".to_string())
+}
+
+/// Turns the text after `=` into a `RegistryValue`. `ansi` is for REGEDIT4 files, whose hex(1), hex(2),
+/// and hex(7) strings are one byte per character rather than UTF-16
+fn parse_data(data: &str, ansi: bool) -> Result<RegistryValue, String> {
+    if let Some(text) = data.strip_prefix('"') {
+        let (value, rest) = read_quoted(text)?;
+        if !rest.trim().is_empty() {
+            return Err(format!("unexpected text after string: {:?}", rest.trim()));
+        }
+        return Ok(RegistryValue::String(value));
+    }
+
+    if let Some(hex) = data.strip_prefix("dword:") {
+        if hex.len() != 8 {
+            return Err(format!("dword needs exactly 8 hex digits, found {:?}", hex));
+        }
+        return u32::from_str_radix(hex, 16)
+            .map(RegistryValue::Dword)
+            .map_err(|_| format!("invalid dword {:?}", hex));
+    }
+
+    let (value_type, bytes) = if let Some(bytes) = data.strip_prefix("hex:") {
+        (REG_BINARY, bytes)
+    } else if let Some(rest) = data.strip_prefix("hex(") {
+        let (kind, bytes) = rest
+            .split_once("):")
+            .ok_or_else(|| format!("malformed hex type in {:?}", data))?;
+        let kind = u32::from_str_radix(kind, 16).map_err(|_| format!("invalid hex type {:?}", kind))?;
+        (kind, bytes)
+    } else {
+        return Err(format!("unknown value data {:?}", data));
+    };
+
+    let bytes: Vec<u8> = bytes
+        .split(',')
+        .map(str::trim)
+        .filter(|byte| !byte.is_empty())
+        .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| format!("invalid hex byte {:?}", byte)))
+        .collect::<Result<_, _>>()?;
+    if ansi && matches!(value_type, REG_SZ | REG_EXPAND_SZ | REG_MULTI_SZ) {
+        let wide: Vec<u8> = bytes.iter().flat_map(|byte| (*byte as u16).to_le_bytes()).collect();
+        return Ok(RegistryValue::from_raw(value_type, &wide));
+    }
+    Ok(RegistryValue::from_raw(value_type, &bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"Windows Registry Editor Version 5.00
+
+; UAC policy exported from a workstation
+[HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System]
+"EnableLUA"=dword:00000001
+"ConsentPromptBehaviorAdmin"=dword:00000000
+"legalnoticecaption"="Say \"hi\" C:\\"
+@="default"
+"Path"=hex(2):25,00,53,00,79,00,73,00,\
+  25,00,00,00
+"Multi"=hex(7):61,00,00,00,62,00,00,00,00,00
+"Big"=hex(b):01,00,00,00,00,00,00,00
+"Gone"=dword:00000005
+"Gone"=-
+
+[HKEY_CLASSES_ROOT\.txt]
+@="txtfile"
+"#;
+
+    const KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Policies\\System";
+
+    #[test]
+    fn parses_values() {
+        let file = RegFile::parse(SAMPLE).unwrap();
+        let value = |name| file.value(RegistryHive::LocalMachine, KEY, name).unwrap();
+
+        assert_eq!(value("EnableLUA"), Some(RegistryValue::Dword(1)));
+        assert_eq!(value("consentpromptbehavioradmin"), Some(RegistryValue::Dword(0)));
+        assert_eq!(value("legalnoticecaption"), Some(RegistryValue::String("Say \"hi\" C:\\".to_string())));
+        assert_eq!(value(""), Some(RegistryValue::String("default".to_string())));
+        assert_eq!(value("Path"), Some(RegistryValue::ExpandString("%Sys%".to_string())));
+        assert_eq!(value("Multi"), Some(RegistryValue::MultiString(vec!["a".to_string(), "b".to_string()])));
+        assert_eq!(value("Big"), Some(RegistryValue::Qword(1)));
+        assert_eq!(value("Gone"), None);
+        assert!(file.value(RegistryHive::LocalMachine, "SOFTWARE\\Other", "EnableLUA").is_none());
+    }
+
+    #[test]
+    fn reads_utf16_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(SAMPLE.replace('\n', "\r\n").encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        let file = RegFile::parse(&decode_text(&bytes)).unwrap();
+        assert_eq!(file.value(RegistryHive::LocalMachine, KEY, "EnableLUA").unwrap(), Some(RegistryValue::Dword(1)));
+    }
+
+    #[test]
+    fn lists_values_and_subkeys_as_written() {
+        let file = RegFile::parse(SAMPLE).unwrap();
+        let values = file.values(RegistryHive::LocalMachine, &KEY.to_uppercase()).unwrap();
+        assert!(values.iter().any(|(name, _)| name == "EnableLUA"));
+        assert_eq!(file.subkeys(RegistryHive::LocalMachine, "software\\microsoft\\windows\\currentversion\\policies").unwrap(), ["System"]);
+        assert!(file.subkeys(RegistryHive::LocalMachine, "SOFTWARE\\Other").is_none());
+    }
+
+    #[test]
+    fn comments_dont_continue() {
+        let text = "Windows Registry Editor Version 5.00\n[HKEY_LOCAL_MACHINE\\SOFTWARE]\n; C:\\Temp\\\n\"A\"=dword:00000001\n";
+        let file = RegFile::parse(text).unwrap();
+        assert_eq!(file.value(RegistryHive::LocalMachine, "SOFTWARE", "A").unwrap(), Some(RegistryValue::Dword(1)));
+    }
+
+    #[test]
+    fn reads_regedit4_strings_as_ansi() {
+        let text = "REGEDIT4\n[HKEY_LOCAL_MACHINE\\SOFTWARE]\n\"Path\"=hex(2):25,54,45,4d,50,25,00\n\"Multi\"=hex(7):61,00,e9,00,00\n";
+        let file = RegFile::parse(text).unwrap();
+        let value = |name: &str| file.value(RegistryHive::LocalMachine, "SOFTWARE", name).unwrap();
+        assert_eq!(value("Path"), Some(RegistryValue::ExpandString("%TEMP%".to_string())));
+        assert_eq!(value("Multi"), Some(RegistryValue::MultiString(vec!["a".to_string(), "\u{e9}".to_string()])));
+    }
+
+    #[test]
+    fn decodes_regedit4_files_as_windows_1252() {
+        let bytes = b"REGEDIT4\r\n[HKEY_LOCAL_MACHINE\\SOFTWARE]\r\n\"Owner\"=\"Caf\xe9 \x80\"\r\n";
+        let file = RegFile::parse(&decode_text(bytes)).unwrap();
+        let value = file.value(RegistryHive::LocalMachine, "SOFTWARE", "Owner").unwrap();
+        assert_eq!(value, Some(RegistryValue::String("Caf\u{e9} \u{20ac}".to_string())));
+    }
+
+    #[test]
+    fn deletes_keys() {
+        let text = format!("{}\n[-HKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft]\n", SAMPLE);
+        let file = RegFile::parse(&text).unwrap();
+        assert!(file.value(RegistryHive::LocalMachine, KEY, "EnableLUA").is_none());
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        let error = |text: &str| RegFile::parse(text).unwrap_err();
+
+        assert_eq!(error("Not a reg file").line, 1);
+        assert_eq!(error("REGEDIT4\n\"A\"=dword:1").message, "value found before any [key] line");
+
+        let base = "Windows Registry Editor Version 5.00\n[HKEY_LOCAL_MACHINE\\SOFTWARE]\n";
+        let malformed = error(&format!("{}\"A\"=dword:1", base));
+        assert_eq!(malformed.line, 3);
+        assert!(malformed.message.contains("8 hex digits"));
+        assert!(error(&format!("{}\"A\"=hex:zz", base)).message.contains("invalid hex byte"));
+        assert!(error(&format!("{}\"A=dword:00000001", base)).message.contains("closing quote"));
+        assert!(error(&format!("{}A=1", base)).message.contains("quoted value name"));
+        assert!(error(&format!("{}[HKEY_LOCAL_MACHINE\\SOFTWARE", base)).message.contains("closing ']'"));
+        assert_eq!(error(&format!("{}\"A\"=qword:1", base)).to_string(), "line 3: unknown value data \"qword:1\"");
+    }
+}
@@ -0,0 +1,131 @@
+//! SARIF 2.1.0 output, the static analysis results format most CI systems and code scanning
+//! dashboards understand. Every finding becomes a result and every finding ID becomes a rule.
+use serde_json::{Value, json};
+
+use super::finding::{Finding, Severity};
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// SARIF only has error, warning, and note
+pub fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note"
+    }
+}
+
+/// The 0.0-10.0 score code scanning tools use to sort security results
+fn security_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "9.5",
+        Severity::High => "8.0",
+        Severity::Medium => "5.5",
+        Severity::Low => "3.0",
+        Severity::Info => "0.0"
+    }
+}
+
+/// Builds a SARIF log with one run holding every finding
+pub fn sarif_log(findings: &[Finding]) -> Value {
+    /*
+        Shugo: SARIF Rules and Results
+
+        SARIF separates "what can go wrong" (rules, under the tool's driver) from "what went wrong here"
+        (results). Each result points back at its rule by `ruleId` and `ruleIndex`. Some findings, like
+        out-of-date antivirus definitions, can show up more than once with the same ID, so rules are only
+        added the first time an ID is seen.
+
+        There's no source file to point at for a machine's settings, so results use a logical location
+        naming the Shugo module instead of a physical file location.
+
+        For more information on SARIF:
+        (https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+    */
+    let mut rules: Vec<Value> = Vec::new();
+    let mut rule_ids: Vec<&str> = Vec::new();
+    let mut results: Vec<Value> = Vec::new();
+
+    for finding in findings {
+        let rule_index = match rule_ids.iter().position(|id| *id == finding.id) {
+            Some(index) => index,
+            None => {
+                rule_ids.push(&finding.id);
+                rules.push(json!({
+                    "id": finding.id,
+                    "name": finding.id.replace('-', ""),
+                    "shortDescription": {"text": finding.title},
+                    "fullDescription": {"text": non_empty(&finding.explanation, &finding.title)},
+                    "help": {"text": non_empty(&finding.remediation, &finding.title)},
+                    "defaultConfiguration": {"level": sarif_level(finding.severity)},
+                    "properties": {
+                        "tags": ["security", finding.module],
+                        "security-severity": security_severity(finding.severity)
+                    }
+                }));
+                rule_ids.len() - 1
+            }
+        };
+
+        results.push(json!({
+            "ruleId": finding.id,
+            "ruleIndex": rule_index,
+            "level": sarif_level(finding.severity),
+            "message": {"text": finding.title},
+            "locations": [{
+                "logicalLocations": [{
+                    "name": finding.module,
+                    "fullyQualifiedName": format!("shugo::{}", finding.module),
+                    "kind": "module"
+                }]
+            }],
+            "properties": {
+                "severity": finding.severity,
+                "evidence": finding.evidence
+            }
+        }));
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "Shugo",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                }
+            },
+            "results": results
+        }]
+    })
+}
+
+fn non_empty<'a>(text: &'a str, fallback: &'a str) -> &'a str {
+    if text.is_empty() {fallback} else {text}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_rules_between_repeated_findings() {
+        let findings = vec![
+            Finding::new("AV-003", "antivirus", Severity::High, "A definitions are out-of-date"),
+            Finding::new("UAS-001", "uas", Severity::High, "Guest account is enabled").evidence("Guest enabled"),
+            Finding::new("AV-003", "antivirus", Severity::High, "B definitions are out-of-date")
+        ];
+        let log = sarif_log(&findings);
+        let run = &log["runs"][0];
+
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+        assert_eq!(run["results"].as_array().unwrap().len(), 3);
+        assert_eq!(run["results"][2]["ruleIndex"], 0);
+        assert_eq!(run["results"][1]["level"], "error");
+        assert_eq!(run["results"][1]["properties"]["evidence"][0], "Guest enabled");
+    }
+}
@@ -0,0 +1,715 @@
+//! A pure Rust reader for Windows security descriptors, in both the self-relative binary form Windows APIs
+//! hand back and as SDDL strings like `D:(A;;FA;;;BA)`. Keeping this away from the Windows security APIs means
+//! ACLs can be evaluated, and tested, on any OS.
+use std::fmt;
+
+use super::error::ShugoError;
+use super::source::SourceResult;
+
+/// ACE type for an access allowed entry
+pub const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+/// ACE type for an access denied entry
+pub const ACCESS_DENIED_ACE_TYPE: u8 = 1;
+pub const SYSTEM_AUDIT_ACE_TYPE: u8 = 2;
+pub const ACCESS_ALLOWED_OBJECT_ACE_TYPE: u8 = 5;
+pub const ACCESS_DENIED_OBJECT_ACE_TYPE: u8 = 6;
+
+/// ACE flags
+pub const OBJECT_INHERIT_ACE: u8 = 0x01;
+pub const CONTAINER_INHERIT_ACE: u8 = 0x02;
+pub const NO_PROPAGATE_INHERIT_ACE: u8 = 0x04;
+pub const INHERIT_ONLY_ACE: u8 = 0x08;
+pub const INHERITED_ACE: u8 = 0x10;
+
+/// Security descriptor control bits
+pub const SE_DACL_PRESENT: u16 = 0x0004;
+pub const SE_DACL_AUTO_INHERITED: u16 = 0x0400;
+pub const SE_DACL_PROTECTED: u16 = 0x1000;
+pub const SE_SELF_RELATIVE: u16 = 0x8000;
+
+/// File access rights
+pub const FILE_WRITE_DATA: u32 = 0x0002;
+pub const FILE_APPEND_DATA: u32 = 0x0004;
+pub const DELETE: u32 = 0x0001_0000;
+pub const WRITE_DAC: u32 = 0x0004_0000;
+pub const WRITE_OWNER: u32 = 0x0008_0000;
+pub const FILE_ALL_ACCESS: u32 = 0x001F_01FF;
+pub const FILE_GENERIC_READ: u32 = 0x0012_0089;
+pub const FILE_GENERIC_WRITE: u32 = 0x0012_0116;
+pub const FILE_GENERIC_EXECUTE: u32 = 0x0012_00A0;
+
+/// Generic rights, mapped to the file rights above before evaluating
+pub const GENERIC_ALL: u32 = 0x1000_0000;
+pub const GENERIC_EXECUTE: u32 = 0x2000_0000;
+pub const GENERIC_WRITE: u32 = 0x4000_0000;
+pub const GENERIC_READ: u32 = 0x8000_0000;
+
+/// The three masks the Sharing tab's Permissions dialog writes
+pub const SHARE_FULL_CONTROL: u32 = FILE_ALL_ACCESS;
+pub const SHARE_CHANGE: u32 = 0x0013_01BF;
+pub const SHARE_READ: u32 = 0x0012_00A9;
+
+/// SDDL aliases, their SIDs, and the names Windows shows for them
+const WELL_KNOWN: &[(&str, &str, &str)] = &[
+    ("WD", "S-1-1-0", "Everyone"),
+    ("CO", "S-1-3-0", "CREATOR OWNER"),
+    ("CG", "S-1-3-1", "CREATOR GROUP"),
+    ("NU", "S-1-5-2", "NETWORK"),
+    ("IU", "S-1-5-4", "INTERACTIVE"),
+    ("SU", "S-1-5-6", "SERVICE"),
+    ("AN", "S-1-5-7", "ANONYMOUS LOGON"),
+    ("PS", "S-1-5-10", "SELF"),
+    ("AU", "S-1-5-11", "Authenticated Users"),
+    ("SY", "S-1-5-18", "SYSTEM"),
+    ("LS", "S-1-5-19", "LOCAL SERVICE"),
+    ("NS", "S-1-5-20", "NETWORK SERVICE"),
+    ("BA", "S-1-5-32-544", "BUILTIN\\Administrators"),
+    ("BU", "S-1-5-32-545", "BUILTIN\\Users"),
+    ("BG", "S-1-5-32-546", "BUILTIN\\Guests"),
+    ("PU", "S-1-5-32-547", "BUILTIN\\Power Users"),
+    ("SO", "S-1-5-32-549", "BUILTIN\\Server Operators"),
+    ("BO", "S-1-5-32-551", "BUILTIN\\Backup Operators"),
+    ("RD", "S-1-5-32-555", "BUILTIN\\Remote Desktop Users")
+];
+
+/// SDDL access right codes and the bits they stand for
+const RIGHTS: &[(&str, u32)] = &[
+    ("GA", GENERIC_ALL),
+    ("GR", GENERIC_READ),
+    ("GW", GENERIC_WRITE),
+    ("GX", GENERIC_EXECUTE),
+    ("FA", FILE_ALL_ACCESS),
+    ("FR", FILE_GENERIC_READ),
+    ("FW", FILE_GENERIC_WRITE),
+    ("FX", FILE_GENERIC_EXECUTE),
+    ("KA", 0x000F_003F),
+    ("KR", 0x0002_0019),
+    ("KW", 0x0002_0006),
+    ("KX", 0x0002_0019),
+    ("RC", 0x0002_0000),
+    ("SD", DELETE),
+    ("WD", WRITE_DAC),
+    ("WO", WRITE_OWNER),
+    ("CC", 0x0001),
+    ("DC", 0x0002),
+    ("LC", 0x0004),
+    ("SW", 0x0008),
+    ("RP", 0x0010),
+    ("WP", 0x0020),
+    ("DT", 0x0040),
+    ("LO", 0x0080),
+    ("CR", 0x0100)
+];
+
+/// SDDL ACE flag codes
+const ACE_FLAGS: &[(&str, u8)] = &[
+    ("OI", OBJECT_INHERIT_ACE),
+    ("CI", CONTAINER_INHERIT_ACE),
+    ("NP", NO_PROPAGATE_INHERIT_ACE),
+    ("IO", INHERIT_ONLY_ACE),
+    ("ID", INHERITED_ACE),
+    ("SA", 0x40),
+    ("FA", 0x80)
+];
+
+/// A security identifier, the number Windows uses for every user, group, and built-in account
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Sid {
+    pub revision: u8,
+    /// The identifier authority, 5 (NT Authority) for almost everything
+    pub authority: u64,
+    pub sub_authorities: Vec<u32>
+}
+
+impl Sid {
+    /// Reads an `S-1-5-32-544` string or a two letter SDDL alias like `BA`
+    pub fn parse(text: &str) -> SourceResult<Sid> {
+        if let Some((_, sid, _)) = WELL_KNOWN.iter().find(|(alias, _, _)| *alias == text) {
+            return Sid::parse(sid);
+        }
+        let error = || ShugoError::parse("SID", format!("{:?} isn't a SID", text));
+        let mut parts = text.split('-');
+        if !parts.next().is_some_and(|prefix| prefix.eq_ignore_ascii_case("S")) {
+            return Err(error());
+        }
+        let revision = parts.next().and_then(|part| part.parse::<u8>().ok()).ok_or_else(error)?;
+        let authority = parts.next().and_then(|part| match part.strip_prefix("0x").or_else(|| part.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => part.parse::<u64>().ok()
+        }).filter(|authority| *authority < 1 << 48).ok_or_else(error)?;
+        let sub_authorities = parts.map(|part| part.parse::<u32>().map_err(|_| error())).collect::<SourceResult<Vec<u32>>>()?;
+        if sub_authorities.len() > 15 {
+            return Err(error());
+        }
+        Ok(Sid { revision, authority, sub_authorities })
+    }
+
+    /// Reads a binary SID, returning it and how many bytes it took up
+    pub fn from_bytes(bytes: &[u8]) -> SourceResult<(Sid, usize)> {
+        /*
+            Shugo: Binary SIDs
+
+            A SID in memory is a revision byte, a count of sub-authorities, a 6 byte big-endian identifier
+            authority, then each sub-authority as a little-endian u32. `S-1-5-32-544` is revision 1,
+            authority 5, sub-authorities 32 and 544.
+
+            For more information on the SID structure:
+            (https://learn.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-sid)
+        */
+        let header = bytes.get(..8).ok_or_else(|| ShugoError::parse("SID", "too short for a SID header"))?;
+        let count = header[1] as usize;
+        let length = 8 + count * 4;
+        let body = bytes.get(8..length).ok_or_else(|| ShugoError::parse("SID", format!("{} sub-authorities don't fit", count)))?;
+        let authority = header[2..8].iter().fold(0u64, |total, byte| (total << 8) | *byte as u64);
+        let sub_authorities = body.chunks_exact(4).map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])).collect();
+        Ok((Sid { revision: header[0], authority, sub_authorities }, length))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.revision, self.sub_authorities.len() as u8];
+        bytes.extend_from_slice(&self.authority.to_be_bytes()[2..]);
+        for sub_authority in self.sub_authorities.iter() {
+            bytes.extend_from_slice(&sub_authority.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// The two letter SDDL alias, when the SID has one
+    pub fn alias(&self) -> Option<&'static str> {
+        let text = self.to_string();
+        WELL_KNOWN.iter().find(|(_, sid, _)| *sid == text).map(|(alias, _, _)| *alias)
+    }
+
+    /// The account name for well-known SIDs, otherwise the SID itself
+    pub fn name(&self) -> String {
+        let text = self.to_string();
+        match WELL_KNOWN.iter().find(|(_, sid, _)| *sid == text) {
+            Some((_, _, name)) => name.to_string(),
+            None => text
+        }
+    }
+}
+
+impl fmt::Display for Sid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.authority < 1 << 32 {
+            write!(f, "S-{}-{}", self.revision, self.authority)?;
+        } else {
+            write!(f, "S-{}-0x{:012X}", self.revision, self.authority)?;
+        }
+        for sub_authority in self.sub_authorities.iter() {
+            write!(f, "-{}", sub_authority)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether an ACE grants or denies access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AceType {
+    Allow,
+    Deny,
+    /// Audit and object ACEs, kept so the ACL round trips but ignored when evaluating access
+    Other(u8)
+}
+
+impl AceType {
+    fn from_u8(value: u8) -> AceType {
+        match value {
+            ACCESS_ALLOWED_ACE_TYPE => AceType::Allow,
+            ACCESS_DENIED_ACE_TYPE => AceType::Deny,
+            other => AceType::Other(other)
+        }
+    }
+
+    fn as_u8(&self) -> u8 {
+        match self {
+            AceType::Allow => ACCESS_ALLOWED_ACE_TYPE,
+            AceType::Deny => ACCESS_DENIED_ACE_TYPE,
+            AceType::Other(other) => *other
+        }
+    }
+}
+
+/// One access control entry: who it applies to, and which rights it allows or denies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ace {
+    pub ace_type: AceType,
+    pub flags: u8,
+    pub mask: u32,
+    pub sid: Sid
+}
+
+impl Ace {
+    pub fn new(ace_type: AceType, mask: u32, sid: Sid) -> Self {
+        Ace { ace_type, flags: 0, mask, sid }
+    }
+
+    /// Inherit-only ACEs are only copied to children, they don't apply to the object they're on
+    pub fn is_inherit_only(&self) -> bool {
+        self.flags & INHERIT_ONLY_ACE != 0
+    }
+}
+
+/// The parts of a security descriptor Shugo evaluates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityDescriptor {
+    pub control: u16,
+    pub owner: Option<Sid>,
+    pub group: Option<Sid>,
+    /// `None` is a NULL DACL, which gives everyone full access. An empty list gives no one access
+    pub dacl: Option<Vec<Ace>>
+}
+
+impl SecurityDescriptor {
+    /// Reads a self-relative security descriptor
+    pub fn from_bytes(bytes: &[u8]) -> SourceResult<SecurityDescriptor> {
+        /*
+            Shugo: Self-Relative Security Descriptors
+
+            APIs like `NetShareEnum` and `RegGetKeySecurity` return descriptors in self-relative form: one block
+            of memory where the 20 byte header holds offsets (from the start of the block) to the owner SID, the
+            group SID, the SACL, and the DACL. An offset of 0 means that part isn't there.
+
+            An ACL has an 8 byte header with the number of ACEs, then the ACEs one after another. Every ACE starts
+            with its type, flags, and total size, so ACE types we don't understand can be stepped over.
+
+            The DACL is what decides access. If the SE_DACL_PRESENT control bit isn't set, or the DACL offset is 0,
+            the object has a NULL DACL and everyone gets full access.
+
+            For more information on the layout:
+            (https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/7d4dac05-9cef-4563-a058-f108abecce1d)
+        */
+        if bytes.len() < 20 {
+            return Err(ShugoError::parse("security descriptor", "too short for a security descriptor header"));
+        }
+        let control = read_u16(bytes, 2)?;
+        if control & SE_SELF_RELATIVE == 0 {
+            return Err(ShugoError::parse("security descriptor", "only self-relative security descriptors can be read"));
+        }
+        let sid_at = |offset: u32| -> SourceResult<Option<Sid>> {
+            if offset == 0 {
+                return Ok(None);
+            }
+            let rest = bytes.get(offset as usize..).ok_or_else(|| ShugoError::parse("security descriptor", format!("SID offset {} is past the end", offset)))?;
+            Sid::from_bytes(rest).map(|(sid, _)| Some(sid))
+        };
+        let owner = sid_at(read_u32(bytes, 4)?)?;
+        let group = sid_at(read_u32(bytes, 8)?)?;
+        let dacl_offset = read_u32(bytes, 16)? as usize;
+        let dacl = if control & SE_DACL_PRESENT == 0 || dacl_offset == 0 {
+            None
+        } else {
+            Some(read_acl(bytes, dacl_offset)?)
+        };
+        Ok(SecurityDescriptor { control, owner, group, dacl })
+    }
+
+    /// Writes the descriptor back out in self-relative form, without a SACL
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut control = (self.control & !SE_DACL_PRESENT) | SE_SELF_RELATIVE;
+        if self.dacl.is_some() {
+            control |= SE_DACL_PRESENT;
+        }
+        let mut bytes = vec![1, 0];
+        bytes.extend_from_slice(&control.to_le_bytes());
+        bytes.resize(20, 0);
+
+        let place = |bytes: &mut Vec<u8>, field: usize, part: Vec<u8>| {
+            let offset = bytes.len() as u32;
+            bytes[field..field + 4].copy_from_slice(&offset.to_le_bytes());
+            bytes.extend(part);
+        };
+        if let Some(owner) = &self.owner {
+            place(&mut bytes, 4, owner.to_bytes());
+        }
+        if let Some(group) = &self.group {
+            place(&mut bytes, 8, group.to_bytes());
+        }
+        if let Some(aces) = &self.dacl {
+            let mut entries = Vec::new();
+            for ace in aces.iter() {
+                let sid = ace.sid.to_bytes();
+                entries.push(ace.ace_type.as_u8());
+                entries.push(ace.flags);
+                entries.extend_from_slice(&(8 + sid.len() as u16).to_le_bytes());
+                entries.extend_from_slice(&ace.mask.to_le_bytes());
+                entries.extend(sid);
+            }
+            let mut acl = vec![2, 0];
+            acl.extend_from_slice(&(8 + entries.len() as u16).to_le_bytes());
+            acl.extend_from_slice(&(aces.len() as u16).to_le_bytes());
+            acl.extend_from_slice(&[0, 0]);
+            acl.extend(entries);
+            place(&mut bytes, 16, acl);
+        }
+        bytes
+    }
+
+    /// Reads an SDDL string like `O:BAG:SYD:PAI(A;OICI;FA;;;BA)(A;;0x1200a9;;;WD)`
+    pub fn from_sddl(text: &str) -> SourceResult<SecurityDescriptor> {
+        /*
+            Shugo: SDDL
+
+            SDDL (Security Descriptor Definition Language) is the text form of a security descriptor, the one
+            `icacls /save`, `Get-Acl | Select Sddl`, and `sc sdshow` print. It has up to four parts:
+
+            - O:owner and G:group, each a SID or a two letter alias (BA = Administrators, SY = SYSTEM, ...)
+            - D:flags(ace)(ace)... the DACL. P = protected from inheritance, AI = auto-inherited,
+              NO_ACCESS_CONTROL = a NULL DACL
+            - S:flags(ace)... the SACL, used for auditing, which Shugo reads past
+
+            Each ACE is `type;flags;rights;object guid;inherited object guid;sid`, like (A;OICI;FA;;;BA):
+            allow, inherited by files and folders, full access, to Administrators.
+
+            For more information on SDDL:
+            (https://learn.microsoft.com/en-us/windows/win32/secauthz/security-descriptor-string-format)
+        */
+        let text = text.trim();
+        let error = |reason: String| ShugoError::parse("SDDL", reason);
+        let mut descriptor = SecurityDescriptor { control: SE_SELF_RELATIVE, owner: None, group: None, dacl: None };
+        let mut rest = text;
+        while !rest.is_empty() {
+            let Some((part, after)) = rest.split_once(':').filter(|(part, _)| part.len() == 1) else {
+                return Err(error(format!("expected O:, G:, D:, or S: at {:?}", rest)));
+            };
+            let end = next_part(after);
+            let (value, next) = after.split_at(end);
+            match part {
+                "O" => descriptor.owner = Some(Sid::parse(value)?),
+                "G" => descriptor.group = Some(Sid::parse(value)?),
+                "D" => {
+                    let (flags, aces) = parse_acl(value)?;
+                    if flags.contains("P") {descriptor.control |= SE_DACL_PROTECTED;}
+                    if flags.contains("AI") {descriptor.control |= SE_DACL_AUTO_INHERITED;}
+                    if flags.contains("NO_ACCESS_CONTROL") {
+                        descriptor.dacl = None;
+                    } else {
+                        descriptor.control |= SE_DACL_PRESENT;
+                        descriptor.dacl = Some(aces);
+                    }
+                },
+                "S" => {parse_acl(value)?;},
+                other => return Err(error(format!("unknown part {:?}", other)))
+            }
+            rest = next;
+        }
+        Ok(descriptor)
+    }
+
+    /// The descriptor as SDDL, without a SACL
+    pub fn to_sddl(&self) -> String {
+        let mut text = String::new();
+        let sid_text = |sid: &Sid| sid.alias().map(str::to_string).unwrap_or_else(|| sid.to_string());
+        if let Some(owner) = &self.owner {
+            text.push_str(&format!("O:{}", sid_text(owner)));
+        }
+        if let Some(group) = &self.group {
+            text.push_str(&format!("G:{}", sid_text(group)));
+        }
+        text.push_str("D:");
+        if self.control & SE_DACL_PROTECTED != 0 {text.push('P');}
+        if self.control & SE_DACL_AUTO_INHERITED != 0 {text.push_str("AI");}
+        match &self.dacl {
+            None => text.push_str("NO_ACCESS_CONTROL"),
+            Some(aces) => for ace in aces.iter() {
+                let ace_type = match ace.ace_type {
+                    AceType::Allow => "A".to_string(),
+                    AceType::Deny => "D".to_string(),
+                    AceType::Other(ACCESS_ALLOWED_OBJECT_ACE_TYPE) => "OA".to_string(),
+                    AceType::Other(ACCESS_DENIED_OBJECT_ACE_TYPE) => "OD".to_string(),
+                    AceType::Other(SYSTEM_AUDIT_ACE_TYPE) => "AU".to_string(),
+                    AceType::Other(other) => format!("0x{:x}", other)
+                };
+                let flags: String = ACE_FLAGS.iter().filter(|(_, bit)| ace.flags & bit != 0).map(|(code, _)| *code).collect();
+                let rights = RIGHTS.iter().find(|(_, bits)| *bits == ace.mask).map(|(code, _)| code.to_string()).unwrap_or_else(|| format!("0x{:x}", ace.mask));
+                text.push_str(&format!("({};{};{};;;{})", ace_type, flags, rights, sid_text(&ace.sid)));
+            }
+        }
+        text
+    }
+
+    /// The rights the DACL grants to someone holding every SID in `sids`, generic rights mapped to file rights
+    pub fn access_for(&self, sids: &[Sid]) -> u32 {
+        /*
+            Shugo: Evaluating A DACL
+
+            Windows walks the DACL in order. Each ACE that names one of the caller's SIDs either grants or denies
+            its rights, but only rights that haven't been decided yet: once a right is denied a later allow can't
+            grant it back, and once it's granted a later deny doesn't take it away. That's why properly ordered
+            DACLs list deny ACEs first.
+
+            Generic rights (GENERIC_READ, GENERIC_ALL, ...) are mapped to the specific file rights they stand
+            for first, so `GA` and `FA` come out the same.
+
+            For more information on how access is checked:
+            (https://learn.microsoft.com/en-us/windows/win32/secauthz/how-dacls-control-access-to-an-object)
+        */
+        let Some(aces) = &self.dacl else {
+            return FILE_ALL_ACCESS;
+        };
+        let mut granted = 0u32;
+        let mut denied = 0u32;
+        for ace in aces.iter().filter(|ace| !ace.is_inherit_only() && sids.contains(&ace.sid)) {
+            let mask = map_generic(ace.mask);
+            match ace.ace_type {
+                AceType::Allow => granted |= mask & !denied,
+                AceType::Deny => denied |= mask & !granted,
+                AceType::Other(_) => {}
+            }
+        }
+        granted
+    }
+}
+
+/// Maps GENERIC_* bits to the file rights they stand for
+pub fn map_generic(mask: u32) -> u32 {
+    let mut mapped = mask & !(GENERIC_ALL | GENERIC_EXECUTE | GENERIC_WRITE | GENERIC_READ);
+    if mask & GENERIC_ALL != 0 {mapped |= FILE_ALL_ACCESS;}
+    if mask & GENERIC_EXECUTE != 0 {mapped |= FILE_GENERIC_EXECUTE;}
+    if mask & GENERIC_WRITE != 0 {mapped |= FILE_GENERIC_WRITE;}
+    if mask & GENERIC_READ != 0 {mapped |= FILE_GENERIC_READ;}
+    mapped
+}
+
+/// Whether the rights let someone change or delete files, or take over the permissions
+pub fn can_write(mask: u32) -> bool {
+    map_generic(mask) & (FILE_WRITE_DATA | FILE_APPEND_DATA | DELETE | WRITE_DAC | WRITE_OWNER) != 0
+}
+
+/// Share permissions the way the Sharing tab names them
+pub fn share_access_text(mask: u32) -> String {
+    match map_generic(mask) {
+        SHARE_FULL_CONTROL => "Full Control".to_string(),
+        SHARE_CHANGE => "Change".to_string(),
+        SHARE_READ => "Read".to_string(),
+        0 => "None".to_string(),
+        other => format!("0x{:08X}", other)
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> SourceResult<u16> {
+    bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| ShugoError::parse("security descriptor", format!("offset {} is past the end", offset)))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> SourceResult<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| ShugoError::parse("security descriptor", format!("offset {} is past the end", offset)))
+}
+
+/// Reads the ACL at `offset`, skipping ACE types that don't carry a mask and SID
+fn read_acl(bytes: &[u8], offset: usize) -> SourceResult<Vec<Ace>> {
+    let count = read_u16(bytes, offset + 4)? as usize;
+    let mut position = offset + 8;
+    let mut aces = Vec::with_capacity(count);
+    for _ in 0..count {
+        let ace_type = *bytes.get(position).ok_or_else(|| ShugoError::parse("security descriptor", "ACL ends early"))?;
+        let flags = bytes[position + 1..].first().copied().unwrap_or_default();
+        let size = read_u16(bytes, position + 2)? as usize;
+        if size < 8 {
+            return Err(ShugoError::parse("security descriptor", format!("ACE of {} bytes", size)));
+        }
+        let body = bytes.get(position + 4..position + size).ok_or_else(|| ShugoError::parse("security descriptor", "ACE runs past the end"))?;
+        match ace_type {
+            ACCESS_ALLOWED_ACE_TYPE | ACCESS_DENIED_ACE_TYPE | SYSTEM_AUDIT_ACE_TYPE => {
+                let mask = read_u32(body, 0)?;
+                let (sid, _) = Sid::from_bytes(&body[4..])?;
+                aces.push(Ace { ace_type: AceType::from_u8(ace_type), flags, mask, sid });
+            },
+            ACCESS_ALLOWED_OBJECT_ACE_TYPE | ACCESS_DENIED_OBJECT_ACE_TYPE => {
+                // Object ACEs have a flags field saying which of the two optional GUIDs follow the mask
+                let mask = read_u32(body, 0)?;
+                let object_flags = read_u32(body, 4)?;
+                let start = 8 + 16 * (object_flags & 1) as usize + 16 * ((object_flags >> 1) & 1) as usize;
+                let sid_bytes = body.get(start..).ok_or_else(|| ShugoError::parse("security descriptor", "object ACE runs past the end"))?;
+                let (sid, _) = Sid::from_bytes(sid_bytes)?;
+                aces.push(Ace { ace_type: AceType::Other(ace_type), flags, mask, sid });
+            },
+            _ => {}
+        }
+        position += size;
+    }
+    Ok(aces)
+}
+
+/// Where the current SDDL part ends: at the next `O:`, `G:`, `D:`, or `S:` outside of parentheses
+fn next_part(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    for (index, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b'O' | b'G' | b'D' | b'S' if depth == 0 && bytes.get(index + 1) == Some(&b':') => return index,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Splits a `D:` or `S:` value into its flags and ACEs
+fn parse_acl(text: &str) -> SourceResult<(String, Vec<Ace>)> {
+    let start = text.find('(').unwrap_or(text.len());
+    let flags = text[..start].to_string();
+    let mut aces = Vec::new();
+    let mut rest = &text[start..];
+    while !rest.is_empty() {
+        let Some(end) = rest.find(')').filter(|_| rest.starts_with('(')) else {
+            return Err(ShugoError::parse("SDDL", format!("expected an ACE at {:?}", rest)));
+        };
+        aces.push(parse_ace(&rest[1..end])?);
+        rest = &rest[end + 1..];
+    }
+    Ok((flags, aces))
+}
+
+fn parse_ace(text: &str) -> SourceResult<Ace> {
+    let error = |reason: String| ShugoError::parse("SDDL", format!("({}): {}", text, reason));
+    let fields: Vec<&str> = text.split(';').collect();
+    if fields.len() < 6 {
+        return Err(error(format!("expected 6 fields, found {}", fields.len())));
+    }
+    let ace_type = match fields[0] {
+        "A" => AceType::Allow,
+        "D" => AceType::Deny,
+        "OA" => AceType::Other(ACCESS_ALLOWED_OBJECT_ACE_TYPE),
+        "OD" => AceType::Other(ACCESS_DENIED_OBJECT_ACE_TYPE),
+        "AU" => AceType::Other(SYSTEM_AUDIT_ACE_TYPE),
+        other => return Err(error(format!("unknown ACE type {:?}", other)))
+    };
+    let mut flags = 0u8;
+    for code in chunks(fields[1]) {
+        flags |= ACE_FLAGS.iter().find(|(name, _)| *name == code).map(|(_, bit)| *bit).ok_or_else(|| error(format!("unknown ACE flag {:?}", code)))?;
+    }
+    let mask = match fields[2].strip_prefix("0x").or_else(|| fields[2].strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).map_err(|_| error(format!("{:?} isn't a mask", fields[2])))?,
+        None if !fields[2].is_empty() && fields[2].bytes().all(|byte| byte.is_ascii_digit()) => {
+            fields[2].parse::<u32>().map_err(|_| error(format!("{:?} isn't a mask", fields[2])))?
+        },
+        None => {
+            let mut mask = 0u32;
+            for code in chunks(fields[2]) {
+                mask |= RIGHTS.iter().find(|(name, _)| *name == code).map(|(_, bits)| *bits).ok_or_else(|| error(format!("unknown right {:?}", code)))?;
+            }
+            mask
+        }
+    };
+    Ok(Ace { ace_type, flags, mask, sid: Sid::parse(fields[5])? })
+}
+
+/// Splits run-together two letter codes like `OICI`. Goes by character, so text that isn't ASCII gives
+/// unknown codes instead of cutting a character in half
+fn chunks(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest.char_indices().nth(2).map(|(index, _)| index).unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sid(text: &str) -> Sid {
+        Sid::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_and_prints_sids() {
+        let administrators = sid("S-1-5-32-544");
+        assert_eq!(administrators, sid("BA"));
+        assert_eq!(administrators.alias(), Some("BA"));
+        assert_eq!(administrators.name(), "BUILTIN\\Administrators");
+        assert_eq!(sid("S-1-5-21-1-2-3-1001").to_string(), "S-1-5-21-1-2-3-1001");
+        assert_eq!(sid("S-1-5-21-1-2-3-1001").name(), "S-1-5-21-1-2-3-1001");
+
+        let bytes = administrators.to_bytes();
+        assert_eq!(bytes, [1, 2, 0, 0, 0, 0, 0, 5, 32, 0, 0, 0, 0x20, 2, 0, 0]);
+        assert_eq!(Sid::from_bytes(&bytes).unwrap(), (administrators, 16));
+
+        assert!(Sid::parse("XX").is_err());
+        assert!(Sid::parse("S-1-five").is_err());
+        assert!(Sid::from_bytes(&[1, 3, 0, 0, 0, 0, 0, 5, 32, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn round_trips_sddl_and_binary() {
+        let sddl = "O:BAG:SYD:PAI(D;;FW;;;BG)(A;OICI;FA;;;BA)(A;;0x1301bf;;;AU)(A;OICIIO;GA;;;CO)";
+        let descriptor = SecurityDescriptor::from_sddl(sddl).unwrap();
+        assert_eq!(descriptor.owner, Some(sid("BA")));
+        assert_eq!(descriptor.group, Some(sid("SY")));
+        let aces = descriptor.dacl.as_ref().unwrap();
+        assert_eq!(aces.len(), 4);
+        assert_eq!((aces[0].ace_type, aces[0].mask), (AceType::Deny, FILE_GENERIC_WRITE));
+        assert_eq!(aces[1].flags, OBJECT_INHERIT_ACE | CONTAINER_INHERIT_ACE);
+        assert_eq!(aces[2].mask, SHARE_CHANGE);
+        assert!(aces[3].is_inherit_only());
+        assert_eq!(descriptor.to_sddl(), sddl);
+
+        let bytes = descriptor.to_bytes();
+        assert_eq!(SecurityDescriptor::from_bytes(&bytes).unwrap(), descriptor);
+    }
+
+    #[test]
+    fn reads_null_and_empty_dacls() {
+        let null = SecurityDescriptor::from_sddl("D:NO_ACCESS_CONTROL").unwrap();
+        assert_eq!(null.dacl, None);
+        assert_eq!(null.access_for(&[sid("WD")]), FILE_ALL_ACCESS);
+        assert_eq!(SecurityDescriptor::from_bytes(&null.to_bytes()).unwrap().dacl, None);
+
+        let empty = SecurityDescriptor::from_sddl("D:").unwrap();
+        assert_eq!(empty.dacl, Some(vec![]));
+        assert_eq!(empty.access_for(&[sid("WD")]), 0);
+    }
+
+    #[test]
+    fn rejects_bad_descriptors() {
+        assert!(SecurityDescriptor::from_sddl("D:(A;;FA;;WD)").is_err());
+        assert!(SecurityDescriptor::from_sddl("D:(X;;FA;;;WD)").is_err());
+        assert!(SecurityDescriptor::from_sddl("D:(A;;ZZ;;;WD)").is_err());
+        assert!(SecurityDescriptor::from_sddl("Q:BA").is_err());
+        assert!(SecurityDescriptor::from_sddl("D:(A;a\u{e9};FA;;;WD)").is_err());
+        assert!(SecurityDescriptor::from_sddl("D:(A;;F\u{e9};;;WD)").is_err());
+        assert!(SecurityDescriptor::from_bytes(&[1, 0, 0x04, 0x80]).is_err());
+
+        // An ACL that claims more ACEs than it holds
+        let mut bytes = SecurityDescriptor::from_sddl("D:(A;;FA;;;WD)").unwrap().to_bytes();
+        let offset = u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize;
+        bytes[offset + 4] = 2;
+        assert!(SecurityDescriptor::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn evaluates_access_in_order() {
+        let descriptor = SecurityDescriptor::from_sddl("D:(D;;FW;;;BG)(A;;GA;;;WD)(A;;FR;;;BU)").unwrap();
+        let everyone = sid("WD");
+        assert_eq!(descriptor.access_for(std::slice::from_ref(&everyone)), FILE_ALL_ACCESS);
+        let guest_access = descriptor.access_for(&[sid("BG"), everyone.clone()]);
+        assert_eq!(guest_access & FILE_WRITE_DATA, 0);
+        assert!(can_write(guest_access), "DELETE is still granted");
+        assert_eq!(descriptor.access_for(&[sid("SY")]), 0);
+
+        // An allow before a deny wins, since the right is already granted
+        let misordered = SecurityDescriptor::from_sddl("D:(A;;FA;;;WD)(D;;FA;;;WD)").unwrap();
+        assert_eq!(misordered.access_for(std::slice::from_ref(&everyone)), FILE_ALL_ACCESS);
+
+        let inherit_only = SecurityDescriptor::from_sddl("D:(A;OICIIO;FA;;;WD)").unwrap();
+        assert_eq!(inherit_only.access_for(&[everyone]), 0);
+    }
+
+    #[test]
+    fn names_share_rights() {
+        assert_eq!(share_access_text(SHARE_READ), "Read");
+        assert_eq!(share_access_text(GENERIC_ALL), "Full Control");
+        assert!(!can_write(SHARE_READ));
+        assert!(can_write(SHARE_CHANGE));
+        assert!(can_write(GENERIC_WRITE));
+    }
+}
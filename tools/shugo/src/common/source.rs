@@ -0,0 +1,347 @@
+//! This is where Shugo gets its data from. Every scanner asks a `SecuritySource` for the raw
+//! information it needs (WMI rows, registry values, firewall policy, updates, user accounts, and shares)
+//! instead of calling Windows directly.
+//!
+//! - `LiveSource` talks to COM/WMI/Registry/NetAPI and only exists on Windows
+//! - `FixtureSource` is an in-memory source used for tests and offline audits
+//!
+//! Splitting it this way means the decoding and assessment code in each module can run anywhere,
+//! while the `unsafe` Windows calls live in one place.
+use std::collections::BTreeMap;
+
+use super::error::ShugoError;
+use super::wmi::CimDateTime;
+use crate::firewall::scanner::{FirewallRule, WindowsFirewallProfile};
+
+/// Result type used by every data source
+pub type SourceResult<T> = std::result::Result<T, ShugoError>;
+
+/// Registry root keys Shugo reads from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegistryHive {
+    LocalMachine,
+    CurrentUser
+}
+
+impl RegistryHive {
+    pub fn root_name(&self) -> &'static str {
+        match self {
+            RegistryHive::LocalMachine => "HKEY_LOCAL_MACHINE",
+            RegistryHive::CurrentUser => "HKEY_CURRENT_USER"
+        }
+    }
+
+    /// The full path of a value, used to say which lookup failed
+    pub fn value_path(&self, key: &str, value: &str) -> String {
+        format!("{}\\{}\\{}", self.root_name(), key, value)
+    }
+}
+
+/// Registry value types as Windows numbers them
+pub const REG_SZ: u32 = 1;
+pub const REG_EXPAND_SZ: u32 = 2;
+pub const REG_BINARY: u32 = 3;
+pub const REG_DWORD: u32 = 4;
+pub const REG_MULTI_SZ: u32 = 7;
+pub const REG_QWORD: u32 = 11;
+
+/// A registry value and the type it was stored as
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryValue {
+    Dword(u32),
+    Qword(u64),
+    String(String),
+    ExpandString(String),
+    MultiString(Vec<String>),
+    Binary(Vec<u8>)
+}
+
+impl RegistryValue {
+    /// Turning raw registry bytes into a `RegistryValue` using the REG_* type number
+    pub fn from_raw(value_type: u32, data: &[u8]) -> RegistryValue {
+        let text = |bytes: &[u8]| -> String {
+            let wide: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+            String::from_utf16_lossy(&wide)
+        };
+
+        match value_type {
+            REG_DWORD if data.len() >= 4 => RegistryValue::Dword(u32::from_le_bytes([data[0], data[1], data[2], data[3]])),
+            REG_QWORD if data.len() >= 8 => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&data[..8]);
+                RegistryValue::Qword(u64::from_le_bytes(bytes))
+            },
+            REG_SZ => RegistryValue::String(text(data).trim_end_matches('\0').to_string()),
+            REG_EXPAND_SZ => RegistryValue::ExpandString(text(data).trim_end_matches('\0').to_string()),
+            REG_MULTI_SZ => RegistryValue::MultiString(
+                text(data)
+                    .split('\0')
+                    .filter(|part| !part.is_empty())
+                    .map(|part| part.to_string())
+                    .collect()
+            ),
+            _ => RegistryValue::Binary(data.to_vec())
+        }
+    }
+
+    /// Gives back the value as a DWORD if it was stored as one
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            RegistryValue::Dword(value) => Some(*value),
+            _ => None
+        }
+    }
+}
+
+/// A single property value from a WMI object
+#[derive(Debug, Clone, PartialEq)]
+pub enum WmiValue {
+    /// The property exists but has no value
+    Null,
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    String(String),
+    DateTime(CimDateTime),
+    Array(Vec<WmiValue>),
+    /// A VARIANT type Shugo doesn't convert, kept so it isn't mistaken for null
+    Unsupported(u16)
+}
+
+impl WmiValue {
+    /// Short name of the stored type, used in type mismatch errors
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            WmiValue::Null => "null",
+            WmiValue::Bool(_) => "boolean",
+            WmiValue::I32(_) => "sint32",
+            WmiValue::U32(_) => "uint32",
+            WmiValue::I64(_) => "sint64",
+            WmiValue::U64(_) => "uint64",
+            WmiValue::F64(_) => "real64",
+            WmiValue::String(_) => "string",
+            WmiValue::DateTime(_) => "datetime",
+            WmiValue::Array(_) => "array",
+            WmiValue::Unsupported(_) => "unsupported"
+        }
+    }
+}
+
+/// One object returned from a WMI query, stored as property name and value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WmiRow {
+    pub properties: BTreeMap<String, WmiValue>
+}
+
+impl WmiRow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder style helper for filling in fixture rows
+    pub fn with(mut self, name: &str, value: WmiValue) -> Self {
+        self.properties.insert(name.to_string(), value);
+        self
+    }
+}
+
+/// A category attached to a pending update (an UpdateClassification or a Product)
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateCategory {
+    pub category_type: String,
+    pub name: String,
+    pub id: String
+}
+
+/// A pending update as reported by the Windows Update Agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateRecord {
+    pub title: String,
+    pub description: String,
+    pub min_download_bytes: u128,
+    pub max_download_bytes: u128,
+    pub categories: Vec<UpdateCategory>,
+    /// When the update was published or last changed on the update service, in Unix seconds
+    pub released: Option<u64>
+}
+
+/// A local account as reported by `NetUserEnum` level 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserRecord {
+    pub username: String,
+    pub privilege: u32,
+    pub flags: u32
+}
+
+/// A shared folder or printer as reported by `NetShareEnum` level 502
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShareRecord {
+    pub name: String,
+    /// STYPE_* value, with STYPE_SPECIAL (0x80000000) set on the shares Windows creates itself
+    pub share_type: u32,
+    pub remark: String,
+    pub path: String,
+    pub current_uses: u32,
+    /// The share's self-relative security descriptor, `Ok(None)` when the share doesn't have one and an error
+    /// when Windows handed back one that isn't valid
+    pub security_descriptor: SourceResult<Option<Vec<u8>>>
+}
+
+/// A registry key held in memory by the fixture and .reg file sources. Lookups ignore case like the
+/// registry does, but key and value names come back the way they were written
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StoredKey {
+    pub(crate) path: String,
+    values: BTreeMap<String, (String, RegistryValue)>
+}
+
+/// Stored keys by hive and lower case path
+pub(crate) type StoredKeys = BTreeMap<(RegistryHive, String), StoredKey>;
+
+impl StoredKey {
+    pub(crate) fn get(&self, name: &str) -> Option<&RegistryValue> {
+        self.values.get(&name.to_lowercase()).map(|(_, data)| data)
+    }
+
+    pub(crate) fn set(&mut self, name: &str, data: RegistryValue) {
+        self.values.insert(name.to_lowercase(), (name.to_string(), data));
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) {
+        self.values.remove(&name.to_lowercase());
+    }
+
+    pub(crate) fn values(&self) -> Vec<(String, RegistryValue)> {
+        self.values.values().cloned().collect()
+    }
+}
+
+/// The lookup form of a key path, lower case without leading or trailing backslashes
+pub(crate) fn stored_path(key: &str) -> String {
+    key.trim_matches('\\').to_lowercase()
+}
+
+/// Finds a stored key, creating it with `key` as its written path when it doesn't exist yet
+pub(crate) fn stored_key<'a>(keys: &'a mut StoredKeys, hive: RegistryHive, key: &str) -> &'a mut StoredKey {
+    keys.entry((hive, stored_path(key))).or_insert_with(|| StoredKey {
+        path: key.trim_matches('\\').to_string(),
+        values: BTreeMap::new()
+    })
+}
+
+/// The names of a key's subkeys. Keys are stored by full path, so these are the next name down from any
+/// longer path. `None` when there's neither the key nor anything under it
+pub(crate) fn stored_subkeys(keys: &StoredKeys, hive: RegistryHive, key: &str) -> Option<Vec<String>> {
+    let key = stored_path(key);
+    let prefix = format!("{}\\", key);
+    let depth = if key.is_empty() { 0 } else { key.split('\\').count() };
+
+    let mut found = keys.contains_key(&(hive, key.clone()));
+    let mut subkeys: Vec<String> = Vec::new();
+    for ((_, path), stored) in keys.iter().filter(|((entry_hive, _), _)| *entry_hive == hive) {
+        // Everything but the root itself is under the root
+        let below = path.starts_with(&prefix) || (key.is_empty() && !path.is_empty());
+        if !below {
+            continue;
+        }
+        found = true;
+
+        // The lookup path is lower case, the subkey name comes from the path as it was written
+        let Some(name) = stored.path.split('\\').nth(depth) else {
+            continue;
+        };
+        if !subkeys.iter().any(|subkey| subkey.eq_ignore_ascii_case(name)) {
+            subkeys.push(name.to_string());
+        }
+    }
+    found.then_some(subkeys)
+}
+
+/// Reads a DWORD value. Gives `ValueMissing` when the value isn't set and `ParseError` when it's stored
+/// as some other type, so callers can tell "not configured" apart from a real 0
+pub fn registry_dword(source: &dyn SecuritySource, hive: RegistryHive, key: &str, value: &str) -> SourceResult<u32> {
+    match source.registry_value(hive, key, value)? {
+        Some(RegistryValue::Dword(data)) => Ok(data),
+        Some(other) => Err(ShugoError::parse(hive.value_path(key, value), format!("expected a DWORD, found {:?}", other))),
+        None => Err(ShugoError::value_missing(hive.value_path(key, value)))
+    }
+}
+
+/// Reads a REG_SZ or REG_EXPAND_SZ value, with the same errors as `registry_dword`
+pub fn registry_string(source: &dyn SecuritySource, hive: RegistryHive, key: &str, value: &str) -> SourceResult<String> {
+    match source.registry_value(hive, key, value)? {
+        Some(RegistryValue::String(data) | RegistryValue::ExpandString(data)) => Ok(data),
+        Some(other) => Err(ShugoError::parse(hive.value_path(key, value), format!("expected a string, found {:?}", other))),
+        None => Err(ShugoError::value_missing(hive.value_path(key, value)))
+    }
+}
+
+/// Everything a Shugo scanner needs to know about a machine
+pub trait SecuritySource {
+    /// Runs a WQL query in a WMI namespace and returns every object it found
+    fn wmi_query(&self, namespace: &str, query: &str) -> SourceResult<Vec<WmiRow>>;
+
+    /// Calls a method on one WMI object, like `Win32_EncryptableVolume.DeviceID="..."`, and returns its out parameters
+    fn wmi_method(&self, namespace: &str, object_path: &str, method: &str, inputs: &[(&str, WmiValue)]) -> SourceResult<WmiRow>;
+
+    /// Reads one value from a registry key. `Ok(None)` means the key exists but the value doesn't
+    fn registry_value(&self, hive: RegistryHive, key: &str, value: &str) -> SourceResult<Option<RegistryValue>>;
+
+    /// Lists every value in a key as (name, value). A key that doesn't exist is `NotFound`
+    fn registry_values(&self, hive: RegistryHive, key: &str) -> SourceResult<Vec<(String, RegistryValue)>>;
+
+    /// Lists the names of a key's subkeys. A key that doesn't exist is `NotFound`
+    fn registry_subkeys(&self, hive: RegistryHive, key: &str) -> SourceResult<Vec<String>>;
+
+    /// Reads the Windows Defender Firewall profile settings
+    fn firewall_policy(&self) -> SourceResult<WindowsFirewallProfile>;
+
+    /// Lists every Windows Defender Firewall rule
+    fn firewall_rules(&self) -> SourceResult<Vec<FirewallRule>>;
+
+    /// Searches the Windows Update Agent with the given criteria
+    fn update_search(&self, criteria: &str) -> SourceResult<Vec<UpdateRecord>>;
+
+    /// Lists the normal local user accounts
+    fn local_users(&self) -> SourceResult<Vec<UserRecord>>;
+
+    /// Lists the folders and printers this machine shares over SMB
+    fn network_shares(&self) -> SourceResult<Vec<ShareRecord>>;
+}
+
+/// Gives the source for the machine Shugo is running on
+#[cfg(windows)]
+pub fn system_source() -> SourceResult<Box<dyn SecuritySource>> {
+    Ok(Box::new(super::live::LiveSource::new()))
+}
+
+/// Gives the source for the machine Shugo is running on
+#[cfg(not(windows))]
+pub fn system_source() -> SourceResult<Box<dyn SecuritySource>> {
+    Err(ShugoError::unavailable("live scan", "live scanning is only available on Windows, use a fixture or offline source instead"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_raw_registry_data() {
+        assert_eq!(RegistryValue::from_raw(REG_DWORD, &[5, 0, 0, 0]), RegistryValue::Dword(5));
+        assert_eq!(RegistryValue::from_raw(REG_QWORD, &[1, 0, 0, 0, 0, 0, 0, 1]), RegistryValue::Qword(0x0100000000000001));
+
+        let text: Vec<u8> = "On\0".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        assert_eq!(RegistryValue::from_raw(REG_SZ, &text), RegistryValue::String("On".to_string()));
+
+        let multi: Vec<u8> = "a\0b\0\0".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        assert_eq!(
+            RegistryValue::from_raw(REG_MULTI_SZ, &multi),
+            RegistryValue::MultiString(vec!["a".to_string(), "b".to_string()])
+        );
+
+        assert_eq!(RegistryValue::from_raw(3, &[1, 2]), RegistryValue::Binary(vec![1, 2]));
+    }
+}
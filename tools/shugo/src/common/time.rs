@@ -9,4 +9,93 @@ pub fn get_time() -> (u32, u32, u32) { // Return Hours, Minutes, Seconds
     let hours = ((secs / 3600) % 24) as u32;
 
     (hours, minutes, seconds)
-}
\ No newline at end of file
+}
+
+/// Seconds since the Unix epoch, used to stamp reports
+pub fn unix_time() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Formats Unix seconds as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_unix_time(secs: u64) -> String {
+    /*
+        Shugo: Days to a Calendar Date
+
+        The standard library doesn't do calendars, so we convert the day count ourselves. Shifting the
+        epoch to 1 March 0000 puts the leap day at the end of each year, which lets every year be split
+        into 400, 100, 4, and 1 year "eras" with plain division.
+
+        For more information on the algorithm:
+        (https://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+    */
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {month_index + 3} else {month_index - 9};
+    let year = year_of_era + era * 400 + if month <= 2 {1} else {0};
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year, month, day, (secs / 3600) % 24, (secs / 60) % 60, secs % 60
+    )
+}
+
+/// Days since 1970-01-01 for a calendar date, the reverse of the conversion in `format_unix_time`
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Same March-first eras as above (https://howardhinnant.github.io/date_algorithms.html#days_from_civil)
+    let year = if month <= 2 {year - 1} else {year};
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 {month - 3} else {month + 9}) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Days since 1970-01-01 for a `YYYY-MM-DD` date, `None` when it isn't a real date
+pub fn parse_date(text: &str) -> Option<i64> {
+    let mut parts = text.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let days_in_month = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None
+    };
+    (1..=days_in_month).contains(&day).then(|| days_from_civil(year, month, day))
+}
+
+/// Today as days since 1970-01-01 (UTC)
+pub fn today() -> i64 {
+    (unix_time() / 86400) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(format_unix_time(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_unix_time(951782400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_unix_time(1792238586), "2026-10-17 12:03:06 UTC");
+    }
+
+    #[test]
+    fn counts_days_from_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 2, 29) * 86400, 951782400);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(parse_date("2000-02-29"), Some(days_from_civil(2000, 2, 29)));
+        assert_eq!(parse_date("2025-02-29"), None);
+        assert_eq!(parse_date("2025-13-01"), None);
+        assert_eq!(parse_date("soon"), None);
+    }
+}
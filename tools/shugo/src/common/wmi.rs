@@ -0,0 +1,356 @@
+//! Typed WMI queries. A scanner describes how to build its struct from one WMI object with
+//! `FromWmiObject`, and `query` runs the WQL and maps every object it returns. Because the mapping
+//! only sees the `WmiObject` trait, it can be tested with fixture rows on any OS.
+//!
+//! The typed getters (`get_bool`, `get_u64`, `get_datetime`, ...) say exactly why a value couldn't be
+//! used: the property is missing, it's null, or it holds a different type.
+use std::fmt;
+
+use super::error::ShugoError;
+use super::source::{SecuritySource, SourceResult, WmiRow, WmiValue};
+use super::time::days_from_civil;
+
+/// CIM type numbers from `IWbemClassObject::Get`/`Next` that change how a string is read
+pub const CIM_SINT64: i32 = 20;
+pub const CIM_UINT64: i32 = 21;
+pub const CIM_DATETIME: i32 = 101;
+/// Set on the CIM type of array properties
+pub const CIM_FLAG_ARRAY: i32 = 0x2000;
+
+/// A CIM_DATETIME like `20240115093000.000000-300`, the local time plus its offset from UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CimDateTime {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub microsecond: u32,
+    /// Minutes east of UTC
+    pub utc_offset_minutes: i32
+}
+
+impl CimDateTime {
+    /// Parses the 25 character `yyyymmddHHMMSS.mmmmmmsUUU` form
+    pub fn parse(text: &str) -> Result<CimDateTime, String> {
+        /*
+            Shugo: CIM_DATETIME
+
+            WMI hands dates back as strings with fixed columns:
+
+            20240115093000.000000-300
+            yyyymmddHHMMSS.mmmmmmsUUU
+
+            The first 14 digits are the local date and time, after the dot are microseconds, and the last four
+            characters are the sign and the offset from UTC in MINUTES (not hours), so -300 is UTC-5.
+            Any field can be filled with `*` when the provider doesn't know it. We accept that for microseconds
+            (read as 0) but not for the date or time, since a guessed date is worse than an error.
+
+            The same 25 columns with a `:` in place of the sign are an interval (a duration), not a date.
+
+            For more information on CIM_DATETIME:
+            (https://learn.microsoft.com/en-us/windows/win32/wmisdk/cim-datetime)
+        */
+        if text.len() != 25 || !text.is_ascii() {
+            return Err(format!("{:?} is not a 25 character CIM_DATETIME", text));
+        }
+        if &text[14..15] != "." {
+            return Err(format!("{:?} is missing the '.' before the microseconds", text));
+        }
+        let field = |range: std::ops::Range<usize>, name: &str| -> Result<u32, String> {
+            text[range].parse::<u32>().map_err(|_| format!("{:?} has an invalid {}", text, name))
+        };
+
+        let sign = match &text[21..22] {
+            "+" => 1,
+            "-" => -1,
+            ":" => return Err(format!("{:?} is a CIM interval, not a date", text)),
+            _ => return Err(format!("{:?} has an invalid UTC offset sign", text))
+        };
+        let microseconds = &text[15..21];
+        let date = CimDateTime {
+            year: field(0..4, "year")? as i32,
+            month: field(4..6, "month")?,
+            day: field(6..8, "day")?,
+            hour: field(8..10, "hour")?,
+            minute: field(10..12, "minute")?,
+            second: field(12..14, "second")?,
+            microsecond: if microseconds == "******" {0} else {field(15..21, "microsecond")?},
+            utc_offset_minutes: sign * field(22..25, "UTC offset")? as i32
+        };
+
+        if !(1..=12).contains(&date.month) || !(1..=31).contains(&date.day) || date.hour > 23 || date.minute > 59 || date.second > 60 {
+            return Err(format!("{:?} is not a real date and time", text));
+        }
+        Ok(date)
+    }
+
+    /// Seconds since the Unix epoch in UTC
+    pub fn unix_time(&self) -> i64 {
+        let days = days_from_civil(self.year as i64, self.month, self.day);
+        let local = days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        local - self.utc_offset_minutes as i64 * 60
+    }
+}
+
+impl fmt::Display for CimDateTime {
+    /// ISO 8601, for example `2024-01-15T09:30:00-05:00`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offset = self.utc_offset_minutes.unsigned_abs();
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second,
+            if self.utc_offset_minutes < 0 {'-'} else {'+'}, offset / 60, offset % 60
+        )
+    }
+}
+
+/// Turning a string property into the type its CIM type says it really is
+pub fn cim_string_value(text: String, cim_type: i32) -> WmiValue {
+    /*
+        Shugo: Numbers That Arrive As Strings
+
+        VARIANTs have no slot WMI trusts for 64-bit integers, so sint64/uint64 properties come back as BSTR text
+        like "17179869184", and dates come back as CIM_DATETIME text. The CIM type tells us which it was so
+        we can hand back a real number or date. If the text doesn't parse we keep the string rather than
+        inventing a value.
+    */
+    match cim_type & !CIM_FLAG_ARRAY {
+        CIM_UINT64 => text.parse().map(WmiValue::U64).unwrap_or(WmiValue::String(text)),
+        CIM_SINT64 => text.parse().map(WmiValue::I64).unwrap_or(WmiValue::String(text)),
+        CIM_DATETIME => CimDateTime::parse(&text).map(WmiValue::DateTime).unwrap_or(WmiValue::String(text)),
+        _ => WmiValue::String(text)
+    }
+}
+
+/// Why a property couldn't be read as the type asked for
+#[derive(Debug, Clone, PartialEq)]
+pub enum WmiValueError {
+    Missing(String),
+    Null(String),
+    TypeMismatch { property: String, expected: &'static str, found: &'static str }
+}
+
+impl fmt::Display for WmiValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WmiValueError::Missing(property) => write!(f, "property {} is missing", property),
+            WmiValueError::Null(property) => write!(f, "property {} is null", property),
+            WmiValueError::TypeMismatch { property, expected, found } => {
+                write!(f, "property {} is {}, expected {}", property, found, expected)
+            }
+        }
+    }
+}
+
+impl From<WmiValueError> for ShugoError {
+    fn from(error: WmiValueError) -> Self {
+        match &error {
+            WmiValueError::Missing(property) | WmiValueError::Null(property) => ShugoError::value_missing(property.clone()),
+            WmiValueError::TypeMismatch { property, .. } => ShugoError::parse(property.clone(), error.to_string())
+        }
+    }
+}
+
+/// Turns a missing or null property into `None`, for properties that are optional
+pub fn optional<T>(result: Result<T, WmiValueError>) -> Result<Option<T>, WmiValueError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(WmiValueError::Missing(_) | WmiValueError::Null(_)) => Ok(None),
+        Err(error) => Err(error)
+    }
+}
+
+/// Anything that has WMI properties: fixture rows, or live `IWbemClassObject`s on Windows
+pub trait WmiObject {
+    /// The value of a property, `None` when the object doesn't have it
+    fn property(&self, name: &str) -> Option<WmiValue>;
+
+    /// The value of a property, with null and missing kept apart
+    fn get(&self, name: &str) -> Result<WmiValue, WmiValueError> {
+        match self.property(name) {
+            Some(WmiValue::Null) => Err(WmiValueError::Null(name.to_string())),
+            Some(value) => Ok(value),
+            None => Err(WmiValueError::Missing(name.to_string()))
+        }
+    }
+
+    fn get_bool(&self, name: &str) -> Result<bool, WmiValueError> {
+        match self.get(name)? {
+            WmiValue::Bool(value) => Ok(value),
+            other => Err(mismatch(name, "boolean", &other))
+        }
+    }
+
+    /// Any integer that fits in an i64
+    fn get_i64(&self, name: &str) -> Result<i64, WmiValueError> {
+        match self.get(name)? {
+            WmiValue::I32(value) => Ok(value as i64),
+            WmiValue::U32(value) => Ok(value as i64),
+            WmiValue::I64(value) => Ok(value),
+            WmiValue::U64(value) => i64::try_from(value).map_err(|_| mismatch(name, "sint64", &WmiValue::U64(value))),
+            other => Err(mismatch(name, "integer", &other))
+        }
+    }
+
+    fn get_i32(&self, name: &str) -> Result<i32, WmiValueError> {
+        let value = self.get_i64(name)?;
+        i32::try_from(value).map_err(|_| mismatch(name, "sint32", &WmiValue::I64(value)))
+    }
+
+    /// Any integer that isn't negative and fits in a u64
+    fn get_u64(&self, name: &str) -> Result<u64, WmiValueError> {
+        match self.get(name)? {
+            WmiValue::U64(value) => Ok(value),
+            other => {
+                let value = self.get_i64(name)?;
+                u64::try_from(value).map_err(|_| mismatch(name, "uint64", &other))
+            }
+        }
+    }
+
+    fn get_u32(&self, name: &str) -> Result<u32, WmiValueError> {
+        let value = self.get_u64(name)?;
+        u32::try_from(value).map_err(|_| mismatch(name, "uint32", &WmiValue::U64(value)))
+    }
+
+    fn get_string(&self, name: &str) -> Result<String, WmiValueError> {
+        match self.get(name)? {
+            WmiValue::String(value) => Ok(value),
+            other => Err(mismatch(name, "string", &other))
+        }
+    }
+
+    /// A date, also accepting CIM_DATETIME text from sources that don't know the CIM type
+    fn get_datetime(&self, name: &str) -> Result<CimDateTime, WmiValueError> {
+        match self.get(name)? {
+            WmiValue::DateTime(value) => Ok(value),
+            WmiValue::String(text) => CimDateTime::parse(&text).map_err(|_| mismatch(name, "datetime", &WmiValue::String(text))),
+            other => Err(mismatch(name, "datetime", &other))
+        }
+    }
+
+    fn get_strings(&self, name: &str) -> Result<Vec<String>, WmiValueError> {
+        match self.get(name)? {
+            WmiValue::Array(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    WmiValue::String(text) => Ok(text),
+                    other => Err(mismatch(name, "string array", &other))
+                })
+                .collect(),
+            other => Err(mismatch(name, "string array", &other))
+        }
+    }
+}
+
+fn mismatch(property: &str, expected: &'static str, found: &WmiValue) -> WmiValueError {
+    WmiValueError::TypeMismatch { property: property.to_string(), expected, found: found.type_name() }
+}
+
+impl WmiObject for WmiRow {
+    fn property(&self, name: &str) -> Option<WmiValue> {
+        self.properties.get(name).cloned()
+    }
+}
+
+/// Building a Rust struct from one WMI object
+pub trait FromWmiObject: Sized {
+    fn from_wmi_object(object: &dyn WmiObject) -> SourceResult<Self>;
+}
+
+/// Runs a WQL query in `namespace` and maps every object it returns to `T`
+pub fn query<T: FromWmiObject>(source: &dyn SecuritySource, namespace: &str, wql: &str) -> SourceResult<Vec<T>> {
+    source
+        .wmi_query(namespace, wql)?
+        .iter()
+        .map(|row| T::from_wmi_object(row))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+
+    /// A fake object with a single property, no fixture source needed
+    struct OneProperty(&'static str, WmiValue);
+
+    impl WmiObject for OneProperty {
+        fn property(&self, name: &str) -> Option<WmiValue> {
+            (name == self.0).then(|| self.1.clone())
+        }
+    }
+
+    struct Named(String);
+
+    impl FromWmiObject for Named {
+        fn from_wmi_object(object: &dyn WmiObject) -> SourceResult<Self> {
+            Ok(Named(object.get_string("Name")?))
+        }
+    }
+
+    #[test]
+    fn maps_objects_to_types() {
+        let fake = OneProperty("Name", WmiValue::String("Shugo".to_string()));
+        assert_eq!(Named::from_wmi_object(&fake).unwrap().0, "Shugo");
+        assert!(matches!(Named::from_wmi_object(&OneProperty("Name", WmiValue::I32(1))), Err(ShugoError::ParseError { .. })));
+
+        let source = FixtureSource::new().with_wmi("root\\cimv2", "Win32_Thing", vec![
+            WmiRow::new().with("Name", WmiValue::String("a".to_string())),
+            WmiRow::new()
+        ]);
+        assert!(matches!(query::<Named>(&source, "root\\cimv2", "SELECT Name FROM Win32_Thing"), Err(ShugoError::ValueMissing { .. })));
+        assert!(query::<Named>(&source, "root\\cimv2", "SELECT Name FROM Missing").is_err());
+    }
+
+    #[test]
+    fn parses_cim_datetimes() {
+        let date = CimDateTime::parse("20240115093000.123456-300").unwrap();
+        assert_eq!((date.year, date.month, date.day, date.hour), (2024, 1, 15, 9));
+        assert_eq!(date.microsecond, 123456);
+        assert_eq!(date.utc_offset_minutes, -300);
+        assert_eq!(date.unix_time(), 1705329000); // 2024-01-15 14:30:00 UTC
+        assert_eq!(date.to_string(), "2024-01-15T09:30:00-05:00");
+
+        assert_eq!(CimDateTime::parse("20240115093000.******+000").unwrap().microsecond, 0);
+        assert!(CimDateTime::parse("00000001000000.000000:000").is_err());
+        assert!(CimDateTime::parse("2024011509300").is_err());
+        assert!(CimDateTime::parse("20241315093000.000000+000").is_err());
+        assert!(CimDateTime::parse("2024****093000.000000+000").is_err());
+    }
+
+    #[test]
+    fn reads_strings_by_cim_type() {
+        assert_eq!(cim_string_value("17179869184".to_string(), CIM_UINT64), WmiValue::U64(17179869184));
+        assert_eq!(cim_string_value("-5".to_string(), CIM_SINT64), WmiValue::I64(-5));
+        assert!(matches!(cim_string_value("20240115093000.000000+000".to_string(), CIM_DATETIME), WmiValue::DateTime(_)));
+        assert_eq!(cim_string_value("abc".to_string(), CIM_UINT64), WmiValue::String("abc".to_string()));
+    }
+
+    #[test]
+    fn typed_getters_explain_failures() {
+        let row = WmiRow::new()
+            .with("Enabled", WmiValue::Bool(true))
+            .with("Size", WmiValue::U64(1 << 40))
+            .with("State", WmiValue::I32(397568))
+            .with("Nothing", WmiValue::Null)
+            .with("Paths", WmiValue::Array(vec![WmiValue::String("C:\\Temp".to_string())]))
+            .with("Installed", WmiValue::String("20240115093000.000000+000".to_string()));
+
+        assert_eq!(row.get_bool("Enabled"), Ok(true));
+        assert_eq!(row.get_u64("Size"), Ok(1 << 40));
+        assert_eq!(row.get_u32("State"), Ok(397568));
+        assert_eq!(row.get_strings("Paths").unwrap(), vec!["C:\\Temp"]);
+        assert_eq!(row.get_datetime("Installed").unwrap().year, 2024);
+
+        assert_eq!(row.get_bool("Nothing"), Err(WmiValueError::Null("Nothing".to_string())));
+        assert_eq!(row.get_bool("Absent"), Err(WmiValueError::Missing("Absent".to_string())));
+        assert!(matches!(row.get_u32("Size"), Err(WmiValueError::TypeMismatch { .. })));
+        assert!(matches!(row.get_bool("State"), Err(WmiValueError::TypeMismatch { found: "sint32", .. })));
+        assert_eq!(optional(row.get_bool("Nothing")), Ok(None));
+        assert!(matches!(ShugoError::from(row.get_bool("Absent").unwrap_err()), ShugoError::ValueMissing { .. }));
+    }
+}
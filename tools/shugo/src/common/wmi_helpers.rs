@@ -1,114 +1,255 @@
-use std::mem::MaybeUninit;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use windows::core::*;
+use windows::Win32::System::Com::SAFEARRAY;
+use windows::Win32::System::Ole::*;
 use windows::Win32::System::Variant::*;
 use windows::Win32::System::Wmi::*;
 use windows::Win32::Foundation::*;
 
-/// Converting BSTR to a Rust String
-pub fn string_property(obj: &IWbemClassObject, name: &str) -> Result<String> {
+use super::source::{WmiRow, WmiValue};
+use super::wmi::{cim_string_value, WmiObject};
+
+/// Reading every property of a WMI object into a `WmiRow`
+pub fn object_properties(obj: &IWbemClassObject) -> Result<WmiRow> {
+    let mut row: WmiRow = WmiRow::new();
     unsafe {
         /*
-            Shugo: Writing In Memory
+            Shugo: Walking An Object's Properties
 
-            To convert our objects to usable data, we need to take the output of the pointers and put them in memory to be written
-            before we can read them.
+            Instead of asking for each property by name, we can ask the object to hand us every property it has.
+            `BeginEnumeration` starts the walk and `WBEM_FLAG_NONSYSTEM_ONLY` skips the system properties that start
+            with two underscores (like `__CLASS` and `__PATH`) since we don't need them.
 
-            "Why do we need to access memory to do this?":
-            Were interfacing with C code which expects uninitialized buffers. So we need to bridge the gap between Rust's strict 
-            memory safety and the low-level operations that require dealing with uninitialized memory.
+            For more information on `BeginEnumeration`:
+            (https://learn.microsoft.com/en-us/windows/win32/api/wbemcli/nf-wbemcli-iwbemclassobject-beginenumeration) - C++
+        */
+        obj.BeginEnumeration(WBEM_FLAG_NONSYSTEM_ONLY.0)?;
 
-            How this works:
-            We'll first call `MaybeUninit` which will create some uninitialized memory for us so all we need to do is put in some
-            data.
+        loop {
+            /*
+                Shugo: Writing In Memory
 
-            For more information on `MaybeUninit`:
-            (https://doc.rust-lang.org/std/mem/union.MaybeUninit.html) - Rust
-        */
-        let mut variant: MaybeUninit<VARIANT> = MaybeUninit::<VARIANT>::zeroed();
+                To convert our objects to usable data, we need to take the output of the pointers and put them in memory to be written
+                before we can read them.
 
-        /*  
-            Shugo: Filling Memory
+                "Why do we need to access memory to do this?":
+                Were interfacing with C code which expects uninitialized buffers. So we need to bridge the gap between Rust's strict 
+                memory safety and the low-level operations that require dealing with uninitialized memory.
+
+                How this works:
+                We'll first call `MaybeUninit` which will create some uninitialized memory for us so all we need to do is put in some
+                data.
+
+                For more information on `MaybeUninit`:
+                (https://doc.rust-lang.org/std/mem/union.MaybeUninit.html) - Rust
+            */
+            let mut name: BSTR = BSTR::new();
+            let mut variant: MaybeUninit<VARIANT> = MaybeUninit::<VARIANT>::zeroed();
+            let mut cim_type: i32 = 0;
+
+            /*  
+                Shugo: Filling Memory
+
+                Then we'll fill that memory using the `Next` method on our object. Each call gives us the name of the next property
+                and puts its value in our uninitialized memory. When there are no properties left, the name comes back empty.
+
+                For more information on `Next`: 
+                (https://learn.microsoft.com/en-us/windows/win32/api/wbemcli/nf-wbemcli-iwbemclassobject-next) - C++
+                (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Wmi/struct.IWbemClassObject.html#method.Next) - Rust
+            */
+            obj.Next(
+                0, // This must be zero
+                &mut name, // Receives the name of the property
+                variant.as_mut_ptr(), // When successful, this assignes the correct type and value for the property
+                &mut cim_type, // The CIM type, which tells a uint64 or a date apart from a plain string
+                std::ptr::null_mut() // We don't need the flavor so we leave it NULL
+            )?;
+            if name.is_empty() {
+                break;
+            }
+
+            /*
+                Shugo: Initializing Memory
+
+                Now That we have filled our memory, we can initialize it using the `assume_init` method. This is us telling Rust that we 
+                have made sure all information in the memory is correct.
+            */
+            let mut variant: VARIANT = variant.assume_init();
+            row.properties.insert(name.to_string(), variant_value(&variant, cim_type));
+
+            /*
+                Shugo: Clearing Memory:
+
+                Once we're done using our initialized memory, we need to clear the data in it. This is important because if we leave this
+                data in memory, it will leave it initialized. This is a memory leak and should be dealt with every time you initialized 
+                memory. To delete data in your initialized memory, call `VariantClear`.
+
+                For information on `VariantClear`:
+                (https://learn.microsoft.com/en-us/windows/win32/api/oleauto/nf-oleauto-variantclear) - C++
+                (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Variant/fn.VariantClear.html) - Rust
+            */
+            VariantClear(&mut variant)?;
+        }
+
+        obj.EndEnumeration()?;
+    }
+    Ok(row)
+}
 
-            Then we'll fill that memory using the `Get` method on our object. This will get certain information from our object and
-            put it in our uninitialized memory.
+/// Live WMI objects can be read one property at a time with `Get`
+impl WmiObject for IWbemClassObject {
+    fn property(&self, name: &str) -> Option<WmiValue> {
+        /*
+            Shugo: Reading One Property
+
+            `Get` is the single property version of `Next`. It fills in a VARIANT the same way, and fails
+            if the object has no property with that name.
 
-            For more information on `Get`: 
+            For more information on `Get`:
             (https://learn.microsoft.com/en-us/windows/win32/api/wbemcli/nf-wbemcli-iwbemclassobject-get) - C++
-            (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Wmi/struct.IWbemClassObject.html#method.Get) - Rust
         */
-        obj.Get(
-            &BSTR::from(name), // Name of the property were wanting
-            0, // This must be zero
-            variant.as_mut_ptr(), // When successful, this assignes the correct type and value for the qualifier 
-            None, // Were leaving this NULL
-            None // This one will be NULL too
-        )?;
+        unsafe {
+            let mut variant: VARIANT = VARIANT::default();
+            let mut cim_type: i32 = 0;
+            self.Get(&HSTRING::from(name), 0, &mut variant, Some(&mut cim_type), None).ok()?;
+            let value = variant_value(&variant, cim_type);
+            VariantClear(&mut variant).ok()?;
+            Some(value)
+        }
+    }
+}
 
+/// Building a VARIANT to pass into a WMI method, `None` for types Shugo never sends
+pub fn to_variant(value: &WmiValue) -> Option<VARIANT> {
+    let mut variant: VARIANT = VARIANT::default();
+    unsafe {
         /*
-            Shugo: Initializing Memory
+            Shugo: Filling In A VARIANT
 
-            Now That we have filled our memory, we can initialize it using the `assume_init` method. This is us telling Rust that we 
-            have made sure all information in the memory is correct.
+            This is `variant_value` in reverse, we set the type tag in `vt` and then the matching field of the inner union.
+            WMI takes uint32 method parameters as VT_I4, so unsigned numbers go in `lVal` too.
+
+            The BSTR we put in is owned by the VARIANT now, so whoever uses it has to call `VariantClear` afterwards.
         */
-        let mut variant: VARIANT = variant.assume_init();
+        let inner = &mut variant.Anonymous.Anonymous;
+        match value {
+            WmiValue::String(text) => {
+                inner.vt = VT_BSTR;
+                inner.Anonymous.bstrVal = ManuallyDrop::new(BSTR::from(text.as_str()));
+            },
+            WmiValue::I32(number) => {
+                inner.vt = VT_I4;
+                inner.Anonymous.lVal = *number;
+            },
+            WmiValue::U32(number) => {
+                inner.vt = VT_I4;
+                inner.Anonymous.lVal = *number as i32;
+            },
+            WmiValue::Bool(flag) => {
+                inner.vt = VT_BOOL;
+                inner.Anonymous.boolVal = if *flag {VARIANT_TRUE} else {VARIANT_FALSE};
+            },
+            _ => return None
+        }
+    }
+    Some(variant)
+}
 
+/// Converting a VARIANT to a `WmiValue`, using the CIM type for values WMI sends as strings
+pub fn variant_value(variant: &VARIANT, cim_type: i32) -> WmiValue {
+    unsafe {
         /*  
             Shugo: Working With VARIANT
 
             VARIANT is pretty much a container for a large union that carries many types of data. To get the data we want, we'll
-            navigate through the VARIANT Rust structs until we get the Value we want returned. We want to get a `BSTR` from this
-            function so we'll go to `variant.Anonymous.Anonymous.vt` and equal it too VT_BSTR so we know we can pull a `BSTR`.
+            navigate through the VARIANT Rust structs until we get the Value we want returned. We check `variant.Anonymous.Anonymous.vt`
+            to see what type is stored, for example VT_BSTR tells us we can pull a `BSTR`.
 
             Than instead of `Anonymous.Anonymous.vt` we'll do `Anonymous.Anonymous.Anonymous.bstrVal` so we can can grab the 
-            `BSTR`.
+            `BSTR`. Every type has its own field: `boolVal` for VT_BOOL, `lVal` for VT_I4, `ullVal` for VT_UI8, and so on.
+
+            Two things to watch for:
+            - VARIANT_BOOL is -1 for true and 0 for false, so we check for "not zero" instead of "equals 1".
+            - When the VT_ARRAY bit (0x2000) is set, the value is a SAFEARRAY pointer in `parray` and the rest of `vt` says
+              what type each element is.
 
             For more information on the `VARIANT` Structure:
             (https://learn.microsoft.com/en-us/windows/win32/api/oaidl/ns-oaidl-variant) - C++
             (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Variant/index.html#structs) - Rust
         */
-        let result: String = if variant.Anonymous.Anonymous.vt == VT_BSTR {
-            let bstr = &variant.Anonymous.Anonymous.Anonymous.bstrVal; // The third 'Anonymous' contains all the possible value types like bstrVal, lVal, boolVal, and more
-            bstr.to_string()// From here we are basically converting the BSTR string to a Rust String
-        } else {
-            "Unknown".to_string()
-        };
+        let vt: VARENUM = variant.Anonymous.Anonymous.vt;
+        let value = &variant.Anonymous.Anonymous.Anonymous; // The third 'Anonymous' contains all the possible value types like bstrVal, lVal, boolVal, and more
+        if vt.0 & VT_ARRAY.0 != 0 {
+            return safe_array_value(value.parray, VARENUM(vt.0 & VT_TYPEMASK.0), cim_type);
+        }
+        match vt {
+            VT_EMPTY | VT_NULL => WmiValue::Null,
+            VT_BOOL => WmiValue::Bool(value.boolVal.0 != 0),
+            VT_BSTR => cim_string_value(value.bstrVal.to_string(), cim_type), // From here we are basically converting the BSTR string to a Rust String
+            VT_I1 => WmiValue::I32(value.cVal as i32),
+            VT_I2 => WmiValue::I32(value.iVal as i32),
+            VT_I4 if cim_type == CIM_UINT32.0 => WmiValue::U32(value.lVal as u32), // WMI hands uint32 back in a VT_I4
+            VT_I4 => WmiValue::I32(value.lVal), // We'll use lVal which is used for long/32-bit integers
+            VT_UI1 => WmiValue::U32(value.bVal as u32),
+            VT_UI2 => WmiValue::U32(value.uiVal as u32),
+            VT_UI4 => WmiValue::U32(value.ulVal),
+            VT_I8 => WmiValue::I64(value.llVal),
+            VT_UI8 => WmiValue::U64(value.ullVal),
+            VT_R4 => WmiValue::F64(value.fltVal as f64),
+            VT_R8 => WmiValue::F64(value.dblVal),
+            _ => WmiValue::Unsupported(vt.0)
+        }
+    }
+}
 
-        /*
-            Shugo: Clearing Memory:
+/// Converting a one dimensional SAFEARRAY to `WmiValue::Array`
+unsafe fn safe_array_value(array: *mut SAFEARRAY, element: VARENUM, cim_type: i32) -> WmiValue {
+    /*
+        Shugo: Reading A SAFEARRAY
 
-            Once we're done using our initialized memory, we need to clear the data in it. This is important because if we leave this
-            data in memory, it will leave it initialized. This is a memory leak and should be dealt with every time you initialized 
-            memory. To delete data in your initialized memory, call `VariantClear`.
+        A SAFEARRAY knows its own bounds, so we ask for the lowest and highest index (`SafeArrayGetLBound`/`UBound`)
+        and copy each element out with `SafeArrayGetElement`. BSTR elements are copied too, so each one is freed when
+        its `BSTR` goes out of scope.
 
-            For information on `VariantClear`:
-            (https://learn.microsoft.com/en-us/windows/win32/api/oleauto/nf-oleauto-variantclear) - C++
-            (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Variant/fn.VariantClear.html) - Rust
-        */
-        VariantClear(&mut variant)?;
-        Ok(result)
+        For more information on SAFEARRAY:
+        (https://learn.microsoft.com/en-us/windows/win32/api/oaidl/ns-oaidl-safearray) - C++
+    */
+    if array.is_null() {
+        return WmiValue::Null;
     }
-}
+    let (Ok(lower), Ok(upper)) = (unsafe { SafeArrayGetLBound(array, 1) }, unsafe { SafeArrayGetUBound(array, 1) }) else {
+        return WmiValue::Unsupported(VT_ARRAY.0 | element.0);
+    };
 
-/// Converting vt to a Rust Integer
-pub fn integer_property(obj: &IWbemClassObject, name: &str) -> Result<i32> {
-    unsafe {
-        let mut variant = MaybeUninit::<VARIANT>::zeroed();
-        obj.Get(
-            &BSTR::from(name),
-            0, // This must be zero
-            variant.as_mut_ptr(),
-            None,
-            None
-        )?;
-        let mut variant = variant.assume_init();
-        let result = if variant.Anonymous.Anonymous.vt == VT_I4 { // Were grabing an integer here so lets use VT_I4
-            variant.Anonymous.Anonymous.Anonymous.lVal // Then we'll use lVal which is used for long/32-bit integers
-        } else {
-            -1
+    let mut values = Vec::new();
+    for index in lower..=upper {
+        let value = unsafe {
+            match element {
+                VT_BSTR => {
+                    let mut text = BSTR::new();
+                    SafeArrayGetElement(array, &index, &mut text as *mut BSTR as *mut _).map(|_| cim_string_value(text.to_string(), cim_type))
+                }
+                VT_I4 => {
+                    let mut number: i32 = 0;
+                    SafeArrayGetElement(array, &index, &mut number as *mut i32 as *mut _).map(|_| WmiValue::I32(number))
+                }
+                VT_UI1 => {
+                    let mut byte: u8 = 0;
+                    SafeArrayGetElement(array, &index, &mut byte as *mut u8 as *mut _).map(|_| WmiValue::U32(byte as u32))
+                }
+                VT_BOOL => {
+                    let mut flag = VARIANT_BOOL(0);
+                    SafeArrayGetElement(array, &index, &mut flag as *mut VARIANT_BOOL as *mut _).map(|_| WmiValue::Bool(flag.0 != 0))
+                }
+                _ => return WmiValue::Unsupported(VT_ARRAY.0 | element.0)
+            }
         };
-        VariantClear(&mut variant)?;
-        Ok(result)
+        match value {
+            Ok(value) => values.push(value),
+            Err(_) => return WmiValue::Unsupported(VT_ARRAY.0 | element.0)
+        }
     }
+    WmiValue::Array(values)
 }
 
 /// Converting DECIMAL to u128
@@ -0,0 +1,362 @@
+//! Assessment for the Defender Module, turns Defender's status, preferences, and exclusions into findings.
+use super::scanner::{ComputerStatus, DefenderStatus, Preferences};
+use crate::common::finding::{Finding, Severity};
+use crate::common::product_state::ScannerState;
+
+const MODULE: &str = "defender";
+
+/// Signatures older than this many days are reported as stale
+pub const STALE_SIGNATURE_DAYS: u32 = 7;
+/// Going longer than this many days without a quick scan is reported
+pub const QUICK_SCAN_DAYS: u32 = 14;
+
+/// Folders anyone can write to, so malware dropped there would never be scanned
+const RISKY_FOLDERS: [&str; 8] = ["\\temp", "\\tmp", "\\downloads", "\\appdata", "\\users\\public", "\\programdata", "\\desktop", "%temp%"];
+/// File types that run code when opened
+const RISKY_EXTENSIONS: [&str; 14] = ["exe", "dll", "scr", "com", "bat", "cmd", "ps1", "vbs", "js", "jse", "wsf", "hta", "msi", "lnk"];
+/// Built in programs attackers use to run their own code ("living off the land" binaries)
+const RISKY_PROCESSES: [&str; 10] = [
+    "powershell.exe", "pwsh.exe", "cmd.exe", "wscript.exe", "cscript.exe",
+    "mshta.exe", "rundll32.exe", "regsvr32.exe", "msbuild.exe", "certutil.exe"
+];
+
+/// Which list an exclusion came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExclusionKind {
+    Path,
+    Extension,
+    Process
+}
+
+/// Why an exclusion is risky, or `None` when it looks narrow enough
+pub fn exclusion_risk(kind: ExclusionKind, value: &str) -> Option<&'static str> {
+    /*
+        Shugo: Risky Exclusions
+
+        An exclusion tells Defender to never look at something. Narrow exclusions (one application's data folder)
+        are normal, but broad ones hand attackers a safe place to work:
+
+        - A whole drive or a wildcard turns scanning off for everything under it.
+        - Folders any user can write to, like Temp or Downloads, are exactly where malware lands first.
+        - Excluding `.exe` or `.ps1` means no program or script of that type is ever scanned.
+        - Excluding a process like powershell.exe means nothing it reads or writes is scanned, and attackers
+          run their tools through these built in programs on purpose.
+
+        Attackers who get administrator rights often add exclusions like these before dropping their tools.
+
+        For more information on exclusions:
+        (https://learn.microsoft.com/en-us/defender-endpoint/common-exclusion-mistakes-microsoft-defender-antivirus)
+    */
+    let value = value.trim().trim_end_matches('\\').to_lowercase();
+    match kind {
+        ExclusionKind::Path => {
+            if value.is_empty() || value == "*" || value.starts_with("*\\") {
+                Some("excludes every file")
+            } else if value.len() == 2 && value.ends_with(':') {
+                Some("excludes an entire drive")
+            } else if value.contains('*') && value.matches('\\').count() <= 1 {
+                Some("uses a wildcard near the top of the drive")
+            } else if RISKY_FOLDERS.iter().any(|folder| value.contains(folder)) {
+                Some("excludes a folder any user can write to")
+            } else {
+                None
+            }
+        },
+        ExclusionKind::Extension => {
+            let extension = value.trim_start_matches("*.").trim_start_matches('.');
+            if extension == "*" || extension.is_empty() {
+                Some("excludes every file type")
+            } else if RISKY_EXTENSIONS.contains(&extension) {
+                Some("excludes a file type that runs code")
+            } else {
+                None
+            }
+        },
+        ExclusionKind::Process => {
+            let name = value.rsplit('\\').next().unwrap_or(&value);
+            RISKY_PROCESSES.contains(&name).then_some("excludes a built in program attackers use to run code")
+        }
+    }
+}
+
+/// Assessing Windows Defender
+pub fn assess_defender(status: &DefenderStatus) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let other_protection: Vec<&str> = status.antispyware
+        .iter()
+        .flatten()
+        .filter(|prod| prod.product_state.scanner == ScannerState::On && !prod.name.to_lowercase().contains("defender"))
+        .map(|prod| prod.name.as_str())
+        .collect();
+
+    if let Some(computer) = &status.computer_status {
+        assess_status(computer, &other_protection, &mut findings);
+    }
+    if let Some(preferences) = &status.preferences {
+        assess_preferences(preferences, &mut findings);
+    }
+
+    if let Some(products) = &status.antispyware
+        && !products.iter().any(|prod| prod.product_state.scanner == ScannerState::On)
+    {
+        findings.push(
+            Finding::new("DEF-009", MODULE, Severity::High, "No active antispyware protection")
+                .explanation("Security Center doesn't list any running antispyware product, so spyware and adware aren't being blocked")
+                .remediation("Turn on Microsoft Defender Antivirus, which also covers spyware, or enable your third-party product")
+                .evidence(format!("products_found={}", products.len()))
+        );
+    }
+
+    if !status.module_info.unreadable.is_empty() {
+        let mut finding = Finding::new("DEF-010", MODULE, Severity::Info, "Some Defender details couldn't be read")
+            .explanation("Without these details Shugo can't check every Defender setting, so a clean result may be incomplete")
+            .remediation("Run Shugo as administrator, or check whether Defender has been removed from this machine");
+        for (class, reason) in &status.module_info.unreadable {
+            finding = finding.evidence(format!("{}: {}", class, reason));
+        }
+        findings.push(finding);
+    }
+
+    findings
+}
+
+fn assess_status(computer: &ComputerStatus, other_protection: &[&str], findings: &mut Vec<Finding>) {
+    if computer.antimalware_enabled == Some(false) {
+        // Defender steps aside when another product takes over, which is expected rather than a problem
+        if other_protection.is_empty() {
+            findings.push(
+                Finding::new("DEF-001", MODULE, Severity::High, "Defender's antimalware service is off")
+                    .explanation("Defender isn't scanning anything and no other antispyware product is running in its place")
+                    .remediation("Turn on Microsoft Defender Antivirus in Windows Security > Virus & threat protection")
+                    .evidence("AMServiceEnabled=false")
+            );
+        } else {
+            let mut finding = Finding::new("DEF-001", MODULE, Severity::Info, "Defender is off, another product is protecting this machine")
+                .explanation("This is normal when a third-party product takes over, as long as that product stays enabled")
+                .remediation("Make sure the third-party product is kept up to date");
+            for name in other_protection {
+                finding = finding.evidence(name.to_string());
+            }
+            findings.push(finding);
+        }
+        // Real-time protection, tamper protection, and signatures only matter while Defender is running
+        return;
+    }
+
+    if computer.real_time_protection_enabled == Some(false) {
+        findings.push(
+            Finding::new("DEF-002", MODULE, Severity::Critical, "Real-time protection is off")
+                .explanation("Files are no longer scanned as they're downloaded, opened, or run, so malware can execute unchallenged")
+                .remediation("Turn on Real-time protection in Windows Security > Virus & threat protection settings")
+                .evidence("RealTimeProtectionEnabled=false")
+        );
+    }
+
+    if computer.tamper_protected == Some(false) {
+        findings.push(
+            Finding::new("DEF-003", MODULE, Severity::Medium, "Tamper Protection is off")
+                .explanation("Malware running as administrator can turn off Defender's protections or add exclusions")
+                .remediation("Turn on Tamper Protection in Windows Security > Virus & threat protection settings")
+                .evidence("IsTamperProtected=false")
+        );
+    }
+
+    if let Some(age) = computer.signature_age_days
+        && age > STALE_SIGNATURE_DAYS
+    {
+        findings.push(
+            Finding::new("DEF-004", MODULE, Severity::High, format!("Defender signatures are {} days old", age))
+                .explanation("Defender can only recognise threats its signatures know about, and new ones are released several times a day")
+                .remediation("Run `Update-MpSignature` or check for updates in Windows Security, then check the machine can reach Windows Update")
+                .evidence(format!("AntivirusSignatureAge={}", age))
+                .evidence(format!("AntivirusSignatureVersion={}", computer.signature_version.as_deref().unwrap_or("Unknown")))
+        );
+    }
+
+    match computer.quick_scan_age_days {
+        Some(age) if age > QUICK_SCAN_DAYS => findings.push(
+            Finding::new("DEF-005", MODULE, Severity::Low, format!("No quick scan in {} days", age))
+                .explanation("Scheduled scans catch threats that slipped past real-time protection before it had signatures for them")
+                .remediation("Run `Start-MpScan -ScanType QuickScan` and check the scheduled scan task is enabled")
+                .evidence(format!("QuickScanAge={}", age))
+        ),
+        None if computer.quick_scan_end.is_none() && computer.full_scan_end.is_none() => findings.push(
+            Finding::new("DEF-005", MODULE, Severity::Low, "Defender has never finished a scan")
+                .explanation("Scheduled scans catch threats that slipped past real-time protection before it had signatures for them")
+                .remediation("Run `Start-MpScan -ScanType QuickScan` and check the scheduled scan task is enabled")
+                .evidence("QuickScanEndTime=null FullScanEndTime=null")
+        ),
+        _ => {}
+    }
+}
+
+fn assess_preferences(preferences: &Preferences, findings: &mut Vec<Finding>) {
+    if preferences.cloud_protection == Some(0) {
+        findings.push(
+            Finding::new("DEF-006", MODULE, Severity::Medium, "Cloud-delivered protection is off")
+                .explanation("Defender can't ask Microsoft's cloud about new files, which is how it blocks threats minutes after they first appear")
+                .remediation("Turn on Cloud-delivered protection in Windows Security, or set MAPSReporting to 2 with `Set-MpPreference -MAPSReporting Advanced`")
+                .evidence("MAPSReporting=0")
+        );
+    }
+
+    if preferences.pua_protection == Some(0) {
+        findings.push(
+            Finding::new("DEF-007", MODULE, Severity::Low, "Potentially unwanted app blocking is off")
+                .explanation("Adware, bundled toolbars, and crypto miners that aren't strictly malware are allowed to install")
+                .remediation("Run `Set-MpPreference -PUAProtection Enabled` or turn on Potentially unwanted app blocking in Windows Security")
+                .evidence("PUAProtection=0")
+        );
+    }
+
+    let exclusions = &preferences.exclusions;
+    let lists = [
+        (ExclusionKind::Path, "ExclusionPath", &exclusions.paths),
+        (ExclusionKind::Extension, "ExclusionExtension", &exclusions.extensions),
+        (ExclusionKind::Process, "ExclusionProcess", &exclusions.processes)
+    ];
+    for (kind, property, values) in lists {
+        for value in values {
+            if let Some(reason) = exclusion_risk(kind, value) {
+                findings.push(
+                    Finding::new("DEF-008", MODULE, Severity::High, format!("Risky Defender exclusion: {}", value))
+                        .explanation(format!("This exclusion {}, giving malware a place Defender will never look", reason))
+                        .remediation("Remove the exclusion in Windows Security > Virus & threat protection settings > Exclusions, or narrow it to one application's folder")
+                        .evidence(format!("{}={}", property, value))
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::{WmiRow, WmiValue};
+    use crate::defender::scanner::{scan_defender_with, ANTISPYWARE_NAMESPACE, NAMESPACE};
+
+    fn product(name: &str, state: u32) -> WmiRow {
+        WmiRow::new()
+            .with("displayName", WmiValue::String(name.to_string()))
+            .with("productState", WmiValue::U32(state))
+    }
+
+    #[test]
+    fn classifies_exclusions() {
+        assert!(exclusion_risk(ExclusionKind::Path, "C:\\").is_some());
+        assert!(exclusion_risk(ExclusionKind::Path, "C:\\Users\\Bob\\AppData\\Local\\Temp").is_some());
+        assert!(exclusion_risk(ExclusionKind::Path, "C:\\*").is_some());
+        assert!(exclusion_risk(ExclusionKind::Path, "C:\\Program Files\\Contoso\\Data").is_none());
+        assert!(exclusion_risk(ExclusionKind::Extension, ".exe").is_some());
+        assert!(exclusion_risk(ExclusionKind::Extension, "*.PS1").is_some());
+        assert!(exclusion_risk(ExclusionKind::Extension, "log").is_none());
+        assert!(exclusion_risk(ExclusionKind::Process, "C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe").is_some());
+        assert!(exclusion_risk(ExclusionKind::Process, "sqlservr.exe").is_none());
+    }
+
+    #[test]
+    fn flags_stale_signatures_and_risky_exclusions() {
+        let source = FixtureSource::new()
+            .with_wmi(NAMESPACE, "MSFT_MpComputerStatus", vec![
+                WmiRow::new()
+                    .with("AMServiceEnabled", WmiValue::Bool(true))
+                    .with("AntivirusSignatureAge", WmiValue::U32(30))
+                    .with("QuickScanAge", WmiValue::U32(1))
+            ])
+            .with_wmi(NAMESPACE, "MSFT_MpPreference", vec![
+                WmiRow::new()
+                    .with("MAPSReporting", WmiValue::U32(0))
+                    .with("ExclusionPath", WmiValue::Array(vec![WmiValue::String("C:\\Temp".to_string()), WmiValue::String("D:\\Builds".to_string())]))
+            ])
+            .with_wmi(ANTISPYWARE_NAMESPACE, "AntiSpywareProduct", vec![product("Windows Defender", 0x061100)]);
+
+        let findings = assess_defender(&scan_defender_with(&source).unwrap());
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["DEF-004", "DEF-006", "DEF-008"]);
+        assert_eq!(findings[0].evidence[1], "AntivirusSignatureVersion=Unknown");
+        assert_eq!(findings[2].evidence, vec!["ExclusionPath=C:\\Temp"]);
+    }
+
+    #[test]
+    fn removed_defender_is_unreadable_not_off() {
+        // Defender's namespace is gone because another product replaced it
+        let source = FixtureSource::new().with_wmi(ANTISPYWARE_NAMESPACE, "AntiSpywareProduct", vec![product("Other AV", 0x041000)]);
+
+        let findings = assess_defender(&scan_defender_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("DEF-010", Severity::Info));
+        assert!(findings[0].evidence.iter().any(|evidence| evidence.starts_with("MSFT_MpComputerStatus: ")));
+    }
+
+    #[test]
+    fn null_values_and_hidden_exclusions_are_not_findings() {
+        let source = FixtureSource::new()
+            .with_wmi(NAMESPACE, "MSFT_MpComputerStatus", vec![
+                WmiRow::new()
+                    .with("AMServiceEnabled", WmiValue::Null)
+                    .with("RealTimeProtectionEnabled", WmiValue::Null)
+                    .with("IsTamperProtected", WmiValue::Null)
+                    .with("AntivirusSignatureAge", WmiValue::U32(u32::MAX))
+                    .with("QuickScanAge", WmiValue::U32(3))
+            ])
+            .with_wmi(NAMESPACE, "MSFT_MpPreference", vec![
+                WmiRow::new()
+                    .with("MAPSReporting", WmiValue::Null)
+                    .with("ExclusionPath", WmiValue::Array(vec![WmiValue::String("N/A: Must be an administrator to view exclusions".to_string())]))
+            ])
+            .with_wmi(ANTISPYWARE_NAMESPACE, "AntiSpywareProduct", vec![product("Windows Defender", 0x061100)]);
+
+        assert_eq!(assess_defender(&scan_defender_with(&source).unwrap()), vec![]);
+    }
+
+    #[test]
+    fn never_scanned_needs_both_end_times_empty() {
+        let status = |full_scan_end: WmiValue| FixtureSource::new()
+            .with_wmi(NAMESPACE, "MSFT_MpComputerStatus", vec![
+                WmiRow::new()
+                    .with("AMServiceEnabled", WmiValue::Bool(true))
+                    .with("QuickScanAge", WmiValue::U32(u32::MAX))
+                    .with("QuickScanEndTime", WmiValue::Null)
+                    .with("FullScanEndTime", full_scan_end)
+            ])
+            .with_wmi(NAMESPACE, "MSFT_MpPreference", vec![WmiRow::new()])
+            .with_wmi(ANTISPYWARE_NAMESPACE, "AntiSpywareProduct", vec![product("Windows Defender", 0x061100)]);
+
+        let findings = assess_defender(&scan_defender_with(&status(WmiValue::Null)).unwrap());
+        assert_eq!(findings[0].title, "Defender has never finished a scan");
+        let full_scan = WmiValue::String("20240115093000.000000+000".to_string());
+        assert!(assess_defender(&scan_defender_with(&status(full_scan)).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn missing_security_center_is_not_missing_protection() {
+        // Windows Server has no Security Center, so there's no product list to judge Defender against
+        let source = FixtureSource::new()
+            .with_wmi(NAMESPACE, "MSFT_MpComputerStatus", vec![WmiRow::new().with("AMServiceEnabled", WmiValue::Bool(false))])
+            .with_wmi(NAMESPACE, "MSFT_MpPreference", vec![WmiRow::new()]);
+
+        let findings = assess_defender(&scan_defender_with(&source).unwrap());
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["DEF-001", "DEF-010"]);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert!(findings[1].evidence[0].starts_with("AntiSpywareProduct: "));
+    }
+
+    #[test]
+    fn third_party_product_downgrades_disabled_defender() {
+        let source = FixtureSource::new()
+            .with_wmi(NAMESPACE, "MSFT_MpComputerStatus", vec![
+                WmiRow::new()
+                    .with("AMServiceEnabled", WmiValue::Bool(false))
+                    .with("RealTimeProtectionEnabled", WmiValue::Bool(false))
+            ])
+            .with_wmi(NAMESPACE, "MSFT_MpPreference", vec![WmiRow::new()])
+            .with_wmi(ANTISPYWARE_NAMESPACE, "AntiSpywareProduct", vec![product("Other AV", 0x041000)]);
+
+        let findings = assess_defender(&scan_defender_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("DEF-001", Severity::Info));
+        assert_eq!(findings[0].evidence, vec!["Other AV"]);
+    }
+}
@@ -0,0 +1,183 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_defender;
+use super::scanner::{ComputerStatus, DefenderStatus, Exclusions, Preferences};
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::{format_unix_time, get_time};
+
+/// Display for Defender Module
+pub fn display_defender(status: &DefenderStatus, verbose: bool) {
+    println!();
+    println!("WINDOWS DEFENDER STATUS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(status);}
+
+    match &status.computer_status {
+        Some(computer) => display_protection(computer, verbose),
+        None => {
+            println!(" - Defender status couldn't be read");
+            println!();
+        }
+    }
+
+    if let Some(preferences) = &status.preferences {
+        display_preferences(preferences, verbose);
+        display_exclusions(&preferences.exclusions);
+    }
+
+    display_antispyware(status, verbose);
+
+    display_findings(&assess_defender(status), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for Defender Module
+pub fn json_defender(status: &DefenderStatus) -> Value {
+    let decoded = json!({
+        "real_time_protection": status.computer_status.as_ref().map(|c| enabled(c.real_time_protection_enabled)),
+        "tamper_protection": status.computer_status.as_ref().map(|c| enabled(c.tamper_protected)),
+        "cloud_protection": status.preferences.as_ref().map(|p| decode_cloud_protection(p.cloud_protection)),
+        "pua_protection": status.preferences.as_ref().map(|p| decode_pua_protection(p.pua_protection))
+    });
+
+    json!({
+        "defender": with_decoded(status, decoded),
+        "findings": assess_defender(status)
+    })
+}
+
+fn display_scan_details(status: &DefenderStatus) {
+    let (h, m, s) = get_time();
+
+    println!("Scan Details:");
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - WMI Namespace: {}", status.module_info.namespace);
+    println!(" - Query:");
+    for query in status.module_info.queries.iter() {
+        println!("   - {}", query);
+    }
+    for (class, reason) in status.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", class, reason);
+    }
+    println!();
+}
+
+fn display_protection(computer: &ComputerStatus, verbose: bool) {
+    println!("Protection:");
+    println!(" - Antimalware Service: {}", enabled(computer.antimalware_enabled));
+    println!(" - Real-time Protection: {}", enabled(computer.real_time_protection_enabled));
+    println!(" - Tamper Protection: {}", enabled(computer.tamper_protected));
+    println!();
+
+    println!("Signatures:");
+    println!(" - Age: {}", days(computer.signature_age_days));
+    if verbose {
+        println!("   - Version: {}", computer.signature_version.as_deref().unwrap_or("Unknown"));
+        println!("   - Last Updated: {}", timestamp(computer.signature_last_updated));
+    }
+    println!();
+
+    println!("Scans:");
+    println!(" - Last Quick Scan: {}", days(computer.quick_scan_age_days));
+    if verbose {println!("   - Finished: {}", timestamp(computer.quick_scan_end));}
+    println!(" - Last Full Scan: {}", days(computer.full_scan_age_days));
+    if verbose {println!("   - Finished: {}", timestamp(computer.full_scan_end));}
+    println!();
+}
+
+fn display_preferences(preferences: &Preferences, verbose: bool) {
+    println!("Settings:");
+    println!(" - Cloud-delivered Protection: {}", decode_cloud_protection(preferences.cloud_protection));
+    if verbose {println!("   - MAPSReporting: {}", raw_value(preferences.cloud_protection));}
+    println!(" - Potentially Unwanted App Blocking: {}", decode_pua_protection(preferences.pua_protection));
+    if verbose {println!("   - PUAProtection: {}", raw_value(preferences.pua_protection));}
+    println!();
+}
+
+fn display_exclusions(exclusions: &Exclusions) {
+    println!("Exclusions:");
+    if exclusions.hidden {
+        println!(" - Hidden, run as administrator to see them");
+    } else if exclusions.paths.is_empty() && exclusions.extensions.is_empty() && exclusions.processes.is_empty() {
+        println!(" - None");
+    }
+    for (label, values) in [("Path", &exclusions.paths), ("Extension", &exclusions.extensions), ("Process", &exclusions.processes)] {
+        for value in values {
+            println!(" - {}: {}", label, value);
+        }
+    }
+    println!();
+}
+
+fn display_antispyware(status: &DefenderStatus, verbose: bool) {
+    println!("Antispyware Products:");
+    match &status.antispyware {
+        None => println!(" - Couldn't be read"),
+        Some(products) if products.is_empty() => println!(" - No Antispyware Products Found!"),
+        Some(products) => {
+            for (i, prod) in products.iter().enumerate() {
+                println!("{}. {}", i + 1, prod.name);
+                println!("   - Status: {}", prod.product_state.scanner);
+                println!("   - Definitions: {}", prod.product_state.definitions);
+                if verbose {println!("   - Hexadecimal State: 0x{:X}", prod.state);}
+            }
+        }
+    }
+    println!();
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - COM Apartment: MTA (Multi-threaded)");
+    println!(" - WMI Context: CLSCTX_INPROC_SERVER");
+    println!();
+}
+
+fn enabled(value: Option<bool>) -> &'static str {
+    match value {
+        Some(true) => "On",
+        Some(false) => "Off",
+        None => "Unknown"
+    }
+}
+
+fn decode_cloud_protection(value: Option<u32>) -> &'static str {
+    match value {
+        Some(0) => "Off",
+        Some(1) => "Basic",
+        Some(2) => "Advanced",
+        _ => "Unknown"
+    }
+}
+
+fn decode_pua_protection(value: Option<u32>) -> &'static str {
+    match value {
+        Some(0) => "Off",
+        Some(1) => "On",
+        Some(2) => "Audit only",
+        _ => "Unknown"
+    }
+}
+
+fn days(value: Option<u32>) -> String {
+    match value {
+        Some(0) => "Today".to_string(),
+        Some(1) => "1 day ago".to_string(),
+        Some(days) => format!("{} days ago", days),
+        None => "Never or unknown".to_string()
+    }
+}
+
+fn timestamp(value: Option<i64>) -> String {
+    match value {
+        Some(secs) if secs >= 0 => format_unix_time(secs as u64),
+        _ => "Unknown".to_string()
+    }
+}
+
+fn raw_value(value: Option<u32>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_else(|| "Not set".to_string())
+}
@@ -0,0 +1,304 @@
+//! This is the Defender Module for Shugo. Here we can see:
+//!
+//! - Real-time, Tamper, Cloud, and PUA Protection
+//! - Signature Version and Age
+//! - Last Quick and Full Scans
+//! - Exclusions (paths, extensions, and processes)
+//! - Antispyware Products
+//!
+//! The antivirus module only sees what Security Center knows. Defender keeps much more detail in its own
+//! WMI namespace, `ROOT\Microsoft\Windows\Defender`, so this module reads it straight from there.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::antivirus::scanner::ProductInfo;
+use crate::common::error::ShugoError;
+use crate::common::source::{SecuritySource, SourceResult, system_source};
+use crate::common::wmi::{FromWmiObject, WmiObject, WmiValueError, optional, query};
+
+#[derive(Serialize, Deserialize)]
+pub struct DefenderStatus {
+    /// `None` when Defender's namespace couldn't be read, for example when it's been removed
+    pub computer_status: Option<ComputerStatus>,
+    pub preferences: Option<Preferences>,
+    pub antispyware: Option<Vec<ProductInfo>>,
+    pub module_info: ModuleInfo
+}
+
+/// The parts of `MSFT_MpComputerStatus` we use. Anything Defender didn't report is `None`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComputerStatus {
+    pub antimalware_enabled: Option<bool>,
+    pub real_time_protection_enabled: Option<bool>,
+    pub tamper_protected: Option<bool>,
+    pub signature_version: Option<String>,
+    pub signature_age_days: Option<u32>,
+    /// Unix time of the last signature update
+    pub signature_last_updated: Option<i64>,
+    pub quick_scan_age_days: Option<u32>,
+    pub quick_scan_end: Option<i64>,
+    pub full_scan_age_days: Option<u32>,
+    pub full_scan_end: Option<i64>
+}
+
+/// The parts of `MSFT_MpPreference` we use
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Preferences {
+    pub realtime_monitoring_disabled: Option<bool>,
+    /// MAPSReporting: 0 = Off, 1 = Basic, 2 = Advanced
+    pub cloud_protection: Option<u32>,
+    /// PUAProtection: 0 = Off, 1 = On, 2 = Audit
+    pub pua_protection: Option<u32>,
+    pub exclusions: Exclusions
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Exclusions {
+    pub paths: Vec<String>,
+    pub extensions: Vec<String>,
+    pub processes: Vec<String>,
+    /// Defender hides exclusions from non-administrators, so an empty list doesn't mean there are none
+    pub hidden: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub namespace: String,
+    pub queries: Vec<String>,
+    /// Parts that couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+pub const NAMESPACE: &str = "ROOT\\Microsoft\\Windows\\Defender";
+pub const STATUS_QUERY: &str = "SELECT AMServiceEnabled, RealTimeProtectionEnabled, IsTamperProtected, AntivirusSignatureVersion, \
+    AntivirusSignatureAge, AntivirusSignatureLastUpdated, QuickScanAge, QuickScanEndTime, FullScanAge, FullScanEndTime \
+    FROM MSFT_MpComputerStatus";
+pub const PREFERENCE_QUERY: &str = "SELECT DisableRealtimeMonitoring, MAPSReporting, PUAProtection, ExclusionPath, \
+    ExclusionExtension, ExclusionProcess FROM MSFT_MpPreference";
+pub const ANTISPYWARE_NAMESPACE: &str = "ROOT\\SecurityCenter2";
+pub const ANTISPYWARE_QUERY: &str = "Select displayName, productState FROM AntiSpywareProduct";
+
+/// Defender reports the age of a scan that never ran as the largest uint32
+const NEVER: u32 = u32::MAX;
+
+/// Scanning Windows Defender
+pub fn scan_defender() -> SourceResult<DefenderStatus> {
+    scan_defender_with(system_source()?.as_ref())
+}
+
+/// Scanning Windows Defender from any source
+pub fn scan_defender_with(source: &dyn SecuritySource) -> SourceResult<DefenderStatus> {
+    /*
+        Shugo: Defender's Own Namespace
+
+        Security Center (`ROOT\SecurityCenter2`) only knows the summary every antivirus reports: on or off, and
+        whether its definitions are current. Defender publishes everything else in `ROOT\Microsoft\Windows\Defender`:
+
+        MSFT_MpComputerStatus: what Defender is doing right now, like whether real-time protection is running,
+        how old the signatures are, and when the last scans finished. This is what `Get-MpComputerStatus` shows.
+
+        MSFT_MpPreference: how Defender is configured, like cloud protection, PUA blocking, and exclusions.
+        This is what `Get-MpPreference` shows.
+
+        When a third-party antivirus takes over, Defender steps aside but these classes usually still exist.
+        On machines where Defender has been removed the namespace is gone entirely, so a missing namespace is
+        kept as "couldn't read" instead of failing the whole module. Being refused MSFT_MpComputerStatus is
+        different, Defender is there but we can't see what it's doing, so that fails the module.
+
+        For more information on these classes:
+        (https://learn.microsoft.com/en-us/previous-versions/windows/desktop/defender/msft-mpcomputerstatus)
+        (https://learn.microsoft.com/en-us/previous-versions/windows/desktop/defender/msft-mppreference)
+    */
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+
+    let computer_status = match query::<ComputerStatus>(source, NAMESPACE, STATUS_QUERY) {
+        Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::Unavailable { .. })) => return Err(error.in_module("defender")),
+        result => readable(&mut unreadable, "MSFT_MpComputerStatus", result)?
+    }.and_then(|rows| rows.into_iter().next());
+    let preferences = readable(&mut unreadable, "MSFT_MpPreference", query::<Preferences>(source, NAMESPACE, PREFERENCE_QUERY))?
+        .and_then(|rows| rows.into_iter().next());
+    let antispyware = readable(&mut unreadable, "AntiSpywareProduct", query::<ProductInfo>(source, ANTISPYWARE_NAMESPACE, ANTISPYWARE_QUERY))?;
+
+    Ok(DefenderStatus {
+        computer_status,
+        preferences,
+        antispyware,
+        module_info: ModuleInfo {
+            namespace: NAMESPACE.to_string(),
+            queries: vec![STATUS_QUERY.to_string(), PREFERENCE_QUERY.to_string(), ANTISPYWARE_QUERY.to_string()],
+            unreadable
+        }
+    })
+}
+
+/// Keeps a class that isn't there or can't be read as `None` with the reason, other errors fail the scan
+fn readable<T>(unreadable: &mut BTreeMap<String, String>, class: &str, result: SourceResult<Vec<T>>) -> SourceResult<Option<Vec<T>>> {
+    match result {
+        Ok(rows) => Ok(Some(rows)),
+        Err(error @ (ShugoError::NotFound { .. } | ShugoError::AccessDenied { .. } | ShugoError::Unavailable { .. })) => {
+            unreadable.insert(class.to_string(), error.detail());
+            Ok(None)
+        },
+        Err(error) => Err(error.in_module("defender"))
+    }
+}
+
+impl FromWmiObject for ComputerStatus {
+    fn from_wmi_object(object: &dyn WmiObject) -> SourceResult<Self> {
+        let age = |name: &str| -> Result<Option<u32>, WmiValueError> {
+            Ok(optional(object.get_u32(name))?.filter(|days| *days != NEVER))
+        };
+        let time = |name: &str| -> Result<Option<i64>, WmiValueError> {
+            Ok(optional(object.get_datetime(name))?.map(|date| date.unix_time()))
+        };
+
+        Ok(ComputerStatus {
+            antimalware_enabled: optional(object.get_bool("AMServiceEnabled"))?,
+            real_time_protection_enabled: optional(object.get_bool("RealTimeProtectionEnabled"))?,
+            tamper_protected: optional(object.get_bool("IsTamperProtected"))?,
+            signature_version: optional(object.get_string("AntivirusSignatureVersion"))?,
+            signature_age_days: age("AntivirusSignatureAge")?,
+            signature_last_updated: time("AntivirusSignatureLastUpdated")?,
+            quick_scan_age_days: age("QuickScanAge")?,
+            quick_scan_end: time("QuickScanEndTime")?,
+            full_scan_age_days: age("FullScanAge")?,
+            full_scan_end: time("FullScanEndTime")?
+        })
+    }
+}
+
+impl FromWmiObject for Preferences {
+    fn from_wmi_object(object: &dyn WmiObject) -> SourceResult<Self> {
+        /*
+            Shugo: Hidden Exclusions
+
+            Exclusions tell Defender to skip files, so attackers love adding them. Because of that, Defender only shows
+            them to administrators. Everyone else gets a single placeholder entry:
+
+            "N/A: Must be an administrator to view exclusions"
+
+            We don't want to report that text as an excluded path, so we mark the list as hidden instead.
+        */
+        let mut hidden = false;
+        let mut list = |name: &str| -> Result<Vec<String>, WmiValueError> {
+            let entries = optional(object.get_strings(name))?.unwrap_or_default();
+            if entries.iter().any(|entry| entry.starts_with("N/A")) {
+                hidden = true;
+                return Ok(Vec::new());
+            }
+            Ok(entries)
+        };
+        let exclusions = Exclusions {
+            paths: list("ExclusionPath")?,
+            extensions: list("ExclusionExtension")?,
+            processes: list("ExclusionProcess")?,
+            hidden
+        };
+
+        Ok(Preferences {
+            realtime_monitoring_disabled: optional(object.get_bool("DisableRealtimeMonitoring"))?,
+            cloud_protection: optional(object.get_u32("MAPSReporting"))?,
+            pua_protection: optional(object.get_u32("PUAProtection"))?,
+            exclusions
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::offline::OfflineSource;
+    use crate::common::source::{WmiRow, WmiValue};
+
+    fn strings(values: &[&str]) -> WmiValue {
+        WmiValue::Array(values.iter().map(|value| WmiValue::String(value.to_string())).collect())
+    }
+
+    #[test]
+    fn reads_status_and_preferences() {
+        let source = FixtureSource::new()
+            .with_wmi(NAMESPACE, "MSFT_MpComputerStatus", vec![
+                WmiRow::new()
+                    .with("AMServiceEnabled", WmiValue::Bool(true))
+                    .with("RealTimeProtectionEnabled", WmiValue::Bool(true))
+                    .with("IsTamperProtected", WmiValue::Bool(false))
+                    .with("AntivirusSignatureVersion", WmiValue::String("1.403.1".to_string()))
+                    .with("AntivirusSignatureAge", WmiValue::U32(2))
+                    .with("AntivirusSignatureLastUpdated", WmiValue::String("20240115093000.000000+000".to_string()))
+                    .with("QuickScanAge", WmiValue::U32(1))
+                    .with("FullScanAge", WmiValue::U32(u32::MAX))
+                    .with("FullScanEndTime", WmiValue::Null)
+            ])
+            .with_wmi(NAMESPACE, "MSFT_MpPreference", vec![
+                WmiRow::new()
+                    .with("DisableRealtimeMonitoring", WmiValue::Bool(false))
+                    .with("MAPSReporting", WmiValue::U32(2))
+                    .with("PUAProtection", WmiValue::U32(0))
+                    .with("ExclusionPath", strings(&["C:\\Temp"]))
+                    .with("ExclusionExtension", WmiValue::Null)
+            ])
+            .with_wmi(ANTISPYWARE_NAMESPACE, "AntiSpywareProduct", vec![
+                WmiRow::new()
+                    .with("displayName", WmiValue::String("Windows Defender".to_string()))
+                    .with("productState", WmiValue::U32(397568))
+            ]);
+
+        let status = scan_defender_with(&source).unwrap();
+        let computer = status.computer_status.unwrap();
+        assert_eq!(computer.tamper_protected, Some(false));
+        assert_eq!(computer.signature_age_days, Some(2));
+        assert_eq!(computer.signature_last_updated, Some(1705311000));
+        assert_eq!(computer.full_scan_age_days, None);
+        assert_eq!(computer.full_scan_end, None);
+
+        let preferences = status.preferences.unwrap();
+        assert_eq!(preferences.cloud_protection, Some(2));
+        assert_eq!(preferences.exclusions.paths, vec!["C:\\Temp"]);
+        assert!(preferences.exclusions.extensions.is_empty());
+        assert_eq!(status.antispyware.unwrap().len(), 1);
+        assert!(status.module_info.unreadable.is_empty());
+    }
+
+    #[test]
+    fn missing_namespace_is_unreadable() {
+        let source = FixtureSource::new().with_wmi(ANTISPYWARE_NAMESPACE, "AntiSpywareProduct", vec![]);
+
+        let status = scan_defender_with(&source).unwrap();
+        assert!(status.computer_status.is_none());
+        assert!(status.preferences.is_none());
+        assert!(status.module_info.unreadable.contains_key("MSFT_MpComputerStatus"));
+        assert_eq!(status.antispyware.unwrap().len(), 0);
+    }
+
+    #[test]
+    fn unreadable_status_fails_the_scan() {
+        let error = scan_defender_with(&OfflineSource::new()).err().unwrap();
+        assert_eq!(error.kind(), "unavailable");
+        assert_eq!(error.context().module.as_deref(), Some("defender"));
+    }
+
+    #[test]
+    fn hidden_exclusions_are_not_paths() {
+        let source = FixtureSource::new().with_wmi(NAMESPACE, "MSFT_MpPreference", vec![
+            WmiRow::new().with("ExclusionPath", strings(&["N/A: Must be an administrator to view exclusions"]))
+        ]);
+
+        let preferences = scan_defender_with(&source).unwrap().preferences.unwrap();
+        assert!(preferences.exclusions.hidden);
+        assert!(preferences.exclusions.paths.is_empty());
+    }
+
+    #[test]
+    fn wrong_types_fail_the_scan() {
+        let source = FixtureSource::new().with_wmi(NAMESPACE, "MSFT_MpComputerStatus", vec![
+            WmiRow::new().with("RealTimeProtectionEnabled", WmiValue::String("yes".to_string()))
+        ]);
+
+        let Err(error) = scan_defender_with(&source) else { panic!("a string isn't a boolean") };
+        assert_eq!(error.kind(), "parse_error");
+        assert_eq!(error.context().module.as_deref(), Some("defender"));
+    }
+}
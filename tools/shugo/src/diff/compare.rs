@@ -0,0 +1,362 @@
+//! This is the Drift Module for Shugo. It compares two saved audits and reports what changed:
+//!
+//! - Firewall profiles turned on or off
+//! - Antivirus products turning off or their definitions going out-of-date
+//! - UAC settings being weakened or strengthened
+//! - New administrator accounts, or the Guest account being enabled
+//! - New pending critical and security updates
+//! - Findings that appeared or were resolved, which is how changes in the other modules show up
+//!
+//! Nothing here touches the machine, it only reads the `AuditReport`s from two snapshots.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::antivirus::scanner::ProductInfo;
+use crate::audit::scanner::AuditReport;
+use crate::common::finding::Finding;
+use crate::common::product_state::{DefinitionsState, ScannerState};
+use crate::firewall::assessment::third_party_active;
+use crate::firewall::scanner::{FirewallProfileDetails, FirewallSummary};
+use crate::uac::display::{decode_prompt_level, uac_decode};
+use crate::uac::scanner::UacInfo;
+use crate::uas::scanner::UserAccountSummary;
+use crate::updates::scanner::UpdateSummary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftKind {
+    /// The machine is less secure than it was
+    Regressed,
+    /// The machine is more secure than it was
+    Improved,
+    /// Something changed that isn't better or worse by itself
+    Changed
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Drift {
+    pub module: String,
+    pub kind: DriftKind,
+    pub description: String,
+    pub before: String,
+    pub after: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DiffReport {
+    pub score_before: u32,
+    pub score_after: u32,
+    pub changes: Vec<Drift>,
+    pub new_findings: Vec<Finding>,
+    pub resolved_findings: Vec<Finding>
+}
+
+impl DiffReport {
+    pub fn count(&self, kind: DriftKind) -> usize {
+        self.changes.iter().filter(|change| change.kind == kind).count()
+    }
+}
+
+/// Comparing two audits, `old` being the earlier one
+pub fn diff_audits(old: &AuditReport, new: &AuditReport) -> DiffReport {
+    let mut changes = DriftList::default();
+
+    changes.compare("antivirus", old.antivirus.as_deref(), new.antivirus.as_deref(), diff_antivirus);
+    changes.compare("firewall", old.firewall.as_ref(), new.firewall.as_ref(), diff_firewall);
+    changes.compare("updates", old.updates.as_ref(), new.updates.as_ref(), diff_updates);
+    changes.compare("uac", old.uac.as_ref(), new.uac.as_ref(), diff_uac);
+    changes.compare("uas", old.uas.as_ref(), new.uas.as_ref(), diff_uas);
+    changes.compare("defender", old.defender.as_ref(), new.defender.as_ref(), findings_only);
+    changes.compare("os", old.os.as_ref(), new.os.as_ref(), findings_only);
+    changes.compare("smb", old.smb.as_ref(), new.smb.as_ref(), findings_only);
+    changes.compare("rdp", old.rdp.as_ref(), new.rdp.as_ref(), findings_only);
+    changes.compare("bitlocker", old.bitlocker.as_ref(), new.bitlocker.as_ref(), findings_only);
+    changes.compare("powershell", old.powershell.as_ref(), new.powershell.as_ref(), findings_only);
+    changes.compare("browser", old.browser.as_ref(), new.browser.as_ref(), findings_only);
+    changes.compare("extensions", old.extensions.as_ref(), new.extensions.as_ref(), findings_only);
+    changes.compare("shares", old.shares.as_ref(), new.shares.as_ref(), findings_only);
+    changes.compare("startup", old.startup.as_ref(), new.startup.as_ref(), findings_only);
+
+    /*
+        Shugo: Matching Findings
+
+        A finding is matched between the two audits by its ID and title together. The ID alone isn't enough
+        because some checks, like out-of-date antivirus definitions, can show up once per product.
+    */
+    let key = |finding: &Finding| (finding.id.clone(), finding.title.clone());
+    let old_keys: Vec<(String, String)> = old.findings.iter().map(key).collect();
+    let new_keys: Vec<(String, String)> = new.findings.iter().map(key).collect();
+
+    DiffReport {
+        score_before: old.score.overall,
+        score_after: new.score.overall,
+        changes: changes.0,
+        new_findings: new.findings.iter().filter(|f| !old_keys.contains(&key(f))).cloned().collect(),
+        resolved_findings: old.findings.iter().filter(|f| !new_keys.contains(&key(f))).cloned().collect()
+    }
+}
+
+#[derive(Default)]
+struct DriftList(Vec<Drift>);
+
+impl DriftList {
+    fn push(&mut self, module: &str, kind: DriftKind, description: impl Into<String>, before: impl ToString, after: impl ToString) {
+        self.0.push(Drift {
+            module: module.to_string(),
+            kind,
+            description: description.into(),
+            before: before.to_string(),
+            after: after.to_string()
+        });
+    }
+
+    /// Compares a module that may have failed in either audit
+    fn compare<T: ?Sized>(&mut self, module: &str, old: Option<&T>, new: Option<&T>, diff: fn(&mut DriftList, &T, &T)) {
+        match (old, new) {
+            (Some(old), Some(new)) => diff(self, old, new),
+            (Some(_), None) => self.push(module, DriftKind::Changed, "Module wasn't scanned in the newer audit", "Scanned", "Not scanned"),
+            (None, Some(_)) => self.push(module, DriftKind::Changed, "Module wasn't scanned in the older audit", "Not scanned", "Scanned"),
+            (None, None) => {}
+        }
+    }
+
+    /// Records a setting where `good` is the secure state
+    fn toggle(&mut self, module: &str, description: &str, before: bool, after: bool, good: bool, labels: (&str, &str)) {
+        if before == after {
+            return;
+        }
+        let kind = if after == good {DriftKind::Improved} else {DriftKind::Regressed};
+        let label = |value: bool| if value {labels.0} else {labels.1};
+        self.push(module, kind, description, label(before), label(after));
+    }
+}
+
+fn diff_antivirus(changes: &mut DriftList, old: &[ProductInfo], new: &[ProductInfo]) {
+    let old_products: BTreeMap<&str, &ProductInfo> = old.iter().map(|prod| (prod.name.as_str(), prod)).collect();
+    let new_products: BTreeMap<&str, &ProductInfo> = new.iter().map(|prod| (prod.name.as_str(), prod)).collect();
+
+    for (name, prod) in &new_products {
+        let Some(before) = old_products.get(name) else {
+            changes.push("antivirus", DriftKind::Changed, format!("Antivirus product appeared: {}", name), "Not installed", prod.product_state.scanner.to_string());
+            continue;
+        };
+        let (was, now) = (&before.product_state, &prod.product_state);
+        if was.scanner != now.scanner {
+            let kind = match (was.scanner, now.scanner) {
+                (_, ScannerState::On) => DriftKind::Improved,
+                (ScannerState::On, _) => DriftKind::Regressed,
+                _ => DriftKind::Changed
+            };
+            changes.push("antivirus", kind, format!("{} status changed", name), was.scanner.to_string(), now.scanner.to_string());
+        }
+        if was.definitions != now.definitions {
+            let kind = match (was.definitions, now.definitions) {
+                (_, DefinitionsState::OutOfDate) => DriftKind::Regressed,
+                (DefinitionsState::OutOfDate, _) => DriftKind::Improved,
+                _ => DriftKind::Changed
+            };
+            changes.push("antivirus", kind, format!("{} definitions changed", name), was.definitions.to_string(), now.definitions.to_string());
+        }
+    }
+    for (name, prod) in &old_products {
+        if !new_products.contains_key(name) {
+            changes.push("antivirus", DriftKind::Changed, format!("Antivirus product removed: {}", name), prod.product_state.scanner.to_string(), "Not installed");
+        }
+    }
+}
+
+fn diff_firewall(changes: &mut DriftList, old: &FirewallSummary, new: &FirewallSummary) {
+    let profiles: [(&str, &FirewallProfileDetails, &FirewallProfileDetails); 3] = [
+        ("Public", &old.profile.public, &new.profile.public),
+        ("Private", &old.profile.private, &new.profile.private),
+        ("Domain", &old.profile.domain, &new.profile.domain)
+    ];
+    for (name, before, after) in profiles {
+        changes.toggle("firewall", &format!("{} profile firewall", name), before.profile_enabled, after.profile_enabled, true, ("Enabled", "Disabled"));
+        changes.toggle("firewall", &format!("{} profile inbound traffic", name), before.inbound_blocked, after.inbound_blocked, true, ("Blocked", "Allowed"));
+        changes.toggle("firewall", &format!("{} profile outbound traffic", name), before.outbound_blocked, after.outbound_blocked, true, ("Blocked", "Allowed"));
+    }
+
+    for prod in &new.products {
+        let before = old.products.iter().find(|old_prod| old_prod.name == prod.name).map(third_party_active);
+        match before {
+            Some(before) => changes.toggle("firewall", &format!("{} firewall", prod.name), before, third_party_active(prod), true, ("Active", "Inactive")),
+            None => changes.push("firewall", DriftKind::Changed, format!("Third-party firewall appeared: {}", prod.name), "Not installed", if third_party_active(prod) {"Active"} else {"Inactive"})
+        }
+    }
+    for prod in old.products.iter().filter(|prod| !new.products.iter().any(|new_prod| new_prod.name == prod.name)) {
+        changes.push("firewall", DriftKind::Changed, format!("Third-party firewall removed: {}", prod.name), if third_party_active(prod) {"Active"} else {"Inactive"}, "Not installed");
+    }
+}
+
+fn diff_updates(changes: &mut DriftList, old: &UpdateSummary, new: &UpdateSummary) {
+    let counts = [
+        ("Pending critical updates", old.critical_count, new.critical_count),
+        ("Pending security updates", old.security_count, new.security_count)
+    ];
+    for (description, before, after) in counts {
+        if before != after {
+            let kind = if after > before {DriftKind::Regressed} else {DriftKind::Improved};
+            changes.push("updates", kind, description, before, after);
+        }
+    }
+
+    for update in &new.update_list {
+        // Classification names are translated, the category GUID is the same in every language
+        let important = update.is_critical() || update.is_security();
+        if important && !old.update_list.iter().any(|old_update| old_update.title == update.title) {
+            changes.push("updates", DriftKind::Regressed, format!("New pending update: {}", update.title), "Not pending", &update.classification);
+        }
+    }
+}
+
+fn diff_uac(changes: &mut DriftList, old: &UacInfo, new: &UacInfo) {
+    let settings = [
+        ("UAC", old.lua_value, new.lua_value),
+        ("Secure Desktop", old.prompt_on_secure_desktop_value, new.prompt_on_secure_desktop_value),
+        ("Installer Detection", old.installer_detection_value, new.installer_detection_value),
+        ("Code Signature Validation", old.validate_admin_code_signatures_value, new.validate_admin_code_signatures_value),
+        ("Virtualization", old.enable_virtualization_value, new.enable_virtualization_value),
+        ("Administrator Token", old.filter_admin_token_value, new.filter_admin_token_value)
+    ];
+    for (description, before, after) in settings {
+        if before != after {
+            let kind = match (before, after) {
+                (_, Some(1)) => DriftKind::Improved,
+                (Some(1), _) => DriftKind::Regressed,
+                _ => DriftKind::Changed
+            };
+            changes.push("uac", kind, description, uac_decode(before), uac_decode(after));
+        }
+    }
+
+    let (before, after) = (old.prompt_level_value, new.prompt_level_value);
+    if before != after {
+        let (kind, description) = match (before.and_then(prompt_rank), after.and_then(prompt_rank)) {
+            (Some(before), Some(after)) if after < before => (DriftKind::Regressed, "Prompt level lowered"),
+            (Some(_), Some(_)) => (DriftKind::Improved, "Prompt level raised"),
+            _ => (DriftKind::Changed, "Prompt level changed")
+        };
+        changes.push("uac", kind, description, decode_prompt_level(before), decode_prompt_level(after));
+    }
+}
+
+/// How secure a ConsentPromptBehaviorAdmin value is, higher is more secure
+fn prompt_rank(level: u32) -> Option<u32> {
+    // The values aren't in order: 1 (credentials on the secure desktop) is the most secure, then 2, 3, 4,
+    // 5 (the default), and 0 (elevate without prompting) is the least
+    match level {
+        0 => Some(0),
+        1..=5 => Some(6 - level),
+        _ => None
+    }
+}
+
+fn diff_uas(changes: &mut DriftList, old: &UserAccountSummary, new: &UserAccountSummary) {
+    changes.toggle("uas", "Guest account", old.guest_enabled, new.guest_enabled, false, ("Enabled", "Disabled"));
+
+    let role = |is_admin: bool| if is_admin {"Administrator"} else {"Standard User"};
+    for account in &new.accounts {
+        let before = old.accounts.iter().find(|old_account| old_account.username.eq_ignore_ascii_case(&account.username));
+        match before {
+            None if account.is_admin => changes.push("uas", DriftKind::Regressed, format!("New administrator account: {}", account.username), "Not present", &account.account_type),
+            None => changes.push("uas", DriftKind::Changed, format!("New account: {}", account.username), "Not present", &account.account_type),
+            Some(before) => {
+                changes.toggle("uas", &format!("{} administrator rights", account.username), before.is_admin, account.is_admin, false, (role(true), role(false)));
+                if before.is_enabled != account.is_enabled {
+                    changes.push("uas", DriftKind::Changed, format!("{} account status", account.username), if before.is_enabled {"Enabled"} else {"Disabled"}, if account.is_enabled {"Enabled"} else {"Disabled"});
+                }
+            }
+        }
+    }
+    for account in old.accounts.iter().filter(|old_account| !new.accounts.iter().any(|a| a.username.eq_ignore_ascii_case(&old_account.username))) {
+        changes.push("uas", DriftKind::Changed, format!("Account removed: {}", account.username), &account.account_type, "Not present");
+    }
+}
+
+/// For modules compared through their findings alone, only whether they were scanned is tracked
+fn findings_only<T>(_: &mut DriftList, _: &T, _: &T) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::scanner::run_audit_with;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::{RegistryHive, RegistryValue, UserRecord};
+    use crate::uac::scanner::UAC_KEY;
+    use crate::uas::scanner::{USER_PRIV_ADMIN, USER_PRIV_USER};
+    use crate::updates::scanner::SECURITY_UPDATES_GUID;
+    use crate::updates::scanner::tests::update;
+
+    fn audit(lua: u32, prompt: u32, users: Vec<(&str, u32)>) -> AuditReport {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, UAC_KEY, "EnableLUA", RegistryValue::Dword(lua))
+            .with_registry(RegistryHive::LocalMachine, UAC_KEY, "ConsentPromptBehaviorAdmin", RegistryValue::Dword(prompt))
+            .with_users(users.into_iter().map(|(username, privilege)| UserRecord { username: username.to_string(), privilege, flags: 0 }).collect());
+        run_audit_with(&source)
+    }
+
+    fn prompt_change(before: u32, after: u32) -> (DriftKind, String) {
+        let diff = diff_audits(&audit(1, before, vec![]), &audit(1, after, vec![]));
+        assert_eq!(diff.changes.len(), 1);
+        (diff.changes[0].kind, diff.changes[0].description.clone())
+    }
+
+    #[test]
+    fn reports_weakened_settings() {
+        let old = audit(1, 2, vec![("alice", USER_PRIV_ADMIN), ("bob", USER_PRIV_USER)]);
+        let new = audit(1, 5, vec![("alice", USER_PRIV_ADMIN), ("bob", USER_PRIV_USER), ("mallory", USER_PRIV_ADMIN)]);
+
+        let diff = diff_audits(&old, &new);
+        let descriptions: Vec<&str> = diff.changes.iter().map(|change| change.description.as_str()).collect();
+        assert_eq!(descriptions, vec!["Prompt level lowered", "New administrator account: mallory"]);
+        assert_eq!(diff.count(DriftKind::Regressed), 2);
+        assert!(diff.new_findings.iter().any(|finding| finding.id == "UAS-002"));
+        assert!(diff.resolved_findings.is_empty());
+    }
+
+    #[test]
+    fn reports_improvements_and_missing_modules() {
+        let old = audit(0, 5, vec![]);
+        let mut new = audit(1, 5, vec![]);
+        new.uas = None;
+
+        let diff = diff_audits(&old, &new);
+        assert_eq!(diff.changes[0].description, "UAC");
+        assert_eq!(diff.changes[0].kind, DriftKind::Improved);
+        assert_eq!(diff.changes[1].after, "Not scanned");
+        assert!(diff.resolved_findings.iter().any(|finding| finding.id == "UAC-001"));
+    }
+
+    #[test]
+    fn ranks_prompt_levels_by_security() {
+        assert_eq!(prompt_change(5, 2), (DriftKind::Improved, "Prompt level raised".to_string()));
+        assert_eq!(prompt_change(2, 5), (DriftKind::Regressed, "Prompt level lowered".to_string()));
+        assert_eq!(prompt_change(1, 0), (DriftKind::Regressed, "Prompt level lowered".to_string()));
+        assert_eq!(prompt_change(5, 9).0, DriftKind::Changed);
+    }
+
+    #[test]
+    fn reports_new_security_updates_in_any_language() {
+        let updates = |records| run_audit_with(&FixtureSource::new().with_users(vec![]).with_updates(records));
+        let old = updates(vec![]);
+        let new = updates(vec![
+            update("Kumulatives Update", "Sicherheitsupdates", SECURITY_UPDATES_GUID),
+            update("Hilfsprogramm", "Tools", "28bc880e-0592-4cbf-8f95-c79b17911d5f")
+        ]);
+
+        let diff = diff_audits(&old, &new);
+        let pending: Vec<&Drift> = diff.changes.iter().filter(|change| change.description.starts_with("New pending update")).collect();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].description, "New pending update: Kumulatives Update");
+        assert_eq!(pending[0].kind, DriftKind::Regressed);
+    }
+
+    #[test]
+    fn identical_audits_have_no_drift() {
+        let diff = diff_audits(&audit(1, 5, vec![]), &audit(1, 5, vec![]));
+        assert!(diff.changes.is_empty());
+        assert!(diff.new_findings.is_empty());
+    }
+}
@@ -0,0 +1,67 @@
+use serde_json::Value;
+
+use super::compare::{DiffReport, Drift, DriftKind};
+use crate::common::finding::Finding;
+
+/// Display for the Drift Module
+pub fn display_diff(diff: &DiffReport, verbose: bool) {
+    println!();
+    println!("AUDIT DRIFT REPORT");
+    println!("{}", "=".repeat(30));
+
+    display_summary(diff);
+
+    if diff.changes.is_empty() && diff.new_findings.is_empty() && diff.resolved_findings.is_empty() {
+        println!(" - No configuration drift detected");
+        println!();
+        return;
+    }
+
+    display_changes(diff, DriftKind::Regressed, "Regressions:");
+    display_changes(diff, DriftKind::Improved, "Improvements:");
+    if verbose {display_changes(diff, DriftKind::Changed, "Other Changes:");}
+
+    display_finding_list(&diff.new_findings, "New Findings:");
+    display_finding_list(&diff.resolved_findings, "Resolved Findings:");
+}
+
+/// JSON for the Drift Module
+pub fn json_diff(diff: &DiffReport) -> Value {
+    serde_json::to_value(diff).unwrap_or(Value::Null)
+}
+
+fn display_summary(diff: &DiffReport) {
+    let delta = diff.score_after as i64 - diff.score_before as i64;
+
+    println!("Summary:");
+    println!(" - Security Score: {} -> {} ({:+})", diff.score_before, diff.score_after, delta);
+    println!(" - Regressions: {}", diff.count(DriftKind::Regressed));
+    println!(" - Improvements: {}", diff.count(DriftKind::Improved));
+    println!(" - Other Changes: {}", diff.count(DriftKind::Changed));
+    println!();
+}
+
+fn display_changes(diff: &DiffReport, kind: DriftKind, header: &str) {
+    let changes: Vec<&Drift> = diff.changes.iter().filter(|change| change.kind == kind).collect();
+    if changes.is_empty() {
+        return;
+    }
+    println!("{}", header);
+    for (i, change) in changes.iter().enumerate() {
+        println!("{}. [{}] {}", i + 1, change.module, change.description);
+        println!("   - Before: {}", change.before);
+        println!("   - After: {}", change.after);
+        println!();
+    }
+}
+
+fn display_finding_list(findings: &[Finding], header: &str) {
+    if findings.is_empty() {
+        return;
+    }
+    println!("{}", header);
+    for finding in findings {
+        println!(" - [{}] {} ({})", finding.severity, finding.title, finding.id);
+    }
+    println!();
+}
@@ -0,0 +1,101 @@
+//! Assessment for the Extensions Module, turns each extension's permissions into findings.
+use super::permissions::RiskLevel;
+use super::scanner::{ExtensionInfo, Extension};
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "extensions";
+
+/// Assessing browser extensions
+pub fn assess_extensions(info: &ExtensionInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for extension in info.extensions.iter() {
+        // A disabled extension can't use its permissions until someone turns it back on
+        if extension.enabled == Some(false) {
+            continue;
+        }
+        let risks = extension.risks();
+        let (id, severity) = match extension.risk_level() {
+            RiskLevel::High => ("EXT-001", Severity::Medium),
+            RiskLevel::Medium => ("EXT-002", Severity::Low),
+            RiskLevel::Low | RiskLevel::None => continue
+        };
+        let reasons: Vec<&str> = risks.iter().filter(|risk| risk.level >= RiskLevel::Medium).map(|risk| risk.reason).collect();
+        findings.push(
+            Finding::new(id, MODULE, severity, format!("The {} extension has {} permissions", extension.name, if id == "EXT-001" {"high-risk"} else {"sensitive"}))
+                .explanation(format!(
+                    "{}. Extensions update themselves, so one that's sold or compromised gets this access without asking again",
+                    reasons.join(". ")
+                ))
+                .remediation("Remove the extension if it isn't needed, or make sure it comes from a trusted publisher. Managed machines can allow-list approved extensions and block the rest")
+                .evidence(format!("id={}", extension.id))
+                .evidence(format!("location={}", extension.location()))
+                .evidence(format!("permissions={}", risky_permissions(extension)))
+                .evidence(format!("score={}", extension.risk_score()))
+        );
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        findings.push(
+            Finding::new("EXT-003", MODULE, Severity::Info, "Some extension files couldn't be read")
+                .explanation("Shugo couldn't read every profile or manifest, so some extensions may be missing from the list")
+                .remediation("Close the browser and run Shugo as the user who owns the profile, or point `--profile` at a copy of it")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+    }
+
+    findings
+}
+
+fn risky_permissions(extension: &Extension) -> String {
+    extension.risks().iter().map(|risk| risk.permission.clone()).collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extensions::scanner::ModuleInfo;
+
+    fn extension(name: &str, permissions: &[&str], enabled: Option<bool>) -> Extension {
+        Extension {
+            id: format!("{}@example.com", name.to_lowercase()),
+            name: name.to_string(),
+            version: "1.0".to_string(),
+            browser: None,
+            profile: "Default".to_string(),
+            enabled,
+            permissions: permissions.iter().map(|permission| permission.to_string()).collect(),
+            hosts: vec![],
+            optional_permissions: vec![]
+        }
+    }
+
+    fn info(extensions: Vec<Extension>) -> ExtensionInfo {
+        ExtensionInfo { profiles: vec![], extensions, module_info: ModuleInfo { files: vec![], unreadable: Default::default() } }
+    }
+
+    #[test]
+    fn scores_extensions_by_their_worst_permission() {
+        let findings = assess_extensions(&info(vec![
+            extension("Bridge", &["nativeMessaging", "storage"], Some(true)),
+            extension("Cookies", &["cookies"], None),
+            extension("Tabs", &["tabs"], None)
+        ]));
+        let ids: Vec<(&str, Severity)> = findings.iter().map(|finding| (finding.id.as_str(), finding.severity)).collect();
+        assert_eq!(ids, [("EXT-001", Severity::Medium), ("EXT-002", Severity::Low)]);
+        assert!(findings[0].explanation.contains("outside the browser's sandbox"));
+    }
+
+    #[test]
+    fn optional_permissions_are_not_scored() {
+        let mut asks = extension("Asks", &["storage"], Some(true));
+        asks.optional_permissions = vec!["debugger".to_string(), "<all_urls>".to_string()];
+        assert_eq!(asks.risk_score(), 0);
+        assert!(assess_extensions(&info(vec![asks])).is_empty());
+    }
+
+    #[test]
+    fn disabled_extensions_are_skipped() {
+        assert!(assess_extensions(&info(vec![extension("Off", &["debugger"], Some(false))])).is_empty());
+    }
+}
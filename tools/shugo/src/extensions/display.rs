@@ -0,0 +1,108 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_extensions;
+use super::permissions::RiskLevel;
+use super::scanner::{ExtensionInfo, Extension};
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::get_time;
+
+/// Display for Extensions Module
+pub fn display_extensions(info: &ExtensionInfo, verbose: bool) {
+    println!();
+    println!("BROWSER EXTENSIONS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_extension_list(info, verbose);
+
+    display_findings(&assess_extensions(info), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for Extensions Module
+pub fn json_extensions(info: &ExtensionInfo) -> Value {
+    let extensions: Vec<Value> = by_risk(info).into_iter().map(|extension| json!({
+        "id": extension.id,
+        "name": extension.name,
+        "location": extension.location(),
+        "risk_level": extension.risk_level(),
+        "risk_score": extension.risk_score(),
+        "risks": extension.risks(),
+        "optional_permissions": extension.optional_permissions
+    })).collect();
+    let decoded = json!({ "extensions": extensions });
+
+    json!({
+        "extensions": with_decoded(info, decoded),
+        "findings": assess_extensions(info)
+    })
+}
+
+/// Extensions with the highest risk score first
+fn by_risk(info: &ExtensionInfo) -> Vec<&Extension> {
+    let mut extensions: Vec<&Extension> = info.extensions.iter().collect();
+    extensions.sort_by(|left, right| right.risk_score().cmp(&left.risk_score()).then_with(|| left.name.cmp(&right.name)));
+    extensions
+}
+
+fn display_scan_details(info: &ExtensionInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Profiles:");
+    for profile in info.profiles.iter() {
+        println!("   - {} ({}): {}", profile.browser_name(), profile.name, profile.path.display());
+    }
+    println!(" - Files:");
+    for file in info.module_info.files.iter() {
+        println!("   - {}", file);
+    }
+    for (value, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", value, reason);
+    }
+    println!();
+}
+
+fn display_extension_list(info: &ExtensionInfo, verbose: bool) {
+    println!("Extensions:");
+    if info.profiles.is_empty() {
+        println!(" - No browser profiles found");
+    } else if info.extensions.is_empty() {
+        println!(" - No extensions installed in {} profile(s)", info.profiles.len());
+    }
+    for extension in by_risk(info) {
+        let disabled = if extension.enabled == Some(false) {", disabled"} else {""};
+        println!(" - {} {} ({}{})", extension.name, extension.version, extension.location(), disabled);
+        println!("   - Risk: {} (score {})", risk_text(extension.risk_level()), extension.risk_score());
+        for risk in extension.risks().iter().filter(|risk| verbose || risk.level >= RiskLevel::Medium) {
+            println!("     - {}: {}", risk.permission, risk.reason);
+        }
+        if verbose && !extension.optional_permissions.is_empty() {
+            println!("   - Can ask for (not scored): {}", extension.optional_permissions.join(", "));
+        }
+        if verbose {
+            println!("   - ID: {}", extension.id);
+        }
+    }
+    println!();
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - Chromium: <profile>\\Extensions\\<id>\\<version>\\manifest.json");
+    println!(" - Firefox: <profile>\\extensions.json");
+    println!(" - Risk Score: High = 5, Medium = 3, Low = 1 per permission, optional permissions aren't counted");
+    println!();
+}
+
+fn risk_text(level: RiskLevel) -> &'static str {
+    match level {
+        RiskLevel::High => "High",
+        RiskLevel::Medium => "Medium",
+        RiskLevel::Low => "Low",
+        RiskLevel::None => "None"
+    }
+}
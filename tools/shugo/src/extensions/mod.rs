@@ -0,0 +1,4 @@
+pub mod scanner;
+pub mod permissions;
+pub mod assessment;
+pub mod display;
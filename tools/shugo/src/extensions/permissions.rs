@@ -0,0 +1,130 @@
+//! How risky each extension permission is. Chromium and Firefox share the WebExtensions permission names,
+//! so one table covers both. Plain Rust with no file or Windows calls, so it's tested on any OS.
+use serde::{Deserialize, Serialize};
+
+/// How much an extension could do with a permission if it turned malicious
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    None,
+    Low,
+    Medium,
+    High
+}
+
+/// One permission and why it's risky
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PermissionRisk {
+    pub permission: String,
+    pub level: RiskLevel,
+    pub reason: &'static str
+}
+
+impl RiskLevel {
+    /// Points toward an extension's risk score
+    pub fn weight(&self) -> u32 {
+        match self {
+            RiskLevel::None => 0,
+            RiskLevel::Low => 1,
+            RiskLevel::Medium => 3,
+            RiskLevel::High => 5
+        }
+    }
+}
+
+/// The risk of one API permission or host pattern, `None` for permissions that can't hurt the user
+pub fn permission_risk(permission: &str) -> Option<PermissionRisk> {
+    /*
+        Shugo: Extension Permissions
+
+        An extension has to list what it wants in its manifest, and the browser shows a warning for the
+        dangerous ones at install time. Once it's installed, updates can quietly change the code behind
+        those permissions, which is how popular extensions get sold and turned into malware.
+
+        The ones worth watching:
+
+        - Host access to every site (`<all_urls>`, or `*` as the host): read and change any page, including what's typed
+          into login forms
+        - debugger: the same access as the browser's developer tools, on any tab
+        - nativeMessaging: talk to a program installed on the computer, outside the browser's sandbox
+        - webRequestBlocking / proxy: rewrite or reroute the user's traffic
+        - cookies: read session cookies, which are as good as a password for most sites
+
+        For more information on permission warnings:
+        (https://developer.chrome.com/docs/extensions/develop/concepts/permission-warnings)
+    */
+    let risk = |level: RiskLevel, reason: &'static str| Some(PermissionRisk { permission: permission.to_string(), level, reason });
+    if is_host_pattern(permission) {
+        return if all_sites(permission) {
+            risk(RiskLevel::High, "Can read and change everything on every website, including passwords typed into pages")
+        } else {
+            risk(RiskLevel::Low, "Can read and change everything on some websites")
+        };
+    }
+    match permission {
+        "debugger" => risk(RiskLevel::High, "Can attach the browser's debugger to any tab and read or change anything in it"),
+        "nativeMessaging" => risk(RiskLevel::High, "Can start and talk to programs installed on the computer, outside the browser's sandbox"),
+        "webRequestBlocking" => risk(RiskLevel::High, "Can block or rewrite any request the browser sends"),
+        "proxy" => risk(RiskLevel::High, "Can send all browsing through a server of its choosing"),
+        "webRequest" => risk(RiskLevel::Medium, "Can see every request the browser sends"),
+        "cookies" => risk(RiskLevel::Medium, "Can read session cookies, which sign in as the user without a password"),
+        "management" => risk(RiskLevel::Medium, "Can turn other extensions off, including security ones"),
+        "privacy" => risk(RiskLevel::Medium, "Can change privacy settings like Safe Browsing"),
+        "history" => risk(RiskLevel::Medium, "Can read the whole browsing history"),
+        "clipboardRead" => risk(RiskLevel::Medium, "Can read whatever is copied, including passwords"),
+        "desktopCapture" | "tabCapture" => risk(RiskLevel::Medium, "Can record the screen or a tab"),
+        "declarativeNetRequestWithHostAccess" => risk(RiskLevel::Medium, "Can redirect requests on the sites it has access to"),
+        "tabs" => risk(RiskLevel::Low, "Can see the address and title of every open tab"),
+        "scripting" => risk(RiskLevel::Low, "Can run code in pages it has host access to"),
+        "downloads" => risk(RiskLevel::Low, "Can start downloads and open downloaded files"),
+        "webNavigation" => risk(RiskLevel::Low, "Can see every page the user navigates to"),
+        "geolocation" => risk(RiskLevel::Low, "Can read the computer's location"),
+        "topSites" | "bookmarks" => risk(RiskLevel::Low, "Can read the most visited sites or bookmarks"),
+        _ => None
+    }
+}
+
+/// Every risky permission from a list, most dangerous first
+pub fn assess_permissions<'a>(permissions: impl IntoIterator<Item = &'a String>) -> Vec<PermissionRisk> {
+    let mut risks: Vec<PermissionRisk> = permissions.into_iter().filter_map(|permission| permission_risk(permission)).collect();
+    risks.sort_by(|left, right| right.level.cmp(&left.level).then_with(|| left.permission.cmp(&right.permission)));
+    risks.dedup_by(|left, right| left.permission == right.permission);
+    risks
+}
+
+/// Match patterns look like `scheme://host/path`, `<all_urls>` covers everything
+fn is_host_pattern(permission: &str) -> bool {
+    permission == "<all_urls>" || permission.contains("://")
+}
+
+/// Whether a match pattern covers every site, like `*://*/*` or `https://*/*`
+fn all_sites(pattern: &str) -> bool {
+    if pattern == "<all_urls>" {
+        return true;
+    }
+    let Some((_, rest)) = pattern.split_once("://") else {return false};
+    let host = rest.split('/').next().unwrap_or("");
+    host == "*" || host.starts_with("*:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_host_patterns() {
+        for pattern in ["<all_urls>", "*://*/*", "https://*/*", "http://*:8080/*"] {
+            assert_eq!(permission_risk(pattern).unwrap().level, RiskLevel::High, "{}", pattern);
+        }
+        assert_eq!(permission_risk("https://*.example.com/*").unwrap().level, RiskLevel::Low);
+        assert!(permission_risk("storage").is_none());
+    }
+
+    #[test]
+    fn sorts_the_most_dangerous_first() {
+        let permissions: Vec<String> = ["tabs", "storage", "nativeMessaging", "cookies", "tabs"].iter().map(|permission| permission.to_string()).collect();
+        let risks = assess_permissions(&permissions);
+        let names: Vec<&str> = risks.iter().map(|risk| risk.permission.as_str()).collect();
+        assert_eq!(names, ["nativeMessaging", "cookies", "tabs"]);
+    }
+}
@@ -0,0 +1,420 @@
+//! This is the Extensions Module for Shugo. Here we can see:
+//!
+//! - Every Extension in each Chrome, Edge, Brave, and Firefox Profile
+//! - The Permissions and Sites each Extension asked for
+//! - A Risk Level and Score from those Permissions
+//!
+//! Everything comes from files in the profile folders, so it works the same on a live machine and on
+//! profile folders copied from another one. No registry or WMI is involved.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::permissions::{assess_permissions, PermissionRisk, RiskLevel};
+use crate::browser::prefs::parse_json;
+use crate::browser::scanner::{BrowserKind, BrowserPaths, profile_folders, folders, read_file};
+use crate::browser::versions::{parse_version, compare_versions};
+use crate::common::source::SourceResult;
+
+/// How a profile stores its extensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileFormat {
+    /// Chrome, Edge, and Brave: `Extensions\<id>\<version>\manifest.json`
+    Chromium,
+    /// Firefox: `extensions.json`
+    Firefox
+}
+
+/// One profile folder to read extensions from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileLocation {
+    /// `None` for a copied profile, where only the format is known
+    pub browser: Option<BrowserKind>,
+    pub format: ProfileFormat,
+    pub name: String,
+    pub path: PathBuf
+}
+
+/// One installed extension
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Extension {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub browser: Option<BrowserKind>,
+    pub profile: String,
+    /// `None` when the profile doesn't say, Chromium keeps this elsewhere
+    pub enabled: Option<bool>,
+    /// API permissions like `tabs` and `cookies`
+    pub permissions: Vec<String>,
+    /// Sites the extension can read and change, from host permissions and content scripts
+    pub hosts: Vec<String>,
+    /// Permissions and sites the extension can ask for while it runs. The user has to agree first, so they
+    /// aren't part of the risk score
+    #[serde(default)]
+    pub optional_permissions: Vec<String>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExtensionInfo {
+    pub profiles: Vec<ProfileLocation>,
+    pub extensions: Vec<Extension>,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    /// Files read to find the extensions
+    pub files: Vec<String>,
+    /// Files that couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+impl fmt::Display for ProfileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProfileFormat::Chromium => "Chromium",
+            ProfileFormat::Firefox => "Firefox"
+        })
+    }
+}
+
+impl ProfileLocation {
+    /// Works out a copied profile's format from the files in it, `None` when it doesn't look like a profile
+    pub fn detect(path: &Path) -> Option<ProfileLocation> {
+        let format = if path.join("extensions.json").is_file() || path.join("prefs.js").is_file() {
+            ProfileFormat::Firefox
+        } else if path.join("Extensions").is_dir() || path.join("Preferences").is_file() {
+            ProfileFormat::Chromium
+        } else {
+            return None;
+        };
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| path.display().to_string());
+        Some(ProfileLocation { browser: None, format, name, path: path.to_path_buf() })
+    }
+
+    /// The browser name, or the format for a copied profile
+    pub fn browser_name(&self) -> String {
+        match self.browser {
+            Some(browser) => browser.to_string(),
+            None => format!("{} profile", self.format)
+        }
+    }
+}
+
+/// Every browser profile of the user Shugo is running as
+pub fn local_profiles(paths: &BrowserPaths, unreadable: &mut BTreeMap<String, String>) -> Vec<ProfileLocation> {
+    let mut profiles = Vec::new();
+    for kind in BrowserKind::ALL {
+        for (name, path) in profile_folders(kind, paths, unreadable) {
+            let format = if kind.is_chromium() {ProfileFormat::Chromium} else {ProfileFormat::Firefox};
+            profiles.push(ProfileLocation { browser: Some(kind), format, name, path });
+        }
+    }
+    profiles
+}
+
+/// Scanning the extensions in this user's browser profiles
+pub fn scan_extensions() -> SourceResult<ExtensionInfo> {
+    let mut unreadable = BTreeMap::new();
+    let profiles = local_profiles(&BrowserPaths::from_env(), &mut unreadable);
+    let mut info = scan_extensions_with(&profiles)?;
+    info.module_info.unreadable.extend(unreadable);
+    Ok(info)
+}
+
+/// Scanning the extensions in any list of profile folders, like ones copied from another machine
+pub fn scan_extensions_with(profiles: &[ProfileLocation]) -> SourceResult<ExtensionInfo> {
+    let mut files: Vec<String> = Vec::new();
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+    let mut extensions = Vec::new();
+
+    for profile in profiles.iter() {
+        match profile.format {
+            ProfileFormat::Chromium => extensions.extend(chromium_extensions(profile, &mut files, &mut unreadable)),
+            ProfileFormat::Firefox => extensions.extend(firefox_extensions(profile, &mut files, &mut unreadable))
+        }
+    }
+
+    Ok(ExtensionInfo {
+        profiles: profiles.to_vec(),
+        extensions,
+        module_info: ModuleInfo { files, unreadable }
+    })
+}
+
+fn chromium_extensions(profile: &ProfileLocation, files: &mut Vec<String>, unreadable: &mut BTreeMap<String, String>) -> Vec<Extension> {
+    /*
+        Shugo: Chromium Extension Folders
+
+        Chrome, Edge, and Brave unpack every extension from their store into the profile:
+
+        <profile>\Extensions\<id>\<version>\manifest.json
+
+        The ID is 32 letters from a to p. An update unpacks into a new version folder, and the old one can stay
+        until the browser restarts, so we read the newest.
+
+        manifest.json lists what the extension asked for:
+        - permissions: API permissions, and in Manifest V2 host patterns as well
+        - optional_permissions and optional_host_permissions: asked for later, while the extension is running.
+          The browser prompts the user first, so these are listed but not scored
+        - host_permissions: the sites it can access (Manifest V3)
+        - content_scripts[].matches: the sites its scripts run in
+
+        Names starting with `__MSG_` are translated, the real text is in `_locales\<default_locale>\messages.json`.
+
+        For more information on the manifest:
+        (https://developer.chrome.com/docs/extensions/reference/manifest)
+    */
+    let mut extensions = Vec::new();
+    for (id, folder) in folders(&profile.path.join("Extensions"), unreadable) {
+        if id.len() != 32 || !id.chars().all(|c| ('a'..='p').contains(&c)) {
+            continue;
+        }
+        let newest = folders(&folder, unreadable)
+            .into_iter()
+            // Version folders have the install count after an underscore, like 1.2.3_0
+            .filter_map(|(name, path)| parse_version(name.split('_').next().unwrap_or("")).map(|parts| (parts, path)))
+            .max_by(|(left, _), (right, _)| compare_versions(left, right));
+        let Some((_, version_folder)) = newest else {continue};
+
+        let manifest_path = version_folder.join("manifest.json");
+        files.push(manifest_path.display().to_string());
+        let Some(text) = read_file(&manifest_path, unreadable) else {continue};
+        match parse_chromium_manifest(&id, &text) {
+            Ok(mut extension) => {
+                if let Some(key) = message_key(&extension.name).map(str::to_string) {
+                    let locale = parse_json(&text, "manifest.json").ok()
+                        .and_then(|manifest| manifest.get("default_locale").and_then(Value::as_str).map(str::to_string))
+                        .unwrap_or_else(|| "en".to_string());
+                    let messages_path = version_folder.join("_locales").join(locale).join("messages.json");
+                    if let Some(name) = read_file(&messages_path, unreadable).and_then(|messages| localized_message(&messages, &key)) {
+                        extension.name = name;
+                    }
+                }
+                extension.browser = profile.browser;
+                extension.profile = profile.name.clone();
+                extensions.push(extension);
+            },
+            Err(error) => {unreadable.insert(manifest_path.display().to_string(), error.detail());}
+        }
+    }
+    extensions
+}
+
+fn firefox_extensions(profile: &ProfileLocation, files: &mut Vec<String>, unreadable: &mut BTreeMap<String, String>) -> Vec<Extension> {
+    let path = profile.path.join("extensions.json");
+    files.push(path.display().to_string());
+    let Some(text) = read_file(&path, unreadable) else {return Vec::new()};
+    match parse_firefox_extensions(&text) {
+        Ok(extensions) => extensions
+            .into_iter()
+            .map(|extension| Extension { browser: profile.browser, profile: profile.name.clone(), ..extension })
+            .collect(),
+        Err(error) => {
+            unreadable.insert(path.display().to_string(), error.detail());
+            Vec::new()
+        }
+    }
+}
+
+/// Reads one Chromium `manifest.json`, the browser and profile are filled in by the caller
+pub fn parse_chromium_manifest(id: &str, text: &str) -> SourceResult<Extension> {
+    let manifest = parse_json(text, "manifest.json")?;
+    let strings = |value: Option<&Value>| -> Vec<String> {
+        value.and_then(Value::as_array).map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect()).unwrap_or_default()
+    };
+
+    // Manifest V2 mixes host patterns into `permissions`, so they're split back out
+    let mut permissions = Vec::new();
+    let mut hosts = strings(manifest.get("host_permissions"));
+    for permission in strings(manifest.get("permissions")) {
+        if permission == "<all_urls>" || permission.contains("://") {hosts.push(permission)} else {permissions.push(permission)}
+    }
+    for script in manifest.get("content_scripts").and_then(Value::as_array).into_iter().flatten() {
+        hosts.extend(strings(script.get("matches")));
+    }
+    let mut optional_permissions = strings(manifest.get("optional_permissions"));
+    optional_permissions.extend(strings(manifest.get("optional_host_permissions")));
+    permissions.sort();
+    permissions.dedup();
+    hosts.sort();
+    hosts.dedup();
+    optional_permissions.sort();
+    optional_permissions.dedup();
+
+    Ok(Extension {
+        id: id.to_string(),
+        name: manifest.get("name").and_then(Value::as_str).unwrap_or(id).to_string(),
+        version: manifest.get("version").and_then(Value::as_str).unwrap_or("unknown").to_string(),
+        browser: None,
+        profile: String::new(),
+        enabled: None,
+        permissions,
+        hosts,
+        optional_permissions
+    })
+}
+
+/// Reads Firefox's `extensions.json`, skipping themes, dictionaries, and the add-ons built into Firefox
+pub fn parse_firefox_extensions(text: &str) -> SourceResult<Vec<Extension>> {
+    /*
+        Shugo: Firefox extensions.json
+
+        Firefox keeps one `extensions.json` per profile describing every add-on it knows about. Each entry in
+        `addons` has the ID, version, `active`, and `userPermissions` with the `permissions` and `origins` (sites)
+        the user agreed to, and `optionalPermissions` with the ones it can still ask for. `location` says where it came from: `app-profile` is installed by the user, while
+        `app-builtin` and `app-system-defaults` ship with Firefox itself.
+    */
+    let document = parse_json(text, "extensions.json")?;
+    let strings = |value: Option<&Value>| -> Vec<String> {
+        value.and_then(Value::as_array).map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect()).unwrap_or_default()
+    };
+
+    let mut extensions = Vec::new();
+    for addon in document.get("addons").and_then(Value::as_array).into_iter().flatten() {
+        let field = |name: &str| addon.get(name).and_then(Value::as_str);
+        if field("type") != Some("extension") || matches!(field("location"), Some("app-builtin" | "app-system-defaults" | "app-system-addons")) {
+            continue;
+        }
+        let Some(id) = field("id") else {continue};
+        let permissions = addon.get("userPermissions");
+        let optional = addon.get("optionalPermissions");
+        extensions.push(Extension {
+            id: id.to_string(),
+            name: addon.pointer("/defaultLocale/name").and_then(Value::as_str).unwrap_or(id).to_string(),
+            version: field("version").unwrap_or("unknown").to_string(),
+            browser: None,
+            profile: String::new(),
+            enabled: addon.get("active").and_then(Value::as_bool),
+            permissions: strings(permissions.and_then(|permissions| permissions.get("permissions"))),
+            hosts: strings(permissions.and_then(|permissions| permissions.get("origins"))),
+            optional_permissions: strings(optional.and_then(|optional| optional.get("permissions")))
+                .into_iter()
+                .chain(strings(optional.and_then(|optional| optional.get("origins"))))
+                .collect()
+        });
+    }
+    Ok(extensions)
+}
+
+/// The message name in `__MSG_appName__`, `None` for a plain name
+fn message_key(name: &str) -> Option<&str> {
+    name.strip_prefix("__MSG_")?.strip_suffix("__")
+}
+
+/// Looks up a message from a `messages.json`, ignoring case like Chromium does
+pub fn localized_message(messages: &str, key: &str) -> Option<String> {
+    let messages = parse_json(messages, "messages.json").ok()?;
+    let (_, entry) = messages.as_object()?.iter().find(|(name, _)| name.eq_ignore_ascii_case(key))?;
+    entry.get("message").and_then(Value::as_str).map(str::to_string)
+}
+
+impl Extension {
+    /// Every risky permission and site, most dangerous first
+    pub fn risks(&self) -> Vec<PermissionRisk> {
+        assess_permissions(self.permissions.iter().chain(self.hosts.iter()))
+    }
+
+    pub fn risk_level(&self) -> RiskLevel {
+        self.risks().first().map(|risk| risk.level).unwrap_or(RiskLevel::None)
+    }
+
+    /// The sum of every permission's weight, for sorting extensions by how much they can do
+    pub fn risk_score(&self) -> u32 {
+        self.risks().iter().map(|risk| risk.level.weight()).sum()
+    }
+
+    /// "Google Chrome (Default)", or "Copied profile (Default)" when the browser isn't known
+    pub fn location(&self) -> String {
+        let browser = self.browser.map(|browser| browser.to_string()).unwrap_or_else(|| "Copied profile".to_string());
+        format!("{} ({})", browser, self.profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use crate::common::path::join;
+
+    const MANIFEST_V3: &str = r#"{
+        "manifest_version": 3,
+        "name": "__MSG_appName__",
+        "default_locale": "en_US",
+        "version": "2.4.1",
+        "permissions": ["storage", "cookies", "nativeMessaging"],
+        "optional_permissions": ["history", "debugger"],
+        "host_permissions": ["<all_urls>"],
+        "content_scripts": [{"matches": ["https://*.example.com/*"], "js": ["content.js"]}]
+    }"#;
+
+    const EXTENSIONS_JSON: &str = r#"{"schemaVersion": 36, "addons": [
+        {"id": "uBlock0@raymondhill.net", "version": "1.60.0", "type": "extension", "location": "app-profile", "active": true,
+         "defaultLocale": {"name": "uBlock Origin"},
+         "userPermissions": {"permissions": ["dns", "webRequest", "webRequestBlocking"], "origins": ["<all_urls>"]},
+         "optionalPermissions": {"permissions": ["tabs"], "origins": []}},
+        {"id": "formautofill@mozilla.org", "version": "1.0.1", "type": "extension", "location": "app-builtin", "active": true},
+        {"id": "default-theme@mozilla.org", "version": "1.3", "type": "theme", "location": "app-builtin", "active": true}
+    ]}"#;
+
+    #[test]
+    fn reads_chromium_manifests() {
+        let extension = parse_chromium_manifest("aaaabbbbccccddddeeeeffffgggghhhh", MANIFEST_V3).unwrap();
+        assert_eq!(extension.permissions, ["cookies", "nativeMessaging", "storage"]);
+        assert_eq!(extension.hosts, ["<all_urls>", "https://*.example.com/*"]);
+        assert_eq!(extension.optional_permissions, ["debugger", "history"]);
+        assert_eq!(extension.risk_level(), RiskLevel::High);
+        assert_eq!(message_key(&extension.name), Some("appName"));
+        assert_eq!(localized_message(r#"{"AppName": {"message": "Sample Helper"}}"#, "appName").as_deref(), Some("Sample Helper"));
+    }
+
+    #[test]
+    fn reads_firefox_extensions_json() {
+        let extensions = parse_firefox_extensions(EXTENSIONS_JSON).unwrap();
+        assert_eq!(extensions.len(), 1);
+        assert_eq!(extensions[0].name, "uBlock Origin");
+        assert_eq!(extensions[0].enabled, Some(true));
+        assert_eq!(extensions[0].risk_score(), 5 + 5 + 3);
+        assert_eq!(extensions[0].optional_permissions, ["tabs"]);
+    }
+
+    #[test]
+    fn scans_copied_profile_folders() {
+        let root = env::temp_dir().join(format!("shugo-extensions-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let write = |path: &str, text: &str| {
+            let path = join(&root, path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        };
+        let id = "aaaabbbbccccddddeeeeffffgggghhhh";
+        write(&format!("chrome\\Default\\Extensions\\{}\\2.4.0_0\\manifest.json", id), r#"{"name": "Old", "version": "2.4.0"}"#);
+        write(&format!("chrome\\Default\\Extensions\\{}\\2.4.1_0\\manifest.json", id), MANIFEST_V3);
+        write(&format!("chrome\\Default\\Extensions\\{}\\2.4.1_0\\_locales\\en_US\\messages.json", id), r#"{"appName": {"message": "Sample Helper"}}"#);
+        write("chrome\\Default\\Extensions\\Temp\\manifest.json", "{}");
+        write("firefox\\abcd.default-release\\extensions.json", EXTENSIONS_JSON);
+        write("firefox\\abcd.default-release\\prefs.js", "");
+
+        let profiles: Vec<ProfileLocation> = ["chrome\\Default", "firefox\\abcd.default-release", "empty"]
+            .iter()
+            .filter_map(|path| ProfileLocation::detect(&join(&root, path)))
+            .collect();
+        let info = scan_extensions_with(&profiles).unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].format, ProfileFormat::Chromium);
+        let names: Vec<&str> = info.extensions.iter().map(|extension| extension.name.as_str()).collect();
+        assert_eq!(names, ["Sample Helper", "uBlock Origin"]);
+        assert_eq!(info.extensions[0].version, "2.4.1");
+        assert_eq!(info.extensions[0].profile, "Default");
+        assert!(info.module_info.unreadable.is_empty(), "{:?}", info.module_info.unreadable);
+    }
+}
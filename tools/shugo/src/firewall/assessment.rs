@@ -0,0 +1,109 @@
+//! Assessment for the Firewall Module, turns the profile states and third-party products into findings.
+use super::scanner::{FirewallProductInfo, FirewallSummary};
+use crate::common::finding::{Finding, Severity};
+use crate::common::product_state::ScannerState;
+
+const MODULE: &str = "firewall";
+
+/// A third-party firewall only counts while its scanner is on, not while it's snoozed or expired
+pub fn third_party_active(product: &FirewallProductInfo) -> bool {
+    product.product_state.scanner == ScannerState::On
+}
+
+/// Assessing Windows Defender Firewall profiles and third-party firewalls
+pub fn assess_firewall(firewall: &FirewallSummary) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let profile = &firewall.profile;
+    let active_third_party: Vec<&FirewallProductInfo> = firewall.products
+        .iter()
+        .filter(|prod| third_party_active(prod))
+        .collect();
+
+    let all_disabled: bool = {
+        !profile.public.profile_enabled &&
+        !profile.private.profile_enabled &&
+        !profile.domain.profile_enabled
+    };
+
+    if all_disabled && active_third_party.is_empty() {
+        findings.push(
+            Finding::new("FW-001", MODULE, Severity::Critical, "No active firewall protection")
+                .explanation("Every Windows Defender Firewall profile is off and no third-party firewall is running, so any listening service is reachable")
+                .remediation("Turn on Windows Defender Firewall for the Public, Private, and Domain profiles")
+                .evidence("public=off private=off domain=off")
+        );
+    } else if all_disabled {
+        let mut finding = Finding::new("FW-002", MODULE, Severity::Info, "Windows Defender Firewall is off, a third-party firewall is active")
+            .explanation("This is normal when another firewall product takes over, as long as that product stays enabled")
+            .remediation("Make sure the third-party firewall is kept up to date and covers every network type");
+        for prod in active_third_party {
+            finding = finding.evidence(prod.name.clone());
+        }
+        findings.push(finding);
+    } else if !profile.public.profile_enabled {
+        findings.push(
+            Finding::new("FW-003", MODULE, Severity::High, "Public profile is disabled")
+                .explanation("The public profile is used on untrusted networks like cafes and airports, where other devices can reach this machine")
+                .remediation("Turn on Windows Defender Firewall for the Public profile")
+                .evidence("public=off")
+        );
+    }
+
+    if profile.public.profile_enabled && !profile.public.inbound_blocked {
+        findings.push(
+            Finding::new("FW-004", MODULE, Severity::Medium, "Public profile allows inbound traffic")
+                .explanation("Unsolicited connections are let in by default on untrusted networks")
+                .remediation("Set the Public profile's default inbound action to Block")
+                .evidence("public.inbound=allow")
+        );
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firewall::scanner::{FirewallProfileDetails, ModuleInfo, WindowsFirewallProfile};
+
+    fn details(profile_enabled: bool, inbound_blocked: bool) -> FirewallProfileDetails {
+        FirewallProfileDetails { profile_enabled, inbound_blocked, outbound_blocked: false, notifications_disabled: false }
+    }
+
+    fn summary(public: FirewallProfileDetails, enabled: bool, products: Vec<FirewallProductInfo>) -> FirewallSummary {
+        FirewallSummary {
+            profile: WindowsFirewallProfile { public, private: details(enabled, true), domain: details(enabled, true) },
+            products,
+            module_info: ModuleInfo { namespace: String::new(), query: String::new() }
+        }
+    }
+
+    #[test]
+    fn flags_no_protection() {
+        let findings = assess_firewall(&summary(details(false, true), false, vec![]));
+        assert_eq!(findings[0].id, "FW-001");
+        assert_eq!(findings[0].severity, Severity::Critical);
+    }
+
+    #[test]
+    fn third_party_firewall_downgrades_disabled_profiles() {
+        let product = FirewallProductInfo::new("Other FW", 0x41000);
+        let findings = assess_firewall(&summary(details(false, true), false, vec![product]));
+        assert_eq!(findings[0].id, "FW-002");
+        assert_eq!(findings[0].severity, Severity::Info);
+    }
+
+    #[test]
+    fn snoozed_third_party_firewall_does_not_count() {
+        let product = FirewallProductInfo::new("Other FW", 0x42000);
+        let findings = assess_firewall(&summary(details(false, true), false, vec![product]));
+        assert_eq!(findings[0].id, "FW-001");
+    }
+
+    #[test]
+    fn flags_open_public_profile() {
+        let findings = assess_firewall(&summary(details(true, false), true, vec![]));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "FW-004");
+    }
+}
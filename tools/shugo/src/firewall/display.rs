@@ -1,8 +1,14 @@
-use super::scanner::{WindowsFirewallProfile, FirewallProfileDetails, FirewallProductInfo, ModuleInfo};
+use serde_json::{Value, json};
+
+use super::assessment::{assess_firewall, third_party_active};
+use super::scanner::{WindowsFirewallProfile, FirewallProfileDetails, FirewallProductInfo, FirewallSummary, ModuleInfo};
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
 use crate::common::time::get_time;
 
-pub fn display_firewalls(firewall: (WindowsFirewallProfile, Vec<FirewallProductInfo>, ModuleInfo), verbose: bool) {
-    let (profile, products, module) = (firewall.0, firewall.1, firewall.2);
+pub fn display_firewalls(firewall: FirewallSummary, verbose: bool) {
+    let findings = assess_firewall(&firewall);
+    let (profile, products, module) = (firewall.profile, firewall.products, firewall.module_info);
 
     println!();
     println!("FIREWALL PROTECTION AUDIT");
@@ -14,11 +20,30 @@ pub fn display_firewalls(firewall: (WindowsFirewallProfile, Vec<FirewallProductI
 
     display_products(&products, verbose);
 
-    display_assessment(&profile, &products);
+    display_findings(&findings, verbose);
 
     if verbose {display_technical();}
 }
 
+/// JSON for Firewall Module
+pub fn json_firewall(firewall: &FirewallSummary) -> Value {
+    let products: Vec<Value> = firewall.products
+        .iter()
+        .map(|prod| with_decoded(prod, json!({
+            "status": third_party_state(prod),
+            "owner": prod.product_state.owner.to_string(),
+            "providers": prod.product_state.providers.names()
+        })))
+        .collect();
+
+    json!({
+        "profile": firewall.profile,
+        "products": products,
+        "module_info": firewall.module_info,
+        "findings": assess_firewall(firewall)
+    })
+}
+
 fn display_scan_details(module: ModuleInfo) {
     println!("Scan Details:"); 
     let (h, m, s) = get_time();
@@ -29,7 +54,7 @@ fn display_scan_details(module: ModuleInfo) {
     println!();
 }
 
-fn display_summary(profile: &WindowsFirewallProfile, products: &Vec<FirewallProductInfo> ,verbose: bool) {
+fn display_summary(profile: &WindowsFirewallProfile, products: &[FirewallProductInfo] ,verbose: bool) {
     println!("Summary:");
     println!(" - Windows Firewall Status:");
     if verbose {println!("   (Blocked = secure, default, Allowed = permissive)");}
@@ -56,7 +81,7 @@ fn display_summary(profile: &WindowsFirewallProfile, products: &Vec<FirewallProd
     println!();
 }
 
-fn display_products(products: &Vec<FirewallProductInfo>, verbose: bool) {
+fn display_products(products: &[FirewallProductInfo], verbose: bool) {
     println!("Third-Party Firewalls:");
     if products.is_empty() {
         println!(" - No Third-Party Firewalls Detected");
@@ -65,8 +90,10 @@ fn display_products(products: &Vec<FirewallProductInfo>, verbose: bool) {
     }
     for (i, prod) in products.iter().enumerate() {
         println!("{}. {}", i + 1, prod.name);
-        println!("   - Status: {}", third_party_state(prod.state));
+        println!("   - Status: {}", third_party_state(prod));
         if verbose {
+            println!("   - Scanner State: {}", prod.product_state.scanner);
+            println!("   - Registered As: {}", prod.product_state.providers);
             println!("   - Product State: {}", prod.state);
             println!("   - Hexadecimal State: 0x{:X}", prod.state);
         }
@@ -74,55 +101,6 @@ fn display_products(products: &Vec<FirewallProductInfo>, verbose: bool) {
     }
 }
 
-fn display_assessment(profile: &WindowsFirewallProfile, products: &Vec<FirewallProductInfo>) {
-    println!("Security Assessment:");
-    println!(" - Windows Defender Firewall:");
-    let all_disabled: bool = {
-        !profile.public.profile_enabled &&
-        !profile.private.profile_enabled &&
-        !profile.domain.profile_enabled
-    };
-
-    if all_disabled {
-        println!("   - All profiles are DISABLED - Critical security risk!");
-    } else if !profile.public.profile_enabled {
-        println!("   - Public profile is DISABLED - Risk on untrusted networks!");
-    } else {
-        println!("   - At least one profile is Enabled");
-    }
-
-    if profile.public.profile_enabled && !profile.public.inbound_blocked {
-        println!("   - Public profile allows inbound traffic - Risky!");
-    }
-
-    println!();
-    println!(" - Third-Party Firewalls:");
-    let active_third_party = products
-        .iter()
-        .filter(
-            |p| {
-                let fw_active: bool = ((p.state >> 12) & 0xF) != 0;
-                fw_active
-            }
-        )
-        .count();
-    
-    if active_third_party == 0 && all_disabled {
-        println!("   - No active firewall protection detected!");
-    } else if active_third_party > 0 {
-        println!("   - {} active third-party firewall(s)", active_third_party);
-        for prod in products {
-            if ((prod.state >> 12) & 0xF) != 0 {
-                println!("     - {}", prod.name);
-            }
-        }
-    } else if active_third_party == 0 && !all_disabled {
-        println!("   - Windows Defender is the active firewall");
-    }
-    println!();
-
-}
-
 fn display_technical() {
     println!("Technical Information:");
     println!(" - COM Apartment: MTA (Multi-threaded)");
@@ -163,11 +141,8 @@ fn firewall_notifications(info: &FirewallProfileDetails) -> String {
     }
 }
 
-fn third_party_state(state: i32) -> String {
-    // Extract bits 12-15 to check if firewall is active
-    let fw_active: bool = ((state >> 12) & 0xF) != 0;
-
-    if fw_active {
+fn third_party_state(prod: &FirewallProductInfo) -> String {
+    if third_party_active(prod) {
         "Active".to_string()
     } else {
         "Inactive".to_string()
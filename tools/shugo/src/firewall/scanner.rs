@@ -1,28 +1,32 @@
 //! This is the Firewall Module for Shugo. We are able to see
-//! 
+//!
 //! - Windows Firewall Profile States (Public, Private, Domain)
 //! - Third-Party Firewall Products
 //! - Firewall Product Status
-//! 
+//! - Firewall Rules (used by other modules, like RDP, to see what's allowed in)
+//!
 //! This module uses TWO different APIs:
 //! 1. Windows Firewall Policy API (INetFwPolicy2) - For Windows Defender Firewall profiles
 //! 2. WMI SecurityCenter2 - For third-party firewall products
-//! 
+//!
+//! Both are called from `tools\shugo\src\common\live.rs`.
+//!
 //! Note: The profile states ONLY reflect Windows Defender Firewall, not third-party firewalls.
-use windows::core::*;
-use windows::Win32::Foundation::*;
-use windows::Win32::System::Com::*;
-use windows::Win32::System::Wmi::*;
-use windows::Win32::NetworkManagement::WindowsFirewall::*;
+use serde::{Deserialize, Serialize};
 
-use crate::common::wmi_helpers::{string_property, integer_property};
+use crate::common::error::ShugoError;
+use crate::common::product_state::ProductState;
+use crate::common::source::{SecuritySource, SourceResult, system_source};
+use crate::common::wmi::{FromWmiObject, WmiObject, query};
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WindowsFirewallProfile {
     pub public: FirewallProfileDetails,
     pub private: FirewallProfileDetails,
     pub domain: FirewallProfileDetails
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FirewallProfileDetails {
     pub profile_enabled: bool,
     pub inbound_blocked: bool,
@@ -31,220 +35,232 @@ pub struct FirewallProfileDetails {
 
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(from = "StoredProduct")]
 pub struct FirewallProductInfo {
     pub name: String,
+    /// The raw `productState`
     pub state: i32,
+    pub product_state: ProductState
 }
 
+/// What a saved snapshot needs to rebuild a `FirewallProductInfo`
+#[derive(Deserialize)]
+struct StoredProduct {
+    name: String,
+    state: i32
+}
+
+impl From<StoredProduct> for FirewallProductInfo {
+    fn from(stored: StoredProduct) -> Self {
+        FirewallProductInfo::new(stored.name, stored.state)
+    }
+}
+
+impl FirewallProductInfo {
+    pub fn new(name: impl Into<String>, state: i32) -> Self {
+        FirewallProductInfo { name: name.into(), state, product_state: ProductState::decode(state) }
+    }
+}
+
+/// One Windows Defender Firewall rule, with only the parts Shugo checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirewallRule {
+    pub name: String,
+    pub enabled: bool,
+    pub inbound: bool,
+    pub allow: bool,
+    /// NET_FW_PROFILE2 bitmask: 1 = Domain, 2 = Private, 4 = Public
+    pub profiles: i32,
+    /// IANA protocol number: 6 = TCP, 17 = UDP, 256 = any
+    pub protocol: i32,
+    /// Like "3389", "5000-5010,3389", or "*"
+    pub local_ports: String,
+    pub application: Option<String>,
+    pub service: Option<String>
+}
+
+pub const PROFILE_DOMAIN: i32 = 1;
+pub const PROFILE_PRIVATE: i32 = 2;
+pub const PROFILE_PUBLIC: i32 = 4;
+pub const PROTOCOL_TCP: i32 = 6;
+pub const PROTOCOL_ANY: i32 = 256;
+
+#[derive(Serialize, Deserialize)]
 pub struct ModuleInfo {
     pub namespace: String,
     pub query: String
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct FirewallSummary {
+    pub profile: WindowsFirewallProfile,
+    pub products: Vec<FirewallProductInfo>,
+    pub module_info: ModuleInfo
+}
+
+pub const NAMESPACE: &str = "ROOT\\SecurityCenter2";
+pub const QUERY: &str = "Select displayName, productState FROM FirewallProduct";
+
 /// Grabing firewall for Windows
-pub fn scan_firewall() -> Result<(WindowsFirewallProfile, Vec<FirewallProductInfo>, ModuleInfo)> {
-    unsafe {
-        /* 
-            Shugo: COM Library Initialization
-
-            Just like in the Antivirus and Update Modules, we need to initialize COM before using 
-            any Windows APIs.
-        */
-        let _com: HRESULT = CoInitializeEx(None, COINIT_MULTITHREADED);
-        match _com { 
-            S_OK => {
-                // COM initialized successfully
-            },
-            E_OUTOFMEMORY => {
-                // Memory problem occured
-                println!("COM initialization failed: Out of memory");
-                return Err(_com.into());
-            },
-            E_INVALIDARG => {
-                // Invalid argument was passed
-                println!("COM initialization failed: Invalid argument");
-                return Err(_com.into());
-            },
-            E_UNEXPECTED => {
-                // Something unexpected happened
-                println!("COM initialization failed: Unexpected error");
-                return Err(_com.into());
-            }
-            _ => {
-                println!("COM initialization failed with HRESULT: 0x{:?}", _com);
-                return Err(_com.into());
-            }
+pub fn scan_firewall() -> SourceResult<FirewallSummary> {
+    scan_firewall_with(system_source()?.as_ref())
+}
+
+/// Grabing firewall from any source
+pub fn scan_firewall_with(source: &dyn SecuritySource) -> SourceResult<FirewallSummary> {
+    /*
+        Shugo: Windows Firewall Profiles
+
+        Windows Firewall has three network profiles (Public, Private, Domain) and each one
+        can be independently enabled or disabled. The source reads them for us through
+        the INetFwPolicy2 interface.
+    */
+    let profile: WindowsFirewallProfile = source.firewall_policy().map_err(|error| error.in_module("firewall"))?;
+
+    /*
+        Shugo: Third-Party Firewall Products via WMI
+
+        Now we'll query WMI's SecurityCenter2 namespace to find third-party firewall products.
+        This logic is nearly identical to the Antivirus Module, we just query "FirewallProduct"
+        instead of "AntiVirusProduct".
+
+        This lets us see products like Norton Firewall, Avast, etc.
+
+        Just like antivirus products, firewall products also use the productState hexadecimal
+        format, so both are decoded by the same `ProductState::decode`. Bits 12-15 tell us if the
+        firewall is running.
+
+        Windows Server doesn't have Security Center, so the namespace isn't there at all. That only means no
+        product registered with it, so a missing namespace leaves the list empty rather than failing the scan.
+    */
+    let products: Vec<FirewallProductInfo> = match query(source, NAMESPACE, QUERY) {
+        Ok(products) => products,
+        Err(ShugoError::NotFound { .. }) => Vec::new(),
+        Err(error) => return Err(error.in_module("firewall"))
+    };
+
+    Ok(FirewallSummary {
+        profile,
+        products,
+        module_info: ModuleInfo {
+            namespace: NAMESPACE.to_string(),
+            query: QUERY.to_string()
         }
-        
-        let firewalls: WindowsFirewallProfile;
-        let mut firewall_products: Vec<FirewallProductInfo> = Vec::new();
-        let module: ModuleInfo;
-
-        {
-            /*
-                Shugo: Windows Firewall Policy Interface
-
-                Unlike the Antivirus and Update modules which use WMI or WUA, we can access 
-                Windows Firewall settings directly through the INetFwPolicy2 interface.
-
-                We use CLSCTX_ALL here because the firewall service may run in a different
-                process, similar to the Windows Update service.
-
-                For more information on `INetFwPolicy2`:
-                (https://learn.microsoft.com/en-us/windows/win32/api/netfw/nn-netfw-inetfwpolicy2) - C++
-            */
-            let policy: INetFwPolicy2 = CoCreateInstance(&NetFwPolicy2, None, CLSCTX_ALL)?;
-
-            /*
-                Shugo: Checking Firewall Profile States
-
-                Windows Firewall has three network profiles:
-                - Public: Used for untrusted networks (coffee shops, airports)
-                - Private: Used for trusted home/work networks
-                - Domain: Used when connected to a corporate domain
-
-                Each profile can be independently enabled or disabled. We check each one using 
-                `get_FirewallEnabled` with the appropriate profile type constant.
-
-                We then check to see the state of inbound and outbound traffic from the profiles
-                using `get_DefaultInboundAction` and `get_DefaultOutboundAction`.
-
-                After that, we see if notifications are disabled using `get_NotificationsDisabled`
-
-                IMPORTANT: These states only reflect Windows Defender Firewall. Third-party 
-                firewalls (Norton, McAfee) are tracked separately through WMI.
-            */
-            let public_details: FirewallProfileDetails = {
-                let profiletype: NET_FW_PROFILE_TYPE2 = NET_FW_PROFILE2_PUBLIC;
-
-                let profile_enabled: bool = policy.get_FirewallEnabled(profiletype)? == VARIANT_TRUE;
-                let inbound_blocked: bool = policy.get_DefaultInboundAction(profiletype)? == NET_FW_ACTION_BLOCK;
-                let outbound_blocked: bool = policy.get_DefaultOutboundAction(profiletype)? == NET_FW_ACTION_BLOCK;
-                let notifications_disabled: bool = policy.get_NotificationsDisabled(profiletype)? == VARIANT_TRUE;
-
-                FirewallProfileDetails {
-                    profile_enabled,
-                    inbound_blocked,
-                    outbound_blocked,
-                    notifications_disabled
-
-                }
-            };
-
-            let private_details: FirewallProfileDetails = {
-                let profiletype: NET_FW_PROFILE_TYPE2 = NET_FW_PROFILE2_PRIVATE;
-
-                let profile_enabled: bool = policy.get_FirewallEnabled(profiletype)? == VARIANT_TRUE;
-                let inbound_blocked: bool = policy.get_DefaultInboundAction(profiletype)? == NET_FW_ACTION_BLOCK;
-                let outbound_blocked: bool = policy.get_DefaultOutboundAction(profiletype)? == NET_FW_ACTION_BLOCK;
-                let notifications_disabled: bool = policy.get_NotificationsDisabled(profiletype)? == VARIANT_TRUE;
-
-                FirewallProfileDetails {
-                    profile_enabled,
-                    inbound_blocked,
-                    outbound_blocked,
-                    notifications_disabled
-
-                }
-            };
-
-            let domain_details: FirewallProfileDetails = {
-                let profiletype: NET_FW_PROFILE_TYPE2 = NET_FW_PROFILE2_DOMAIN;
-
-                let profile_enabled: bool = policy.get_FirewallEnabled(profiletype)? == VARIANT_TRUE;
-                let inbound_blocked: bool = policy.get_DefaultInboundAction(profiletype)? == NET_FW_ACTION_BLOCK;
-                let outbound_blocked: bool = policy.get_DefaultOutboundAction(profiletype)? == NET_FW_ACTION_BLOCK;
-                let notifications_disabled: bool = policy.get_NotificationsDisabled(profiletype)? == VARIANT_TRUE;
-
-                FirewallProfileDetails {
-                    profile_enabled,
-                    inbound_blocked,
-                    outbound_blocked,
-                    notifications_disabled
-
-                }
-            };
-
-            firewalls = WindowsFirewallProfile {
-                public: public_details,
-                private: private_details,
-                domain: domain_details
-            };
-
-            /*
-                Shugo: Third-Party Firewall Products via WMI
-
-                Now we'll query WMI's SecurityCenter2 namespace to find third-party firewall products.
-                This logic is nearly identical to the Antivirus Module, we just query "FirewallProduct"
-                instead of "AntiVirusProduct".
-
-                This lets us see products like Norton Firewall, Avast, etc.
-            */
-            let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)?;
-            let namespace_path: BSTR = BSTR::from("ROOT\\SecurityCenter2");
-            let services: IWbemServices = locator.ConnectServer(
-                &namespace_path, 
-                &BSTR::default(),
-                &BSTR::default(),
-                &BSTR::default(),
-                0,
-                &BSTR::default(),
-                None
-            )?;
-
-            let query: BSTR = BSTR::from("Select displayName, productState FROM FirewallProduct");
-            let enum_object = services.ExecQuery(
-                &BSTR::from("WQL"),
-                &query,
-                WBEM_FLAG_RETURN_IMMEDIATELY | WBEM_FLAG_FORWARD_ONLY, 
-                None 
-            )?;
-
-            /*
-                Shugo: Processing Firewall Products
-
-                Just like antivirus products, firewall products also use the productState hexadecimal 
-                format. We extract bits 12-15 to determine if the firewall is active.
-            */
-            loop { 
-                let mut objects: [Option<IWbemClassObject>; 1] = [None; 1];
-                let mut returned = 0;
-                let _ = enum_object.Next(
-                    WBEM_INFINITE, 
-                    &mut objects,
-                    &mut returned 
-                );
-                if returned == 0 {
-                    break;
-                }
-
-                if let Some(class_object) = &objects[0] {
-                    let name: String = string_property(class_object, "displayName")?;
-                    let state: i32 = integer_property(class_object, "productState")?;
-                    
-                    let product: FirewallProductInfo = FirewallProductInfo {
-                        name,
-                        state,
-                    };
-
-                    firewall_products.push(product);
-                }
-            }
-
-            module = ModuleInfo {
-                namespace: namespace_path.to_string(), 
-                query: query.to_string()
-            }
+    })
+}
+
+/// Grabing firewall rules from any source
+pub fn scan_firewall_rules_with(source: &dyn SecuritySource) -> SourceResult<Vec<FirewallRule>> {
+    /*
+        Shugo: Firewall Rules
+
+        The profile settings only tell us what happens to traffic no rule matched. A profile that blocks inbound
+        traffic can still let a port in through an allow rule, and that's where most exposure comes from.
+
+        The source reads the rules through `INetFwPolicy2::Rules`, the same list `wf.msc` shows. Each rule says which
+        profiles it applies to as a bitmask, so a rule for "All" profiles covers Public too.
+    */
+    source.firewall_rules().map_err(|error| error.in_module("firewall"))
+}
+
+impl FirewallRule {
+    pub fn applies_to(&self, profile: i32) -> bool {
+        self.profiles & profile != 0
+    }
+
+    /// Whether this rule lets inbound TCP traffic reach `port`
+    pub fn allows_inbound_tcp(&self, port: u32) -> bool {
+        self.enabled && self.inbound && self.allow
+            && (self.protocol == PROTOCOL_TCP || self.protocol == PROTOCOL_ANY)
+            && port_listed(&self.local_ports, port)
+    }
+}
+
+/// Whether a firewall port list like "80,443,5000-5010" covers `port`. "*" and an empty list mean any port,
+/// keywords like "RPC" or "IPHTTPS" never match a plain port number
+pub fn port_listed(ports: &str, port: u32) -> bool {
+    let ports = ports.trim();
+    if ports.is_empty() || ports == "*" {
+        return true;
+    }
+    ports.split(',').map(str::trim).any(|entry| match entry.split_once('-') {
+        Some((low, high)) => match (low.trim().parse::<u32>(), high.trim().parse::<u32>()) {
+            (Ok(low), Ok(high)) => (low..=high).contains(&port),
+            _ => false
+        },
+        None => entry.parse::<u32>() == Ok(port)
+    })
+}
+
+impl FromWmiObject for FirewallProductInfo {
+    fn from_wmi_object(object: &dyn WmiObject) -> SourceResult<Self> {
+        // Like antivirus products, one without a name or state fails the scan instead of becoming a made up product
+        Ok(FirewallProductInfo::new(object.get_string("displayName")?, object.get_i32("productState")?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::product_state::ScannerState;
+    use crate::common::source::{WmiRow, WmiValue};
+
+    fn details(profile_enabled: bool) -> FirewallProfileDetails {
+        FirewallProfileDetails {
+            profile_enabled,
+            inbound_blocked: true,
+            outbound_blocked: false,
+            notifications_disabled: false
         }
-        /*
-            Shugo: Closing Thread
+    }
 
-            Clean up COM when we're done.
+    #[test]
+    fn combines_profiles_and_products() {
+        let profile = WindowsFirewallProfile {
+            public: details(true),
+            private: details(true),
+            domain: details(false)
+        };
+        let source = FixtureSource::new()
+            .with_firewall(profile.clone())
+            .with_wmi(NAMESPACE, "FirewallProduct", vec![
+                WmiRow::new()
+                    .with("displayName", WmiValue::String("Norton Firewall".to_string()))
+                    .with("productState", WmiValue::I32(0x41000))
+            ]);
+
+        let summary = scan_firewall_with(&source).unwrap();
+        assert_eq!(summary.profile, profile);
+        assert_eq!(summary.products.len(), 1);
+        assert_eq!(summary.products[0].name, "Norton Firewall");
+        assert_eq!(summary.products[0].product_state.scanner, ScannerState::On);
+        assert_eq!(summary.module_info.query, QUERY);
+    }
+
+    #[test]
+    fn servers_without_security_center_have_no_products() {
+        let profile = WindowsFirewallProfile {
+            public: details(true),
+            private: details(true),
+            domain: details(true)
+        };
+        let source = FixtureSource::new().with_firewall(profile.clone());
+
+        let summary = scan_firewall_with(&source).unwrap();
+        assert_eq!(summary.profile, profile);
+        assert!(summary.products.is_empty());
+    }
 
-            For more information on `CoUninitialize`:
-            (https://learn.microsoft.com/en-us/windows/win32/api/combaseapi/nf-combaseapi-couninitialize) - C++
-        */
-        CoUninitialize();
-        Ok((firewalls, firewall_products, module))
+    #[test]
+    fn matches_port_lists() {
+        assert!(port_listed("3389", 3389));
+        assert!(port_listed("80, 3389", 3389));
+        assert!(port_listed("3000-4000", 3389));
+        assert!(port_listed("*", 3389));
+        assert!(port_listed("", 3389));
+        assert!(!port_listed("RPC", 3389));
+        assert!(!port_listed("3390,4000-5000", 3389));
     }
-}
\ No newline at end of file
+}
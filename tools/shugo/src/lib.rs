@@ -1,27 +1,149 @@
 mod antivirus;
+mod defender;
 mod updates;
 mod firewall;
 mod uac;
 mod uas;
+mod os;
+mod smb;
+mod rdp;
+mod powershell;
+mod bitlocker;
+mod browser;
+mod extensions;
+mod shares;
+mod startup;
+mod audit;
+mod diff;
+mod policy;
 mod common;
 
+pub use common::source::{
+    SecuritySource,
+    SourceResult,
+    RegistryHive,
+    RegistryValue,
+    WmiRow,
+    WmiValue,
+    UpdateRecord,
+    UpdateCategory,
+    UserRecord,
+    registry_dword,
+    registry_string,
+    system_source
+};
+pub use common::error::{ShugoError, ErrorContext};
+pub use common::wmi::{cim_string_value, optional, query, CimDateTime, FromWmiObject, WmiObject, WmiValueError};
+pub use common::product_state::{ProductState, SecurityProviders, ScannerState, ProductOwner, DefinitionsState};
+pub use common::feature::{find_feature, OptionalFeature, FEATURE_ENABLED, FEATURE_NAMESPACE};
+pub use common::fixture::FixtureSource;
+pub use common::hive::HiveFile;
+pub use common::offline::OfflineSource;
+pub use common::regfile::{RegFile, RegParseError};
+pub use common::finding::{Finding, Severity, count_at_least};
+pub use common::output::{json_report, SCHEMA_VERSION};
+pub use common::html::{html_document, html_report, HtmlSection};
+pub use common::sarif::{sarif_log, sarif_level, SARIF_VERSION};
+pub use common::junit::{finding_cases, junit_xml, TestCase, TestOutcome};
+#[cfg(windows)]
+pub use common::live::LiveSource;
+
 pub use antivirus::{
-    scanner::scan_antivirus,
-    display::display_antivirus
+    assessment::assess_antivirus,
+    scanner::{scan_antivirus, scan_antivirus_with, ProductInfo},
+    display::{display_antivirus, json_antivirus}
+};
+pub use defender::{
+    assessment::{assess_defender, exclusion_risk, ExclusionKind},
+    scanner::{scan_defender, scan_defender_with, DefenderStatus, ComputerStatus, Preferences, Exclusions},
+    display::{display_defender, json_defender}
 };
 pub use updates::{
-    scanner::scan_updates,
-    display::display_updates
+    assessment::assess_updates,
+    scanner::{scan_updates, scan_updates_with, UpdateSummary, UpdateInfo},
+    display::{display_updates, json_updates}
 };
 pub use firewall::{
-    scanner::scan_firewall,
-    display::display_firewalls
+    assessment::assess_firewall,
+    scanner::{
+        scan_firewall, scan_firewall_with, scan_firewall_rules_with, port_listed,
+        FirewallSummary, WindowsFirewallProfile, FirewallProfileDetails, FirewallProductInfo, FirewallRule,
+        PROFILE_DOMAIN, PROFILE_PRIVATE, PROFILE_PUBLIC, PROTOCOL_TCP, PROTOCOL_ANY
+    },
+    display::{display_firewalls, json_firewall}
 };
 pub use uac::{
-    scanner::scan_uac,
-    display::display_uac
+    assessment::assess_uac,
+    scanner::{scan_uac, scan_uac_with, UacInfo},
+    display::{display_uac, json_uac}
+};
+pub use os::{
+    assessment::assess_os,
+    lifecycle::{LifecycleTable, Release, ServicingChannel, SupportStatus, BUNDLED_LIFECYCLE},
+    scanner::{scan_os, scan_os_with, OsInfo},
+    display::{display_os, json_os}
+};
+pub use smb::{
+    assessment::assess_smb,
+    scanner::{scan_smb, scan_smb_with, signing_state, SmbInfo, SmbServerSettings, SmbClientSettings, SmbState},
+    display::{display_smb, json_smb}
+};
+pub use rdp::{
+    assessment::assess_rdp,
+    scanner::{scan_rdp, scan_rdp_with, RdpInfo, RdpSettings, RdpFirewall},
+    display::{display_rdp, json_rdp}
+};
+pub use powershell::{
+    assessment::assess_powershell,
+    scanner::{scan_powershell, scan_powershell_with, ExecutionPolicy, ExecutionPolicies, PolicyScope, LoggingSettings, PowerShellV2, PowerShellInfo},
+    display::{display_powershell, json_powershell}
+};
+pub use bitlocker::{
+    assessment::assess_bitlocker,
+    scanner::{scan_bitlocker, scan_bitlocker_with, BitLockerInfo, EncryptableVolume},
+    display::{display_bitlocker, json_bitlocker}
+};
+pub use browser::{
+    assessment::assess_browsers,
+    scanner::{scan_browsers, scan_browsers_with, BrowserKind, BrowserPaths, BrowserStatus, BrowserPolicies, ProfileSettings, BrowserInfo},
+    versions::{VersionTable, BUNDLED_VERSIONS},
+    display::{display_browsers, json_browsers}
+};
+pub use extensions::{
+    assessment::assess_extensions,
+    permissions::{permission_risk, PermissionRisk, RiskLevel},
+    scanner::{scan_extensions, scan_extensions_with, local_profiles, Extension, ExtensionInfo, ProfileFormat, ProfileLocation},
+    display::{display_extensions, json_extensions}
+};
+pub use shares::{
+    assessment::assess_shares,
+    scanner::{scan_shares, scan_shares_with, Share, ShareInfo},
+    display::{display_shares, json_shares}
+};
+pub use startup::{
+    assessment::assess_startup,
+    scanner::{scan_startup, scan_startup_with, AutostartEntry, EntryKind, StartupInfo, StartupPaths},
+    commandline::Trait,
+    display::{display_startup, json_startup}
 };
 pub use uas::{
-    scanner::scan_uas,
-    display::display_uas
-};
\ No newline at end of file
+    assessment::assess_uas,
+    scanner::{scan_uas, scan_uas_with, UserAccountSummary, UserAccountInfo},
+    display::{display_uas, json_uas}
+};
+pub use audit::{
+    assessment::{score_audit, AuditScore, ModuleScore, MODULE_WEIGHTS},
+    scanner::{run_audit, run_audit_with, AuditReport, ModuleError},
+    snapshot::{save_snapshot, load_snapshot, parse_snapshot},
+    display::{display_audit, json_audit, html_audit, sarif_audit, junit_audit}
+};
+pub use diff::{
+    compare::{diff_audits, DiffReport, Drift, DriftKind},
+    display::{display_diff, json_diff}
+};
+pub use policy::{
+    facts::{audit_facts, fact_matches, FactValue, Facts, FACT_NAMES},
+    rules::{evaluate_policy, Operator, Policy, PolicyReport, PolicyRule, RuleResult, RuleStatus},
+    baselines::{bundled_baseline, Baseline, BASELINES},
+    display::{display_policy, json_policy, sarif_policy, junit_policy}
+};
@@ -0,0 +1,146 @@
+//! Assessment for the OS Module, turns the Windows version and its support dates into findings.
+use super::scanner::OsInfo;
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "os";
+
+/// Support ending within this many days is reported so there's time to plan an upgrade
+pub const ENDING_SOON_DAYS: i64 = 90;
+
+/// Assessing the Windows version
+pub fn assess_os(info: &OsInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let Some(build) = info.build else {
+        findings.push(
+            Finding::new("OS-004", MODULE, Severity::Info, "The Windows build couldn't be read")
+                .explanation("Without the build number Shugo can't tell which release this is or whether it's still supported")
+                .remediation("Run Shugo as administrator, or run `winver` to check the version by hand")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+        return findings;
+    };
+
+    let Some(support) = &info.support else {
+        findings.push(
+            Finding::new("OS-003", MODULE, Severity::Info, format!("Build {} isn't in the lifecycle table", build))
+                .explanation("This is usually a release newer than Shugo's table, or an Insider build, so its support dates are unknown")
+                .remediation("Update `os/lifecycle.toml` or pass a newer table with `--lifecycle`")
+                .evidence(format!("build={}", info.full_build()))
+        );
+        return findings;
+    };
+
+    match (&support.end_of_servicing, support.days_remaining) {
+        (Some(end), Some(days)) if days < 0 => findings.push(
+            Finding::new("OS-001", MODULE, Severity::Critical, format!("{} is no longer supported", support.release))
+                .explanation(format!(
+                    "{} editions of {} stopped getting security updates on {}, so every vulnerability found since then stays open",
+                    support.channel, support.release, end
+                ))
+                .remediation("Upgrade to a supported release with Windows Update or the Installation Assistant. For Windows 10, paid Extended Security Updates are the only way to keep getting fixes")
+                .evidence(format!("build={}", info.full_build()))
+                .evidence(format!("end_of_servicing={}", end))
+                .evidence(format!("days_since={}", -days))
+        ),
+        (Some(end), Some(days)) if days <= ENDING_SOON_DAYS => findings.push(
+            Finding::new("OS-002", MODULE, Severity::Medium, format!("{} support ends in {} days", support.release, days))
+                .explanation(format!("{} editions stop getting security updates on {}", support.channel, end))
+                .remediation("Plan the upgrade to a newer release before the end of servicing date")
+                .evidence(format!("build={}", info.full_build()))
+                .evidence(format!("end_of_servicing={}", end))
+        ),
+        (None, _) => findings.push(
+            Finding::new("OS-003", MODULE, Severity::Info, format!("{} has no {} support date", support.release, support.channel))
+                .explanation("The lifecycle table doesn't list this edition for this release, so its support dates are unknown")
+                .remediation("Check the edition on Microsoft's lifecycle pages and add the date to the lifecycle table")
+                .evidence(format!("edition={}", info.edition_id.as_deref().unwrap_or("Unknown")))
+        ),
+        _ => {}
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::{RegistryHive, RegistryValue};
+    use crate::common::time::parse_date;
+    use crate::os::lifecycle::{LifecycleTable, ServicingChannel};
+    use crate::os::scanner::{scan_os_with, VERSION_KEY};
+
+    /// One release long out of support and one Windows 11 release without an LTSC edition
+    const TABLE: &str = r#"
+        updated = "2026-10-01"
+
+        [[release]]
+        product = "Windows 10"
+        version = "1809"
+        build = 17763
+        home_pro = "2020-11-10"
+
+        [[release]]
+        product = "Windows 11"
+        version = "23H2"
+        build = 22631
+        home_pro = "2025-11-11"
+        enterprise = "2026-11-10"
+    "#;
+
+    fn scan(edition: &str, build: RegistryValue) -> OsInfo {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, VERSION_KEY, "EditionID", RegistryValue::String(edition.to_string()))
+            .with_registry(RegistryHive::LocalMachine, VERSION_KEY, "CurrentBuildNumber", build);
+        scan_os_with(&source, &LifecycleTable::from_toml(TABLE).unwrap()).unwrap()
+    }
+
+    fn build(number: &str) -> RegistryValue {
+        RegistryValue::String(number.to_string())
+    }
+
+    #[test]
+    fn ended_release_is_critical() {
+        let findings = assess_os(&scan("Professional", build("17763")));
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("OS-001", Severity::Critical));
+        assert!(findings[0].evidence.contains(&"end_of_servicing=2020-11-10".to_string()));
+    }
+
+    #[test]
+    fn warns_before_support_ends() {
+        let mut info = scan("Enterprise", build("22631"));
+        let table = LifecycleTable::from_toml(TABLE).unwrap();
+        info.support = table.support(22631, ServicingChannel::Enterprise, parse_date("2026-10-17").unwrap());
+        let findings = assess_os(&info);
+        assert_eq!(findings[0].id, "OS-002");
+        assert!(findings[0].title.ends_with("24 days"));
+
+        info.support = table.support(22631, ServicingChannel::Enterprise, parse_date("2026-01-01").unwrap());
+        assert!(assess_os(&info).is_empty());
+    }
+
+    #[test]
+    fn unreadable_build_is_not_unsupported() {
+        // A DWORD where Windows writes a string, and a string that isn't a number
+        for value in [RegistryValue::Dword(22631), build("22631a")] {
+            let findings = assess_os(&scan("Professional", value));
+            assert_eq!(findings.len(), 1);
+            assert_eq!((findings[0].id.as_str(), findings[0].severity), ("OS-004", Severity::Info));
+            assert_eq!(findings[0].evidence, vec!["unreadable=CurrentBuildNumber"]);
+        }
+    }
+
+    #[test]
+    fn missing_support_dates_are_info() {
+        let findings = assess_os(&scan("Professional", build("27000")));
+        assert_eq!(findings[0].id, "OS-003");
+        assert_eq!(findings[0].evidence, vec!["build=27000"]);
+
+        // 23H2 never had an LTSC edition, so the build is known but the channel has no date
+        let findings = assess_os(&scan("EnterpriseS", build("22631")));
+        assert_eq!(findings[0].id, "OS-003");
+        assert!(findings[0].title.ends_with("has no Enterprise LTSC support date"));
+        assert_eq!(findings[0].evidence, vec!["edition=EnterpriseS"]);
+    }
+}
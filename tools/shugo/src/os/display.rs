@@ -0,0 +1,96 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_os;
+use super::scanner::OsInfo;
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::get_time;
+
+/// Display for OS Module
+pub fn display_os(info: &OsInfo, verbose: bool) {
+    println!();
+    println!("OPERATING SYSTEM LIFECYCLE");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_version(info, verbose);
+
+    display_support(info);
+
+    display_findings(&assess_os(info), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for OS Module
+pub fn json_os(info: &OsInfo) -> Value {
+    let decoded = json!({
+        "name": info.name(),
+        "full_build": info.full_build(),
+        "support": support_text(info)
+    });
+
+    json!({
+        "os": with_decoded(info, decoded),
+        "findings": assess_os(info)
+    })
+}
+
+fn display_scan_details(info: &OsInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Registry Key: {}", info.module_info.registry_key);
+    println!(" - Query:");
+    for query in info.module_info.queries.iter() {
+        println!("   - {}", query);
+    }
+    for (value, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", value, reason);
+    }
+    println!();
+}
+
+fn display_version(info: &OsInfo, verbose: bool) {
+    println!("Windows Version:");
+    println!(" - Product: {}", info.name());
+    if verbose {println!("   - ProductName: {}", info.product_name.as_deref().unwrap_or("Not set"));}
+    println!(" - Version: {}", info.display_version.as_deref().unwrap_or("Unknown"));
+    println!(" - Build: {}", info.full_build());
+    println!(" - Edition: {}", info.edition_id.as_deref().unwrap_or("Unknown"));
+    if verbose {println!(" - Installation Type: {}", info.installation_type.as_deref().unwrap_or("Unknown"));}
+    println!();
+}
+
+fn display_support(info: &OsInfo) {
+    println!("Support Lifecycle:");
+    match &info.support {
+        Some(support) => {
+            println!(" - Release: {}", support.release);
+            println!(" - Servicing Channel: {}", support.channel);
+            println!(" - End of Servicing: {}", support.end_of_servicing.as_deref().unwrap_or("Unknown"));
+            println!(" - Status: {}", support_text(info));
+            println!(" - Lifecycle Table Updated: {}", support.table_updated);
+        },
+        None => println!(" - This build isn't in the lifecycle table")
+    }
+    println!();
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - Access Rights: KEY_READ");
+    println!(" - Registry Hive: HKEY_LOCAL_MACHINE");
+    println!(" - Lifecycle Table: os/lifecycle.toml (or --lifecycle)");
+    println!();
+}
+
+fn support_text(info: &OsInfo) -> String {
+    match info.support.as_ref().and_then(|support| support.days_remaining) {
+        Some(days) if days < 0 => format!("Unsupported, ended {} days ago", -days),
+        Some(0) => "Supported, ends today".to_string(),
+        Some(days) => format!("Supported, {} days remaining", days),
+        None => "Unknown".to_string()
+    }
+}
@@ -0,0 +1,237 @@
+//! The Windows end of servicing table. The bundled copy lives in `os/lifecycle.toml` and a newer one
+//! can be loaded from a file, so support dates can be updated without a new Shugo release. Looking up
+//! a build is plain Rust with no Windows calls, so it's tested on any OS.
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::ShugoError;
+use crate::common::source::SourceResult;
+use crate::common::time::parse_date;
+
+pub const BUNDLED_LIFECYCLE: &str = include_str!("lifecycle.toml");
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LifecycleTable {
+    /// When the dates were last checked, shown so readers know how fresh the answer is
+    pub updated: String,
+    #[serde(rename = "release")]
+    pub releases: Vec<Release>
+}
+
+/// One Windows release and the day each servicing channel stops getting updates
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Release {
+    pub product: String,
+    pub version: String,
+    pub build: u32,
+    #[serde(default)]
+    pub server: bool,
+    pub home_pro: Option<String>,
+    pub enterprise: Option<String>,
+    pub ltsc: Option<String>,
+    pub iot_ltsc: Option<String>
+}
+
+/// Which support timeline an edition follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServicingChannel {
+    HomePro,
+    Enterprise,
+    Ltsc,
+    IotLtsc,
+    Server
+}
+
+/// How long a release has left, worked out for one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupportStatus {
+    /// For example "Windows 11 23H2"
+    pub release: String,
+    pub channel: ServicingChannel,
+    /// `None` when this channel was never offered for the release, like LTSC for Windows 11 23H2
+    pub end_of_servicing: Option<String>,
+    /// Negative once support has ended
+    pub days_remaining: Option<i64>,
+    /// The `updated` date of the table used
+    pub table_updated: String
+}
+
+impl ServicingChannel {
+    /// Sorts an EditionID (and InstallationType) into the support timeline it follows
+    pub fn from_edition(edition_id: &str, installation_type: Option<&str>) -> ServicingChannel {
+        /*
+            Shugo: Editions And Servicing Channels
+
+            The same Windows build is supported for different lengths of time depending on the edition:
+
+            Home and Pro get about 18 months (Windows 10) or 24 months (Windows 11) per release.
+            Enterprise and Education get an extra year on the same release.
+            LTSC (Long-Term Servicing Channel) editions never get feature updates and are supported for 5 years,
+            10 for IoT Enterprise LTSC.
+            Windows Server follows its own fixed lifecycle, even though it shares build numbers with Windows 10/11.
+
+            The registry tells us which edition is installed through `EditionID`. Names ending in `S` (EnterpriseS,
+            IoTEnterpriseS) are the LTSC editions, and `InstallationType` says whether it's a Server install.
+
+            For more information on servicing channels:
+            (https://learn.microsoft.com/en-us/windows/deployment/update/waas-overview#servicing-channels)
+        */
+        let edition = edition_id.to_lowercase();
+        let is_server = installation_type.is_some_and(|kind| kind.to_lowercase().starts_with("server")) || edition.starts_with("server");
+        if is_server {
+            ServicingChannel::Server
+        } else if edition.starts_with("iotenterprises") {
+            ServicingChannel::IotLtsc
+        } else if edition.starts_with("enterprises") {
+            ServicingChannel::Ltsc
+        } else if edition.starts_with("enterprise") || edition.starts_with("education") || edition.starts_with("iotenterprise") {
+            ServicingChannel::Enterprise
+        } else {
+            ServicingChannel::HomePro
+        }
+    }
+}
+
+impl fmt::Display for ServicingChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ServicingChannel::HomePro => "Home/Pro",
+            ServicingChannel::Enterprise => "Enterprise/Education",
+            ServicingChannel::Ltsc => "Enterprise LTSC",
+            ServicingChannel::IotLtsc => "IoT Enterprise LTSC",
+            ServicingChannel::Server => "Windows Server"
+        })
+    }
+}
+
+impl LifecycleTable {
+    /// The table compiled into Shugo
+    pub fn bundled() -> SourceResult<LifecycleTable> {
+        LifecycleTable::from_toml(BUNDLED_LIFECYCLE)
+    }
+
+    /// Loads a table from a TOML file laid out like `os/lifecycle.toml`
+    pub fn open(path: &Path) -> SourceResult<LifecycleTable> {
+        let text = fs::read_to_string(path).map_err(|error| ShugoError::io(path.display().to_string(), &error))?;
+        LifecycleTable::from_toml(&text).map_err(|error| error.for_query(path.display().to_string()))
+    }
+
+    pub fn from_toml(text: &str) -> SourceResult<LifecycleTable> {
+        let table: LifecycleTable = toml::from_str(text).map_err(|error| ShugoError::parse("lifecycle", error.to_string()))?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Catches typos in dates and releases listed twice
+    fn validate(&self) -> SourceResult<()> {
+        let invalid = |reason: String| Err(ShugoError::parse("lifecycle", reason));
+        if parse_date(&self.updated).is_none() {
+            return invalid(format!("updated {:?} is not a YYYY-MM-DD date", self.updated));
+        }
+        for (index, release) in self.releases.iter().enumerate() {
+            let dates = [&release.home_pro, &release.enterprise, &release.ltsc, &release.iot_ltsc];
+            if let Some(date) = dates.into_iter().flatten().find(|date| parse_date(date).is_none()) {
+                return invalid(format!("{} {} has an invalid date {:?}", release.product, release.version, date));
+            }
+            if self.releases[..index].iter().any(|other| other.build == release.build && other.server == release.server) {
+                return invalid(format!("build {} is listed twice", release.build));
+            }
+        }
+        Ok(())
+    }
+
+    /// The release a build belongs to, server builds only match server releases
+    pub fn release(&self, build: u32, server: bool) -> Option<&Release> {
+        self.releases.iter().find(|release| release.build == build && release.server == server)
+    }
+
+    /// How much support a build on this channel has left on `today` (days since 1970-01-01)
+    pub fn support(&self, build: u32, channel: ServicingChannel, today: i64) -> Option<SupportStatus> {
+        let release = self.release(build, channel == ServicingChannel::Server)?;
+        let end_of_servicing = release.end_of_servicing(channel).cloned();
+        let days_remaining = end_of_servicing.as_deref().and_then(parse_date).map(|end| end - today);
+        Some(SupportStatus {
+            release: if release.server {release.product.clone()} else {format!("{} {}", release.product, release.version)},
+            channel,
+            end_of_servicing,
+            days_remaining,
+            table_updated: self.updated.clone()
+        })
+    }
+}
+
+impl Release {
+    /// The end date for a channel. IoT LTSC falls back to the Enterprise LTSC date when it isn't listed
+    pub fn end_of_servicing(&self, channel: ServicingChannel) -> Option<&String> {
+        match channel {
+            ServicingChannel::HomePro => self.home_pro.as_ref(),
+            ServicingChannel::Enterprise => self.enterprise.as_ref(),
+            ServicingChannel::Ltsc | ServicingChannel::Server => self.ltsc.as_ref(),
+            ServicingChannel::IotLtsc => self.iot_ltsc.as_ref().or(self.ltsc.as_ref())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str) -> i64 {
+        parse_date(date).unwrap()
+    }
+
+    #[test]
+    fn bundled_table_loads() {
+        let table = LifecycleTable::bundled().unwrap();
+        assert!(table.releases.len() > 20);
+        assert!(table.release(19045, false).is_some());
+        assert_eq!(table.release(17763, true).unwrap().product, "Windows Server 2019");
+    }
+
+    #[test]
+    fn windows_10_22h2_is_past_end_of_servicing() {
+        let table = LifecycleTable::bundled().unwrap();
+        let support = table.support(19045, ServicingChannel::HomePro, day("2026-10-17")).unwrap();
+        assert_eq!(support.release, "Windows 10 22H2");
+        assert_eq!(support.end_of_servicing.as_deref(), Some("2025-10-14"));
+        assert_eq!(support.days_remaining, Some(-368));
+    }
+
+    #[test]
+    fn channels_change_the_end_date() {
+        let table = LifecycleTable::bundled().unwrap();
+        let today = day("2026-10-17");
+        assert_eq!(table.support(22631, ServicingChannel::HomePro, today).unwrap().days_remaining, Some(-340));
+        assert_eq!(table.support(22631, ServicingChannel::Enterprise, today).unwrap().days_remaining, Some(24));
+        assert_eq!(table.support(22631, ServicingChannel::Ltsc, today).unwrap().end_of_servicing, None);
+        assert_eq!(table.support(19044, ServicingChannel::IotLtsc, today).unwrap().end_of_servicing.as_deref(), Some("2032-01-13"));
+        assert_eq!(table.support(17763, ServicingChannel::Server, today).unwrap().release, "Windows Server 2019");
+        assert!(table.support(99999, ServicingChannel::HomePro, today).is_none());
+    }
+
+    #[test]
+    fn sorts_editions() {
+        assert_eq!(ServicingChannel::from_edition("Professional", Some("Client")), ServicingChannel::HomePro);
+        assert_eq!(ServicingChannel::from_edition("Core", None), ServicingChannel::HomePro);
+        assert_eq!(ServicingChannel::from_edition("Enterprise", Some("Client")), ServicingChannel::Enterprise);
+        assert_eq!(ServicingChannel::from_edition("EducationN", Some("Client")), ServicingChannel::Enterprise);
+        assert_eq!(ServicingChannel::from_edition("EnterpriseS", Some("Client")), ServicingChannel::Ltsc);
+        assert_eq!(ServicingChannel::from_edition("IoTEnterpriseS", Some("Client")), ServicingChannel::IotLtsc);
+        assert_eq!(ServicingChannel::from_edition("ServerDatacenter", Some("Server Core")), ServicingChannel::Server);
+    }
+
+    #[test]
+    fn rejects_bad_tables() {
+        assert!(LifecycleTable::from_toml("updated = \"soon\"\nrelease = []").is_err());
+        let duplicate = "updated = \"2026-01-01\"\n[[release]]\nproduct = \"A\"\nversion = \"1\"\nbuild = 1\n[[release]]\nproduct = \"B\"\nversion = \"2\"\nbuild = 1";
+        assert!(LifecycleTable::from_toml(duplicate).is_err());
+        let date = "updated = \"2026-01-01\"\n[[release]]\nproduct = \"A\"\nversion = \"1\"\nbuild = 1\nhome_pro = \"2026-02-30\"";
+        assert!(LifecycleTable::from_toml(date).is_err());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod scanner;
+pub mod lifecycle;
+pub mod assessment;
+pub mod display;
\ No newline at end of file
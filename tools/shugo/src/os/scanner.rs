@@ -0,0 +1,223 @@
+//! This is the OS Module for Shugo. Here we can see:
+//!
+//! - Windows Product, Edition, and Version
+//! - Build Number and Update Revision (UBR)
+//! - Servicing Channel and End of Servicing Date
+//! - Days of Support Remaining
+//!
+//! Like the UAC module this reads the registry, so it also works on hives exported from another machine.
+//! The support dates come from the lifecycle table in `os/lifecycle.toml`.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::lifecycle::{LifecycleTable, ServicingChannel, SupportStatus};
+use crate::common::error::ShugoError;
+use crate::common::source::{SecuritySource, SourceResult, RegistryHive, registry_dword, registry_string, system_source};
+use crate::common::time::today;
+
+/// Everything is `None` when it isn't set or couldn't be read
+#[derive(Serialize, Deserialize)]
+pub struct OsInfo {
+    /// ProductName as the registry has it, which still says "Windows 10" on Windows 11
+    pub product_name: Option<String>,
+    pub edition_id: Option<String>,
+    /// DisplayVersion like "23H2", or ReleaseId on releases before 20H2
+    pub display_version: Option<String>,
+    pub installation_type: Option<String>,
+    pub build: Option<u32>,
+    /// Update Build Revision, the part after the dot that goes up with every cumulative update
+    pub ubr: Option<u32>,
+    /// `None` when the build isn't in the lifecycle table
+    pub support: Option<SupportStatus>,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub registry_key: String,
+    pub queries: Vec<String>,
+    /// Values that exist but couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+pub const VERSION_KEY: &str = "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion";
+
+/// The first Windows 11 build. Windows 11 kept "Windows 10" in ProductName, so the build is how we tell them apart
+pub const WINDOWS_11_BUILD: u32 = 22000;
+
+/// Scanning the Windows version against the bundled lifecycle table
+pub fn scan_os() -> SourceResult<OsInfo> {
+    scan_os_with(system_source()?.as_ref(), &LifecycleTable::bundled()?)
+}
+
+/// Scanning the Windows version from any source against any lifecycle table
+pub fn scan_os_with(source: &dyn SecuritySource, table: &LifecycleTable) -> SourceResult<OsInfo> {
+    /*
+        Shugo: Where Windows Keeps Its Version
+
+        Everything `winver` shows lives under one registry key:
+        HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows NT\CurrentVersion
+
+        CurrentBuildNumber (REG_SZ): the build, like "22631". This is what identifies a release.
+        UBR (DWORD): the Update Build Revision. Every monthly cumulative update bumps it, so 22631.4317 is newer
+        than 22631.3880 on the same release.
+        DisplayVersion (REG_SZ): the marketing version, like "23H2". Releases before 20H2 used ReleaseId instead.
+        EditionID (REG_SZ): Professional, Enterprise, EnterpriseS, and so on, which decides the support timeline.
+        InstallationType (REG_SZ): Client, Server, or Server Core.
+
+        Watch out for ProductName: Windows 11 never changed it, so it still says "Windows 10 Pro".
+        We use the build number to tell them apart instead.
+    */
+    let mut queries: Vec<String> = Vec::new();
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+    let mut keep = |value: &str, result: SourceResult<String>| -> SourceResult<Option<String>> {
+        queries.push(value.to_string());
+        match result {
+            Ok(data) => Ok(Some(data.trim().to_string()).filter(|data| !data.is_empty())),
+            Err(ShugoError::ValueMissing { .. }) => Ok(None),
+            Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::ParseError { .. })) => {
+                unreadable.insert(value.to_string(), error.to_string());
+                Ok(None)
+            },
+            Err(error) => Err(error.in_module("os"))
+        }
+    };
+    let string = |value: &str| registry_string(source, RegistryHive::LocalMachine, VERSION_KEY, value);
+
+    let product_name = keep("ProductName", string("ProductName"))?;
+    let edition_id = keep("EditionID", string("EditionID"))?;
+    let display_version = match keep("DisplayVersion", string("DisplayVersion"))? {
+        Some(version) => Some(version),
+        None => keep("ReleaseId", string("ReleaseId"))?
+    };
+    let installation_type = keep("InstallationType", string("InstallationType"))?;
+    let build_text = keep("CurrentBuildNumber", string("CurrentBuildNumber"))?;
+    let ubr = keep("UBR", registry_dword(source, RegistryHive::LocalMachine, VERSION_KEY, "UBR").map(|ubr| ubr.to_string()))?
+        .and_then(|ubr| ubr.parse().ok());
+
+    let build = match build_text {
+        Some(text) => match text.parse::<u32>() {
+            Ok(build) => Some(build),
+            Err(_) => {
+                unreadable.insert("CurrentBuildNumber".to_string(), format!("{:?} is not a build number", text));
+                None
+            }
+        },
+        None => None
+    };
+
+    let channel = ServicingChannel::from_edition(edition_id.as_deref().unwrap_or(""), installation_type.as_deref());
+    let support = build.and_then(|build| table.support(build, channel, today()));
+
+    Ok(OsInfo {
+        product_name,
+        edition_id,
+        display_version,
+        installation_type,
+        build,
+        ubr,
+        support,
+        module_info: ModuleInfo {
+            registry_key: VERSION_KEY.to_string(),
+            queries,
+            unreadable
+        }
+    })
+}
+
+impl OsInfo {
+    /// The product name with Windows 11 builds corrected from the "Windows 10" the registry reports
+    pub fn name(&self) -> String {
+        let name = self.product_name.clone().unwrap_or_else(|| "Windows (unknown edition)".to_string());
+        match self.build {
+            Some(build) if build >= WINDOWS_11_BUILD && !self.is_server() => name.replacen("Windows 10", "Windows 11", 1),
+            _ => name
+        }
+    }
+
+    /// The full build like `22631.4317`, what `winver` shows
+    pub fn full_build(&self) -> String {
+        match (self.build, self.ubr) {
+            (Some(build), Some(ubr)) => format!("{}.{}", build, ubr),
+            (Some(build), None) => build.to_string(),
+            _ => "Unknown".to_string()
+        }
+    }
+
+    pub fn is_server(&self) -> bool {
+        self.support.as_ref().map(|support| support.channel == ServicingChannel::Server).unwrap_or_else(|| {
+            ServicingChannel::from_edition(self.edition_id.as_deref().unwrap_or(""), self.installation_type.as_deref()) == ServicingChannel::Server
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::RegistryValue;
+
+    fn machine(values: &[(&str, RegistryValue)]) -> FixtureSource {
+        FixtureSource::new().with_registry_values(RegistryHive::LocalMachine, VERSION_KEY, values)
+    }
+
+    fn text(value: &str) -> RegistryValue {
+        RegistryValue::String(value.to_string())
+    }
+
+    #[test]
+    fn reads_windows_11_that_says_windows_10() {
+        let source = machine(&[
+            ("ProductName", text("Windows 10 Pro")),
+            ("EditionID", text("Professional")),
+            ("DisplayVersion", text("23H2")),
+            ("InstallationType", text("Client")),
+            ("CurrentBuildNumber", text("22631")),
+            ("UBR", RegistryValue::Dword(4317))
+        ]);
+
+        let info = scan_os_with(&source, &LifecycleTable::bundled().unwrap()).unwrap();
+        assert_eq!(info.name(), "Windows 11 Pro");
+        assert_eq!(info.full_build(), "22631.4317");
+        let support = info.support.unwrap();
+        assert_eq!(support.release, "Windows 11 23H2");
+        assert_eq!(support.channel, ServicingChannel::HomePro);
+        assert_eq!(support.end_of_servicing.as_deref(), Some("2025-11-11"));
+    }
+
+    #[test]
+    fn falls_back_to_release_id() {
+        let source = machine(&[
+            ("ProductName", text("Windows Server 2019 Datacenter")),
+            ("EditionID", text("ServerDatacenter")),
+            ("ReleaseId", text("1809")),
+            ("InstallationType", text("Server Core")),
+            ("CurrentBuildNumber", text("17763"))
+        ]);
+
+        let info = scan_os_with(&source, &LifecycleTable::bundled().unwrap()).unwrap();
+        assert_eq!(info.display_version.as_deref(), Some("1809"));
+        assert!(info.is_server());
+        assert_eq!(info.name(), "Windows Server 2019 Datacenter");
+        assert_eq!(info.support.unwrap().release, "Windows Server 2019");
+    }
+
+    #[test]
+    fn unreadable_build_has_no_support_status() {
+        let source = machine(&[("CurrentBuildNumber", RegistryValue::Dword(22631))]);
+
+        let info = scan_os_with(&source, &LifecycleTable::bundled().unwrap()).unwrap();
+        assert_eq!(info.build, None);
+        assert!(info.support.is_none());
+        assert!(info.module_info.unreadable.contains_key("CurrentBuildNumber"));
+    }
+
+    #[test]
+    fn missing_key_fails_the_scan() {
+        let error = scan_os_with(&FixtureSource::new(), &LifecycleTable::bundled().unwrap()).err().unwrap();
+        assert_eq!(error.kind(), "not_found");
+        assert_eq!(error.context().module.as_deref(), Some("os"));
+    }
+}
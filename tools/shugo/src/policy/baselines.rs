@@ -0,0 +1,66 @@
+//! Baselines bundled into the binary, so common hardening guides can be checked without writing a
+//! policy file. Each is a normal policy file under `policy/baselines` and every rule carries the
+//! benchmark reference it comes from. The CIS Windows 10 and 11 baselines only differ in their name, so
+//! their rules live in one file appended to each header.
+use super::rules::Policy;
+use crate::common::error::ShugoError;
+use crate::common::source::SourceResult;
+
+pub struct Baseline {
+    pub name: &'static str,
+    pub source: &'static str
+}
+
+pub const BASELINES: &[Baseline] = &[
+    Baseline {
+        name: "cis-win11-l1",
+        source: concat!(include_str!("baselines/cis_windows_11_l1.toml"), "\n", include_str!("baselines/cis_windows_l1_rules.toml"))
+    },
+    Baseline {
+        name: "cis-win10-l1",
+        source: concat!(include_str!("baselines/cis_windows_10_l1.toml"), "\n", include_str!("baselines/cis_windows_l1_rules.toml"))
+    },
+    Baseline { name: "ms-baseline-win11", source: include_str!("baselines/ms_security_baseline_windows_11.toml") }
+];
+
+/// Loads a bundled baseline by name
+pub fn bundled_baseline(name: &str) -> SourceResult<Policy> {
+    let baseline = BASELINES
+        .iter()
+        .find(|baseline| baseline.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let names: Vec<&str> = BASELINES.iter().map(|baseline| baseline.name).collect();
+            ShugoError::not_found(format!("baseline {}, choose from: {}", name, names.join(", ")))
+        })?;
+    Policy::from_toml(baseline.source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_baselines_load_with_references() {
+        for baseline in BASELINES {
+            let policy = bundled_baseline(baseline.name).unwrap();
+            assert!(!policy.rules.is_empty(), "{} has no rules", baseline.name);
+            for rule in &policy.rules {
+                assert!(!rule.reference.is_empty(), "{} rule {} has no reference", baseline.name, rule.id);
+                assert!(!rule.remediation.is_empty(), "{} rule {} has no remediation", baseline.name, rule.id);
+            }
+        }
+        assert!(bundled_baseline("nope").is_err());
+    }
+
+    #[test]
+    fn cis_baselines_share_one_consent_prompt_rule() {
+        let win10 = bundled_baseline("cis-win10-l1").unwrap();
+        let win11 = bundled_baseline("cis-win11-l1").unwrap();
+        assert_ne!(win10.name, win11.name);
+        assert_eq!(win10.rules.len(), win11.rules.len());
+
+        let prompt: Vec<_> = win10.rules.iter().filter(|rule| rule.check == "uac.ConsentPromptBehaviorAdmin").collect();
+        assert_eq!(prompt.len(), 1);
+        assert_eq!(prompt[0].id, "cis-2.3.17.2");
+    }
+}
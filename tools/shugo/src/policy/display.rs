@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+use super::rules::{PolicyReport, RuleResult, RuleStatus};
+use crate::common::finding::Finding;
+use crate::common::junit::{TestCase, TestOutcome, junit_xml};
+use crate::common::sarif::sarif_log;
+
+/// Display for the Policy Module
+pub fn display_policy(report: &PolicyReport, verbose: bool) {
+    println!();
+    println!("POLICY COMPLIANCE CHECK");
+    println!("{}", "=".repeat(30));
+
+    display_summary(report);
+
+    display_results(report, RuleStatus::Fail, "Violations:", true);
+    display_results(report, RuleStatus::Unknown, "Not Evaluated:", verbose);
+    if verbose {display_results(report, RuleStatus::Pass, "Passed:", verbose);}
+}
+
+/// JSON for the Policy Module
+pub fn json_policy(report: &PolicyReport) -> Value {
+    let mut value = serde_json::to_value(report).unwrap_or(Value::Null);
+    if let Value::Object(map) = &mut value {
+        map.insert("compliant".to_string(), Value::Bool(report.compliant()));
+        map.insert("complete".to_string(), Value::Bool(report.complete()));
+    }
+    value
+}
+
+/// SARIF for the Policy Module, one result per violated rule
+pub fn sarif_policy(report: &PolicyReport) -> Value {
+    let violations: Vec<Finding> = report.results
+        .iter()
+        .filter(|result| result.status == RuleStatus::Fail)
+        .map(RuleResult::finding)
+        .collect();
+    sarif_log(&violations)
+}
+
+/// JUnit XML for the Policy Module, one test case per rule
+pub fn junit_policy(report: &PolicyReport) -> String {
+    let cases: Vec<TestCase> = report.results
+        .iter()
+        .map(|result| TestCase {
+            classname: report.policy.clone(),
+            name: format!("{}: {}", result.rule.id, result.rule.title),
+            outcome: match result.status {
+                RuleStatus::Pass => TestOutcome::Passed,
+                RuleStatus::Fail => TestOutcome::Failed {
+                    message: format!("[{}] {}", result.rule.severity, result.message),
+                    details: result.actual.join("\n")
+                },
+                RuleStatus::Unknown => TestOutcome::Skipped(result.message.clone())
+            }
+        })
+        .collect();
+    junit_xml("Shugo Policy Check", &cases)
+}
+
+fn display_summary(report: &PolicyReport) {
+    println!("Summary:");
+    println!(" - Policy: {}", report.policy);
+    println!(" - Rules: {}", report.results.len());
+    println!("   - Passed: {}", report.count(RuleStatus::Pass));
+    println!("   - Failed: {}", report.count(RuleStatus::Fail));
+    println!("   - Not Evaluated: {}", report.count(RuleStatus::Unknown));
+    println!(" - Result: {}", match report.exit_code() {
+        0 => "Compliant",
+        1 => "Not Compliant",
+        _ => "Incomplete, some rules couldn't be evaluated"
+    });
+    println!();
+}
+
+fn display_results(report: &PolicyReport, status: RuleStatus, header: &str, details: bool) {
+    let results: Vec<&RuleResult> = report.results.iter().filter(|result| result.status == status).collect();
+    if results.is_empty() {
+        return;
+    }
+    println!("{}", header);
+    for (i, result) in results.iter().enumerate() {
+        println!("{}. [{}] {} ({})", i + 1, result.rule.severity, result.rule.title, result.rule.id);
+        if details {
+            println!("   - {}", result.message);
+            if !result.rule.reference.is_empty() {
+                println!("   - Reference: {}", result.rule.reference);
+            }
+            if status == RuleStatus::Fail && !result.rule.remediation.is_empty() {
+                println!("   - Fix: {}", result.rule.remediation);
+            }
+        }
+    }
+    println!();
+}
@@ -0,0 +1,365 @@
+//! Facts are the flat, named values a policy is checked against, for example
+//! `uac.ConsentPromptBehaviorAdmin` or `uas.admin_count`. They're pulled out of an `AuditReport` so
+//! policy files don't depend on how the result structs are laid out.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audit::scanner::AuditReport;
+use crate::bitlocker::scanner::{EncryptableVolume, PROTECTION_ON};
+use crate::common::product_state::{DefinitionsState, ScannerState};
+use crate::extensions::permissions::RiskLevel;
+use crate::firewall::assessment::third_party_active;
+use crate::smb::scanner::{SmbState, signing_state};
+use crate::startup::scanner::{EntryKind, IFEO_KEYS};
+use crate::updates::scanner::{CRITICAL_UPDATES_GUID, SECURITY_UPDATES_GUID};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FactValue {
+    Bool(bool),
+    Number(f64)
+}
+
+impl fmt::Display for FactValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactValue::Bool(value) => write!(f, "{}", value),
+            FactValue::Number(value) => write!(f, "{}", value)
+        }
+    }
+}
+
+pub type Facts = BTreeMap<String, FactValue>;
+
+/// Every fact name a policy can check. A `*` in a policy's `check` matches one dotted segment,
+/// so `firewall.*.enabled` covers all three profiles
+pub const FACT_NAMES: &[&str] = &[
+    "antivirus.active_products",
+    "antivirus.out_of_date_products",
+    "firewall.public.enabled",
+    "firewall.public.inbound_blocked",
+    "firewall.public.outbound_blocked",
+    "firewall.private.enabled",
+    "firewall.private.inbound_blocked",
+    "firewall.private.outbound_blocked",
+    "firewall.domain.enabled",
+    "firewall.domain.inbound_blocked",
+    "firewall.domain.outbound_blocked",
+    "firewall.active_third_party",
+    "updates.pending",
+    "updates.pending_critical",
+    "updates.pending_security",
+    "updates.oldest_critical_days",
+    "updates.oldest_security_days",
+    "uac.EnableLUA",
+    "uac.ConsentPromptBehaviorAdmin",
+    "uac.PromptOnSecureDesktop",
+    "uac.EnableInstallerDetection",
+    "uac.ValidateAdminCodeSignatures",
+    "uac.FilterAdministratorToken",
+    "uac.EnableVirtualization",
+    "uas.total_accounts",
+    "uas.enabled_accounts",
+    "uas.admin_count",
+    "uas.guest_enabled",
+    "defender.real_time_protection",
+    "defender.tamper_protected",
+    "defender.signature_age_days",
+    "defender.exclusions",
+    "os.build",
+    "os.support_days_remaining",
+    "smb.smb1_server",
+    "smb.smb1_client",
+    "smb.server_signing_required",
+    "smb.client_signing_required",
+    "smb.encryption",
+    "rdp.enabled",
+    "rdp.nla_required",
+    "rdp.public_exposure",
+    "powershell.permissive_policy",
+    "powershell.v2_installed",
+    "bitlocker.os_volume_protected",
+    "bitlocker.unprotected_volumes",
+    "browser.outdated",
+    "browser.safe_browsing_disabled",
+    "extensions.total",
+    "extensions.high_risk",
+    "shares.broad_write_access",
+    "startup.entries",
+    "startup.debuggers"
+];
+
+/// Pulling facts out of an audit. Modules that failed add no facts
+pub fn audit_facts(report: &AuditReport) -> Facts {
+    let mut facts = Facts::new();
+
+    if let Some(products) = &report.antivirus {
+        let active = products.iter().filter(|prod| prod.product_state.scanner == ScannerState::On);
+        number(&mut facts, "antivirus.active_products", active.clone().count() as f64);
+        number(&mut facts, "antivirus.out_of_date_products", active.filter(|prod| prod.product_state.definitions == DefinitionsState::OutOfDate).count() as f64);
+    }
+
+    if let Some(firewall) = &report.firewall {
+        let profiles = [
+            ("public", &firewall.profile.public),
+            ("private", &firewall.profile.private),
+            ("domain", &firewall.profile.domain)
+        ];
+        for (name, profile) in profiles {
+            facts.insert(format!("firewall.{}.enabled", name), FactValue::Bool(profile.profile_enabled));
+            facts.insert(format!("firewall.{}.inbound_blocked", name), FactValue::Bool(profile.inbound_blocked));
+            facts.insert(format!("firewall.{}.outbound_blocked", name), FactValue::Bool(profile.outbound_blocked));
+        }
+        number(&mut facts, "firewall.active_third_party", firewall.products.iter().filter(|prod| third_party_active(prod)).count() as f64);
+    }
+
+    if let Some(updates) = &report.updates {
+        number(&mut facts, "updates.pending", updates.total_count as f64);
+        number(&mut facts, "updates.pending_critical", updates.critical_count as f64);
+        number(&mut facts, "updates.pending_security", updates.security_count as f64);
+
+        /*
+            Shugo: Oldest Pending Update
+
+            "No pending security updates older than 30 days" is checked as `updates.oldest_security_days <= 30`.
+            With nothing pending the oldest is 0 days. If updates are pending but the source couldn't tell us
+            when they were released, the fact is left out so the rule shows as not evaluated instead of passing.
+        */
+        // Classification names are translated, so updates are matched on their category GUID
+        for (name, class) in [("updates.oldest_critical_days", CRITICAL_UPDATES_GUID), ("updates.oldest_security_days", SECURITY_UPDATES_GUID)] {
+            let pending: Vec<Option<u64>> = updates.update_list
+                .iter()
+                .filter(|update| update.classification_id == class)
+                .map(|update| update.age_days)
+                .collect();
+            if pending.is_empty() {
+                number(&mut facts, name, 0.0);
+            } else if let Some(oldest) = pending.iter().flatten().max() {
+                number(&mut facts, name, *oldest as f64);
+            }
+        }
+    }
+
+    if let Some(uac) = &report.uac {
+        let values = [
+            ("uac.EnableLUA", uac.lua_value),
+            ("uac.ConsentPromptBehaviorAdmin", uac.prompt_level_value),
+            ("uac.PromptOnSecureDesktop", uac.prompt_on_secure_desktop_value),
+            ("uac.EnableInstallerDetection", uac.installer_detection_value),
+            ("uac.ValidateAdminCodeSignatures", uac.validate_admin_code_signatures_value),
+            ("uac.FilterAdministratorToken", uac.filter_admin_token_value),
+            ("uac.EnableVirtualization", uac.enable_virtualization_value)
+        ];
+        // Settings that aren't set are left out, so rules checking them come back unknown instead of failing
+        for (name, value) in values {
+            if let Some(value) = value {
+                number(&mut facts, name, value as f64);
+            }
+        }
+    }
+
+    if let Some(uas) = &report.uas {
+        number(&mut facts, "uas.total_accounts", uas.total_users as f64);
+        number(&mut facts, "uas.enabled_accounts", uas.enabled_users as f64);
+        number(&mut facts, "uas.admin_count", uas.admin_count as f64);
+        facts.insert("uas.guest_enabled".to_string(), FactValue::Bool(uas.guest_enabled));
+    }
+
+    // Like UAC, anything the module reported as unknown is left out rather than guessed
+    if let Some(defender) = &report.defender {
+        if let Some(status) = &defender.computer_status {
+            boolean(&mut facts, "defender.real_time_protection", status.real_time_protection_enabled);
+            boolean(&mut facts, "defender.tamper_protected", status.tamper_protected);
+            if let Some(age) = status.signature_age_days {
+                number(&mut facts, "defender.signature_age_days", age as f64);
+            }
+        }
+        // Hidden exclusions can't be counted, so there's nothing to compare against
+        if let Some(exclusions) = defender.preferences.as_ref().map(|preferences| &preferences.exclusions).filter(|exclusions| !exclusions.hidden) {
+            number(&mut facts, "defender.exclusions", (exclusions.paths.len() + exclusions.extensions.len() + exclusions.processes.len()) as f64);
+        }
+    }
+
+    if let Some(os) = &report.os {
+        if let Some(build) = os.build {
+            number(&mut facts, "os.build", build as f64);
+        }
+        if let Some(days) = os.support.as_ref().and_then(|support| support.days_remaining) {
+            number(&mut facts, "os.support_days_remaining", days as f64);
+        }
+    }
+
+    if let Some(smb) = &report.smb {
+        let states = [
+            ("smb.smb1_server", smb.smb1_server()),
+            ("smb.smb1_client", smb.smb1_client()),
+            ("smb.server_signing_required", signing_state(smb.server.require_security_signature)),
+            ("smb.client_signing_required", signing_state(smb.client.require_security_signature)),
+            ("smb.encryption", smb.encryption())
+        ];
+        for (name, state) in states {
+            match state {
+                SmbState::Enabled => boolean(&mut facts, name, Some(true)),
+                SmbState::Disabled => boolean(&mut facts, name, Some(false)),
+                SmbState::Unknown => {}
+            }
+        }
+    }
+
+    if let Some(rdp) = &report.rdp {
+        boolean(&mut facts, "rdp.enabled", rdp.enabled());
+        boolean(&mut facts, "rdp.nla_required", Some(rdp.nla_required()));
+        boolean(&mut facts, "rdp.public_exposure", rdp.public_exposure());
+    }
+
+    if let Some(powershell) = &report.powershell {
+        boolean(&mut facts, "powershell.permissive_policy", powershell.effective_policy().map(|policy| policy.is_permissive()));
+        boolean(&mut facts, "powershell.v2_installed", powershell.v2_installed());
+    }
+
+    if let Some(volumes) = report.bitlocker.as_ref().and_then(|bitlocker| bitlocker.volumes.as_ref()) {
+        // A volume whose protection status wasn't reported is neither protected nor unprotected
+        let protected = |volume: &EncryptableVolume| volume.protection_status.map(|status| status == PROTECTION_ON);
+        boolean(&mut facts, "bitlocker.os_volume_protected", volumes.iter().find(|volume| volume.is_os_volume()).and_then(protected));
+        if volumes.iter().all(|volume| protected(volume).is_some()) {
+            number(&mut facts, "bitlocker.unprotected_volumes", volumes.iter().filter(|volume| protected(volume) == Some(false)).count() as f64);
+        }
+    }
+    if report.bitlocker.as_ref().and_then(|bitlocker| bitlocker.available) == Some(false) {
+        // Without BitLocker nothing Windows encrypts the OS drive
+        boolean(&mut facts, "bitlocker.os_volume_protected", Some(false));
+    }
+
+    if let Some(browser) = &report.browser {
+        number(&mut facts, "browser.outdated", browser.browsers.iter().filter(|status| status.outdated() == Some(true)).count() as f64);
+        number(&mut facts, "browser.safe_browsing_disabled", browser.browsers.iter().filter(|status| !status.safe_browsing_disabled().is_empty()).count() as f64);
+    }
+
+    if let Some(extensions) = &report.extensions {
+        number(&mut facts, "extensions.total", extensions.extensions.len() as f64);
+        number(&mut facts, "extensions.high_risk", extensions.extensions.iter().filter(|extension| extension.risk_level() == RiskLevel::High).count() as f64);
+    }
+
+    if let Some(shares) = report.shares.as_ref().and_then(|shares| shares.shares.as_ref()) {
+        let broad = shares.iter().filter(|share| !share.is_default() && !share.broad_write_access().is_empty());
+        number(&mut facts, "shares.broad_write_access", broad.count() as f64);
+    }
+
+    // A count missing the locations that couldn't be read would pass `== 0` rules on data nobody saw
+    if let Some(startup) = &report.startup {
+        let unreadable = &startup.module_info.unreadable;
+        if unreadable.is_empty() {
+            number(&mut facts, "startup.entries", startup.entries.len() as f64);
+        }
+        if !unreadable.keys().any(|location| IFEO_KEYS.iter().any(|key| location.contains(key))) {
+            number(&mut facts, "startup.debuggers", startup.entries.iter().filter(|entry| entry.kind == EntryKind::Debugger).count() as f64);
+        }
+    }
+
+    facts
+}
+
+fn number(facts: &mut Facts, name: &str, value: f64) {
+    facts.insert(name.to_string(), FactValue::Number(value));
+}
+
+/// Adds a yes/no fact, leaving it out when the module couldn't tell
+fn boolean(facts: &mut Facts, name: &str, value: Option<bool>) {
+    if let Some(value) = value {
+        facts.insert(name.to_string(), FactValue::Bool(value));
+    }
+}
+
+/// Matches a fact name against a pattern where `*` stands for one dotted segment
+pub fn fact_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    let name: Vec<&str> = name.split('.').collect();
+    pattern.len() == name.len()
+        && pattern.iter().zip(&name).all(|(p, n)| *p == "*" || p.eq_ignore_ascii_case(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::scanner::run_audit_with;
+    use crate::bitlocker::scanner::{BITLOCKER_NAMESPACE, VOLUME_OS};
+    use crate::common::fixture::FixtureSource;
+    use crate::common::offline::OfflineSource;
+    use crate::common::source::{RegistryHive, RegistryValue, WmiRow, WmiValue};
+    use crate::rdp::scanner::TERMINAL_SERVER_KEY;
+    use crate::uac::scanner::UAC_KEY;
+    use crate::updates::scanner::tests::update;
+
+    #[test]
+    fn only_produces_documented_facts() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, UAC_KEY, "ConsentPromptBehaviorAdmin", RegistryValue::Dword(5))
+            .with_users(vec![])
+            .with_updates(vec![]);
+        let facts = audit_facts(&run_audit_with(&source));
+
+        assert!(facts.keys().all(|name| FACT_NAMES.contains(&name.as_str())));
+        assert_eq!(facts["uac.ConsentPromptBehaviorAdmin"], FactValue::Number(5.0));
+        assert_eq!(facts["updates.oldest_security_days"], FactValue::Number(0.0));
+        assert_eq!(facts["uas.guest_enabled"], FactValue::Bool(false));
+        assert!(!facts.contains_key("firewall.public.enabled"));
+    }
+
+    #[test]
+    fn finds_oldest_updates_in_any_language() {
+        let source = FixtureSource::new()
+            .with_users(vec![])
+            .with_updates(vec![
+                update("Kumulatives Update", "Sicherheitsupdates", SECURITY_UPDATES_GUID),
+                update("Mise à jour critique", "Mises à jour critiques", CRITICAL_UPDATES_GUID)
+            ]);
+        let facts = audit_facts(&run_audit_with(&source));
+        assert_eq!(facts["updates.oldest_security_days"], FactValue::Number(10.0));
+        assert_eq!(facts["updates.oldest_critical_days"], FactValue::Number(10.0));
+    }
+
+    #[test]
+    fn leaves_out_settings_modules_couldnt_read() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, TERMINAL_SERVER_KEY, "fDenyTSConnections", RegistryValue::Dword(0))
+            .with_users(vec![]);
+        let facts = audit_facts(&run_audit_with(&source));
+
+        assert_eq!(facts["rdp.enabled"], FactValue::Bool(true));
+        assert_eq!(facts["rdp.nla_required"], FactValue::Bool(true));
+        // The firewall wasn't readable, so neither is whether RDP is open on the Public profile
+        assert!(!facts.contains_key("rdp.public_exposure"));
+        assert!(!facts.contains_key("defender.real_time_protection"));
+        // Extensions can't be read offline, so there's no count at all rather than a count of 0
+        assert!(!facts.contains_key("extensions.total"));
+    }
+
+    #[test]
+    fn leaves_out_counts_over_unread_data() {
+        // The OS volume didn't report a protection status, which isn't the same as unprotected
+        let volume = WmiRow::new()
+            .with("DeviceID", WmiValue::String("\\\\?\\Volume{1}\\".to_string()))
+            .with("DriveLetter", WmiValue::String("C:".to_string()))
+            .with("VolumeType", WmiValue::U32(VOLUME_OS));
+        let source = FixtureSource::new().with_wmi(BITLOCKER_NAMESPACE, "Win32_EncryptableVolume", vec![volume]);
+        let facts = audit_facts(&run_audit_with(&source));
+        assert!(!facts.contains_key("bitlocker.os_volume_protected"));
+        assert!(!facts.contains_key("bitlocker.unprotected_volumes"));
+
+        // None of the startup locations could be read, so there's no count to compare against, and without
+        // SOFTWARE or NTUSER.DAT there's no execution policy either
+        let facts = audit_facts(&run_audit_with(&OfflineSource::new()));
+        assert!(!facts.contains_key("powershell.permissive_policy"));
+        assert!(!facts.contains_key("startup.entries"));
+        assert!(!facts.contains_key("startup.debuggers"));
+    }
+
+    #[test]
+    fn wildcards_match_one_segment() {
+        assert!(fact_matches("firewall.*.enabled", "firewall.public.enabled"));
+        assert!(fact_matches("UAC.enablelua", "uac.EnableLUA"));
+        assert!(!fact_matches("firewall.*", "firewall.public.enabled"));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod facts;
+pub mod rules;
+pub mod baselines;
+pub mod display;
\ No newline at end of file
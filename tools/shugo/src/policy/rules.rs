@@ -0,0 +1,346 @@
+//! Policy files describe what "compliant" means for an organization as a list of rules, each
+//! comparing one fact against a value. They can be written in TOML or YAML:
+//!
+//! ```toml
+//! name = "Workstation Baseline"
+//!
+//! [[rules]]
+//! id = "uac-prompt"
+//! title = "Administrators are prompted for consent on the secure desktop"
+//! check = "uac.ConsentPromptBehaviorAdmin"
+//! operator = "=="
+//! value = 2
+//! severity = "high"
+//! reference = "CIS 2.3.17.2"
+//! ```
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::facts::{FACT_NAMES, FactValue, Facts, fact_matches};
+use crate::common::finding::{Finding, Severity};
+use crate::common::error::ShugoError;
+use crate::common::source::SourceResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operator {
+    #[serde(rename = "==")]
+    Equal,
+    #[serde(rename = "!=")]
+    NotEqual,
+    #[serde(rename = "<")]
+    Less,
+    #[serde(rename = "<=")]
+    LessOrEqual,
+    #[serde(rename = ">")]
+    Greater,
+    #[serde(rename = ">=")]
+    GreaterOrEqual
+}
+
+impl Operator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">="
+        }
+    }
+
+    /// Compares `actual` against `expected`, `None` if the operator doesn't apply to these values
+    pub fn compare(&self, actual: FactValue, expected: FactValue) -> Option<bool> {
+        match (actual, expected) {
+            (FactValue::Number(actual), FactValue::Number(expected)) => Some(match self {
+                Operator::Equal => actual == expected,
+                Operator::NotEqual => actual != expected,
+                Operator::Less => actual < expected,
+                Operator::LessOrEqual => actual <= expected,
+                Operator::Greater => actual > expected,
+                Operator::GreaterOrEqual => actual >= expected
+            }),
+            (FactValue::Bool(actual), FactValue::Bool(expected)) => match self {
+                Operator::Equal => Some(actual == expected),
+                Operator::NotEqual => Some(actual != expected),
+                _ => None
+            },
+            _ => None
+        }
+    }
+}
+
+fn default_severity() -> Severity {
+    Severity::Medium
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    pub id: String,
+    pub title: String,
+    /// Fact name to check, see `FACT_NAMES`
+    pub check: String,
+    pub operator: Operator,
+    pub value: FactValue,
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+    #[serde(default)]
+    pub remediation: String,
+    /// Where the rule comes from, for example a CIS Benchmark recommendation number
+    #[serde(default)]
+    pub reference: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>
+}
+
+impl Policy {
+    /// Loads a policy, `.toml` files as TOML and `.yaml`/`.yml` files as YAML
+    pub fn open(path: &Path) -> SourceResult<Policy> {
+        let text = fs::read_to_string(path).map_err(|error| ShugoError::io(path.display().to_string(), &error))?;
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let policy = match extension.as_str() {
+            "toml" => Policy::from_toml(&text),
+            "yaml" | "yml" => Policy::from_yaml(&text),
+            _ => Err(ShugoError::parse("policy", "policy files need a .toml, .yaml, or .yml extension"))
+        };
+        policy.map_err(|error| error.for_query(path.display().to_string()))
+    }
+
+    pub fn from_toml(text: &str) -> SourceResult<Policy> {
+        let policy: Policy = toml::from_str(text).map_err(|error| ShugoError::parse("policy", error.to_string()))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn from_yaml(text: &str) -> SourceResult<Policy> {
+        let policy: Policy = serde_yaml_ng::from_str(text).map_err(|error| ShugoError::parse("policy", error.to_string()))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Catches typos before any checks run: duplicate rule IDs and checks that match no fact
+    fn validate(&self) -> SourceResult<()> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if self.rules[..i].iter().any(|other| other.id == rule.id) {
+                return Err(ShugoError::parse("policy", format!("rule id {} is used more than once", rule.id)));
+            }
+            if !FACT_NAMES.iter().any(|name| fact_matches(&rule.check, name)) {
+                return Err(ShugoError::parse("policy", format!("rule {} checks unknown fact {}", rule.id, rule.check)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleStatus {
+    Pass,
+    Fail,
+    /// The data the rule needs wasn't collected, usually because its module didn't run
+    Unknown
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleResult {
+    pub rule: PolicyRule,
+    pub status: RuleStatus,
+    /// The facts the rule was checked against, as `name=value`
+    pub actual: Vec<String>,
+    pub message: String
+}
+
+impl RuleResult {
+    /// The rule as a finding, so violations can go wherever module findings go
+    pub fn finding(&self) -> Finding {
+        let mut finding = Finding::new(&self.rule.id, "policy", self.rule.severity, &self.rule.title)
+            .explanation(&self.message)
+            .remediation(&self.rule.remediation);
+        finding.evidence = self.actual.clone();
+        if !self.rule.reference.is_empty() {
+            finding = finding.evidence(format!("Reference: {}", self.rule.reference));
+        }
+        finding
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyReport {
+    pub policy: String,
+    pub results: Vec<RuleResult>
+}
+
+impl PolicyReport {
+    pub fn count(&self, status: RuleStatus) -> usize {
+        self.results.iter().filter(|result| result.status == status).count()
+    }
+
+    /// True when no rule failed. Rules that couldn't be evaluated don't count as violations, see `complete`
+    pub fn compliant(&self) -> bool {
+        self.count(RuleStatus::Fail) == 0
+    }
+
+    /// True when every rule could be evaluated
+    pub fn complete(&self) -> bool {
+        self.count(RuleStatus::Unknown) == 0
+    }
+
+    /// The exit code for `shugo check`: 1 when a rule failed, 3 when none failed but some couldn't be evaluated,
+    /// and 0 when every rule passed. A module that didn't run can't hide a violation behind a passing gate
+    pub fn exit_code(&self) -> i32 {
+        if !self.compliant() {
+            1
+        } else if !self.complete() {
+            3
+        } else {
+            0
+        }
+    }
+}
+
+/// Checking every rule in a policy against a set of facts
+pub fn evaluate_policy(policy: &Policy, facts: &Facts) -> PolicyReport {
+    let results = policy.rules.iter().map(|rule| evaluate_rule(rule, facts)).collect();
+    PolicyReport { policy: policy.name.clone(), results }
+}
+
+fn evaluate_rule(rule: &PolicyRule, facts: &Facts) -> RuleResult {
+    /*
+        Shugo: Wildcard Checks
+
+        A check like `firewall.*.enabled` matches several facts. The rule only passes if every one of them
+        passes, so one disabled profile is enough to fail "all firewall profiles enabled".
+    */
+    let matched: Vec<(&String, &FactValue)> = facts.iter().filter(|(name, _)| fact_matches(&rule.check, name)).collect();
+    let actual: Vec<String> = matched.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+    let result = |status: RuleStatus, message: String| RuleResult { rule: rule.clone(), status, actual: actual.clone(), message };
+
+    if matched.is_empty() {
+        return result(RuleStatus::Unknown, format!("No data for {}, its module didn't run", rule.check));
+    }
+
+    let mut failed = Vec::new();
+    for (name, value) in &matched {
+        match rule.operator.compare(**value, rule.value) {
+            Some(true) => {},
+            Some(false) => failed.push(format!("{}={}", name, value)),
+            None => return result(
+                RuleStatus::Unknown,
+                format!("Can't compare {}={} with {} {}", name, value, rule.operator.symbol(), rule.value)
+            )
+        }
+    }
+
+    if failed.is_empty() {
+        result(RuleStatus::Pass, format!("{} {} {}", rule.check, rule.operator.symbol(), rule.value))
+    } else {
+        result(RuleStatus::Fail, format!("Expected {} {} {}, found {}", rule.check, rule.operator.symbol(), rule.value, failed.join(", ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML_POLICY: &str = r#"
+name = "Test Baseline"
+
+[[rules]]
+id = "max-admins"
+title = "No more than two administrators"
+check = "uas.admin_count"
+operator = "<="
+value = 2
+
+[[rules]]
+id = "firewall-on"
+title = "All firewall profiles enabled"
+check = "firewall.*.enabled"
+operator = "=="
+value = true
+severity = "critical"
+
+[[rules]]
+id = "uac-prompt"
+title = "Administrators are prompted"
+check = "uac.ConsentPromptBehaviorAdmin"
+operator = ">="
+value = 2
+"#;
+
+    fn facts() -> Facts {
+        let mut facts = Facts::new();
+        facts.insert("uas.admin_count".to_string(), FactValue::Number(3.0));
+        facts.insert("firewall.public.enabled".to_string(), FactValue::Bool(true));
+        facts.insert("firewall.private.enabled".to_string(), FactValue::Bool(false));
+        facts
+    }
+
+    #[test]
+    fn evaluates_rules() {
+        let policy = Policy::from_toml(TOML_POLICY).unwrap();
+        let report = evaluate_policy(&policy, &facts());
+
+        assert_eq!(report.results[0].status, RuleStatus::Fail);
+        assert_eq!(report.results[1].status, RuleStatus::Fail);
+        assert_eq!(report.results[1].rule.severity, Severity::Critical);
+        assert!(report.results[1].message.contains("firewall.private.enabled=false"));
+        assert_eq!(report.results[2].status, RuleStatus::Unknown);
+        assert!(!report.compliant());
+        assert_eq!(report.exit_code(), 1);
+    }
+
+    #[test]
+    fn unevaluated_rules_are_not_a_pass() {
+        let policy = Policy::from_toml(TOML_POLICY).unwrap();
+        let mut facts = Facts::new();
+        facts.insert("uas.admin_count".to_string(), FactValue::Number(1.0));
+        facts.insert("firewall.public.enabled".to_string(), FactValue::Bool(true));
+
+        let report = evaluate_policy(&policy, &facts);
+        assert_eq!(report.count(RuleStatus::Unknown), 1);
+        assert!(report.compliant() && !report.complete());
+        assert_eq!(report.exit_code(), 3);
+
+        let report = evaluate_policy(&policy, &Facts::new());
+        assert_eq!(report.count(RuleStatus::Unknown), 3);
+        assert_eq!(report.exit_code(), 3);
+    }
+
+    #[test]
+    fn reads_yaml() {
+        let policy = Policy::from_yaml(
+            "name: Yaml Baseline\nrules:\n  - id: guest\n    title: Guest disabled\n    check: uas.guest_enabled\n    operator: \"==\"\n    value: false\n"
+        ).unwrap();
+        let mut facts = Facts::new();
+        facts.insert("uas.guest_enabled".to_string(), FactValue::Bool(false));
+
+        let report = evaluate_policy(&policy, &facts);
+        assert!(report.compliant() && report.complete());
+        assert_eq!(report.count(RuleStatus::Pass), 1);
+        assert_eq!(report.exit_code(), 0);
+    }
+
+    #[test]
+    fn rejects_bad_policies() {
+        let unknown = "name = \"x\"\n[[rules]]\nid = \"a\"\ntitle = \"a\"\ncheck = \"uac.Nope\"\noperator = \"==\"\nvalue = 1\n";
+        assert!(Policy::from_toml(unknown).is_err());
+        let operator = "name = \"x\"\n[[rules]]\nid = \"a\"\ntitle = \"a\"\ncheck = \"uas.admin_count\"\noperator = \"=>\"\nvalue = 1\n";
+        assert!(Policy::from_toml(operator).is_err());
+        assert_eq!(Operator::Less.compare(FactValue::Bool(true), FactValue::Bool(false)), None);
+    }
+}
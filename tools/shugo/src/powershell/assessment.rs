@@ -0,0 +1,161 @@
+//! Assessment for the PowerShell Module, turns execution policies, logging, and PowerShell 2.0 into findings.
+use super::scanner::PowerShellInfo;
+use crate::common::finding::{Finding, Severity};
+use crate::common::source::RegistryHive;
+
+const MODULE: &str = "powershell";
+
+/// Assessing PowerShell
+pub fn assess_powershell(info: &PowerShellInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if let Some((scope, policy)) = info.execution_policies.winning_scope().filter(|(_, policy)| policy.is_permissive()) {
+        findings.push(
+            Finding::new("PS-001", MODULE, Severity::High, format!("The PowerShell execution policy is {}", policy))
+                .explanation(format!(
+                    "{} runs any script, including ones downloaded from the internet, without a signature or a warning. The execution policy isn't a security boundary, but it's what stops a script someone was tricked into opening from just running",
+                    policy
+                ))
+                .remediation("Run `Set-ExecutionPolicy RemoteSigned -Scope LocalMachine`, or set \"Turn on Script Execution\" to \"Allow local scripts and remote signed scripts\" in Group Policy")
+                .evidence(format!("scope={}", scope))
+                .evidence(format!("policy={}", policy))
+        );
+    }
+
+    if info.v2_installed() == Some(true) {
+        let clr2 = info.v2.clr2_installed;
+        // Without the .NET 2.0/3.5 runtime the engine can't start, but one install away from working is still a risk
+        let runnable = clr2 != Some(false);
+        findings.push(
+            Finding::new("PS-002", MODULE, if runnable {Severity::High} else {Severity::Medium}, "The PowerShell 2.0 engine is installed")
+                .explanation(if runnable {
+                    "`powershell -Version 2` starts a session without AMSI, Script Block Logging, or Constrained Language Mode, so attackers downgrade to it to run scripts nothing would catch"
+                } else {
+                    "The .NET Framework 3.5 runtime PowerShell 2.0 needs isn't installed so it can't start yet, but installing .NET 3.5 for any other program would bring back the downgrade attack"
+                })
+                .remediation("Run `Disable-WindowsOptionalFeature -Online -FeatureName MicrosoftWindowsPowerShellV2Root`")
+                .evidence(format!("engine_version={}", info.v2.engine_version.as_deref().unwrap_or("not set")))
+                .evidence(format!("clr2_installed={}", clr2.map(|installed| installed.to_string()).unwrap_or_else(|| "unknown".to_string())))
+        );
+    }
+
+    // A logging value that couldn't be read is reported by PS-006, not as logging being off
+    let unread = |value: &str| info.module_info.unreadable.keys().any(|key| {
+        key == RegistryHive::LocalMachine.root_name() || key.ends_with(&format!("\\{}", value))
+    });
+
+    if info.logging.script_block_logging != Some(1) && !unread("EnableScriptBlockLogging") {
+        findings.push(
+            Finding::new("PS-003", MODULE, Severity::Medium, "PowerShell Script Block Logging is off")
+                .explanation("Script Block Logging records every script PowerShell runs, after it's been decoded. Without it, attacks that use encoded or obfuscated commands leave almost nothing behind")
+                .remediation("Enable \"Turn on PowerShell Script Block Logging\" under Administrative Templates > Windows Components > Windows PowerShell")
+                .evidence(format!("EnableScriptBlockLogging={}", value(info.logging.script_block_logging)))
+        );
+    }
+
+    if info.logging.module_logging != Some(1) && !unread("EnableModuleLogging") {
+        findings.push(
+            Finding::new("PS-004", MODULE, Severity::Low, "PowerShell Module Logging is off")
+                .explanation("Module Logging records the commands run from PowerShell modules and their parameters, which helps rebuild what happened during an incident")
+                .remediation("Enable \"Turn on Module Logging\" and set the module names to `*`")
+                .evidence(format!("EnableModuleLogging={}", value(info.logging.module_logging)))
+        );
+    }
+
+    if info.logging.transcription != Some(1) && !unread("EnableTranscripting") {
+        findings.push(
+            Finding::new("PS-005", MODULE, Severity::Low, "PowerShell Transcription is off")
+                .explanation("Transcription saves a text copy of every PowerShell session, including the output, which the event logs don't keep")
+                .remediation("Enable \"Turn on PowerShell Transcription\" and point the output directory at a share users can write to but not read")
+                .evidence(format!("EnableTranscripting={}", value(info.logging.transcription)))
+        );
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        findings.push(
+            Finding::new("PS-006", MODULE, Severity::Info, "Some PowerShell settings couldn't be read")
+                .explanation("Shugo couldn't check every scope or setting, so the effective policy or logging state may be different")
+                .remediation("Run Shugo as administrator, or include both the SOFTWARE hive and NTUSER.DAT when scanning offline")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+    }
+
+    findings
+}
+
+fn value(data: Option<u32>) -> String {
+    data.map(|data| data.to_string()).unwrap_or_else(|| "not set".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::feature::FEATURE_NAMESPACE;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::{RegistryValue, WmiRow, WmiValue};
+    use crate::powershell::scanner::{scan_powershell_with, ENGINE_KEY, POLICY_KEY, SHELL_KEY};
+
+    /// Script Block Logging, Module Logging, and Transcription all turned on by Group Policy
+    fn logged() -> FixtureSource {
+        [("ScriptBlockLogging", "EnableScriptBlockLogging"), ("ModuleLogging", "EnableModuleLogging"), ("Transcription", "EnableTranscripting")]
+            .into_iter()
+            .fold(FixtureSource::new(), |source, (policy, value)| {
+                source.with_registry(RegistryHive::LocalMachine, &format!("{}\\{}", POLICY_KEY, policy), value, RegistryValue::Dword(1))
+            })
+            .with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![])
+    }
+
+    fn text(value: &str) -> RegistryValue {
+        RegistryValue::String(value.to_string())
+    }
+
+    #[test]
+    fn hardened_powershell_has_no_findings() {
+        let source = logged().with_registry(RegistryHive::LocalMachine, SHELL_KEY, "ExecutionPolicy", text("RemoteSigned"));
+        assert!(assess_powershell(&scan_powershell_with(&source).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn flags_permissive_policy_and_missing_logging() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, SHELL_KEY, "ExecutionPolicy", text("Bypass"))
+            .with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![]);
+        let findings = assess_powershell(&scan_powershell_with(&source).unwrap());
+        assert_eq!(findings[0].evidence, vec!["scope=LocalMachine", "policy=Bypass"]);
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["PS-001", "PS-003", "PS-004", "PS-005"]);
+    }
+
+    #[test]
+    fn v2_severity_depends_on_clr2() {
+        // Without the optional features listed the engine key decides, and the CLR2 key is gone when .NET 3.5 isn't installed
+        let source = logged()
+            .with_registry(RegistryHive::LocalMachine, ENGINE_KEY, "PowerShellVersion", text("2.0"))
+            .with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![
+                WmiRow::new()
+                    .with("Name", WmiValue::String("MicrosoftWindowsPowerShellV2".to_string()))
+                    .with("InstallState", WmiValue::U32(1))
+            ]);
+        let findings = assess_powershell(&scan_powershell_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("PS-002", Severity::Medium));
+        assert!(findings[0].evidence.contains(&"clr2_installed=false".to_string()));
+    }
+
+    #[test]
+    fn unreadable_logging_is_not_off() {
+        let source = logged().with_registry(RegistryHive::LocalMachine, &format!("{}\\ScriptBlockLogging", POLICY_KEY), "EnableScriptBlockLogging", text("1"));
+        let findings = assess_powershell(&scan_powershell_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("PS-006", Severity::Info));
+        assert!(findings[0].evidence[0].ends_with("ScriptBlockLogging\\EnableScriptBlockLogging"));
+    }
+
+    #[test]
+    fn unknown_policy_text_is_not_permissive() {
+        let source = logged().with_registry(RegistryHive::LocalMachine, SHELL_KEY, "ExecutionPolicy", text("Whatever"));
+        let findings = assess_powershell(&scan_powershell_with(&source).unwrap());
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["PS-006"]);
+    }
+}
@@ -0,0 +1,122 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_powershell;
+use super::scanner::PowerShellInfo;
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::get_time;
+
+/// Display for PowerShell Module
+pub fn display_powershell(info: &PowerShellInfo, verbose: bool) {
+    println!();
+    println!("POWERSHELL SECURITY STATUS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_execution_policy(info);
+
+    display_logging(info, verbose);
+
+    display_v2(info, verbose);
+
+    display_findings(&assess_powershell(info), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for PowerShell Module
+pub fn json_powershell(info: &PowerShellInfo) -> Value {
+    let decoded = json!({
+        "effective_policy": info.effective_policy(),
+        "winning_scope": info.execution_policies.winning_scope().map(|(scope, _)| scope),
+        "script_block_logging": info.logging.script_block_logging == Some(1),
+        "module_logging": info.logging.module_logging == Some(1),
+        "transcription": info.logging.transcription == Some(1),
+        "v2_installed": info.v2_installed()
+    });
+
+    json!({
+        "powershell": with_decoded(info, decoded),
+        "findings": assess_powershell(info)
+    })
+}
+
+fn display_scan_details(info: &PowerShellInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Registry Keys:");
+    for key in info.module_info.registry_keys.iter() {
+        println!("   - {}", key);
+    }
+    println!(" - Query:");
+    for query in info.module_info.queries.iter() {
+        println!("   - {}", query);
+    }
+    for (location, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", location, reason);
+    }
+    println!();
+}
+
+fn display_execution_policy(info: &PowerShellInfo) {
+    println!("Execution Policy:");
+    for (scope, policy) in info.execution_policies.scopes() {
+        println!(" - {}: {}", scope, policy.map(|policy| policy.to_string()).unwrap_or_else(|| "Undefined".to_string()));
+    }
+    match (info.effective_policy(), info.execution_policies.winning_scope()) {
+        (Some(policy), Some((scope, _))) => println!(" - Effective: {} (from {})", policy, scope),
+        (Some(policy), None) => println!(" - Effective: {} (Windows default)", policy),
+        (None, _) => println!(" - Effective: Unknown (some scopes couldn't be read)")
+    }
+    println!();
+}
+
+fn display_logging(info: &PowerShellInfo, verbose: bool) {
+    let on = |flag: Option<u32>| if flag == Some(1) {"Enabled"} else {"Disabled"};
+    println!("Logging:");
+    println!(" - Script Block Logging: {}", on(info.logging.script_block_logging));
+    if verbose {println!("   - Invocation Logging: {}", on(info.logging.script_block_invocation_logging));}
+    println!(" - Module Logging: {}", on(info.logging.module_logging));
+    println!(" - Transcription: {}", on(info.logging.transcription));
+    if verbose && let Some(directory) = &info.logging.transcription_directory {
+        println!("   - Output Directory: {}", directory);
+    }
+    println!();
+}
+
+fn display_v2(info: &PowerShellInfo, verbose: bool) {
+    println!("PowerShell 2.0:");
+    let installed = match info.v2_installed() {
+        Some(true) => "Installed",
+        Some(false) => "Not installed",
+        None => "Unknown"
+    };
+    println!(" - Engine: {}", installed);
+    let clr2 = match info.v2.clr2_installed {
+        Some(true) => "Installed",
+        Some(false) => "Not installed",
+        None => "Unknown"
+    };
+    println!(" - .NET Framework 2.0/3.5 Runtime: {}", clr2);
+    if verbose {
+        match &info.v2.features {
+            Some(features) => {
+                for feature in features.iter() {
+                    println!("   - {}: {}", feature.name, feature.install_state_text());
+                }
+            },
+            None => println!("   - Optional features weren't checked")
+        }
+    }
+    println!();
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - Access Rights: KEY_READ");
+    println!(" - Registry Hives: HKEY_LOCAL_MACHINE, HKEY_CURRENT_USER");
+    println!(" - Process Scope: %PSExecutionPolicyPreference% (live scans only)");
+    println!();
+}
@@ -0,0 +1,459 @@
+//! This is the PowerShell Module for Shugo. Here we can see:
+//!
+//! - Execution Policy at every scope and the one in effect
+//! - Script Block Logging, Module Logging, and Transcription
+//! - Whether the PowerShell 2.0 engine is still installed
+//!
+//! Everything but the optional feature state and the Process scope comes from the registry, so it also
+//! works on exported hives (SOFTWARE for the machine, NTUSER.DAT for the user).
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::ShugoError;
+use crate::common::feature::{find_feature, OptionalFeature, FEATURE_NAMESPACE};
+use crate::common::source::{SecuritySource, SourceResult, RegistryHive, registry_dword, registry_string, system_source};
+use crate::common::wmi::query;
+
+/// The execution policies PowerShell knows about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExecutionPolicy {
+    Restricted,
+    AllSigned,
+    RemoteSigned,
+    Unrestricted,
+    Bypass,
+    Undefined
+}
+
+/// The scopes an execution policy can be set at, in the order PowerShell checks them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolicyScope {
+    MachinePolicy,
+    UserPolicy,
+    Process,
+    CurrentUser,
+    LocalMachine
+}
+
+/// The execution policy at each scope, `None` when it isn't set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionPolicies {
+    pub machine_policy: Option<ExecutionPolicy>,
+    pub user_policy: Option<ExecutionPolicy>,
+    /// From the PSExecutionPolicyPreference environment variable, only read on a live machine
+    pub process: Option<ExecutionPolicy>,
+    pub current_user: Option<ExecutionPolicy>,
+    pub local_machine: Option<ExecutionPolicy>
+}
+
+/// PowerShell logging Group Policy, each `None` when it isn't set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoggingSettings {
+    pub script_block_logging: Option<u32>,
+    pub script_block_invocation_logging: Option<u32>,
+    pub module_logging: Option<u32>,
+    pub transcription: Option<u32>,
+    pub transcription_directory: Option<String>
+}
+
+/// What's left of PowerShell 2.0 on the machine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PowerShellV2 {
+    /// The MicrosoftWindowsPowerShellV2 features, `None` when the source can't run WMI
+    pub features: Option<Vec<OptionalFeature>>,
+    /// PowerShellVersion under the PowerShell\1\PowerShellEngine key, "2.0" while the engine is installed
+    pub engine_version: Option<String>,
+    /// Whether the .NET Framework 2.0/3.5 runtime PowerShell 2.0 needs is installed
+    pub clr2_installed: Option<bool>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PowerShellInfo {
+    pub execution_policies: ExecutionPolicies,
+    /// Whether this is Windows Server, which changes the default execution policy
+    pub server: Option<bool>,
+    pub logging: LoggingSettings,
+    pub v2: PowerShellV2,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub registry_keys: Vec<String>,
+    pub queries: Vec<String>,
+    /// Keys and values that couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+pub const POLICY_KEY: &str = "SOFTWARE\\Policies\\Microsoft\\Windows\\PowerShell";
+pub const SHELL_KEY: &str = "SOFTWARE\\Microsoft\\PowerShell\\1\\ShellIds\\Microsoft.PowerShell";
+pub const ENGINE_KEY: &str = "SOFTWARE\\Microsoft\\PowerShell\\1\\PowerShellEngine";
+pub const CLR2_KEY: &str = "SOFTWARE\\Microsoft\\NET Framework Setup\\NDP\\v2.0.50727";
+pub const VERSION_KEY: &str = "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion";
+pub const FEATURE_QUERY: &str = "SELECT Name, InstallState FROM Win32_OptionalFeature WHERE Name LIKE 'MicrosoftWindowsPowerShellV2%'";
+pub const PROCESS_VARIABLE: &str = "PSExecutionPolicyPreference";
+
+/// Scanning PowerShell for Windows, including the Process scope of Shugo's own environment
+pub fn scan_powershell() -> SourceResult<PowerShellInfo> {
+    let mut info = scan_powershell_with(system_source()?.as_ref())?;
+    info.execution_policies.process = env::var(PROCESS_VARIABLE).ok().and_then(|text| ExecutionPolicy::parse(&text));
+    info.module_info.queries.push(format!("%{}%", PROCESS_VARIABLE));
+    Ok(info)
+}
+
+/// Scanning PowerShell from any source. The Process scope only exists on a live machine so it's left unset
+pub fn scan_powershell_with(source: &dyn SecuritySource) -> SourceResult<PowerShellInfo> {
+    /*
+        Shugo: Where Execution Policies Live
+
+        `Get-ExecutionPolicy -List` shows five scopes, and PowerShell uses the first one that isn't Undefined:
+
+        MachinePolicy: Group Policy for the machine, HKLM\SOFTWARE\Policies\Microsoft\Windows\PowerShell
+        UserPolicy: Group Policy for the user, the same path under HKCU
+        Process: the PSExecutionPolicyPreference environment variable, or `-ExecutionPolicy` on the command line
+        CurrentUser: HKCU\SOFTWARE\Microsoft\PowerShell\1\ShellIds\Microsoft.PowerShell
+        LocalMachine: the same path under HKLM
+
+        The Group Policy keys store `EnableScripts` (0 means Restricted) and `ExecutionPolicy`, the other two only
+        store `ExecutionPolicy`. If nothing is set the default is Restricted on Windows and RemoteSigned on Windows Server.
+
+        The execution policy is not a security boundary, anyone can run `powershell -ExecutionPolicy Bypass`. It stops
+        scripts from running by accident, which is why Unrestricted and Bypass as a default are still worth flagging.
+
+        For more information on execution policies:
+        (https://learn.microsoft.com/en-us/powershell/module/microsoft.powershell.core/about/about_execution_policies)
+    */
+    let mut queries: Vec<String> = Vec::new();
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+    let mut record = |hive: RegistryHive, key: &str, value: &str, result: SourceResult<RegistryData>| -> SourceResult<Option<RegistryData>> {
+        let path = format!("{}\\{}\\{}", hive.root_name(), key, value);
+        queries.push(path.clone());
+        match result {
+            Ok(data) => Ok(Some(data)),
+            // Most of these keys only exist once someone sets a policy, so a missing key is the same as a missing value
+            Err(ShugoError::ValueMissing { .. } | ShugoError::NotFound { .. }) => Ok(None),
+            // An offline scan without NTUSER.DAT or SOFTWARE can't answer for that hive
+            Err(error @ ShugoError::Unavailable { .. }) => {
+                unreadable.entry(hive.root_name().to_string()).or_insert_with(|| error.detail());
+                Ok(None)
+            },
+            Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::ParseError { .. })) => {
+                unreadable.insert(path, error.to_string());
+                Ok(None)
+            },
+            Err(error) => Err(error.in_module("powershell"))
+        }
+    };
+    let dword = |hive: RegistryHive, key: &str, value: &str| registry_dword(source, hive, key, value).map(RegistryData::Dword);
+    let string = |hive: RegistryHive, key: &str, value: &str| registry_string(source, hive, key, value).map(RegistryData::String);
+
+    let mut policy_at = |hive: RegistryHive, key: &str, group_policy: bool| -> SourceResult<Option<ExecutionPolicy>> {
+        if group_policy && record(hive, key, "EnableScripts", dword(hive, key, "EnableScripts"))?.and_then(RegistryData::dword) == Some(0) {
+            return Ok(Some(ExecutionPolicy::Restricted));
+        }
+        let Some(text) = record(hive, key, "ExecutionPolicy", string(hive, key, "ExecutionPolicy"))?.and_then(RegistryData::string) else {
+            return Ok(None);
+        };
+        match ExecutionPolicy::parse(&text) {
+            Some(policy) => Ok(Some(policy)),
+            None => {
+                record(hive, key, "ExecutionPolicy", Err(ShugoError::parse(hive.value_path(key, "ExecutionPolicy"), format!("{:?} is not an execution policy", text))))?;
+                Ok(None)
+            }
+        }
+    };
+
+    let execution_policies = ExecutionPolicies {
+        machine_policy: policy_at(RegistryHive::LocalMachine, POLICY_KEY, true)?,
+        user_policy: policy_at(RegistryHive::CurrentUser, POLICY_KEY, true)?,
+        process: None,
+        current_user: policy_at(RegistryHive::CurrentUser, SHELL_KEY, false)?,
+        local_machine: policy_at(RegistryHive::LocalMachine, SHELL_KEY, false)?
+    };
+
+    /*
+        Shugo: PowerShell Logging
+
+        PowerShell can record what it runs in three ways, all turned on through Group Policy:
+
+        Script Block Logging (ScriptBlockLogging\EnableScriptBlockLogging): every block of code PowerShell compiles
+        goes to the Microsoft-Windows-PowerShell/Operational event log as event 4104, after deobfuscation.
+        Module Logging (ModuleLogging\EnableModuleLogging): the commands run from the modules listed in ModuleNames.
+        Transcription (Transcription\EnableTranscripting): a text copy of every session's input and output.
+
+        Script Block Logging is the one defenders lean on most, since attackers often hide scripts in encoded commands.
+    */
+    let machine = RegistryHive::LocalMachine;
+    let mut logging_flag = |policy: &str, value: &str| -> SourceResult<Option<u32>> {
+        let key = format!("{}\\{}", POLICY_KEY, policy);
+        Ok(record(machine, &key, value, dword(machine, &key, value))?.and_then(RegistryData::dword))
+    };
+    let script_block_logging = logging_flag("ScriptBlockLogging", "EnableScriptBlockLogging")?;
+    let script_block_invocation_logging = logging_flag("ScriptBlockLogging", "EnableScriptBlockInvocationLogging")?;
+    let module_logging = logging_flag("ModuleLogging", "EnableModuleLogging")?;
+    let transcription = logging_flag("Transcription", "EnableTranscripting")?;
+    let transcription_key = format!("{}\\Transcription", POLICY_KEY);
+    let logging = LoggingSettings {
+        script_block_logging,
+        script_block_invocation_logging,
+        module_logging,
+        transcription,
+        transcription_directory: record(machine, &transcription_key, "OutputDirectory", string(machine, &transcription_key, "OutputDirectory"))?.and_then(RegistryData::string)
+    };
+
+    /*
+        Shugo: The PowerShell 2.0 Downgrade
+
+        PowerShell 2.0 came before AMSI, Script Block Logging, and Constrained Language Mode. If its engine is still
+        installed, `powershell -Version 2` starts a session where none of those exist, so an attacker can run a script
+        that every newer protection would have caught or logged.
+
+        We check the MicrosoftWindowsPowerShellV2 optional features through WMI, and the PowerShellEngine registry key
+        as a fallback for offline scans. PowerShell 2.0 also needs the .NET Framework 2.0/3.5 runtime to start, so we
+        look for that too.
+    */
+    let engine_version = record(machine, ENGINE_KEY, "PowerShellVersion", string(machine, ENGINE_KEY, "PowerShellVersion"))?.and_then(RegistryData::string);
+    let clr2_install = record(machine, CLR2_KEY, "Install", dword(machine, CLR2_KEY, "Install"))?.and_then(RegistryData::dword);
+    let installation_type = record(machine, VERSION_KEY, "InstallationType", string(machine, VERSION_KEY, "InstallationType"))?.and_then(RegistryData::string);
+    let server = installation_type.map(|kind| kind.to_lowercase().starts_with("server"));
+    // The v2.0.50727 key only exists while the runtime is installed, unless HKLM couldn't be read at all
+    let clr2_installed = match clr2_install {
+        Some(install) => Some(install == 1),
+        None if unreadable.contains_key(machine.root_name()) => None,
+        None => Some(false)
+    };
+
+    queries.push(FEATURE_QUERY.to_string());
+    let features = match query::<OptionalFeature>(source, FEATURE_NAMESPACE, FEATURE_QUERY) {
+        Ok(features) => Some(features),
+        Err(ShugoError::Unavailable { .. }) => None,
+        Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::NotFound { .. })) => {
+            unreadable.insert("Win32_OptionalFeature".to_string(), error.detail());
+            None
+        },
+        Err(error) => return Err(error.in_module("powershell"))
+    };
+
+    Ok(PowerShellInfo {
+        execution_policies,
+        server,
+        logging,
+        v2: PowerShellV2 { features, engine_version, clr2_installed },
+        module_info: ModuleInfo {
+            registry_keys: vec![POLICY_KEY.to_string(), SHELL_KEY.to_string(), ENGINE_KEY.to_string(), CLR2_KEY.to_string()],
+            queries,
+            unreadable
+        }
+    })
+}
+
+/// A registry value read as the type we asked for
+enum RegistryData {
+    Dword(u32),
+    String(String)
+}
+
+impl RegistryData {
+    fn dword(self) -> Option<u32> {
+        match self {
+            RegistryData::Dword(data) => Some(data),
+            RegistryData::String(_) => None
+        }
+    }
+
+    fn string(self) -> Option<String> {
+        match self {
+            RegistryData::String(data) => Some(data.trim().to_string()).filter(|data| !data.is_empty()),
+            RegistryData::Dword(_) => None
+        }
+    }
+}
+
+impl ExecutionPolicy {
+    /// Reads a policy name the way PowerShell writes it, ignoring case. "Default" is Restricted
+    pub fn parse(text: &str) -> Option<ExecutionPolicy> {
+        match text.trim().to_lowercase().as_str() {
+            "restricted" | "default" => Some(ExecutionPolicy::Restricted),
+            "allsigned" => Some(ExecutionPolicy::AllSigned),
+            "remotesigned" => Some(ExecutionPolicy::RemoteSigned),
+            "unrestricted" => Some(ExecutionPolicy::Unrestricted),
+            "bypass" => Some(ExecutionPolicy::Bypass),
+            "undefined" => Some(ExecutionPolicy::Undefined),
+            _ => None
+        }
+    }
+
+    /// Unrestricted and Bypass run downloaded scripts without any check
+    pub fn is_permissive(&self) -> bool {
+        matches!(self, ExecutionPolicy::Unrestricted | ExecutionPolicy::Bypass)
+    }
+}
+
+impl fmt::Display for ExecutionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl fmt::Display for PolicyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl ExecutionPolicies {
+    /// Every scope in the order PowerShell checks them
+    pub fn scopes(&self) -> [(PolicyScope, Option<ExecutionPolicy>); 5] {
+        [
+            (PolicyScope::MachinePolicy, self.machine_policy),
+            (PolicyScope::UserPolicy, self.user_policy),
+            (PolicyScope::Process, self.process),
+            (PolicyScope::CurrentUser, self.current_user),
+            (PolicyScope::LocalMachine, self.local_machine)
+        ]
+    }
+
+    /// The first scope that sets a policy, `None` when PowerShell falls back to its default
+    pub fn winning_scope(&self) -> Option<(PolicyScope, ExecutionPolicy)> {
+        self.scopes().into_iter().find_map(|(scope, policy)| {
+            policy.filter(|policy| *policy != ExecutionPolicy::Undefined).map(|policy| (scope, policy))
+        })
+    }
+}
+
+impl PowerShellInfo {
+    /// Neither of the machine's execution policy scopes could be read, which means HKLM itself couldn't be,
+    /// so the audit leaves PowerShell out of the score
+    pub fn nothing_read(&self) -> bool {
+        self.scope_unread(PolicyScope::MachinePolicy) && self.scope_unread(PolicyScope::LocalMachine)
+    }
+
+    /// The execution policy PowerShell would use, with the Windows or Windows Server default when nothing is set.
+    /// `None` when a scope that could have set it couldn't be read, like an offline scan without NTUSER.DAT
+    pub fn effective_policy(&self) -> Option<ExecutionPolicy> {
+        for (scope, policy) in self.execution_policies.scopes() {
+            if let Some(policy) = policy.filter(|policy| *policy != ExecutionPolicy::Undefined) {
+                return Some(policy);
+            }
+            if self.scope_unread(scope) {
+                return None;
+            }
+        }
+        Some(if self.server == Some(true) {ExecutionPolicy::RemoteSigned} else {ExecutionPolicy::Restricted})
+    }
+
+    /// Whether the hive or the values behind a scope couldn't be read
+    fn scope_unread(&self, scope: PolicyScope) -> bool {
+        let (hive, key) = match scope {
+            PolicyScope::MachinePolicy => (RegistryHive::LocalMachine, POLICY_KEY),
+            PolicyScope::UserPolicy => (RegistryHive::CurrentUser, POLICY_KEY),
+            PolicyScope::CurrentUser => (RegistryHive::CurrentUser, SHELL_KEY),
+            PolicyScope::LocalMachine => (RegistryHive::LocalMachine, SHELL_KEY),
+            PolicyScope::Process => return false
+        };
+        let prefix = format!("{}\\{}\\", hive.root_name(), key);
+        self.module_info.unreadable.keys().any(|unread| {
+            // The logging values sit in keys under the policy key, those don't decide the execution policy
+            unread == hive.root_name() || unread.strip_prefix(&prefix).is_some_and(|value| !value.contains('\\'))
+        })
+    }
+
+    /// `Some(true)` when the PowerShell 2.0 engine is installed, from the optional features first and the registry second
+    pub fn v2_installed(&self) -> Option<bool> {
+        if let Some(features) = &self.v2.features {
+            if let Some(feature) = find_feature(features, "MicrosoftWindowsPowerShellV2").or_else(|| find_feature(features, "MicrosoftWindowsPowerShellV2Root")) {
+                return Some(feature.is_enabled());
+            }
+            // Windows releases without the feature at all (like Windows 11 24H2) have removed PowerShell 2.0
+            if self.v2.engine_version.is_none() {return Some(false);}
+        }
+        match &self.v2.engine_version {
+            Some(version) => Some(version.starts_with("2.")),
+            None if self.module_info.unreadable.contains_key(RegistryHive::LocalMachine.root_name()) => None,
+            None => Some(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::offline::OfflineSource;
+    use crate::common::source::{RegistryValue, WmiRow, WmiValue};
+
+    fn text(value: &str) -> RegistryValue {
+        RegistryValue::String(value.to_string())
+    }
+
+    #[test]
+    fn group_policy_wins_over_local_machine() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, SHELL_KEY, "ExecutionPolicy", text("Unrestricted"))
+            .with_registry(RegistryHive::CurrentUser, SHELL_KEY, "ExecutionPolicy", text("Undefined"))
+            .with_registry(RegistryHive::LocalMachine, POLICY_KEY, "EnableScripts", RegistryValue::Dword(1))
+            .with_registry(RegistryHive::LocalMachine, POLICY_KEY, "ExecutionPolicy", text("AllSigned"));
+
+        let info = scan_powershell_with(&source).unwrap();
+        assert_eq!(info.execution_policies.local_machine, Some(ExecutionPolicy::Unrestricted));
+        assert_eq!(info.execution_policies.current_user, Some(ExecutionPolicy::Undefined));
+        assert_eq!(info.execution_policies.winning_scope(), Some((PolicyScope::MachinePolicy, ExecutionPolicy::AllSigned)));
+        assert_eq!(info.effective_policy(), Some(ExecutionPolicy::AllSigned));
+    }
+
+    #[test]
+    fn defaults_depend_on_server() {
+        let info = scan_powershell_with(&FixtureSource::new()).unwrap();
+        assert_eq!(info.effective_policy(), Some(ExecutionPolicy::Restricted));
+
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, VERSION_KEY, "InstallationType", text("Server Core"))
+            .with_registry(RegistryHive::LocalMachine, POLICY_KEY, "EnableScripts", RegistryValue::Dword(0));
+        let info = scan_powershell_with(&source).unwrap();
+        assert_eq!(info.server, Some(true));
+        assert_eq!(info.effective_policy(), Some(ExecutionPolicy::Restricted)); // EnableScripts=0 is Restricted
+    }
+
+    #[test]
+    fn bad_policy_text_is_unreadable() {
+        let source = FixtureSource::new().with_registry(RegistryHive::LocalMachine, SHELL_KEY, "ExecutionPolicy", text("Sometimes"));
+        let info = scan_powershell_with(&source).unwrap();
+        assert_eq!(info.execution_policies.local_machine, None);
+        assert!(info.module_info.unreadable.keys().any(|key| key.ends_with("ExecutionPolicy")));
+        assert_eq!(info.effective_policy(), None);
+    }
+
+    #[test]
+    fn unread_hives_leave_the_policy_unknown() {
+        // Without NTUSER.DAT the user scopes could have set anything
+        let info = scan_powershell_with(&OfflineSource::new()).unwrap();
+        assert_eq!(info.effective_policy(), None);
+
+        // A policy set above the unread scope still decides
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, POLICY_KEY, "ExecutionPolicy", text("AllSigned"))
+            .with_registry(RegistryHive::CurrentUser, SHELL_KEY, "ExecutionPolicy", RegistryValue::Dword(1));
+        let info = scan_powershell_with(&source).unwrap();
+        assert_eq!(info.effective_policy(), Some(ExecutionPolicy::AllSigned));
+    }
+
+    #[test]
+    fn finds_v2_from_features_or_registry() {
+        let v2 = |state: u32| WmiRow::new()
+            .with("Name", WmiValue::String("MicrosoftWindowsPowerShellV2".to_string()))
+            .with("InstallState", WmiValue::U32(state));
+
+        let source = FixtureSource::new().with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![v2(1)]);
+        assert_eq!(scan_powershell_with(&source).unwrap().v2_installed(), Some(true));
+
+        let source = FixtureSource::new().with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![v2(2)]);
+        assert_eq!(scan_powershell_with(&source).unwrap().v2_installed(), Some(false));
+
+        let source = FixtureSource::new().with_registry(RegistryHive::LocalMachine, ENGINE_KEY, "PowerShellVersion", text("2.0"));
+        assert_eq!(scan_powershell_with(&source).unwrap().v2_installed(), Some(true));
+    }
+}
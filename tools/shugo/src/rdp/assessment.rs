@@ -0,0 +1,180 @@
+//! Assessment for the RDP Module, turns the Remote Desktop settings and firewall exposure into findings.
+use super::scanner::{RdpInfo, DEFAULT_PORT};
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "rdp";
+
+/// Assessing Remote Desktop
+pub fn assess_rdp(info: &RdpInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    // The hardening settings only matter while Remote Desktop can be turned on, so they're skipped when it's off
+    if info.enabled() != Some(false) {
+        if info.enabled() == Some(true) && info.public_exposure() == Some(true) {
+            let mut finding = Finding::new("RDP-001", MODULE, Severity::High, "Remote Desktop is reachable on public networks")
+                .explanation("On a Public network anyone nearby can try to log in over RDP, which is one of the most common ways attackers get into Windows machines")
+                .remediation("Limit the Remote Desktop firewall rules to the Domain and Private profiles, or reach the machine through a VPN or RD Gateway instead")
+                .evidence(format!("port={}", info.listening_port()));
+            if let Some(firewall) = &info.firewall {
+                if !firewall.public_profile.profile_enabled {
+                    finding = finding.evidence("public_profile=disabled");
+                } else if !firewall.public_profile.inbound_blocked {
+                    finding = finding.evidence("public_inbound=allowed");
+                }
+                for rule in firewall.public_rules.iter() {
+                    finding = finding.evidence(format!("rule={}", rule.name));
+                }
+                for product in firewall.third_party.iter() {
+                    finding = finding.evidence(format!("third_party_firewall={}", product));
+                }
+            }
+            findings.push(finding);
+        }
+
+        if !info.nla_required() {
+            findings.push(
+                Finding::new("RDP-002", MODULE, Severity::High, "Network Level Authentication isn't required")
+                    .explanation("Without NLA the login screen is shown before anyone has proven who they are, which exposes it to brute force and pre-authentication bugs like BlueKeep")
+                    .remediation("Enable \"Require user authentication for remote connections by using Network Level Authentication\", or set RDP-Tcp\\UserAuthentication to 1")
+                    .evidence("UserAuthentication=0")
+            );
+        }
+
+        if info.effective(|settings| settings.security_layer) == Some(0) {
+            findings.push(
+                Finding::new("RDP-003", MODULE, Severity::Medium, "Remote Desktop uses the RDP security layer instead of TLS")
+                    .explanation("The RDP security layer can't prove the server is who it says it is, so connections can be intercepted")
+                    .remediation("Set \"Require use of specific security layer for remote (RDP) connections\" to SSL, or set RDP-Tcp\\SecurityLayer to 2")
+                    .evidence("SecurityLayer=0")
+            );
+        }
+
+        if info.effective(|settings| settings.min_encryption_level) == Some(1) {
+            findings.push(
+                Finding::new("RDP-004", MODULE, Severity::Medium, "Remote Desktop encryption is set to Low")
+                    .explanation("Low encryption only protects data sent from the client, so what the server sends back can be read on the network")
+                    .remediation("Set \"Set client connection encryption level\" to High Level, or set RDP-Tcp\\MinEncryptionLevel to 3")
+                    .evidence("MinEncryptionLevel=1")
+            );
+        }
+
+        if info.listening_port() != DEFAULT_PORT {
+            findings.push(
+                Finding::new("RDP-005", MODULE, Severity::Info, format!("Remote Desktop listens on port {} instead of {}", info.listening_port(), DEFAULT_PORT))
+                    .explanation("A different port cuts down on automated scans but doesn't stop a targeted attacker. Firewall rules have to allow the new port for RDP to work")
+                    .remediation("Keep the port if it's intended, but don't rely on it instead of NLA and firewall rules")
+                    .evidence(format!("PortNumber={}", info.listening_port()))
+            );
+        }
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        findings.push(
+            Finding::new("RDP-006", MODULE, Severity::Info, "Some Remote Desktop settings couldn't be read")
+                .explanation("Shugo couldn't check everything, so RDP exposure or weak settings might not be reported")
+                .remediation("Run Shugo as administrator on the machine itself, firewall rules can't be read from offline registry files")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::{RegistryHive, RegistryValue};
+    use crate::firewall::scanner::{FirewallProfileDetails, FirewallRule, WindowsFirewallProfile, NAMESPACE, PROFILE_PUBLIC, PROTOCOL_TCP};
+    use crate::rdp::scanner::{scan_rdp_with, POLICY_KEY, RDP_TCP_KEY, TERMINAL_SERVER_KEY};
+
+    /// The registry values given as (key, name, data), without any firewall data like an offline scan
+    fn registry(values: &[(&str, &str, u32)]) -> FixtureSource {
+        FixtureSource::new()
+            .with_registry_key(RegistryHive::LocalMachine, RDP_TCP_KEY)
+            .with_dwords(RegistryHive::LocalMachine, values)
+    }
+
+    /// Adds a firewall with every profile on, and the built in Remote Desktop rule allowed on the Public profile when `public_rule`
+    fn with_firewall(source: FixtureSource, public_rule: bool) -> FixtureSource {
+        let profile = FirewallProfileDetails { profile_enabled: true, inbound_blocked: true, outbound_blocked: false, notifications_disabled: false };
+        let rule = FirewallRule {
+            name: "Remote Desktop - User Mode (TCP-In)".to_string(),
+            enabled: true,
+            inbound: true,
+            allow: true,
+            profiles: PROFILE_PUBLIC,
+            protocol: PROTOCOL_TCP,
+            local_ports: "3389".to_string(),
+            application: None,
+            service: None
+        };
+        source
+            .with_firewall(WindowsFirewallProfile { public: profile.clone(), private: profile.clone(), domain: profile })
+            .with_firewall_rules(if public_rule {vec![rule]} else {vec![]})
+            .with_wmi(NAMESPACE, "FirewallProduct", vec![])
+    }
+
+    #[test]
+    fn hardened_rdp_has_no_findings() {
+        let source = with_firewall(registry(&[
+            (TERMINAL_SERVER_KEY, "fDenyTSConnections", 0),
+            (RDP_TCP_KEY, "UserAuthentication", 1),
+            (RDP_TCP_KEY, "SecurityLayer", 2),
+            (RDP_TCP_KEY, "PortNumber", 3389)
+        ]), false);
+        assert!(assess_rdp(&scan_rdp_with(&source).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn flags_public_rule() {
+        let source = with_firewall(registry(&[(TERMINAL_SERVER_KEY, "fDenyTSConnections", 0)]), true);
+        let findings = assess_rdp(&scan_rdp_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "RDP-001");
+        assert_eq!(findings[0].evidence, vec!["port=3389", "rule=Remote Desktop - User Mode (TCP-In)"]);
+    }
+
+    #[test]
+    fn flags_weak_settings_only_while_enabled() {
+        let weak = [(RDP_TCP_KEY, "UserAuthentication", 0), (RDP_TCP_KEY, "SecurityLayer", 0), (RDP_TCP_KEY, "MinEncryptionLevel", 1), (RDP_TCP_KEY, "PortNumber", 50000)];
+        let enabled = with_firewall(registry(&[&weak[..], &[(TERMINAL_SERVER_KEY, "fDenyTSConnections", 0)]].concat()), false);
+        let ids: Vec<String> = assess_rdp(&scan_rdp_with(&enabled).unwrap()).into_iter().map(|finding| finding.id).collect();
+        assert_eq!(ids, ["RDP-002", "RDP-003", "RDP-004", "RDP-005"]);
+
+        let disabled = with_firewall(registry(&[&weak[..], &[(TERMINAL_SERVER_KEY, "fDenyTSConnections", 1)]].concat()), false);
+        assert!(assess_rdp(&scan_rdp_with(&disabled).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn unread_firewall_is_not_public_exposure() {
+        // Firewall rules can't be read from registry files, so an offline scan can't say whether RDP is exposed
+        let findings = assess_rdp(&scan_rdp_with(&registry(&[(TERMINAL_SERVER_KEY, "fDenyTSConnections", 0)])).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("RDP-006", Severity::Info));
+        assert_eq!(findings[0].evidence, vec!["unreadable=firewall"]);
+    }
+
+    #[test]
+    fn unknown_state_still_checks_hardening() {
+        // Without the Terminal Server key there's no telling whether RDP is on, so weak settings are still reported
+        // but exposure, which only matters while it's on, isn't
+        let source = with_firewall(registry(&[(RDP_TCP_KEY, "UserAuthentication", 0)]), true);
+        let findings = assess_rdp(&scan_rdp_with(&source).unwrap());
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["RDP-002", "RDP-006"]);
+        assert_eq!(findings[1].evidence, vec![format!("unreadable={}", TERMINAL_SERVER_KEY)]);
+    }
+
+    #[test]
+    fn unreadable_values_are_kept_per_key() {
+        // The local and Group Policy keys share value names, one mustn't hide the other
+        let text = || RegistryValue::String("0".to_string());
+        let source = with_firewall(registry(&[])
+            .with_registry(RegistryHive::LocalMachine, TERMINAL_SERVER_KEY, "fDenyTSConnections", text())
+            .with_registry(RegistryHive::LocalMachine, POLICY_KEY, "fDenyTSConnections", text()), false);
+        let findings = assess_rdp(&scan_rdp_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].evidence, vec![format!("unreadable={}\\fDenyTSConnections,{}\\fDenyTSConnections", POLICY_KEY, TERMINAL_SERVER_KEY)]);
+    }
+}
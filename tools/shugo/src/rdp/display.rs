@@ -0,0 +1,136 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_rdp;
+use super::scanner::{RdpInfo, DEFAULT_PORT};
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::get_time;
+
+/// Display for RDP Module
+pub fn display_rdp(info: &RdpInfo, verbose: bool) {
+    println!();
+    println!("REMOTE DESKTOP STATUS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_settings(info, verbose);
+
+    display_firewall(info, verbose);
+
+    display_findings(&assess_rdp(info), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for RDP Module
+pub fn json_rdp(info: &RdpInfo) -> Value {
+    let decoded = json!({
+        "enabled": info.enabled(),
+        "nla_required": info.nla_required(),
+        "security_layer": security_layer_text(info.effective(|settings| settings.security_layer)),
+        "encryption_level": encryption_text(info.effective(|settings| settings.min_encryption_level)),
+        "listening_port": info.listening_port(),
+        "public_exposure": info.public_exposure()
+    });
+
+    json!({
+        "rdp": with_decoded(info, decoded),
+        "findings": assess_rdp(info)
+    })
+}
+
+fn display_scan_details(info: &RdpInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Registry Keys:");
+    for key in info.module_info.registry_keys.iter() {
+        println!("   - {}", key);
+    }
+    println!(" - Query:");
+    for query in info.module_info.queries.iter() {
+        println!("   - {}", query);
+    }
+    for (location, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", location, reason);
+    }
+    println!();
+}
+
+fn display_settings(info: &RdpInfo, verbose: bool) {
+    println!("Remote Desktop:");
+    let status = match info.enabled() {
+        Some(true) => "Enabled",
+        Some(false) => "Disabled",
+        None => "Unknown"
+    };
+    println!(" - Status: {}", status);
+    println!(" - Network Level Authentication: {}", if info.nla_required() {"Required"} else {"Not required"});
+    println!(" - Security Layer: {}", security_layer_text(info.effective(|settings| settings.security_layer)));
+    println!(" - Encryption Level: {}", encryption_text(info.effective(|settings| settings.min_encryption_level)));
+    let port = info.listening_port();
+    println!(" - Port: {}{}", port, if port == DEFAULT_PORT {" (default)"} else {" (non-default)"});
+    if verbose && info.policy != Default::default() {
+        println!("   - Group Policy overrides local settings");
+    }
+    println!();
+}
+
+fn display_firewall(info: &RdpInfo, verbose: bool) {
+    println!("Firewall (Public Profile):");
+    let Some(firewall) = &info.firewall else {
+        println!(" - Firewall couldn't be read");
+        println!();
+        return;
+    };
+    println!(" - Profile: {}", if firewall.public_profile.profile_enabled {"Enabled"} else {"Disabled"});
+    println!(" - Inbound Default: {}", if firewall.public_profile.inbound_blocked {"Block"} else {"Allow"});
+    if firewall.public_rules.is_empty() {
+        println!(" - No rules allow RDP on this profile");
+    } else {
+        println!(" - Rules allowing RDP:");
+        for rule in firewall.public_rules.iter() {
+            println!("   - {}", rule.name);
+            if verbose {
+                println!("     - Ports: {}", if rule.local_ports.is_empty() {"Any"} else {&rule.local_ports});
+                if let Some(program) = rule.application.as_ref().or(rule.service.as_ref()) {
+                    println!("     - Program: {}", program);
+                }
+            }
+        }
+    }
+    for product in firewall.third_party.iter() {
+        println!(" - Third-Party Firewall: {} (its own rules aren't checked)", product);
+    }
+    println!();
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - Access Rights: KEY_READ");
+    println!(" - Registry Hive: HKEY_LOCAL_MACHINE");
+    println!(" - Firewall Interface: INetFwPolicy2 (CLSCTX_ALL)");
+    println!();
+}
+
+fn security_layer_text(security_layer: Option<u32>) -> &'static str {
+    match security_layer {
+        Some(0) => "RDP",
+        Some(1) => "Negotiate",
+        Some(2) => "TLS",
+        Some(_) => "Unknown",
+        None => "Not set (Windows default)"
+    }
+}
+
+fn encryption_text(level: Option<u32>) -> &'static str {
+    match level {
+        Some(1) => "Low",
+        Some(2) => "Client Compatible",
+        Some(3) => "High",
+        Some(4) => "FIPS Compliant",
+        Some(_) => "Unknown",
+        None => "Not set (Windows default)"
+    }
+}
@@ -0,0 +1,3 @@
+pub mod scanner;
+pub mod assessment;
+pub mod display;
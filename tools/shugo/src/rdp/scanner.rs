@@ -0,0 +1,341 @@
+//! This is the RDP Module for Shugo. Here we can see:
+//!
+//! - Whether Remote Desktop is Enabled
+//! - Network Level Authentication (NLA)
+//! - Security Layer and Encryption Level
+//! - Listening Port
+//! - Firewall Rules that let RDP in on the Public Profile
+//!
+//! The Remote Desktop settings come from the registry, so they can also be read from exported hives.
+//! The firewall side reuses the Firewall module's data, which needs a live machine.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::ShugoError;
+use crate::common::product_state::ScannerState;
+use crate::common::source::{SecuritySource, SourceResult, RegistryHive, registry_dword, system_source};
+use crate::firewall::scanner::{scan_firewall_with, scan_firewall_rules_with, FirewallProfileDetails, FirewallRule, PROFILE_PUBLIC};
+
+/// One set of Remote Desktop values, each `None` when it isn't set or couldn't be read
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RdpSettings {
+    /// fDenyTSConnections: 0 = connections allowed
+    pub deny_connections: Option<u32>,
+    /// UserAuthentication: 1 = Network Level Authentication required
+    pub user_authentication: Option<u32>,
+    /// SecurityLayer: 0 = RDP, 1 = Negotiate, 2 = TLS
+    pub security_layer: Option<u32>,
+    /// MinEncryptionLevel: 1 = Low, 2 = Client Compatible, 3 = High, 4 = FIPS
+    pub min_encryption_level: Option<u32>
+}
+
+/// What the Windows Firewall does with RDP traffic on the Public profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RdpFirewall {
+    pub public_profile: FirewallProfileDetails,
+    /// Enabled rules that allow inbound TCP on the RDP port for the Public profile
+    pub public_rules: Vec<FirewallRule>,
+    /// Third-party firewalls that are on, which can allow or block RDP on their own
+    pub third_party: Vec<String>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RdpInfo {
+    /// The values set on the machine itself
+    pub local: RdpSettings,
+    /// The values set by Group Policy, which win over the local ones
+    pub policy: RdpSettings,
+    pub port: Option<u32>,
+    /// `None` when the firewall couldn't be read, like on an offline registry file
+    pub firewall: Option<RdpFirewall>,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub registry_keys: Vec<String>,
+    pub queries: Vec<String>,
+    /// Keys, values, and firewall data that couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+pub const TERMINAL_SERVER_KEY: &str = "SYSTEM\\CurrentControlSet\\Control\\Terminal Server";
+pub const RDP_TCP_KEY: &str = "SYSTEM\\CurrentControlSet\\Control\\Terminal Server\\WinStations\\RDP-Tcp";
+pub const POLICY_KEY: &str = "SOFTWARE\\Policies\\Microsoft\\Windows NT\\Terminal Services";
+
+pub const DEFAULT_PORT: u32 = 3389;
+
+/// Scanning Remote Desktop for Windows
+pub fn scan_rdp() -> SourceResult<RdpInfo> {
+    scan_rdp_with(system_source()?.as_ref())
+}
+
+/// Scanning Remote Desktop from any source
+pub fn scan_rdp_with(source: &dyn SecuritySource) -> SourceResult<RdpInfo> {
+    /*
+        Shugo: Remote Desktop In The Registry
+
+        Remote Desktop keeps its on/off switch and its listener settings in two keys:
+
+        HKLM\SYSTEM\CurrentControlSet\Control\Terminal Server
+        - fDenyTSConnections: 1 = Remote Desktop is off (the default), 0 = it's on
+
+        HKLM\SYSTEM\CurrentControlSet\Control\Terminal Server\WinStations\RDP-Tcp
+        - UserAuthentication: 1 = Network Level Authentication, users have to log in before a session is created
+        - SecurityLayer: how the connection is protected, 0 = the old RDP encryption, 1 = negotiate, 2 = TLS
+        - MinEncryptionLevel: only used by the RDP security layer, 1 = Low up to 4 = FIPS
+        - PortNumber: the TCP port RDP listens on, 3389 unless someone changed it
+
+        Group Policy writes the same value names to HKLM\SOFTWARE\Policies\Microsoft\Windows NT\Terminal Services,
+        and when a policy value is set it wins over the local one. That key usually doesn't exist at all.
+
+        For more information on these settings:
+        (https://learn.microsoft.com/en-us/windows-hardware/customize/desktop/unattend/microsoft-windows-terminalservices-rdp-winstationextensions)
+    */
+    let mut queries: Vec<String> = Vec::new();
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+    let mut registry_query = |key: &str, value: &str| -> SourceResult<Option<u32>> {
+        queries.push(format!("{}\\{}", key, value));
+        match registry_dword(source, RegistryHive::LocalMachine, key, value) {
+            Ok(data) => Ok(Some(data)),
+            Err(ShugoError::ValueMissing { .. }) => Ok(None),
+            // No Group Policy for Remote Desktop is the normal case
+            Err(ShugoError::NotFound { .. }) if key == POLICY_KEY => Ok(None),
+            Err(error @ ShugoError::NotFound { .. }) => {
+                unreadable.entry(key.to_string()).or_insert_with(|| error.detail());
+                Ok(None)
+            },
+            // The local and policy keys use the same value names, so the key is kept with the value
+            Err(error @ (ShugoError::Unavailable { .. } | ShugoError::AccessDenied { .. } | ShugoError::ParseError { .. })) => {
+                unreadable.insert(format!("{}\\{}", key, value), error.detail());
+                Ok(None)
+            },
+            Err(error) => Err(error.in_module("rdp"))
+        }
+    };
+
+    let local = RdpSettings {
+        deny_connections: registry_query(TERMINAL_SERVER_KEY, "fDenyTSConnections")?,
+        user_authentication: registry_query(RDP_TCP_KEY, "UserAuthentication")?,
+        security_layer: registry_query(RDP_TCP_KEY, "SecurityLayer")?,
+        min_encryption_level: registry_query(RDP_TCP_KEY, "MinEncryptionLevel")?
+    };
+    let policy = RdpSettings {
+        deny_connections: registry_query(POLICY_KEY, "fDenyTSConnections")?,
+        user_authentication: registry_query(POLICY_KEY, "UserAuthentication")?,
+        security_layer: registry_query(POLICY_KEY, "SecurityLayer")?,
+        min_encryption_level: registry_query(POLICY_KEY, "MinEncryptionLevel")?
+    };
+    let port = registry_query(RDP_TCP_KEY, "PortNumber")?;
+
+    /*
+        Shugo: Cross-Checking The Firewall
+
+        Remote Desktop being on only matters as much as who can reach it. The Public profile is the one Windows uses on
+        networks it doesn't trust (coffee shops, hotels, airports), so RDP should never be reachable there.
+
+        We ask the Firewall module for the profiles and rules it reads, and keep the Public profile plus every enabled
+        rule that lets inbound TCP reach the RDP port on it. Rules can also be limited to one program or service, and
+        Windows makes plenty of those ("allow python.exe on Public networks?") with every port open. Those only let
+        that program's traffic in, so only rules Remote Desktop itself can use count: no program or svchost.exe/System,
+        and no service or TermService.
+    */
+    queries.push("INetFwPolicy2 (Public profile and rules)".to_string());
+    let rdp_port = port.unwrap_or(DEFAULT_PORT);
+    let firewall = match scan_firewall_with(source).and_then(|summary| Ok((summary, scan_firewall_rules_with(source)?))) {
+        Ok((summary, rules)) => Some(RdpFirewall {
+            public_profile: summary.profile.public,
+            public_rules: rules.into_iter().filter(|rule| rule.applies_to(PROFILE_PUBLIC) && rule.allows_inbound_tcp(rdp_port) && reaches_remote_desktop(rule)).collect(),
+            third_party: summary.products.into_iter()
+                .filter(|product| product.product_state.scanner == ScannerState::On)
+                .map(|product| product.name)
+                .collect()
+        }),
+        Err(error @ (ShugoError::NotFound { .. } | ShugoError::AccessDenied { .. } | ShugoError::Unavailable { .. })) => {
+            unreadable.insert("firewall".to_string(), error.detail());
+            None
+        },
+        Err(error) => return Err(error.in_module("rdp"))
+    };
+
+    Ok(RdpInfo {
+        local,
+        policy,
+        port,
+        firewall,
+        module_info: ModuleInfo {
+            registry_keys: vec![TERMINAL_SERVER_KEY.to_string(), RDP_TCP_KEY.to_string(), POLICY_KEY.to_string()],
+            queries,
+            unreadable
+        }
+    })
+}
+
+/// Whether a rule's program and service limits still let Remote Desktop's own traffic through
+fn reaches_remote_desktop(rule: &FirewallRule) -> bool {
+    let application = rule.application.as_deref().map(|application| {
+        let application = application.trim().to_ascii_lowercase();
+        application.rsplit('\\').next().unwrap_or_default().to_string()
+    });
+    let service = rule.service.as_deref().map(str::trim);
+    matches!(application.as_deref(), None | Some("svchost.exe" | "system"))
+        && service.is_none_or(|service| service == "*" || service.eq_ignore_ascii_case("TermService"))
+}
+
+impl RdpInfo {
+    /// The value in effect, Group Policy first
+    pub fn effective(&self, pick: fn(&RdpSettings) -> Option<u32>) -> Option<u32> {
+        pick(&self.policy).or(pick(&self.local))
+    }
+
+    /// `None` when fDenyTSConnections couldn't be read anywhere
+    pub fn enabled(&self) -> Option<bool> {
+        self.effective(|settings| settings.deny_connections).map(|deny| deny == 0)
+    }
+
+    /// Whether Remote Desktop is on couldn't be read, so the audit leaves RDP out of the score
+    pub fn nothing_read(&self) -> bool {
+        let deny_connections = format!("{}\\fDenyTSConnections", TERMINAL_SERVER_KEY);
+        self.enabled().is_none() && self.module_info.unreadable.keys().any(|unread| unread == TERMINAL_SERVER_KEY || *unread == deny_connections)
+    }
+
+    /// NLA is required unless UserAuthentication is set to 0, it's been the default since Windows Vista
+    pub fn nla_required(&self) -> bool {
+        self.effective(|settings| settings.user_authentication) != Some(0)
+    }
+
+    pub fn listening_port(&self) -> u32 {
+        self.port.unwrap_or(DEFAULT_PORT)
+    }
+
+    /// Whether the firewall lets RDP in on the Public profile, `None` when the firewall wasn't read
+    pub fn public_exposure(&self) -> Option<bool> {
+        let firewall = self.firewall.as_ref()?;
+        Some(!firewall.public_profile.profile_enabled || !firewall.public_profile.inbound_blocked || !firewall.public_rules.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::RegistryValue;
+    use crate::firewall::scanner::{WindowsFirewallProfile, NAMESPACE, PROFILE_PRIVATE, PROTOCOL_TCP};
+
+    fn profile(profile_enabled: bool) -> FirewallProfileDetails {
+        FirewallProfileDetails { profile_enabled, inbound_blocked: true, outbound_blocked: false, notifications_disabled: false }
+    }
+
+    fn rule(name: &str, profiles: i32, local_ports: &str) -> FirewallRule {
+        FirewallRule {
+            name: name.to_string(),
+            enabled: true,
+            inbound: true,
+            allow: true,
+            profiles,
+            protocol: PROTOCOL_TCP,
+            local_ports: local_ports.to_string(),
+            application: None,
+            service: Some("TermService".to_string())
+        }
+    }
+
+    fn machine(values: &[(&str, &str, u32)], rules: Vec<FirewallRule>) -> FixtureSource {
+        FixtureSource::new()
+            .with_registry_key(RegistryHive::LocalMachine, TERMINAL_SERVER_KEY)
+            .with_registry_key(RegistryHive::LocalMachine, RDP_TCP_KEY)
+            .with_dwords(RegistryHive::LocalMachine, values)
+            .with_firewall(WindowsFirewallProfile { public: profile(true), private: profile(true), domain: profile(true) })
+            .with_firewall_rules(rules)
+            .with_wmi(NAMESPACE, "FirewallProduct", vec![])
+    }
+
+    #[test]
+    fn keeps_only_public_rules_for_the_rdp_port() {
+        let source = machine(
+            &[(TERMINAL_SERVER_KEY, "fDenyTSConnections", 0), (RDP_TCP_KEY, "PortNumber", 3390)],
+            vec![
+                rule("Remote Desktop - Private", PROFILE_PRIVATE, "3389"),
+                rule("Custom RDP", 0x7FFFFFFF, "3390"),
+                rule("Web", PROFILE_PUBLIC, "443")
+            ]
+        );
+
+        let info = scan_rdp_with(&source).unwrap();
+        assert_eq!(info.enabled(), Some(true));
+        assert_eq!(info.listening_port(), 3390);
+        let firewall = info.firewall.as_ref().unwrap();
+        assert_eq!(firewall.public_rules.len(), 1);
+        assert_eq!(firewall.public_rules[0].name, "Custom RDP");
+        assert_eq!(info.public_exposure(), Some(true));
+        assert!(info.module_info.unreadable.is_empty());
+    }
+
+    #[test]
+    fn program_rules_on_any_port_are_not_exposure() {
+        let program = |name: &str, application: &str| FirewallRule { application: Some(application.to_string()), service: None, ..rule(name, PROFILE_PUBLIC, "*") };
+        let other_service = FirewallRule { service: Some("Dnscache".to_string()), ..rule("DNS", PROFILE_PUBLIC, "*") };
+        let any_service = FirewallRule { service: Some("*".to_string()), ..rule("Services", PROFILE_PUBLIC, "*") };
+        let source = machine(&[(TERMINAL_SERVER_KEY, "fDenyTSConnections", 0)], vec![
+            program("python.exe", "C:\\Users\\alice\\AppData\\Local\\Programs\\Python\\python.exe"),
+            program("Microsoft Teams", "C:\\Program Files\\WindowsApps\\ms-teams.exe"),
+            other_service,
+            program("Remote Desktop - Shadow", "%SystemRoot%\\system32\\svchost.exe"),
+            any_service
+        ]);
+
+        let info = scan_rdp_with(&source).unwrap();
+        let names: Vec<&str> = info.firewall.as_ref().unwrap().public_rules.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(names, ["Remote Desktop - Shadow", "Services"]);
+    }
+
+    #[test]
+    fn program_rules_alone_leave_rdp_closed() {
+        let python = FirewallRule { application: Some("C:\\Python312\\python.exe".to_string()), service: None, ..rule("python.exe", PROFILE_PUBLIC, "*") };
+        let info = scan_rdp_with(&machine(&[(TERMINAL_SERVER_KEY, "fDenyTSConnections", 0)], vec![python])).unwrap();
+        assert_eq!(info.public_exposure(), Some(false));
+    }
+
+    #[test]
+    fn group_policy_wins_over_local_settings() {
+        let source = machine(&[
+            (TERMINAL_SERVER_KEY, "fDenyTSConnections", 0),
+            (RDP_TCP_KEY, "UserAuthentication", 1),
+            (POLICY_KEY, "fDenyTSConnections", 1),
+            (POLICY_KEY, "UserAuthentication", 0)
+        ], vec![]);
+
+        let info = scan_rdp_with(&source).unwrap();
+        assert_eq!(info.enabled(), Some(false));
+        assert!(!info.nla_required());
+        assert_eq!(info.public_exposure(), Some(false));
+    }
+
+    #[test]
+    fn checks_the_firewall_without_security_center() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, TERMINAL_SERVER_KEY, "fDenyTSConnections", RegistryValue::Dword(0))
+            .with_firewall(WindowsFirewallProfile { public: profile(true), private: profile(true), domain: profile(true) })
+            .with_firewall_rules(vec![rule("Remote Desktop - User Mode (TCP-In)", PROFILE_PUBLIC, "3389")]);
+
+        let info = scan_rdp_with(&source).unwrap();
+        assert!(info.firewall.as_ref().unwrap().third_party.is_empty());
+        assert_eq!(info.public_exposure(), Some(true));
+        assert!(!info.module_info.unreadable.contains_key("firewall"));
+    }
+
+    #[test]
+    fn missing_firewall_is_unreadable() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, TERMINAL_SERVER_KEY, "fDenyTSConnections", RegistryValue::Dword(1));
+
+        let info = scan_rdp_with(&source).unwrap();
+        assert!(info.firewall.is_none());
+        assert_eq!(info.public_exposure(), None);
+        assert!(info.module_info.unreadable.contains_key("firewall"));
+        assert!(info.module_info.unreadable.contains_key(RDP_TCP_KEY));
+        assert!(!info.module_info.unreadable.contains_key(POLICY_KEY));
+    }
+}
@@ -0,0 +1,122 @@
+//! Assessment for the Network Shares Module, turns share permissions, names, and paths into findings.
+use super::scanner::{ShareInfo, Share};
+use crate::common::finding::{Finding, Severity};
+use crate::common::sddl::share_access_text;
+
+const MODULE: &str = "shares";
+
+/// Assessing network shares
+pub fn assess_shares(info: &ShareInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for share in info.shares.iter().flatten() {
+        let groups = share.broad_write_access();
+        if !groups.is_empty() && !share.is_default() {
+            let names = groups.iter().map(|(sid, _)| sid.name()).collect::<Vec<_>>();
+            let mut finding = Finding::new("SHR-001", MODULE, Severity::High, format!("{} lets {} write", share.name, names.join(", ")))
+                .explanation("Anyone in these groups can change, delete, or drop files into the share from another machine, which is how ransomware spreads and how malicious files get planted where others will open them. Only the folder's NTFS permissions stand in the way")
+                .remediation("Remove the group from the share permissions, or give it Read, and grant Change only to the accounts that need it (`Revoke-SmbShareAccess` / `Grant-SmbShareAccess`)")
+                .evidence(share_evidence(share));
+            for (sid, access) in groups.iter() {
+                finding = finding.evidence(format!("access={}:{}", sid.name(), share_access_text(*access)));
+            }
+            findings.push(finding);
+        }
+
+        if share.is_hidden() && !share.is_default() {
+            findings.push(
+                Finding::new("SHR-002", MODULE, Severity::Medium, format!("{} is a hidden share", share.name))
+                    .explanation("Shares ending in $ don't show up when browsing the machine, but anyone who knows the name can still connect. Hiding a share isn't access control, and attackers enumerate them with tools that list every share")
+                    .remediation("Remove the share if it isn't needed, otherwise rely on its permissions rather than the hidden name")
+                    .evidence(share_evidence(share))
+            );
+        }
+
+        if share.is_disk() && share.is_system_directory() && !share.is_default() {
+            findings.push(
+                Finding::new("SHR-003", MODULE, Severity::High, format!("{} shares the system folder {}", share.name, share.path))
+                    .explanation("Drive roots, the Windows folder, Program Files, ProgramData, and Users hold executables, services, and every user's profile. A share over them exposes far more than one folder, and write access there lets someone replace programs that run as SYSTEM")
+                    .remediation("Share a dedicated folder instead, with `Remove-SmbShare` for this one")
+                    .evidence(share_evidence(share))
+            );
+        }
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        findings.push(
+            Finding::new("SHR-004", MODULE, Severity::Info, "Some share information couldn't be read")
+                .explanation("Shugo couldn't list every share or read its permissions, so open shares may not be reported")
+                .remediation("Run Shugo as administrator on a live machine, shares can't be checked from offline registry files")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+    }
+
+    findings
+}
+
+fn share_evidence(share: &Share) -> String {
+    format!("share={} path={}", share.name, share.path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::error::ShugoError;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::sddl::SecurityDescriptor;
+    use crate::common::source::ShareRecord;
+    use crate::shares::scanner::{scan_shares_with, BROAD_GROUPS, STYPE_DISKTREE, STYPE_SPECIAL};
+
+    /// A share as NetShareEnum returns it, `None` being a share without a security descriptor
+    fn record(name: &str, path: &str, share_type: u32, sddl: Option<&str>) -> ShareRecord {
+        ShareRecord {
+            name: name.to_string(),
+            share_type,
+            remark: String::new(),
+            path: path.to_string(),
+            current_uses: 0,
+            security_descriptor: Ok(sddl.map(|sddl| SecurityDescriptor::from_sddl(sddl).unwrap().to_bytes()))
+        }
+    }
+
+    fn assess(records: Vec<ShareRecord>) -> Vec<Finding> {
+        assess_shares(&scan_shares_with(&FixtureSource::new().with_shares(records)).unwrap())
+    }
+
+    #[test]
+    fn flags_writable_hidden_system_share() {
+        let findings = assess(vec![record("Root$", "C:\\", STYPE_DISKTREE, Some("D:(A;;FA;;;WD)"))]);
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["SHR-001", "SHR-002", "SHR-003"]);
+        assert_eq!(findings[0].evidence, vec!["share=Root$ path=C:\\", "access=Everyone:Full Control"]);
+    }
+
+    #[test]
+    fn missing_descriptor_is_open_except_on_administrative_shares() {
+        let findings = assess(vec![
+            record("Drop", "D:\\Drop", STYPE_DISKTREE, None),
+            record("ADMIN$", "C:\\Windows", STYPE_DISKTREE | STYPE_SPECIAL, None)
+        ]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "SHR-001");
+        assert_eq!(findings[0].evidence.len(), 1 + BROAD_GROUPS.len());
+    }
+
+    #[test]
+    fn invalid_descriptor_is_unreadable_not_open() {
+        let mut invalid = record("Team", "D:\\Team", STYPE_DISKTREE, None);
+        invalid.security_descriptor = Err(ShugoError::parse("Invalid security descriptor", "IsValidSecurityDescriptor rejected it"));
+        let findings = assess(vec![invalid]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("SHR-004", Severity::Info));
+        assert_eq!(findings[0].evidence, vec!["unreadable=Team.security_descriptor"]);
+    }
+
+    #[test]
+    fn unlisted_shares_are_only_unreadable() {
+        // The fixture has no shares at all, like an offline scan
+        let findings = assess_shares(&scan_shares_with(&FixtureSource::new()).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].evidence, vec!["unreadable=NetShareEnum"]);
+    }
+}
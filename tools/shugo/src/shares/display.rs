@@ -0,0 +1,107 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_shares;
+use super::scanner::{ShareInfo, share_type_text};
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::sddl::{AceType, share_access_text};
+use crate::common::time::get_time;
+
+/// Display for Network Shares Module
+pub fn display_shares(info: &ShareInfo, verbose: bool) {
+    println!();
+    println!("NETWORK SHARES STATUS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_share_list(info, verbose);
+
+    display_findings(&assess_shares(info), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for Network Shares Module
+pub fn json_shares(info: &ShareInfo) -> Value {
+    let shares: Vec<Value> = info.shares.iter().flatten().map(|share| json!({
+        "name": share.name,
+        "type": share_type_text(share.share_type),
+        "hidden": share.is_hidden(),
+        "default": share.is_default(),
+        "permissions": share.descriptor().map(|descriptor| descriptor.dacl.map(|aces| aces.iter().map(|ace| json!({
+            "account": ace.sid.name(),
+            "type": match ace.ace_type {AceType::Allow => "Allow", AceType::Deny => "Deny", AceType::Other(_) => "Other"},
+            "access": share_access_text(ace.mask)
+        })).collect::<Vec<_>>())),
+        "broad_write": share.broad_write_access().iter().map(|(sid, _)| sid.name()).collect::<Vec<_>>()
+    })).collect();
+    let decoded = json!({ "shares": shares });
+
+    json!({
+        "shares": with_decoded(info, decoded),
+        "findings": assess_shares(info)
+    })
+}
+
+fn display_scan_details(info: &ShareInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Query:");
+    for query in info.module_info.queries.iter() {
+        println!("   - {}", query);
+    }
+    for (value, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", value, reason);
+    }
+    println!();
+}
+
+fn display_share_list(info: &ShareInfo, verbose: bool) {
+    println!("Shares:");
+    let Some(shares) = &info.shares else {
+        println!(" - Shares couldn't be read (share permissions need administrator rights)");
+        println!();
+        return;
+    };
+    if shares.is_empty() {
+        println!(" - No shares found");
+    }
+    for share in shares.iter() {
+        let hidden = if share.is_hidden() {", Hidden"} else {""};
+        println!(" - {} ({}{})", share.name, share_type_text(share.share_type), hidden);
+        if !share.path.is_empty() {
+            println!("   - Path: {}", share.path);
+        }
+        match share.descriptor().map(|descriptor| descriptor.dacl) {
+            Some(Some(aces)) => {
+                for ace in aces.iter().filter(|ace| verbose || !matches!(ace.ace_type, AceType::Other(_))) {
+                    let verb = if ace.ace_type == AceType::Deny {"Deny "} else {""};
+                    println!("   - {}{}: {}", verb, ace.sid.name(), share_access_text(ace.mask));
+                }
+            },
+            Some(None) => println!("   - Permissions: Everyone (no access control)"),
+            None if share.security_descriptor.is_some() => println!("   - Permissions: Couldn't be read"),
+            None => println!("   - Permissions: Default")
+        }
+        if verbose {
+            if !share.remark.is_empty() {
+                println!("   - Remark: {}", share.remark);
+            }
+            println!("   - Connections: {}", share.current_uses);
+            if let Some(sddl) = &share.security_descriptor {
+                println!("   - SDDL: {}", sddl);
+            }
+        }
+    }
+    println!();
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - API: NetShareEnum (netapi32), level 502 (SHARE_INFO_502)");
+    println!(" - Permissions: share security descriptor, NTFS permissions aren't checked");
+    println!(" - Requires: Administrator");
+    println!();
+}
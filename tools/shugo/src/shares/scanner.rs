@@ -0,0 +1,330 @@
+//! This is the Network Shares Module for Shugo. Here we can see:
+//!
+//! - Every Folder, Printer, and Admin Share the Machine Offers
+//! - Each Share's Path, Type, and Open Connections
+//! - Share Permissions from the Share's Security Descriptor
+//!
+//! Shares come from `NetShareEnum` at level 502, the only level that includes the security descriptor, which
+//! needs administrator rights. Offline registry files don't have any of it, so an offline scan only reports that
+//! the shares couldn't be read.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::ShugoError;
+use crate::common::sddl::{SecurityDescriptor, Sid, can_write};
+use crate::common::source::{SecuritySource, SourceResult, ShareRecord, system_source};
+
+pub const SHARE_QUERY: &str = "NetShareEnum (level 502)";
+
+/// The low byte of the share type: 0 = Disk, 1 = Print Queue, 2 = Device, 3 = IPC
+pub const STYPE_MASK: u32 = 0x0000_00FF;
+pub const STYPE_DISKTREE: u32 = 0;
+/// Set on the administrative shares Windows creates itself (C$, ADMIN$, IPC$)
+pub const STYPE_SPECIAL: u32 = 0x8000_0000;
+/// Set on shares that won't survive a restart
+pub const STYPE_TEMPORARY: u32 = 0x4000_0000;
+
+/// SIDs that cover anyone on the network, or any account on the machine
+pub const BROAD_GROUPS: &[&str] = &["S-1-1-0", "S-1-5-7", "S-1-5-11", "S-1-5-2", "S-1-5-32-545", "S-1-5-32-546"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Share {
+    pub name: String,
+    pub share_type: u32,
+    pub remark: String,
+    pub path: String,
+    pub current_uses: u32,
+    /// The share permissions as SDDL, `None` when they couldn't be read. A share without a security descriptor
+    /// has no share permissions at all and is kept as a NULL DACL, `D:NO_ACCESS_CONTROL`
+    pub security_descriptor: Option<String>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShareInfo {
+    /// `None` when the shares couldn't be listed, like without administrator rights
+    pub shares: Option<Vec<Share>>,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub registry_keys: Vec<String>,
+    pub queries: Vec<String>,
+    /// Calls and descriptors that couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+/// Scanning network shares for Windows
+pub fn scan_shares() -> SourceResult<ShareInfo> {
+    scan_shares_with(system_source()?.as_ref())
+}
+
+/// Scanning network shares from any source
+pub fn scan_shares_with(source: &dyn SecuritySource) -> SourceResult<ShareInfo> {
+    /*
+        Shugo: Network Shares
+
+        Every folder shared from this machine is reachable over SMB as \\machine\name. Two sets of permissions
+        decide who can do what through it:
+
+        - Share permissions, set on the Sharing tab and stored with the share. Only Full Control, Change,
+          and Read can be picked there
+        - NTFS permissions on the folder itself
+
+        Someone connecting over the network gets whichever is stricter. Shugo checks the share permissions,
+        since a share granting Everyone Change leaves the folder's NTFS permissions as the only thing in the way.
+
+        Windows also creates hidden administrative shares (C$, ADMIN$, IPC$) that end with `$`. Only
+        administrators can use them, and they're flagged with STYPE_SPECIAL.
+
+        For more information on SHARE_INFO_502:
+        (https://learn.microsoft.com/en-us/windows/win32/api/lmshare/ns-lmshare-share_info_502)
+    */
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+
+    let records: Option<Vec<ShareRecord>> = match source.network_shares() {
+        Ok(records) => Some(records),
+        Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::Unavailable { .. })) => {
+            unreadable.insert("NetShareEnum".to_string(), error.detail());
+            None
+        },
+        Err(error) => return Err(error.in_module("shares"))
+    };
+
+    let shares = records.map(|records| records.into_iter().map(|record| {
+        let parsed = record.security_descriptor.and_then(|bytes| bytes.as_deref().map(SecurityDescriptor::from_bytes).transpose());
+        let security_descriptor = match parsed {
+            Ok(Some(descriptor)) => {
+                // The checks read the SDDL back, so one that doesn't parse again would quietly pass them
+                let sddl = descriptor.to_sddl();
+                if let Err(error) = SecurityDescriptor::from_sddl(&sddl) {
+                    unreadable.insert(format!("{}.security_descriptor", record.name), error.detail());
+                }
+                Some(sddl)
+            },
+            // Windows doesn't check share permissions when a share has none, the same as a NULL DACL
+            Ok(None) => Some(SecurityDescriptor { control: 0, owner: None, group: None, dacl: None }.to_sddl()),
+            Err(error) => {
+                unreadable.insert(format!("{}.security_descriptor", record.name), error.detail());
+                None
+            }
+        };
+        Share {
+            name: record.name,
+            share_type: record.share_type,
+            remark: record.remark,
+            path: record.path,
+            current_uses: record.current_uses,
+            security_descriptor
+        }
+    }).collect());
+
+    Ok(ShareInfo {
+        shares,
+        module_info: ModuleInfo {
+            registry_keys: vec![],
+            queries: vec![SHARE_QUERY.to_string()],
+            unreadable
+        }
+    })
+}
+
+impl ShareInfo {
+    /// The shares couldn't be listed, so the audit leaves them out of the score
+    pub fn nothing_read(&self) -> bool {
+        self.shares.is_none()
+    }
+}
+
+impl Share {
+    pub fn is_disk(&self) -> bool {
+        self.share_type & STYPE_MASK == STYPE_DISKTREE
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.name.ends_with('$')
+    }
+
+    /// The administrative shares Windows creates on its own: ADMIN$, IPC$, PRINT$, and one per drive (C$, D$, ...).
+    /// Anyone can name a share `D$`, so a drive share only counts when it's special and shares that drive's root
+    pub fn is_default(&self) -> bool {
+        let name = self.name.to_ascii_uppercase();
+        if matches!(name.as_str(), "ADMIN$" | "IPC$" | "PRINT$") {
+            return true;
+        }
+        let drive = name.strip_suffix('$').filter(|letter| letter.len() == 1 && letter.chars().all(|c| c.is_ascii_alphabetic()));
+        let Some(letter) = drive else {
+            return false;
+        };
+        let root = self.path.trim_end_matches(['\\', '/']);
+        self.share_type & STYPE_SPECIAL != 0 && root.eq_ignore_ascii_case(&format!("{}:", letter))
+    }
+
+    /// The parsed share permissions, `None` when the share has none or they don't parse (the scan records those as unreadable)
+    pub fn descriptor(&self) -> Option<SecurityDescriptor> {
+        self.security_descriptor.as_deref().and_then(|sddl| SecurityDescriptor::from_sddl(sddl).ok())
+    }
+
+    /// The broad groups the share permissions let write, with the rights each one ends up with
+    pub fn broad_write_access(&self) -> Vec<(Sid, u32)> {
+        /*
+            Shugo: Who Counts As Everyone
+
+            A user's access token holds their own SID plus every group they're in, and the DACL is checked against
+            all of them at once. Anyone who can sign in is also in Everyone, so a deny on Everyone applies to
+            Authenticated Users as well. Anonymous connections are the exception, they're only in Everyone when
+            "Let Everyone permissions apply to anonymous users" is turned on.
+        */
+        let Some(descriptor) = self.descriptor() else {return vec![]};
+        let everyone = Sid::parse("WD").expect("WD is a known alias");
+        BROAD_GROUPS.iter().filter_map(|group| {
+            let sid = Sid::parse(group).ok()?;
+            let token = if sid == everyone || sid.alias() == Some("AN") {vec![sid.clone()]} else {vec![sid.clone(), everyone.clone()]};
+            let access = descriptor.access_for(&token);
+            // Only report a group when its own ACE is what gives it write, not just being part of Everyone
+            let own = descriptor.access_for(std::slice::from_ref(&sid));
+            (can_write(access) && (sid == everyone || can_write(own))).then_some((sid, access))
+        }).collect()
+    }
+
+    /// Whether the share exposes a drive root, the Windows folder, Program Files, ProgramData, or Users
+    pub fn is_system_directory(&self) -> bool {
+        let path = self.path.replace('/', "\\").trim_end_matches('\\').to_ascii_uppercase();
+        let Some(rest) = path.get(2..).filter(|_| path.as_bytes().get(1) == Some(&b':') && path.starts_with(|c: char| c.is_ascii_alphabetic())) else {
+            return false;
+        };
+        let under = |folder: &str| rest == folder || rest.starts_with(&format!("{}\\", folder));
+        rest.is_empty()
+            || rest == "\\USERS"
+            || rest == "\\PROGRAMDATA"
+            || under("\\WINDOWS")
+            || under("\\PROGRAM FILES")
+            || under("\\PROGRAM FILES (X86)")
+    }
+}
+
+pub fn share_type_text(share_type: u32) -> String {
+    let base = match share_type & STYPE_MASK {
+        0 => "Disk",
+        1 => "Print Queue",
+        2 => "Device",
+        3 => "IPC",
+        _ => "Unknown"
+    };
+    let mut text = base.to_string();
+    if share_type & STYPE_SPECIAL != 0 {text.push_str(", Administrative");}
+    if share_type & STYPE_TEMPORARY != 0 {text.push_str(", Temporary");}
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::sddl::{Ace, AceType};
+
+    fn record(name: &str, path: &str, sddl: Option<&str>) -> ShareRecord {
+        ShareRecord {
+            name: name.to_string(),
+            share_type: STYPE_DISKTREE,
+            remark: String::new(),
+            path: path.to_string(),
+            current_uses: 0,
+            security_descriptor: Ok(sddl.map(|sddl| SecurityDescriptor::from_sddl(sddl).unwrap().to_bytes()))
+        }
+    }
+
+    fn share(name: &str, path: &str, sddl: &str) -> Share {
+        Share { name: name.to_string(), path: path.to_string(), security_descriptor: Some(sddl.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn reads_share_descriptors() {
+        let mut broken = record("Broken", "D:\\Broken", None);
+        broken.security_descriptor = Ok(Some(vec![1, 0, 4, 0x80]));
+        let source = FixtureSource::new().with_shares(vec![record("Public", "D:\\Public", Some("D:(A;;0x1301bf;;;WD)")), broken]);
+
+        let info = scan_shares_with(&source).unwrap();
+        let shares = info.shares.as_ref().unwrap();
+        assert_eq!(shares[0].security_descriptor.as_deref(), Some("D:(A;;0x1301bf;;;WD)"));
+        assert_eq!(shares[1].security_descriptor, None);
+        assert!(info.module_info.unreadable.contains_key("Broken.security_descriptor"));
+    }
+
+    #[test]
+    fn records_descriptors_that_dont_read_back() {
+        // A binary SID can hold more sub-authorities than SDDL allows, so the SDDL doesn't read back
+        let long_sid = Sid { revision: 1, authority: 5, sub_authorities: vec![1; 16] };
+        let ace = Ace::new(AceType::Allow, 0x1f01ff, long_sid);
+        let descriptor = SecurityDescriptor { control: 0, owner: None, group: None, dacl: Some(vec![ace]) };
+        let mut labelled = record("Labelled", "D:\\Labelled", None);
+        labelled.security_descriptor = Ok(Some(descriptor.to_bytes()));
+
+        let info = scan_shares_with(&FixtureSource::new().with_shares(vec![labelled])).unwrap();
+        let share = &info.shares.as_ref().unwrap()[0];
+        assert!(share.security_descriptor.is_some() && share.descriptor().is_none());
+        assert!(info.module_info.unreadable.contains_key("Labelled.security_descriptor"));
+    }
+
+    #[test]
+    fn shares_without_a_descriptor_are_open_to_everyone() {
+        let info = scan_shares_with(&FixtureSource::new().with_shares(vec![record("Open", "D:\\Open", None)])).unwrap();
+        let share = &info.shares.as_ref().unwrap()[0];
+        assert_eq!(share.security_descriptor.as_deref(), Some("D:NO_ACCESS_CONTROL"));
+        assert_eq!(share.broad_write_access().len(), BROAD_GROUPS.len());
+        assert!(info.module_info.unreadable.is_empty());
+    }
+
+    #[test]
+    fn invalid_descriptors_are_unreadable() {
+        let mut invalid = record("Invalid", "D:\\Invalid", None);
+        invalid.security_descriptor = Err(ShugoError::parse("Invalid security descriptor", "IsValidSecurityDescriptor rejected it"));
+
+        let info = scan_shares_with(&FixtureSource::new().with_shares(vec![invalid])).unwrap();
+        let share = &info.shares.as_ref().unwrap()[0];
+        assert!(share.security_descriptor.is_none() && share.broad_write_access().is_empty());
+        assert!(info.module_info.unreadable["Invalid.security_descriptor"].contains("IsValidSecurityDescriptor"));
+    }
+
+    #[test]
+    fn missing_shares_are_unreadable() {
+        let info = scan_shares_with(&FixtureSource::new()).unwrap();
+        assert!(info.shares.is_none());
+        assert!(info.module_info.unreadable.contains_key("NetShareEnum"));
+    }
+
+    #[test]
+    fn finds_broad_write_access() {
+        let everyone_change = share("Public", "D:\\Public", "D:(A;;0x1301bf;;;WD)");
+        let granted: Vec<String> = everyone_change.broad_write_access().iter().map(|(sid, _)| sid.name()).collect();
+        assert_eq!(granted, ["Everyone"]);
+
+        let users_read = share("Docs", "D:\\Docs", "D:(A;;0x1200a9;;;WD)(A;;FA;;;BA)");
+        assert!(users_read.broad_write_access().is_empty());
+
+        let denied = share("Team", "D:\\Team", "D:(D;;FA;;;WD)(A;;FA;;;AU)");
+        assert!(denied.broad_write_access().is_empty());
+
+        let authenticated = share("Drop", "D:\\Drop", "D:(A;;FA;;;AU)");
+        assert_eq!(authenticated.broad_write_access()[0].0.name(), "Authenticated Users");
+    }
+
+    #[test]
+    fn recognises_default_and_system_shares() {
+        let special = Share { name: "C$".to_string(), path: "C:\\".to_string(), share_type: STYPE_SPECIAL, ..Default::default() };
+        assert!(special.is_hidden() && special.is_default() && special.is_system_directory());
+        assert!(!share("Backup$", "E:\\Backup", "D:").is_default());
+        assert!(!share("D$", "D:\\", "D:").is_default());
+        let elsewhere = Share { name: "D$".to_string(), path: "C:\\Temp".to_string(), share_type: STYPE_SPECIAL, ..Default::default() };
+        assert!(!elsewhere.is_default());
+
+        assert!(share("Win", "c:/windows/System32/", "D:").is_system_directory());
+        assert!(share("Apps", "C:\\Program Files (x86)\\App", "D:").is_system_directory());
+        assert!(share("Profiles", "D:\\Users", "D:").is_system_directory());
+        assert!(!share("Projects", "C:\\Users\\alice\\Projects", "D:").is_system_directory());
+        assert!(!share("WindowsStuff", "C:\\WindowsStuff", "D:").is_system_directory());
+        assert_eq!(share_type_text(STYPE_SPECIAL | 3), "IPC, Administrative");
+    }
+}
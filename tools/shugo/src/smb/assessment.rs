@@ -0,0 +1,175 @@
+//! Assessment for the SMB Module, turns the SMBv1, signing, and encryption settings into findings.
+use super::scanner::{SmbInfo, SmbState, signing_state};
+use crate::common::feature::find_feature;
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "smb";
+
+/// Assessing SMB hardening
+pub fn assess_smb(info: &SmbInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if info.smb1_server() == SmbState::Enabled {
+        findings.push(
+            Finding::new("SMB-001", MODULE, Severity::Critical, "The SMBv1 server is enabled")
+                .explanation("SMBv1 has no protection against tampering and is what EternalBlue, WannaCry, and NotPetya spread through. Any machine on the network can talk to it")
+                .remediation("Remove the feature with `Disable-WindowsOptionalFeature -Online -FeatureName SMB1Protocol`, or set LanmanServer\\Parameters\\SMB1 to 0")
+                .evidence(format!("SMB1={}", value(info.server.smb1)))
+                .evidence(format!("feature={}", feature_text(info, "SMB1Protocol-Server")))
+        );
+    }
+
+    if info.smb1_client() == SmbState::Enabled {
+        findings.push(
+            Finding::new("SMB-002", MODULE, Severity::High, "The SMBv1 client is enabled")
+                .explanation("With the SMBv1 client on, an attacker can downgrade this machine's connections to SMBv1 and attack it through a malicious server")
+                .remediation("Remove the SMB1Protocol-Client feature, or set the mrxsmb10 service Start value to 4")
+                .evidence(format!("mrxsmb10 Start={}", value(info.client.smb1_driver_start)))
+                .evidence(format!("feature={}", feature_text(info, "SMB1Protocol-Client")))
+        );
+    }
+
+    if signing_state(info.server.require_security_signature) == SmbState::Disabled {
+        findings.push(
+            Finding::new("SMB-003", MODULE, Severity::High, "The SMB server doesn't require signing")
+                .explanation("Without signing, an attacker on the network can relay NTLM logons to this machine or change files in transit")
+                .remediation("Enable the \"Microsoft network server: Digitally sign communications (always)\" policy, or set LanmanServer\\Parameters\\RequireSecuritySignature to 1")
+                .evidence("RequireSecuritySignature=0")
+        );
+    }
+
+    if signing_state(info.client.require_security_signature) == SmbState::Disabled {
+        findings.push(
+            Finding::new("SMB-004", MODULE, Severity::Medium, "The SMB client doesn't require signing")
+                .explanation("Connections this machine makes to file shares can be tampered with or relayed when the server doesn't ask for signing")
+                .remediation("Enable the \"Microsoft network client: Digitally sign communications (always)\" policy, or set LanmanWorkstation\\Parameters\\RequireSecuritySignature to 1")
+                .evidence("RequireSecuritySignature=0")
+        );
+    }
+
+    if info.encryption() == SmbState::Disabled {
+        findings.push(
+            Finding::new("SMB-005", MODULE, Severity::Low, "SMB encryption isn't required for every share")
+                .explanation("Files copied to and from this machine's shares can be read by anyone on the network path, unless a share turns encryption on itself")
+                .remediation("Run `Set-SmbServerConfiguration -EncryptData $true`, or turn on encryption for the shares holding sensitive data")
+                .evidence(format!("EncryptData={}", value(info.server.encrypt_data)))
+        );
+    }
+
+    if info.server.smb2 == Some(0) {
+        findings.push(
+            Finding::new("SMB-006", MODULE, Severity::Medium, "SMBv2 and SMBv3 are disabled on the server")
+                .explanation("Turning off SMBv2 also turns off SMBv3, which removes signing improvements, encryption, and leaves only SMBv1 for file sharing")
+                .remediation("Set LanmanServer\\Parameters\\SMB2 to 1 or delete the value. Microsoft doesn't recommend disabling SMBv2 to fix problems")
+                .evidence("SMB2=0")
+        );
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        findings.push(
+            Finding::new("SMB-007", MODULE, Severity::Info, "Some SMB settings couldn't be read")
+                .explanation("Shugo couldn't check every setting, so a problem in the missing ones wouldn't be reported")
+                .remediation("Run Shugo as administrator, or include the SYSTEM hive when scanning offline")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+    }
+
+    findings
+}
+
+fn value(data: Option<u32>) -> String {
+    data.map(|data| data.to_string()).unwrap_or_else(|| "not set".to_string())
+}
+
+fn feature_text(info: &SmbInfo, name: &str) -> String {
+    match &info.smb1_features {
+        Some(features) => find_feature(features, name)
+            .map(|feature| feature.install_state_text().to_string())
+            .unwrap_or_else(|| "not listed".to_string()),
+        None => "not checked".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::{RegistryHive, RegistryValue, WmiRow, WmiValue};
+    use crate::common::feature::FEATURE_NAMESPACE;
+    use crate::smb::scanner::{scan_smb_with, CLIENT_KEY, SERVER_KEY, SMB1_DRIVER_KEY};
+    use crate::smb::scanner::tests::smb_source as source;
+
+    #[test]
+    fn flags_smb1_and_unsigned_traffic() {
+        let findings = assess_smb(&scan_smb_with(&source(&[
+            (SERVER_KEY, "SMB1", 1),
+            (SERVER_KEY, "RequireSecuritySignature", 0),
+            (CLIENT_KEY, "RequireSecuritySignature", 0),
+            (SMB1_DRIVER_KEY, "Start", 2)
+        ])).unwrap());
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("SMB-001", Severity::Critical));
+        assert_eq!(findings[0].evidence, vec!["SMB1=1", "feature=not checked"]);
+        let ids: Vec<&str> = findings.iter().map(|finding| finding.id.as_str()).collect();
+        // The fixture has no Win32_OptionalFeature rows, so that query is reported as unreadable
+        assert_eq!(ids, ["SMB-001", "SMB-002", "SMB-003", "SMB-004", "SMB-005", "SMB-007"]);
+    }
+
+    #[test]
+    fn hardened_machine_has_no_findings() {
+        let removed = WmiRow::new()
+            .with("Name", WmiValue::String("SMB1Protocol".to_string()))
+            .with("InstallState", WmiValue::U32(2));
+        let source = source(&[
+            (SERVER_KEY, "RequireSecuritySignature", 1),
+            (SERVER_KEY, "EncryptData", 1),
+            (CLIENT_KEY, "RequireSecuritySignature", 1)
+        ]).with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![removed]);
+        assert!(assess_smb(&scan_smb_with(&source).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn names_the_feature_install_state() {
+        let installed = WmiRow::new()
+            .with("Name", WmiValue::String("SMB1Protocol-Server".to_string()))
+            .with("InstallState", WmiValue::U32(1));
+        let source = source(&[]).with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![installed]);
+        let findings = assess_smb(&scan_smb_with(&source).unwrap());
+        assert_eq!(findings[0].id, "SMB-001");
+        assert!(findings[0].evidence.contains(&"feature=Enabled".to_string()));
+    }
+
+    #[test]
+    fn unset_signing_is_not_a_finding_but_disabled_smb2_is() {
+        let source = source(&[(SERVER_KEY, "SMB2", 0), (SERVER_KEY, "EncryptData", 1)])
+            .with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![]);
+        let findings = assess_smb(&scan_smb_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "SMB-006");
+    }
+
+    #[test]
+    fn missing_parameters_keys_are_unreadable_not_insecure() {
+        // A SOFTWARE hive on its own has neither Parameters key, so nothing can be said about SMBv1 or signing
+        let source = FixtureSource::new().with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![]);
+        let findings = assess_smb(&scan_smb_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].id.as_str(), findings[0].severity), ("SMB-007", Severity::Info));
+        assert_eq!(findings[0].evidence, vec![format!("unreadable={},{}", SERVER_KEY, CLIENT_KEY)]);
+    }
+
+    #[test]
+    fn wrong_value_types_are_unreadable() {
+        let text = || RegistryValue::String("1".to_string());
+        let source = source(&[(SERVER_KEY, "EncryptData", 1)])
+            .with_registry(RegistryHive::LocalMachine, SERVER_KEY, "SMB1", text())
+            .with_registry(RegistryHive::LocalMachine, SERVER_KEY, "RequireSecuritySignature", text())
+            .with_registry(RegistryHive::LocalMachine, CLIENT_KEY, "RequireSecuritySignature", text())
+            .with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![]);
+        let findings = assess_smb(&scan_smb_with(&source).unwrap());
+        assert_eq!(findings.len(), 1);
+        // The server and client values share a name, so each is kept under its own key
+        assert_eq!(findings[0].evidence, vec![format!(
+            "unreadable={0}\\RequireSecuritySignature,{0}\\SMB1,{1}\\RequireSecuritySignature", SERVER_KEY, CLIENT_KEY
+        )]);
+    }
+}
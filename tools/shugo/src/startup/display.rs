@@ -0,0 +1,112 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_startup;
+use super::scanner::{AutostartEntry, EntryKind, StartupInfo};
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
+use crate::common::time::get_time;
+
+/// Display for Startup Programs Module
+pub fn display_startup(info: &StartupInfo, verbose: bool) {
+    println!();
+    println!("STARTUP PROGRAMS STATUS");
+    println!("{}", "=".repeat(30));
+
+    if verbose {display_scan_details(info);}
+
+    display_entries(info, verbose);
+
+    display_findings(&assess_startup(info), verbose);
+
+    if verbose {display_technical();}
+}
+
+/// JSON for Startup Programs Module
+pub fn json_startup(info: &StartupInfo) -> Value {
+    let entries: Vec<Value> = info.entries.iter().map(|entry| json!({
+        "kind": entry.kind.to_string(),
+        "name": entry.name,
+        "location": entry.location,
+        "command": entry.command,
+        "binary": entry.binary,
+        "machine_wide": entry.machine_wide,
+        "run_as": entry.run_as,
+        "traits": entry.traits.iter().map(ToString::to_string).collect::<Vec<_>>()
+    })).collect();
+    let decoded = json!({ "entries": entries });
+
+    json!({
+        "startup": with_decoded(info, decoded),
+        "findings": assess_startup(info)
+    })
+}
+
+fn display_scan_details(info: &StartupInfo) {
+    println!("Scan Details:");
+    let (h, m, s) = get_time();
+    println!(" - Scan Started: {:02}:{:02}:{:02} UTC", h, m, s);
+    println!(" - Registry Keys:");
+    for key in info.module_info.registry_keys.iter() {
+        println!("   - {}", key);
+    }
+    if !info.module_info.files.is_empty() {
+        println!(" - Folders:");
+        for folder in info.module_info.files.iter() {
+            println!("   - {}", folder);
+        }
+    }
+    for (value, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", value, reason);
+    }
+    println!();
+}
+
+/// Services and tasks Windows ships with are numerous, so without verbose only the ones with traits are listed
+fn is_shown(entry: &AutostartEntry, verbose: bool) -> bool {
+    verbose || !entry.traits.is_empty() || !matches!(entry.kind, EntryKind::Service | EntryKind::ScheduledTask)
+}
+
+fn display_entries(info: &StartupInfo, verbose: bool) {
+    for kind in EntryKind::ALL.iter() {
+        let entries: Vec<&AutostartEntry> = info.entries.iter().filter(|entry| entry.kind == *kind).collect();
+        if entries.is_empty() {
+            continue;
+        }
+        println!("{}s:", kind);
+        let hidden = entries.iter().filter(|entry| !is_shown(entry, verbose)).count();
+        for entry in entries.into_iter().filter(|entry| is_shown(entry, verbose)) {
+            let scope = if entry.machine_wide {"All Users"} else {"Current User"};
+            println!(" - {} ({})", entry.name, scope);
+            println!("   - Command: {}", entry.command);
+            if let Some(binary) = &entry.binary && (verbose || !entry.command.contains(binary.as_str())) {
+                println!("   - Binary: {}", binary);
+            }
+            if let Some(run_as) = &entry.run_as {
+                println!("   - Runs As: {}", run_as);
+            }
+            if !entry.traits.is_empty() {
+                println!("   - Traits: {}", entry.traits.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "));
+            }
+            if verbose {
+                println!("   - Location: {}", entry.location);
+            }
+        }
+        if hidden > 0 {
+            println!(" - {} more without suspicious traits (use --verbose to list them)", hidden);
+        }
+        println!();
+    }
+    if info.entries.is_empty() {
+        println!("Autostart Entries:");
+        println!(" - No autostart entries found");
+        println!();
+    }
+}
+
+fn display_technical() {
+    println!("Technical Information:");
+    println!(" - Registry: Run/RunOnce, Services (Start = 2), Winlogon Shell/Userinit, Image File Execution Options Debugger");
+    println!(" - Files: Startup folders (.lnk shortcuts), %SystemRoot%\\System32\\Tasks (task XML)");
+    println!(" - Requires: Administrator for every scheduled task and service");
+    println!();
+}
@@ -0,0 +1,6 @@
+pub mod commandline;
+pub mod tasks;
+pub mod shortcut;
+pub mod scanner;
+pub mod assessment;
+pub mod display;
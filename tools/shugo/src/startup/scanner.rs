@@ -0,0 +1,565 @@
+//! This is the Startup Programs Module for Shugo. Here we can see:
+//!
+//! - Run and RunOnce Keys (HKLM/HKCU, including WOW6432Node)
+//! - Startup Folders (all users and the current user)
+//! - Scheduled Tasks from `System32\Tasks`
+//! - Automatically Started Services
+//! - Winlogon Shell/Userinit and Image File Execution Options Debuggers
+//!
+//! Every entry gets its command line, the program that runs, and any suspicious traits. The registry parts
+//! also work from exported hives, while the Startup folders and task files are only read on a live machine.
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::commandline::{Trait, command_traits, file_name, resolve_binary};
+use super::shortcut::parse_shortcut;
+use super::tasks::{TaskAction, parse_task_xml};
+use crate::common::error::ShugoError;
+use crate::common::path::join;
+use crate::common::regfile::decode_text;
+use crate::common::source::{RegistryHive, RegistryValue, SecuritySource, SourceResult, system_source};
+
+/// Run and RunOnce keys, and whether they start programs for every user
+pub const RUN_KEYS: &[(RegistryHive, &str, EntryKind)] = &[
+    (RegistryHive::LocalMachine, "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run", EntryKind::Run),
+    (RegistryHive::LocalMachine, "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\RunOnce", EntryKind::RunOnce),
+    (RegistryHive::LocalMachine, "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Run", EntryKind::Run),
+    (RegistryHive::LocalMachine, "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\RunOnce", EntryKind::RunOnce),
+    (RegistryHive::CurrentUser, "Software\\Microsoft\\Windows\\CurrentVersion\\Run", EntryKind::Run),
+    (RegistryHive::CurrentUser, "Software\\Microsoft\\Windows\\CurrentVersion\\RunOnce", EntryKind::RunOnce),
+    (RegistryHive::CurrentUser, "Software\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Run", EntryKind::Run),
+    (RegistryHive::CurrentUser, "Software\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\RunOnce", EntryKind::RunOnce)
+];
+pub const WINLOGON_KEY: &str = "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Winlogon";
+pub const IFEO_KEYS: &[&str] = &[
+    "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Image File Execution Options",
+    "SOFTWARE\\WOW6432Node\\Microsoft\\Windows NT\\CurrentVersion\\Image File Execution Options"
+];
+pub const SERVICES_KEY: &str = "SYSTEM\\CurrentControlSet\\Services";
+pub const STARTUP_FOLDER: &str = "Microsoft\\Windows\\Start Menu\\Programs\\Startup";
+
+/// Services.Start for services the Service Control Manager starts at boot
+pub const SERVICE_AUTO_START: u32 = 2;
+/// Services.Type bits for services that run in their own process or inside svchost, rather than drivers
+pub const SERVICE_WIN32: u32 = 0x30;
+pub const SERVICE_WIN32_SHARE_PROCESS: u32 = 0x20;
+
+/// Where an autostart entry was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum EntryKind {
+    Run,
+    RunOnce,
+    StartupFolder,
+    ScheduledTask,
+    Service,
+    Winlogon,
+    Debugger
+}
+
+impl EntryKind {
+    pub const ALL: [EntryKind; 7] = [EntryKind::Run, EntryKind::RunOnce, EntryKind::StartupFolder, EntryKind::ScheduledTask, EntryKind::Service, EntryKind::Winlogon, EntryKind::Debugger];
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EntryKind::Run => "Run Key",
+            EntryKind::RunOnce => "RunOnce Key",
+            EntryKind::StartupFolder => "Startup Folder",
+            EntryKind::ScheduledTask => "Scheduled Task",
+            EntryKind::Service => "Service",
+            EntryKind::Winlogon => "Winlogon",
+            EntryKind::Debugger => "IFEO Debugger"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One program Windows starts on its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutostartEntry {
+    pub kind: EntryKind,
+    /// The registry key or file the entry came from
+    pub location: String,
+    pub name: String,
+    pub command: String,
+    /// The program that actually runs, with environment variables expanded when they're known
+    pub binary: Option<String>,
+    /// Whether it runs for every user or as a service account, rather than for one user
+    pub machine_wide: bool,
+    /// The account it runs as, for services and tasks
+    pub run_as: Option<String>,
+    pub traits: Vec<Trait>
+}
+
+/// Folders to read Startup entries and task files from, and the environment for expanding `%NAME%`
+#[derive(Debug, Clone, Default)]
+pub struct StartupPaths {
+    /// %SystemRoot%, for System32\Tasks
+    pub system_root: Option<PathBuf>,
+    /// %ProgramData%, for the all users Startup folder
+    pub program_data: Option<PathBuf>,
+    /// %APPDATA%, for this user's Startup folder
+    pub app_data: Option<PathBuf>,
+    /// Environment variables by upper case name
+    pub environment: BTreeMap<String, String>
+}
+
+impl StartupPaths {
+    /// The folders and environment of the user Shugo is running as
+    pub fn from_env() -> StartupPaths {
+        let folder = |name: &str| env::var_os(name).map(PathBuf::from);
+        let environment = [
+            "SystemRoot", "windir", "SystemDrive", "ProgramFiles", "ProgramFiles(x86)", "ProgramW6432", "CommonProgramFiles",
+            "ProgramData", "ALLUSERSPROFILE", "PUBLIC", "USERPROFILE", "APPDATA", "LOCALAPPDATA", "TEMP", "TMP"
+        ].into_iter().filter_map(|name| env::var(name).ok().map(|value| (name.to_ascii_uppercase(), value))).collect();
+        StartupPaths {
+            system_root: folder("SystemRoot"),
+            program_data: folder("ProgramData"),
+            app_data: folder("APPDATA"),
+            environment
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StartupInfo {
+    pub entries: Vec<AutostartEntry>,
+    pub module_info: ModuleInfo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub registry_keys: Vec<String>,
+    /// Folders the Startup entries and task files were read from
+    pub files: Vec<String>,
+    /// Keys, values, and files that couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
+}
+
+/// Scanning startup programs for Windows
+pub fn scan_startup() -> SourceResult<StartupInfo> {
+    scan_startup_with(system_source()?.as_ref(), Some(&StartupPaths::from_env()))
+}
+
+/// Scanning autostart registry keys from any source, and Startup folders and tasks from `paths` when given
+pub fn scan_startup_with(source: &dyn SecuritySource, paths: Option<&StartupPaths>) -> SourceResult<StartupInfo> {
+    /*
+        Shugo: Autostart Locations
+
+        Windows has a lot of places that start programs without anyone clicking on them, and malware uses all
+        of them to survive a restart (MITRE ATT&CK calls this Persistence, T1547 and T1543 among others):
+
+        - Run/RunOnce keys: every value is a command run at logon. HKLM runs for every user, HKCU for one,
+          and WOW6432Node holds the ones 32-bit installers wrote
+        - Startup folders: anything in them (usually shortcuts) is opened at logon
+        - Scheduled tasks: can run at boot, at logon, or on a timer, as any account including SYSTEM
+        - Services: Start = 2 makes the Service Control Manager start them at boot, usually as SYSTEM
+        - Winlogon Shell/Userinit: the programs started right after logon, explorer.exe and userinit.exe.
+          Anything added here runs for every user
+        - Image File Execution Options: a Debugger value makes Windows start that program instead of the one
+          being launched, so setting it on sethc.exe or utilman.exe gives a SYSTEM shell at the logon screen
+
+        For more information on autostart locations:
+        (https://attack.mitre.org/techniques/T1547/001/)
+    */
+    let mut registry_keys: Vec<String> = Vec::new();
+    let mut files: Vec<String> = Vec::new();
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+    let mut entries: Vec<AutostartEntry> = Vec::new();
+    let no_environment = BTreeMap::new();
+    let environment = paths.map(|paths| &paths.environment).unwrap_or(&no_environment);
+
+    for (hive, key, kind) in RUN_KEYS.iter() {
+        let location = format!("{}\\{}", hive.root_name(), key);
+        registry_keys.push(location.clone());
+        let Some(values) = readable(source.registry_values(*hive, key), &location, &mut unreadable)? else {continue};
+        for (name, value) in values.into_iter() {
+            let Some(command) = text(&value).filter(|command| !command.trim().is_empty()) else {continue};
+            entries.push(entry(*kind, &location, &name, &command, *hive == RegistryHive::LocalMachine, environment));
+        }
+    }
+
+    if let Some(paths) = paths {
+        read_startup_folders(paths, &mut entries, &mut files, &mut unreadable);
+        read_tasks(paths, &mut entries, &mut files, &mut unreadable);
+    }
+
+    read_services(source, environment, &mut entries, &mut registry_keys, &mut unreadable)?;
+    read_winlogon(source, environment, &mut entries, &mut registry_keys, &mut unreadable)?;
+    read_debuggers(source, environment, &mut entries, &mut registry_keys, &mut unreadable)?;
+
+    Ok(StartupInfo {
+        entries,
+        module_info: ModuleInfo {
+            registry_keys,
+            files,
+            unreadable
+        }
+    })
+}
+
+impl StartupInfo {
+    /// Not one autostart key or folder could be read, so the audit leaves startup programs out of the score
+    pub fn nothing_read(&self) -> bool {
+        let unreadable = &self.module_info.unreadable;
+        let unread = |location: &String| unreadable.keys().any(|unread| unread.strip_prefix(location.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('\\')));
+        self.module_info.registry_keys.iter().chain(self.module_info.files.iter()).all(unread)
+    }
+}
+
+impl AutostartEntry {
+    /// Winlogon entries that still point at Windows' own explorer.exe and userinit.exe
+    pub fn is_winlogon_default(&self) -> bool {
+        let expected = if self.name.eq_ignore_ascii_case("Userinit") {"userinit.exe"} else {"explorer.exe"};
+        self.binary.as_deref().is_some_and(|binary| file_name(binary) == expected && !self.traits.contains(&Trait::UserWritablePath))
+    }
+
+    /// Scheduled tasks under \Microsoft\, which Windows and Microsoft software create
+    pub fn is_microsoft_task(&self) -> bool {
+        self.kind == EntryKind::ScheduledTask && self.name.to_ascii_lowercase().starts_with("\\microsoft\\")
+    }
+}
+
+/// Works out the binary and traits for one command
+fn entry(kind: EntryKind, location: &str, name: &str, command: &str, machine_wide: bool, environment: &BTreeMap<String, String>) -> AutostartEntry {
+    let binary = resolve_binary(command, environment);
+    let traits = command_traits(command, binary.as_deref());
+    AutostartEntry {
+        kind,
+        location: location.to_string(),
+        name: name.to_string(),
+        command: command.trim().to_string(),
+        binary,
+        machine_wide,
+        run_as: None,
+        traits
+    }
+}
+
+/// Keeps a read that worked. A missing key is normal, other failures are recorded as unreadable
+fn readable<T>(result: SourceResult<T>, what: &str, unreadable: &mut BTreeMap<String, String>) -> SourceResult<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ShugoError::NotFound { .. }) => Ok(None),
+        Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::Unavailable { .. } | ShugoError::ParseError { .. })) => {
+            unreadable.insert(what.to_string(), error.detail());
+            Ok(None)
+        },
+        Err(error) => Err(error.in_module("startup"))
+    }
+}
+
+fn text(value: &RegistryValue) -> Option<String> {
+    match value {
+        RegistryValue::String(text) | RegistryValue::ExpandString(text) => Some(text.clone()),
+        _ => None
+    }
+}
+
+/// Looks a value up by name without caring about case, registry value names aren't case sensitive
+fn value<'a>(values: &'a [(String, RegistryValue)], name: &str) -> Option<&'a RegistryValue> {
+    values.iter().find(|(value_name, _)| value_name.eq_ignore_ascii_case(name)).map(|(_, value)| value)
+}
+
+fn read_startup_folders(paths: &StartupPaths, entries: &mut Vec<AutostartEntry>, files: &mut Vec<String>, unreadable: &mut BTreeMap<String, String>) {
+    let folders = [(paths.program_data.as_ref(), true), (paths.app_data.as_ref(), false)];
+    for (root, machine_wide) in folders.into_iter() {
+        let Some(root) = root else {continue};
+        let folder = join(root, STARTUP_FOLDER);
+        files.push(folder.display().to_string());
+        for path in list_files(&folder, false, unreadable) {
+            let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            if name.eq_ignore_ascii_case("desktop.ini") {
+                continue;
+            }
+            let location = path.display().to_string();
+            let is_shortcut = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("lnk"));
+            let command = if is_shortcut {
+                let Some(bytes) = read_bytes(&path, unreadable) else {continue};
+                match parse_shortcut(&bytes) {
+                    Ok(shortcut) => shortcut.command_line().unwrap_or_else(|| location.clone()),
+                    Err(error) => {
+                        unreadable.insert(location, error.detail());
+                        continue;
+                    }
+                }
+            } else {
+                location.clone()
+            };
+            entries.push(entry(EntryKind::StartupFolder, &location, &name, &command, machine_wide, &paths.environment));
+        }
+    }
+}
+
+fn read_tasks(paths: &StartupPaths, entries: &mut Vec<AutostartEntry>, files: &mut Vec<String>, unreadable: &mut BTreeMap<String, String>) {
+    let Some(system_root) = &paths.system_root else {return};
+    let folder = join(system_root, "System32\\Tasks");
+    files.push(folder.display().to_string());
+    for path in list_files(&folder, true, unreadable) {
+        let location = path.display().to_string();
+        let Some(bytes) = read_bytes(&path, unreadable) else {continue};
+        let task = match parse_task_xml(&decode_text(&bytes)) {
+            Ok(task) => task,
+            Err(error) => {
+                unreadable.insert(location, error.detail());
+                continue;
+            }
+        };
+        if !task.enabled {
+            continue;
+        }
+
+        // The task's name is its path under the Tasks folder, which is also what Task Scheduler shows
+        let relative = path.strip_prefix(&folder).map(|relative| relative.components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("\\")).unwrap_or_default();
+        let name = task.uri.clone().unwrap_or_else(|| format!("\\{}", relative));
+        let system_account = task.user_id.as_deref().is_some_and(|user| {
+            let user = user.to_ascii_uppercase();
+            matches!(user.as_str(), "S-1-5-18" | "S-1-5-19" | "S-1-5-20" | "SYSTEM" | "LOCAL SERVICE" | "NETWORK SERVICE") || user.starts_with("NT AUTHORITY\\")
+        });
+        let machine_wide = task.group_id.is_some() || task.user_id.is_none() || system_account;
+
+        for action in task.actions.iter() {
+            let mut task_entry = entry(EntryKind::ScheduledTask, &location, &name, &action.command_line(), machine_wide, &paths.environment);
+            if let TaskAction::ComHandler(_) = action {
+                task_entry.binary = None;
+                task_entry.traits = Vec::new();
+            }
+            task_entry.run_as = task.user_id.clone().or_else(|| task.group_id.clone());
+            entries.push(task_entry);
+        }
+    }
+}
+
+fn read_services(source: &dyn SecuritySource, environment: &BTreeMap<String, String>, entries: &mut Vec<AutostartEntry>, registry_keys: &mut Vec<String>, unreadable: &mut BTreeMap<String, String>) -> SourceResult<()> {
+    /*
+        Shugo: Services In The Registry
+
+        Every service has a key under HKLM\SYSTEM\CurrentControlSet\Services with:
+
+        - Start: 0 = Boot, 1 = System (both for drivers), 2 = Automatic, 3 = Manual, 4 = Disabled
+        - Type: 0x10 = its own process, 0x20 = shares a process (svchost), 0x1/0x2 = kernel and file system drivers
+        - ImagePath: the command line the Service Control Manager runs
+        - ObjectName: the account it runs as, LocalSystem when not set
+
+        Services that share svchost.exe all have the same ImagePath, the DLL that actually runs is the
+        ServiceDll value under the service's Parameters key.
+    */
+    let location = format!("{}\\{}", RegistryHive::LocalMachine.root_name(), SERVICES_KEY);
+    registry_keys.push(location.clone());
+    let Some(services) = readable(source.registry_subkeys(RegistryHive::LocalMachine, SERVICES_KEY), &location, unreadable)? else {
+        return Ok(());
+    };
+    for service in services.iter() {
+        let key = format!("{}\\{}", SERVICES_KEY, service);
+        let service_location = format!("{}\\{}", location, service);
+        let Some(values) = readable(source.registry_values(RegistryHive::LocalMachine, &key), &service_location, unreadable)? else {continue};
+        let start = value(&values, "Start").and_then(RegistryValue::as_u32);
+        let service_type = value(&values, "Type").and_then(RegistryValue::as_u32).unwrap_or_default();
+        let Some(command) = value(&values, "ImagePath").and_then(text) else {continue};
+        if start != Some(SERVICE_AUTO_START) || service_type & SERVICE_WIN32 == 0 {
+            continue;
+        }
+
+        let mut service_entry = entry(EntryKind::Service, &service_location, service, &command, true, environment);
+        service_entry.run_as = Some(value(&values, "ObjectName").and_then(text).unwrap_or_else(|| "LocalSystem".to_string()));
+        if service_type & SERVICE_WIN32_SHARE_PROCESS != 0 {
+            let parameters = format!("{}\\Parameters", key);
+            let dll = readable(source.registry_value(RegistryHive::LocalMachine, &parameters, "ServiceDll"), &format!("{}\\Parameters", service_location), unreadable)?;
+            if let Some(dll) = dll.flatten().as_ref().and_then(text) {
+                service_entry.binary = resolve_binary(&dll, environment);
+                service_entry.traits = command_traits(&command, service_entry.binary.as_deref());
+            }
+        }
+        entries.push(service_entry);
+    }
+    Ok(())
+}
+
+fn read_winlogon(source: &dyn SecuritySource, environment: &BTreeMap<String, String>, entries: &mut Vec<AutostartEntry>, registry_keys: &mut Vec<String>, unreadable: &mut BTreeMap<String, String>) -> SourceResult<()> {
+    // HKCU can override the shell for one user, Userinit is only read from HKLM
+    let values: [(RegistryHive, &str); 3] = [(RegistryHive::LocalMachine, "Shell"), (RegistryHive::LocalMachine, "Userinit"), (RegistryHive::CurrentUser, "Shell")];
+    for (hive, name) in values.into_iter() {
+        let location = format!("{}\\{}", hive.root_name(), WINLOGON_KEY);
+        if !registry_keys.contains(&location) {
+            registry_keys.push(location.clone());
+        }
+        let Some(Some(data)) = readable(source.registry_value(hive, WINLOGON_KEY, name), &hive.value_path(WINLOGON_KEY, name), unreadable)? else {continue};
+        let Some(data) = text(&data) else {continue};
+        // Userinit is a comma separated list, and Windows starts each program in it
+        for command in data.split(',').map(str::trim).filter(|command| !command.is_empty()) {
+            entries.push(entry(EntryKind::Winlogon, &location, name, command, hive == RegistryHive::LocalMachine, environment));
+        }
+    }
+    Ok(())
+}
+
+fn read_debuggers(source: &dyn SecuritySource, environment: &BTreeMap<String, String>, entries: &mut Vec<AutostartEntry>, registry_keys: &mut Vec<String>, unreadable: &mut BTreeMap<String, String>) -> SourceResult<()> {
+    for key in IFEO_KEYS.iter() {
+        let location = format!("{}\\{}", RegistryHive::LocalMachine.root_name(), key);
+        registry_keys.push(location.clone());
+        let Some(programs) = readable(source.registry_subkeys(RegistryHive::LocalMachine, key), &location, unreadable)? else {continue};
+        for program in programs.iter() {
+            let program_key = format!("{}\\{}", key, program);
+            let debugger = readable(source.registry_value(RegistryHive::LocalMachine, &program_key, "Debugger"), &format!("{}\\{}", location, program), unreadable)?;
+            let Some(command) = debugger.flatten().as_ref().and_then(text).filter(|command| !command.trim().is_empty()) else {continue};
+            entries.push(entry(EntryKind::Debugger, &format!("{}\\{}", location, program), program, &command, true, environment));
+        }
+    }
+    Ok(())
+}
+
+/// The files in a folder, and in every folder under it when `recursive`. Empty when it doesn't exist
+fn list_files(folder: &Path, recursive: bool, unreadable: &mut BTreeMap<String, String>) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            unreadable.insert(folder.display().to_string(), ShugoError::io(folder.display().to_string(), &error).detail());
+            return Vec::new();
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(Result::ok).map(|entry| entry.path()).collect();
+    paths.sort();
+    let mut files = Vec::new();
+    for path in paths.into_iter() {
+        if path.is_dir() {
+            if recursive {files.extend(list_files(&path, true, unreadable));}
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn read_bytes(path: &Path, unreadable: &mut BTreeMap<String, String>) -> Option<Vec<u8>> {
+    match fs::read(path) {
+        Ok(bytes) => Some(bytes),
+        Err(error) => {
+            unreadable.insert(path.display().to_string(), ShugoError::io(path.display().to_string(), &error).detail());
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::startup::shortcut::tests::build_shortcut;
+
+    const RUN: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run";
+
+    fn string(text: &str) -> RegistryValue {
+        RegistryValue::String(text.to_string())
+    }
+
+    fn environment() -> BTreeMap<String, String> {
+        BTreeMap::from([("SYSTEMROOT".to_string(), "C:\\Windows".to_string())])
+    }
+
+    fn service(source: FixtureSource, name: &str, start: u32, service_type: u32, image: &str) -> FixtureSource {
+        let key = format!("{}\\{}", SERVICES_KEY, name);
+        source
+            .with_registry(RegistryHive::LocalMachine, &key, "Start", RegistryValue::Dword(start))
+            .with_registry(RegistryHive::LocalMachine, &key, "Type", RegistryValue::Dword(service_type))
+            .with_registry(RegistryHive::LocalMachine, &key, "ImagePath", RegistryValue::ExpandString(image.to_string()))
+    }
+
+    #[test]
+    fn reads_registry_autostarts() {
+        let ifeo = format!("{}\\sethc.exe", IFEO_KEYS[0]);
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, RUN, "SecurityHealth", RegistryValue::ExpandString("%windir%\\system32\\SecurityHealthSystray.exe".to_string()))
+            .with_registry(RegistryHive::CurrentUser, "Software\\Microsoft\\Windows\\CurrentVersion\\Run", "Updater", string("C:\\Users\\alice\\AppData\\Local\\upd.exe"))
+            .with_registry(RegistryHive::LocalMachine, WINLOGON_KEY, "Shell", string("explorer.exe"))
+            .with_registry(RegistryHive::LocalMachine, WINLOGON_KEY, "Userinit", string("C:\\Windows\\system32\\userinit.exe,C:\\ProgramData\\evil.exe,"))
+            .with_registry(RegistryHive::LocalMachine, &ifeo, "Debugger", string("cmd.exe"))
+            .with_registry(RegistryHive::LocalMachine, &format!("{}\\notepad.exe", IFEO_KEYS[0]), "MitigationOptions", RegistryValue::Dword(0));
+        let source = service(source, "Spooler", 2, 0x110, "%SystemRoot%\\System32\\spoolsv.exe");
+        let source = service(source, "wuauserv", 3, 0x20, "%systemroot%\\system32\\svchost.exe -k netsvcs -p");
+        let source = service(source, "Dhcp", 2, 0x20, "%systemroot%\\system32\\svchost.exe -k LocalServiceNetworkRestricted -p")
+            .with_registry(RegistryHive::LocalMachine, &format!("{}\\Dhcp\\Parameters", SERVICES_KEY), "ServiceDll", RegistryValue::ExpandString("%SystemRoot%\\system32\\dhcpcore.dll".to_string()));
+        let source = service(source, "disk", 0, 0x1, "System32\\drivers\\disk.sys");
+
+        let paths = StartupPaths { environment: environment(), ..Default::default() };
+        let info = scan_startup_with(&source, Some(&paths)).unwrap();
+        let names: Vec<(EntryKind, &str)> = info.entries.iter().map(|entry| (entry.kind, entry.name.as_str())).collect();
+        assert_eq!(names, [
+            (EntryKind::Run, "SecurityHealth"),
+            (EntryKind::Run, "Updater"),
+            (EntryKind::Service, "Dhcp"),
+            (EntryKind::Service, "Spooler"),
+            (EntryKind::Winlogon, "Shell"),
+            (EntryKind::Winlogon, "Userinit"),
+            (EntryKind::Winlogon, "Userinit"),
+            (EntryKind::Debugger, "sethc.exe")
+        ]);
+
+        let updater = &info.entries[1];
+        assert!(!updater.machine_wide);
+        assert_eq!(updater.traits, [Trait::UserWritablePath]);
+        assert_eq!(info.entries[0].binary.as_deref(), Some("%windir%\\system32\\SecurityHealthSystray.exe"));
+        assert_eq!(info.entries[2].binary.as_deref(), Some("C:\\Windows\\system32\\dhcpcore.dll"));
+        assert_eq!(info.entries[3].run_as.as_deref(), Some("LocalSystem"));
+        assert!(info.entries[4].is_winlogon_default() && info.entries[5].is_winlogon_default());
+        assert!(!info.entries[6].is_winlogon_default());
+        assert_eq!(info.entries[7].binary.as_deref(), Some("C:\\Windows\\System32\\cmd.exe"));
+    }
+
+    #[test]
+    fn missing_sources_are_unreadable() {
+        let info = scan_startup_with(&crate::common::offline::OfflineSource::new(), None).unwrap();
+        assert!(info.entries.is_empty());
+        assert!(info.module_info.unreadable.contains_key(&format!("HKEY_LOCAL_MACHINE\\{}", SERVICES_KEY)));
+        assert!(info.module_info.files.is_empty());
+    }
+
+    #[test]
+    fn reads_startup_folders_and_tasks() {
+        let root = env::temp_dir().join(format!("shugo-startup-{}", std::process::id()));
+        let startup = join(&root, &format!("ProgramData\\{}", STARTUP_FOLDER));
+        let tasks = join(&root, "Windows\\System32\\Tasks\\Vendor");
+        fs::create_dir_all(&startup).unwrap();
+        fs::create_dir_all(&tasks).unwrap();
+        fs::write(startup.join("desktop.ini"), "[.ShellClassInfo]").unwrap();
+        fs::write(startup.join("Tool.lnk"), build_shortcut("C:\\Program Files\\Tool\\tool.exe", "--tray")).unwrap();
+        fs::write(startup.join("run.bat"), "@echo off").unwrap();
+
+        // Task Scheduler writes UTF-16 with a byte order mark
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-16\"?><Task><Principals><Principal><GroupId>S-1-5-32-545</GroupId></Principal></Principals>\
+            <Actions><Exec><Command>powershell.exe</Command><Arguments>-w hidden -enc SQBFAFgA</Arguments></Exec></Actions></Task>";
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(xml.encode_utf16().flat_map(|c| c.to_le_bytes()));
+        fs::write(tasks.join("Sync"), bytes).unwrap();
+        fs::write(tasks.join("Broken"), "<Task>").unwrap();
+        fs::write(tasks.join("Off"), "<Task><Settings><Enabled>false</Enabled></Settings><Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>").unwrap();
+
+        let paths = StartupPaths {
+            system_root: Some(root.join("Windows")),
+            program_data: Some(root.join("ProgramData")),
+            app_data: None,
+            environment: environment()
+        };
+        let info = scan_startup_with(&FixtureSource::new(), Some(&paths)).unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        let commands: Vec<(&str, &str)> = info.entries.iter().map(|entry| (entry.name.as_str(), entry.command.as_str())).collect();
+        assert_eq!(commands[0], ("Tool.lnk", "\"C:\\Program Files\\Tool\\tool.exe\" --tray"));
+        assert_eq!(commands[1].0, "run.bat");
+        assert_eq!(commands[2], ("\\Vendor\\Sync", "powershell.exe -w hidden -enc SQBFAFgA"));
+        assert_eq!(commands.len(), 3);
+        assert!(info.entries[0].machine_wide && info.entries[2].machine_wide);
+        assert_eq!(info.entries[2].traits, [Trait::EncodedPowerShell]);
+        assert!(info.module_info.unreadable.keys().any(|key| key.ends_with("Broken")));
+    }
+}
@@ -0,0 +1,204 @@
+//! Reads the target and arguments out of a Windows shortcut (`.lnk`) file, the usual thing found in a
+//! Startup folder. Only takes bytes, so it's tested with shortcuts built in memory.
+use crate::common::error::ShugoError;
+use crate::common::source::SourceResult;
+
+const HEADER_SIZE: usize = 0x4C;
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const HAS_NAME: u32 = 0x04;
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const IS_UNICODE: u32 = 0x80;
+
+/// What a shortcut starts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Shortcut {
+    /// The full path of the target, `None` when the shortcut only stores it as a shell item list
+    pub target: Option<String>,
+    pub relative_path: Option<String>,
+    pub working_dir: Option<String>,
+    pub arguments: Option<String>
+}
+
+impl Shortcut {
+    /// The shortcut as one command line, quoting the target when it has spaces
+    pub fn command_line(&self) -> Option<String> {
+        let target = self.target.as_ref().or(self.relative_path.as_ref())?;
+        let program = if target.contains(' ') {format!("\"{}\"", target)} else {target.clone()};
+        Some(match &self.arguments {
+            Some(arguments) if !arguments.is_empty() => format!("{} {}", program, arguments),
+            _ => program
+        })
+    }
+}
+
+/// Reads a `.lnk` file
+pub fn parse_shortcut(bytes: &[u8]) -> SourceResult<Shortcut> {
+    /*
+        Shugo: Shell Link Files
+
+        A `.lnk` file is binary. It starts with a 76 byte header whose LinkFlags say which optional parts follow,
+        in this order:
+
+        - LinkTargetIDList: the target as a list of shell items, skipped here since LinkInfo has the same path
+        - LinkInfo: the target's local path (C:\...) or network path (\\server\share\...)
+        - StringData: counted strings for the description, relative path, working folder, and arguments
+
+        Shortcuts made by Explorer have both an ID list and LinkInfo. Ones that point at special folders may only
+        have the ID list, and then we can't tell the target.
+
+        For more information on the format:
+        (https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-shllink/16cb4ca1-9339-4d0c-a68d-bf1d6cc0f943)
+    */
+    let error = |reason: &str| ShugoError::parse("shortcut", reason.to_string());
+    if bytes.len() < HEADER_SIZE || read_u32(bytes, 0)? != HEADER_SIZE as u32 {
+        return Err(error("not a shell link file"));
+    }
+    let flags = read_u32(bytes, 0x14)?;
+    let mut position = HEADER_SIZE;
+
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        position += 2 + read_u16(bytes, position)? as usize;
+    }
+
+    let mut shortcut = Shortcut::default();
+    if flags & HAS_LINK_INFO != 0 {
+        let size = read_u32(bytes, position)? as usize;
+        let info = bytes.get(position..position + size).ok_or_else(|| error("LinkInfo runs past the end"))?;
+        shortcut.target = link_info_target(info)?;
+        position += size;
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    for (flag, field) in [(HAS_NAME, None), (HAS_RELATIVE_PATH, Some(0)), (HAS_WORKING_DIR, Some(1)), (HAS_ARGUMENTS, Some(2))] {
+        if flags & flag == 0 {
+            continue;
+        }
+        let count = read_u16(bytes, position)? as usize;
+        position += 2;
+        let length = if unicode {count * 2} else {count};
+        let raw = bytes.get(position..position + length).ok_or_else(|| error("string data runs past the end"))?;
+        position += length;
+        let text = if unicode {
+            String::from_utf16_lossy(&raw.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect::<Vec<u16>>())
+        } else {
+            String::from_utf8_lossy(raw).into_owned()
+        };
+        match field {
+            Some(0) => shortcut.relative_path = Some(text),
+            Some(1) => shortcut.working_dir = Some(text),
+            Some(_) => shortcut.arguments = Some(text),
+            None => {}
+        }
+    }
+    Ok(shortcut)
+}
+
+/// The local or network path stored in a LinkInfo block
+fn link_info_target(info: &[u8]) -> SourceResult<Option<String>> {
+    let header_size = read_u32(info, 4)? as usize;
+    let info_flags = read_u32(info, 8)?;
+    let suffix = ansi_at(info, read_u32(info, 0x18)? as usize).unwrap_or_default();
+
+    // VolumeIDAndLocalBasePath, with a Unicode copy of the path when the header is long enough to point at one
+    if info_flags & 0x1 != 0 {
+        if header_size >= 0x24 && let Some(path) = unicode_at(info, read_u32(info, 0x1C)? as usize) {
+            return Ok(Some(format!("{}{}", path, suffix)));
+        }
+        if let Some(path) = ansi_at(info, read_u32(info, 0x10)? as usize) {
+            return Ok(Some(format!("{}{}", path, suffix)));
+        }
+    }
+    // CommonNetworkRelativeLinkAndPathSuffix, a share path like \\server\share
+    if info_flags & 0x2 != 0 {
+        let link = read_u32(info, 0x14)? as usize;
+        let share = ansi_at(info, link + read_u32(info, link + 8)? as usize);
+        if let Some(share) = share {
+            let separator = if suffix.is_empty() || share.ends_with('\\') {""} else {"\\"};
+            return Ok(Some(format!("{}{}{}", share, separator, suffix)));
+        }
+    }
+    Ok(None)
+}
+
+fn ansi_at(bytes: &[u8], offset: usize) -> Option<String> {
+    let rest = bytes.get(offset..).filter(|_| offset != 0)?;
+    let end = rest.iter().position(|byte| *byte == 0)?;
+    Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+fn unicode_at(bytes: &[u8], offset: usize) -> Option<String> {
+    let rest = bytes.get(offset..).filter(|_| offset != 0)?;
+    let wide: Vec<u16> = rest.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).take_while(|c| *c != 0).collect();
+    Some(String::from_utf16_lossy(&wide))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> SourceResult<u16> {
+    bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| ShugoError::parse("shortcut", format!("offset {} is past the end", offset)))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> SourceResult<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| ShugoError::parse("shortcut", format!("offset {} is past the end", offset)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn counted(text: &str) -> Vec<u8> {
+        let wide: Vec<u16> = text.encode_utf16().collect();
+        let mut bytes = (wide.len() as u16).to_le_bytes().to_vec();
+        bytes.extend(wide.iter().flat_map(|c| c.to_le_bytes()));
+        bytes
+    }
+
+    /// A shortcut with an ID list, a local path in LinkInfo, and arguments, like Explorer writes
+    pub(crate) fn build_shortcut(target: &str, arguments: &str) -> Vec<u8> {
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        bytes[..4].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        let flags = HAS_LINK_TARGET_ID_LIST | HAS_LINK_INFO | HAS_WORKING_DIR | HAS_ARGUMENTS | IS_UNICODE;
+        bytes[0x14..0x18].copy_from_slice(&flags.to_le_bytes());
+
+        // An ID list we never look inside
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        bytes.extend_from_slice(&[2, 0, 0, 0]);
+
+        let mut info = vec![0u8; 0x1C];
+        let path_offset = info.len() as u32;
+        info.extend(target.bytes());
+        info.push(0);
+        let suffix_offset = info.len() as u32;
+        info.push(0);
+        let size = info.len() as u32;
+        info[..4].copy_from_slice(&size.to_le_bytes());
+        info[4..8].copy_from_slice(&0x1Cu32.to_le_bytes());
+        info[8..12].copy_from_slice(&1u32.to_le_bytes());
+        info[0x10..0x14].copy_from_slice(&path_offset.to_le_bytes());
+        info[0x18..0x1C].copy_from_slice(&suffix_offset.to_le_bytes());
+        bytes.extend(info);
+
+        bytes.extend(counted("C:\\"));
+        bytes.extend(counted(arguments));
+        bytes
+    }
+
+    #[test]
+    fn reads_shortcut_targets() {
+        let shortcut = parse_shortcut(&build_shortcut("C:\\Program Files\\Tool\\tool.exe", "--tray")).unwrap();
+        assert_eq!(shortcut.target.as_deref(), Some("C:\\Program Files\\Tool\\tool.exe"));
+        assert_eq!(shortcut.working_dir.as_deref(), Some("C:\\"));
+        assert_eq!(shortcut.command_line().as_deref(), Some("\"C:\\Program Files\\Tool\\tool.exe\" --tray"));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse_shortcut(b"[InternetShortcut]\r\nURL=https://example.com\r\n").is_err());
+        let mut truncated = build_shortcut("C:\\a.exe", "");
+        truncated.truncate(HEADER_SIZE + 10);
+        assert!(parse_shortcut(&truncated).is_err());
+    }
+}
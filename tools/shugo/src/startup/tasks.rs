@@ -0,0 +1,387 @@
+//! Reads the task files Task Scheduler keeps under `System32\Tasks`. Each one is a small XML document, read
+//! with the minimal XML parser below so task parsing can be tested on any OS.
+use crate::common::error::ShugoError;
+use crate::common::source::SourceResult;
+
+/// What a task does when it runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskAction {
+    Exec {
+        command: String,
+        arguments: Option<String>
+    },
+    /// A COM class run inside the Task Scheduler host, by CLSID
+    ComHandler(String)
+}
+
+impl TaskAction {
+    /// The action as one command line, quoting the program when it has spaces
+    pub fn command_line(&self) -> String {
+        match self {
+            TaskAction::Exec { command, arguments } => {
+                let program = if command.contains(' ') && !command.starts_with('"') {format!("\"{}\"", command)} else {command.clone()};
+                match arguments {
+                    Some(arguments) if !arguments.is_empty() => format!("{} {}", program, arguments),
+                    _ => program
+                }
+            },
+            TaskAction::ComHandler(class_id) => format!("COM handler {}", class_id)
+        }
+    }
+}
+
+/// The parts of a task definition Shugo looks at
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskDefinition {
+    pub uri: Option<String>,
+    pub author: Option<String>,
+    pub enabled: bool,
+    pub hidden: bool,
+    /// The account the task runs as, like `S-1-5-18` for SYSTEM
+    pub user_id: Option<String>,
+    /// The group whose members it runs for, set instead of `user_id` for tasks that run for every user
+    pub group_id: Option<String>,
+    /// `HighestAvailable` when the task runs elevated
+    pub run_level: Option<String>,
+    /// Enabled trigger types, like `LogonTrigger` or `BootTrigger`
+    pub triggers: Vec<String>,
+    pub actions: Vec<TaskAction>
+}
+
+/// Reads a task file, `text` already decoded from UTF-16
+pub fn parse_task_xml(text: &str) -> SourceResult<TaskDefinition> {
+    /*
+        Shugo: Scheduled Task XML
+
+        Task Scheduler stores every task as an XML file under C:\Windows\System32\Tasks, in folders matching
+        the task's path (\Microsoft\Windows\Defrag\ScheduledDefrag is Tasks\Microsoft\Windows\Defrag\ScheduledDefrag).
+        The parts that matter for persistence:
+
+        - Triggers: when it runs. LogonTrigger and BootTrigger make a task an autostart entry
+        - Actions: Exec actions run Command with Arguments, ComHandler actions load a COM class by CLSID
+        - Principals: who it runs as. UserId S-1-5-18 is SYSTEM, and RunLevel HighestAvailable runs it elevated
+        - Settings: Enabled, and Hidden, which keeps the task out of Task Scheduler's default view
+
+        For more information on the task XML schema:
+        (https://learn.microsoft.com/en-us/windows/win32/taskschd/task-scheduler-schema)
+    */
+    let root = parse_xml(text)?;
+    if root.name != "Task" {
+        return Err(ShugoError::parse("task XML", format!("expected a <Task> element, found <{}>", root.name)));
+    }
+    let text_at = |path: &[&str]| root.find(path).map(|element| element.text.trim().to_string()).filter(|text| !text.is_empty());
+
+    let triggers = root.find(&["Triggers"]).map(|triggers| triggers.children.iter()
+        .filter(|trigger| trigger.find(&["Enabled"]).is_none_or(|enabled| enabled.text.trim() != "false"))
+        .map(|trigger| trigger.name.clone())
+        .collect()).unwrap_or_default();
+
+    let mut actions = Vec::new();
+    for action in root.find(&["Actions"]).map(|actions| actions.children.as_slice()).unwrap_or_default() {
+        let field = |name: &str| action.find(&[name]).map(|element| element.text.trim().to_string()).filter(|text| !text.is_empty());
+        match action.name.as_str() {
+            "Exec" => actions.push(TaskAction::Exec {
+                command: field("Command").ok_or_else(|| ShugoError::parse("task XML", "an Exec action has no Command"))?,
+                arguments: field("Arguments")
+            }),
+            "ComHandler" => actions.push(TaskAction::ComHandler(field("ClassId").unwrap_or_default())),
+            // SendEmail and ShowMessage were removed in Windows 8 and don't run anything
+            _ => {}
+        }
+    }
+
+    Ok(TaskDefinition {
+        uri: text_at(&["RegistrationInfo", "URI"]),
+        author: text_at(&["RegistrationInfo", "Author"]),
+        enabled: text_at(&["Settings", "Enabled"]).is_none_or(|enabled| enabled != "false"),
+        hidden: text_at(&["Settings", "Hidden"]).is_some_and(|hidden| hidden == "true"),
+        user_id: text_at(&["Principals", "Principal", "UserId"]),
+        group_id: text_at(&["Principals", "Principal", "GroupId"]),
+        run_level: text_at(&["Principals", "Principal", "RunLevel"]),
+        triggers,
+        actions
+    })
+}
+
+/// One XML element, with namespace prefixes dropped from its name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlElement>,
+    /// The element's own text, with entities decoded
+    pub text: String
+}
+
+impl XmlElement {
+    /// Follows child element names down from this element, taking the first match at each step
+    pub fn find(&self, path: &[&str]) -> Option<&XmlElement> {
+        path.iter().try_fold(self, |element, name| element.children.iter().find(|child| child.name == *name))
+    }
+}
+
+/// Parses a whole XML document and gives back its root element
+pub fn parse_xml(text: &str) -> SourceResult<XmlElement> {
+    let mut parser = XmlParser { chars: text.chars().collect(), position: 0 };
+    parser.skip_misc()?;
+    let root = parser.element(0)?;
+    parser.skip_misc()?;
+    if parser.position < parser.chars.len() {
+        return Err(parser.error("content after the root element"));
+    }
+    Ok(root)
+}
+
+/// Real task files are about five levels deep
+const MAX_DEPTH: usize = 64;
+
+/// A small reader for the XML Task Scheduler writes: elements, attributes, text, comments, and CDATA
+struct XmlParser {
+    chars: Vec<char>,
+    position: usize
+}
+
+impl XmlParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars().enumerate().all(|(index, c)| self.chars.get(self.position + index) == Some(&c))
+    }
+
+    fn error(&self, reason: &str) -> ShugoError {
+        let line = self.chars[..self.position.min(self.chars.len())].iter().filter(|c| **c == '\n').count() + 1;
+        ShugoError::parse("task XML", format!("line {}: {}", line, reason))
+    }
+
+    /// Moves past `end`, failing if it never shows up
+    fn skip_past(&mut self, end: &str) -> SourceResult<()> {
+        while self.position < self.chars.len() {
+            if self.starts_with(end) {
+                self.position += end.chars().count();
+                return Ok(());
+            }
+            self.position += 1;
+        }
+        Err(self.error(&format!("missing {:?}", end)))
+    }
+
+    /// Skips whitespace, the byte order mark, the `<?xml ?>` declaration, comments, and DOCTYPE
+    fn skip_misc(&mut self) -> SourceResult<()> {
+        loop {
+            while self.peek().is_some_and(|c| c.is_whitespace() || c == '\u{feff}') {self.position += 1;}
+            if self.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.starts_with("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> SourceResult<String> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')) {self.position += 1;}
+        if start == self.position {
+            return Err(self.error("expected a name"));
+        }
+        let name: String = self.chars[start..self.position].iter().collect();
+        Ok(name.rsplit(':').next().unwrap_or(&name).to_string())
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {self.position += 1;}
+    }
+
+    fn element(&mut self, depth: usize) -> SourceResult<XmlElement> {
+        // Task files from an offline image can be anything, and each level of nesting is a level of recursion
+        if depth > MAX_DEPTH {
+            return Err(self.error(&format!("elements nested more than {} deep", MAX_DEPTH)));
+        }
+        if self.peek() != Some('<') {
+            return Err(self.error("expected an element"));
+        }
+        self.position += 1;
+        let mut element = XmlElement { name: self.name()?, ..Default::default() };
+
+        loop {
+            self.skip_space();
+            match self.peek() {
+                Some('/') if self.starts_with("/>") => {
+                    self.position += 2;
+                    return Ok(element);
+                },
+                Some('>') => {
+                    self.position += 1;
+                    break;
+                },
+                Some(_) => {
+                    let name = self.name()?;
+                    self.skip_space();
+                    if self.peek() != Some('=') {
+                        return Err(self.error(&format!("attribute {} has no value", name)));
+                    }
+                    self.position += 1;
+                    self.skip_space();
+                    let Some(quote) = self.peek().filter(|c| *c == '"' || *c == '\'') else {
+                        return Err(self.error("expected a quoted attribute value"));
+                    };
+                    self.position += 1;
+                    let start = self.position;
+                    while self.peek().is_some_and(|c| c != quote) {self.position += 1;}
+                    if self.peek().is_none() {
+                        return Err(self.error("unterminated attribute value"));
+                    }
+                    let raw: String = self.chars[start..self.position].iter().collect();
+                    self.position += 1;
+                    element.attributes.push((name, self.decode(&raw)?));
+                },
+                None => return Err(self.error(&format!("<{}> is never closed", element.name)))
+            }
+        }
+
+        loop {
+            if self.starts_with("</") {
+                self.position += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("</{}> closes <{}>", name, element.name)));
+                }
+                self.skip_space();
+                if self.peek() != Some('>') {
+                    return Err(self.error("expected '>'"));
+                }
+                self.position += 1;
+                return Ok(element);
+            } else if self.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.starts_with("<![CDATA[") {
+                self.position += 9;
+                let start = self.position;
+                self.skip_past("]]>")?;
+                element.text.extend(&self.chars[start..self.position - 3]);
+            } else if self.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.peek() == Some('<') {
+                element.children.push(self.element(depth + 1)?);
+            } else if self.peek().is_some() {
+                let start = self.position;
+                while self.peek().is_some_and(|c| c != '<') {self.position += 1;}
+                let raw: String = self.chars[start..self.position].iter().collect();
+                let decoded = self.decode(&raw)?;
+                element.text.push_str(&decoded);
+            } else {
+                return Err(self.error(&format!("<{}> is never closed", element.name)));
+            }
+        }
+    }
+
+    /// Decodes `&lt;`, `&amp;`, and the other entities, plus `&#NN;` and `&#xNN;` character references
+    fn decode(&self, raw: &str) -> SourceResult<String> {
+        let mut text = String::new();
+        let mut rest = raw;
+        while let Some(start) = rest.find('&') {
+            text.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find(';') else {
+                return Err(self.error("unterminated entity"));
+            };
+            let entity = &rest[start + 1..start + end];
+            let c = match entity {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "quot" => '"',
+                "apos" => '\'',
+                _ => {
+                    let code = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else {
+                        entity.strip_prefix('#').and_then(|decimal| decimal.parse::<u32>().ok())
+                    };
+                    code.and_then(char::from_u32).ok_or_else(|| self.error(&format!("unknown entity &{};", entity)))?
+                }
+            };
+            text.push(c);
+            rest = &rest[start + end + 1..];
+        }
+        text.push_str(rest);
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASK_XML: &str = r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Author>CONTOSO\admin</Author>
+    <URI>\Updater</URI>
+  </RegistrationInfo>
+  <!-- Runs at every logon and at boot -->
+  <Triggers>
+    <LogonTrigger><Enabled>true</Enabled></LogonTrigger>
+    <BootTrigger />
+    <TimeTrigger><Enabled>false</Enabled><StartBoundary>2026-01-01T00:00:00</StartBoundary></TimeTrigger>
+  </Triggers>
+  <Principals>
+    <Principal id="Author">
+      <UserId>S-1-5-18</UserId>
+      <RunLevel>HighestAvailable</RunLevel>
+    </Principal>
+  </Principals>
+  <Settings>
+    <Enabled>true</Enabled>
+    <Hidden>true</Hidden>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>C:\Program Files\Updater\update.exe</Command>
+      <Arguments>-q &amp; -url "https://example.com/a?b=1&amp;c=2"</Arguments>
+    </Exec>
+    <ComHandler><ClassId>{A6BA00FE-40E8-477C-B713-C64A14F18ADB}</ClassId></ComHandler>
+  </Actions>
+</Task>
+"#;
+
+    #[test]
+    fn reads_task_definitions() {
+        let task = parse_task_xml(TASK_XML).unwrap();
+        assert_eq!(task.uri.as_deref(), Some("\\Updater"));
+        assert_eq!(task.author.as_deref(), Some("CONTOSO\\admin"));
+        assert!(task.enabled && task.hidden);
+        assert_eq!((task.user_id.as_deref(), task.group_id.as_deref()), (Some("S-1-5-18"), None));
+        assert_eq!(task.run_level.as_deref(), Some("HighestAvailable"));
+        assert_eq!(task.triggers, ["LogonTrigger", "BootTrigger"]);
+        assert_eq!(task.actions[0].command_line(), "\"C:\\Program Files\\Updater\\update.exe\" -q & -url \"https://example.com/a?b=1&c=2\"");
+        assert_eq!(task.actions[1], TaskAction::ComHandler("{A6BA00FE-40E8-477C-B713-C64A14F18ADB}".to_string()));
+    }
+
+    #[test]
+    fn parses_xml_details() {
+        let root = parse_xml("\u{feff}<a x='1' y=\"&#x41;&#66;\"><t:b><![CDATA[<raw>]]></t:b><!-- c --><c/></a>").unwrap();
+        assert_eq!(root.attributes, [("x".to_string(), "1".to_string()), ("y".to_string(), "AB".to_string())]);
+        assert_eq!(root.find(&["b"]).unwrap().text, "<raw>");
+        assert!(root.find(&["c"]).is_some());
+        assert!(root.find(&["b", "missing"]).is_none());
+    }
+
+    #[test]
+    fn rejects_broken_xml() {
+        assert!(parse_xml("<a><b></a>").is_err());
+        assert!(parse_xml("<a>").is_err());
+        assert!(parse_xml("<a>&nope;</a>").is_err());
+        assert!(parse_xml("<a/><b/>").is_err());
+        assert!(parse_task_xml("<NotATask/>").is_err());
+        assert!(parse_task_xml("<Task><Actions><Exec/></Actions></Task>").is_err());
+        let deep = format!("{}{}", "<a>".repeat(100_000), "</a>".repeat(100_000));
+        assert!(parse_xml(&deep).unwrap_err().to_string().contains("nested more than 64 deep"));
+        let error = parse_xml("<a>\n<b x=1/></a>").unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{}", error);
+    }
+}
@@ -0,0 +1,117 @@
+//! Assessment for the UAC Module, turns the registry values into findings.
+use super::scanner::UacInfo;
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "uac";
+
+/// Assessing UAC settings
+pub fn assess_uac(info: &UacInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    // Only settings we actually read as 0 are findings, a setting that isn't set is unknown rather than disabled
+    if info.lua_value == Some(0) {
+        findings.push(
+            Finding::new("UAC-001", MODULE, Severity::Critical, "UAC is disabled")
+                .explanation("All programs run with full administrator privileges, so anything you run can change the system")
+                .remediation("Set EnableLUA to 1 (Control Panel > User Accounts > Change User Account Control settings) and restart")
+                .evidence("EnableLUA=0")
+        );
+    } else if info.prompt_level_value == Some(0) {
+        findings.push(
+            Finding::new("UAC-002", MODULE, Severity::High, "UAC is set to 'Never notify'")
+                .explanation("Administrators are elevated silently, which makes UAC ineffective against malware")
+                .remediation("Set ConsentPromptBehaviorAdmin to 2 (Prompt for consent on the secure desktop)")
+                .evidence("ConsentPromptBehaviorAdmin=0")
+        );
+    }
+
+    if info.prompt_on_secure_desktop_value == Some(0) {
+        findings.push(
+            Finding::new("UAC-003", MODULE, Severity::Medium, "Secure Desktop is disabled")
+                .explanation("UAC prompts appear on the normal desktop where other programs can spoof or click them")
+                .remediation("Set PromptOnSecureDesktop to 1")
+                .evidence("PromptOnSecureDesktop=0")
+        );
+    }
+    if info.installer_detection_value == Some(0) {
+        findings.push(
+            Finding::new("UAC-004", MODULE, Severity::Medium, "Installer Detection is disabled")
+                .explanation("Installers that need administrator rights aren't detected, so silent installations are possible")
+                .remediation("Set EnableInstallerDetection to 1")
+                .evidence("EnableInstallerDetection=0")
+        );
+    }
+    if info.validate_admin_code_signatures_value == Some(0) {
+        findings.push(
+            Finding::new("UAC-005", MODULE, Severity::Low, "Code Signature Validation is disabled")
+                .explanation("Unsigned applications can ask to elevate")
+                .remediation("Set ValidateAdminCodeSignatures to 1 if every admin tool you use is signed")
+                .evidence("ValidateAdminCodeSignatures=0")
+        );
+    }
+    if info.enable_virtualization_value == Some(0) {
+        findings.push(
+            Finding::new("UAC-006", MODULE, Severity::Low, "Virtualization is disabled")
+                .explanation("Legacy apps that write to protected locations fail instead of being redirected")
+                .remediation("Set EnableVirtualization to 1")
+                .evidence("EnableVirtualization=0")
+        );
+    }
+
+    if !info.module_info.unreadable.is_empty() {
+        let mut finding = Finding::new("UAC-007", MODULE, Severity::Info, "Some UAC settings couldn't be read")
+            .explanation("These settings are shown as unknown instead of being judged, so the assessment may be incomplete")
+            .remediation("Run Shugo as administrator, or check the values with regedit");
+        for (value, reason) in &info.module_info.unreadable {
+            finding = finding.evidence(format!("{}: {}", value, reason));
+        }
+        findings.push(finding);
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uac::scanner::ModuleInfo;
+
+    fn info(lua: u32, prompt: u32) -> UacInfo {
+        UacInfo {
+            lua_value: Some(lua),
+            prompt_level_value: Some(prompt),
+            prompt_on_secure_desktop_value: Some(1),
+            installer_detection_value: Some(1),
+            validate_admin_code_signatures_value: Some(1),
+            filter_admin_token_value: Some(1),
+            enable_virtualization_value: Some(1),
+            module_info: ModuleInfo { registry_key: String::new(), queries: vec![], unreadable: Default::default() }
+        }
+    }
+
+    #[test]
+    fn secure_settings_have_no_findings() {
+        assert!(assess_uac(&info(1, 5)).is_empty());
+    }
+
+    #[test]
+    fn flags_disabled_uac() {
+        let findings = assess_uac(&info(0, 5));
+        assert_eq!(findings[0].id, "UAC-001");
+        assert_eq!(findings[0].evidence, vec!["EnableLUA=0"]);
+        assert_eq!(assess_uac(&info(1, 0))[0].id, "UAC-002");
+    }
+
+    #[test]
+    fn unknown_settings_are_not_findings() {
+        let mut unknown = info(1, 5);
+        unknown.lua_value = None;
+        unknown.prompt_on_secure_desktop_value = None;
+        assert!(assess_uac(&unknown).is_empty());
+
+        unknown.module_info.unreadable.insert("EnableLUA".to_string(), "access denied".to_string());
+        let findings = assess_uac(&unknown);
+        assert_eq!(findings[0].id, "UAC-007");
+        assert_eq!(findings[0].severity, Severity::Info);
+    }
+}
@@ -1,4 +1,9 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_uac;
 use super::scanner::UacInfo;
+use crate::common::finding::display_findings;
+use crate::common::output::with_decoded;
 use crate::common::time::get_time;
 
 pub fn display_uac(info: UacInfo, verbose: bool) {
@@ -12,12 +17,30 @@ pub fn display_uac(info: UacInfo, verbose: bool) {
     
     display_settings(&info, verbose);
 
-    display_assessment(&info);
+    display_findings(&assess_uac(&info), verbose);
 
     if verbose {display_technical();}
     
 }
 
+/// JSON for UAC Module
+pub fn json_uac(info: &UacInfo) -> Value {
+    let decoded = json!({
+        "uac_status": uac_decode(info.lua_value),
+        "prompt_level": decode_prompt_level(info.prompt_level_value),
+        "secure_desktop": uac_decode(info.prompt_on_secure_desktop_value),
+        "installer_detection": uac_decode(info.installer_detection_value),
+        "code_signature_validation": uac_decode(info.validate_admin_code_signatures_value),
+        "virtualization": uac_decode(info.enable_virtualization_value),
+        "administrator_token": uac_decode(info.filter_admin_token_value)
+    });
+
+    json!({
+        "uac": with_decoded(info, decoded),
+        "findings": assess_uac(info)
+    })
+}
+
 fn display_scan_details(info: &UacInfo) {
     println!("Scan Details:");
     let (h, m, s) = get_time();
@@ -27,6 +50,9 @@ fn display_scan_details(info: &UacInfo) {
     for query in info.module_info.queries.iter() {
         println!("   - {}", query);
     }
+    for (value, reason) in info.module_info.unreadable.iter() {
+        println!(" - Unreadable: {} ({})", value, reason);
+    }
 
     println!();
 }
@@ -34,62 +60,25 @@ fn display_scan_details(info: &UacInfo) {
 fn display_summary(info: &UacInfo, verbose: bool) {
     println!("Summary:");
     println!(" - UAC Status: {}", uac_decode(info.lua_value));
-    if verbose {println!("   - Value: {}", info.lua_value);}
+    if verbose {println!("   - Value: {}", raw_value(info.lua_value));}
     println!(" - Prompt Level: {}", decode_prompt_level(info.prompt_level_value));
-    if verbose {println!("   - Value: {}", info.prompt_level_value);}
+    if verbose {println!("   - Value: {}", raw_value(info.prompt_level_value));}
     println!();
 }
 
 fn display_settings(info: &UacInfo, verbose: bool) {
     println!("UAC Settings:");
     println!(" - Secure Desktop: {}", uac_decode(info.prompt_on_secure_desktop_value));
-    if verbose {println!("   - Value: {}", info.prompt_on_secure_desktop_value);}
+    if verbose {println!("   - Value: {}", raw_value(info.prompt_on_secure_desktop_value));}
     println!(" - Installer Detection: {}", uac_decode(info.installer_detection_value));
-    if verbose {println!("   - Value: {}", info.installer_detection_value);}
+    if verbose {println!("   - Value: {}", raw_value(info.installer_detection_value));}
     println!(" - Code Signature Validation: {}", uac_decode(info.validate_admin_code_signatures_value));
-    if verbose {println!("   - Value: {}", info.validate_admin_code_signatures_value);}
+    if verbose {println!("   - Value: {}", raw_value(info.validate_admin_code_signatures_value));}
     println!(" - Virtualization: {}", uac_decode(info.enable_virtualization_value));
-    if verbose {println!("   - Value: {}", info.enable_virtualization_value);}
+    if verbose {println!("   - Value: {}", raw_value(info.enable_virtualization_value));}
     println!(" - Administrator Token: {}", uac_decode(info.filter_admin_token_value));
-    if verbose {println!("   - Value: {}", info.filter_admin_token_value);}
-    println!();
-}
-
-fn display_assessment(info: &UacInfo) {
-    println!("Security Assessment:");
-    println!(" - UAC Protection:");
-
-    if info.lua_value == 0 {
-        println!("   - UAC is DISABLED - Critical security risk!");
-        println!("   - All programs run with full administrator privileges");
-    } else if info.prompt_level_value == 0 {
-        println!("   - UAC is enabled but set to 'Never notify' - Ineffective!");
-    } else {
-        println!("   - UAC is enabled");
-    }
+    if verbose {println!("   - Value: {}", raw_value(info.filter_admin_token_value));}
     println!();
-
-    println!("- Security Weaknesses:");
-    let mut weakness = Vec::new();
-
-    if info.prompt_on_secure_desktop_value == 0 {
-        weakness.push("Secure Desktop is disabled - UAC prompts vulnerable to malware");
-    } if info.installer_detection_value == 0 {
-        weakness.push("Installer Detection is disabled - Silent installations possible");
-    } if info.validate_admin_code_signatures_value == 0 {
-        weakness.push("Code Signature Validation is disabled - Unsigned apps can elevate");
-    } if info.enable_virtualization_value == 0 {
-        weakness.push("Virtualization is disabled - Legacy app compatibility may suffer");
-    }
-
-    if weakness.is_empty() {
-        println!("   - No significant weaknesses detected");
-    } else {
-        for (i, weakness) in weakness.iter().enumerate() {
-            println!("   {}. {}", i + 1, weakness);
-            println!();
-        }
-    }
 }
 
 fn display_technical() {
@@ -99,22 +88,29 @@ fn display_technical() {
     println!();
 }
 
-fn uac_decode(state: u32) -> String {
+pub(crate) fn uac_decode(state: Option<u32>) -> String {
     match state {
-        0 => "Disabled".to_string(),
-        1 => "Enabled".to_string(),
-        _ => "Unknown".to_string()
+        Some(0) => "Disabled".to_string(),
+        Some(1) => "Enabled".to_string(),
+        Some(_) => "Unknown".to_string(),
+        None => "Unknown (not set or unreadable)".to_string()
     }
 }
 
-fn decode_prompt_level(level: u32) -> &'static str {
+pub(crate) fn decode_prompt_level(level: Option<u32>) -> &'static str {
     match level {
-        0 => "Never notify (Least Secure)",
-        1 => "Prompt for credentials on secure desktop",
-        2 => "Prompt for consent on secure desktop",
-        3 => "Prompt for credentials",
-        4 => "Prompt for consent",
-        5 => "Prompt for consent for non-Windows binaries (Most Secure, Default)",
-        _ => "Unknown configuration"
+        Some(0) => "Never notify (Least Secure)",
+        Some(1) => "Prompt for credentials on secure desktop (Most Secure)",
+        Some(2) => "Prompt for consent on secure desktop",
+        Some(3) => "Prompt for credentials",
+        Some(4) => "Prompt for consent",
+        Some(5) => "Prompt for consent for non-Windows binaries (Default)",
+        Some(_) => "Unknown configuration",
+        None => "Unknown (not set or unreadable)"
     }
+}
+
+/// The raw value for verbose output
+fn raw_value(value: Option<u32>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_else(|| "Not set".to_string())
 }
\ No newline at end of file
@@ -8,31 +8,48 @@
 //! - Filter Administration Token level
 //! - Enable Virtualization level
 //! 
-//! Unlike the other modules, this one accesses the Windows Registry directly 
-//! instead of using COM/WMI APIs. The Registry is Windows Heirarchical database for 
-//! system configuration settings.
-use windows::core::*;
-use windows::Win32::Foundation::*;
-use windows::Win32::System::Registry::*;
+//! Unlike the other modules, this one reads the Windows Registry instead of using
+//! COM/WMI APIs. The Registry is Windows Heirarchical database for system
+//! configuration settings.
+use std::collections::BTreeMap;
 
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::ShugoError;
+use crate::common::source::{SecuritySource, SourceResult, RegistryHive, registry_dword, system_source};
+
+/// Each setting is `None` when it isn't set or couldn't be read, so it shows as unknown instead of 0
+#[derive(Serialize, Deserialize)]
 pub struct UacInfo {
-    pub lua_value: u32,
-    pub prompt_level_value: u32,
-    pub prompt_on_secure_desktop_value: u32,
-    pub installer_detection_value: u32,
-    pub validate_admin_code_signatures_value: u32,
-    pub filter_admin_token_value: u32,
-    pub enable_virtualization_value: u32,
+    pub lua_value: Option<u32>,
+    pub prompt_level_value: Option<u32>,
+    pub prompt_on_secure_desktop_value: Option<u32>,
+    pub installer_detection_value: Option<u32>,
+    pub validate_admin_code_signatures_value: Option<u32>,
+    pub filter_admin_token_value: Option<u32>,
+    pub enable_virtualization_value: Option<u32>,
     pub module_info: ModuleInfo
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ModuleInfo {
     pub registry_key: String,
-    pub queries: Vec<String>
+    pub queries: Vec<String>,
+    /// Values that exist but couldn't be read, with the reason
+    #[serde(default)]
+    pub unreadable: BTreeMap<String, String>
 }
 
+/// This is our key path to the UAC settings in HKEY_LOCAL_MACHINE
+pub const UAC_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Policies\\System";
+
 /// Scanning UAC settings for Windows 
-pub fn scan_uac() -> Result<UacInfo> {
+pub fn scan_uac() -> SourceResult<UacInfo> {
+    scan_uac_with(system_source()?.as_ref())
+}
+
+/// Scanning UAC settings from any source
+pub fn scan_uac_with(source: &dyn SecuritySource) -> SourceResult<UacInfo> {
     /* 
         Shugo: Using Windows Registry
 
@@ -42,176 +59,181 @@ pub fn scan_uac() -> Result<UacInfo> {
 
         UAC helps prevent unathorized changes to your system by prompting for administrator 
         approval before allowing apps to make changes.
+
+        The key path we're reading is:
+        HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System
+
+        Note: Registry key names are NOT case sensitive
     */
-    unsafe {
-        /*
-            Shugo: Opening a Registry Key
-
-            We need to open the registry key that contains UAC settings before we can read
-            values from it. The key path we're opening is:
-            HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System
-
-            Note: Registry key names are NOT case sensitive
-
-            For more information on `RegOpenKeyExW`:
-            (https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regopenkeyexw) - C++
-            (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Registry/fn.RegOpenKeyExW.html) - Rust
-        */
-        let mut key: HKEY = HKEY::default();
-        let subkey: PCWSTR = w!("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Policies\\System"); // This is our key path to the specified registry
-
-        let _reg: WIN32_ERROR = RegOpenKeyExW( 
-            HKEY_LOCAL_MACHINE, // This is a handle to open the specified registry path (e.g. HKEY_CLASSES_ROOT, HKEY_CURRENT_CONFIG, HKEY_CURRENT_USER, HKEY_USERS)
-            subkey, // The name of the registry subkey to be opened
-            Some(0), // Specifies the option to apply when opening the key. Were gonna set it to 0
-            KEY_READ, // This mask specifies the directed access rights to the key being opened. for info: https://learn.microsoft.com/en-us/windows/win32/sysinfo/registry-key-security-and-access-rights
-            &mut key // This is a pointer to a variable that receives a handle to the opened key
-        );
-        match _reg {
-            ERROR_SUCCESS => {
-                // Registry key found
-            },
-            ERROR_FILE_NOT_FOUND => {
-                // Registry key not found
-                println!("Key not found");
-                return Err(_reg.into());
+
+    /*
+        Shugo: Querying Registry Values
+
+        The UAC settings all live under one key, we ask the source for each value inside it.
+
+        EnableLUA (0 or 1):
+        - 0 = UAC is disabled (all apps run with full admin rights)
+        - 1 = UAC is enabled (apps need approval for admin tasks)
+
+        ConsentPromptBehaviorAdmin:
+        - 0 = Elevate without prompting (Most dangerous)
+        - 1 = Prompt for credentials on secure desktop (Most secure)
+        - 2 = Prompt for consent on secure desktop
+        - 3 = Prompt for credentials
+        - 4 = Prompt for consent
+        - 5 = Prompt for consent for non-Windows binaries (Default)
+
+        Lower is stricter from 1 to 5, but 0 turns the prompt off altogether.
+
+        PromptOnSecureDesktop:
+        - 0 = Consent prompting occurs on user desktop
+        - 1 = Force all UAC prompts to happen on user secure desktop
+
+        EnableInstallerDetection Value:
+        - 0 = Heuristically detection off for installing packages that require administrator
+        - 1 = Heuristically detection on for installing packages that require administrator
+
+        ValidateAdminCodeSignatures:
+        - 0 = Doesn't enforce cryptographic signatures on interactive applications that require administrator
+        - 1 = Enforces cryptographic signatures on interactive applications that require administrator
+
+        FilterAdministratorToken: 
+        - 0 = Built in admin accounts don't get UAC prompts
+        - 1 = Built in admin accounts get UAC prompts
+
+        EnableVirtualization:
+        - 0 = Legacy apps that write to protected locations fail
+        - 1 = Legacy app writes are redirected to per-user locations
+    */
+    /*
+        Shugo: Unknown Is Not Disabled
+
+        A value that isn't there is not the same as a value set to 0. When EnableLUA is missing Windows uses
+        its default (enabled), so reading it as 0 would report UAC as disabled on a perfectly healthy machine.
+        Instead every setting is an `Option`: `Some` when we read a DWORD, `None` when the value isn't set or
+        we couldn't read it. Values we couldn't read are kept with the reason so the report can say why.
+
+        Only errors about a single value are kept this way. If the whole key is missing or the source can't
+        read the registry at all, the scan fails like it did before.
+    */
+    let mut query: Vec<String> = Vec::new();
+    let mut unreadable: BTreeMap<String, String> = BTreeMap::new();
+    let mut registry_query = |value: &str| -> SourceResult<Option<u32>> {
+        query.push(value.to_string()); // Query name
+        match registry_dword(source, RegistryHive::LocalMachine, UAC_KEY, value) {
+            Ok(data) => Ok(Some(data)),
+            Err(ShugoError::ValueMissing { .. }) => Ok(None),
+            Err(error @ (ShugoError::AccessDenied { .. } | ShugoError::ParseError { .. })) => {
+                unreadable.insert(value.to_string(), error.to_string());
+                Ok(None)
             },
-            _ => {
-                // Some error opening
-                println!("Error opening key");
-                return Err(_reg.into());
-            }
+            Err(error) => Err(error.in_module("uac"))
         }
+    };
+
+    let enable_lua: Option<u32> = registry_query("EnableLUA")?;
+    let prompt_behavior: Option<u32> = registry_query("ConsentPromptBehaviorAdmin")?;
+    let prompt_secure_desktop: Option<u32> = registry_query("PromptOnSecureDesktop")?;
+    let installer_detection: Option<u32> = registry_query("EnableInstallerDetection")?;
+    let admin_signature: Option<u32> = registry_query("ValidateAdminCodeSignatures")?;
+    let admin_token: Option<u32> = registry_query("FilterAdministratorToken")?;
+    let virtualization: Option<u32> = registry_query("EnableVirtualization")?;
+
+    let module_info: ModuleInfo = ModuleInfo {
+        registry_key: UAC_KEY.to_string(),
+        queries: query,
+        unreadable
+    };
+
+    Ok(UacInfo {
+        lua_value: enable_lua,
+        prompt_level_value: prompt_behavior,
+        prompt_on_secure_desktop_value: prompt_secure_desktop,
+        installer_detection_value: installer_detection,
+        validate_admin_code_signatures_value: admin_signature,
+        filter_admin_token_value: admin_token,
+        enable_virtualization_value: virtualization,
+        module_info
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fixture::FixtureSource;
+    use crate::common::source::RegistryValue;
+
+    #[test]
+    fn reads_uac_values() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, UAC_KEY, "EnableLUA", RegistryValue::Dword(1))
+            .with_registry(RegistryHive::LocalMachine, UAC_KEY, "ConsentPromptBehaviorAdmin", RegistryValue::Dword(5))
+            .with_registry(RegistryHive::LocalMachine, UAC_KEY, "PromptOnSecureDesktop", RegistryValue::Dword(1));
+
+        let info = scan_uac_with(&source).unwrap();
+        assert_eq!(info.lua_value, Some(1));
+        assert_eq!(info.prompt_level_value, Some(5));
+        assert_eq!(info.prompt_on_secure_desktop_value, Some(1));
+        assert_eq!(info.enable_virtualization_value, None);
+        assert_eq!(info.module_info.queries.len(), 7);
+    }
 
-        /*
-            Shugo: Querying Registry Values
-
-            Now that we have the key open, we can query specific values within it.
-
-            EnableLUA (0 or 1):
-            - 0 = UAC is disabled (all apps run with full admin rights)
-            - 1 = UAC is enabled (apps need approval for admin tasks)
-
-            ConsentPromptBehaviorAdmin:
-            - 0 = Elevate without prompting (Most dangerous)
-            - 1 = Prompt for credentials on secure desktop
-            - 2 = Prompt for consent on secure desktop
-            - 3 = Prompt for credentials (Default for non-admins)
-            - 4 = Prompt for consent (Default for admins)
-            - 5 = prompt for consent for non-Windows binaries (Most secure)
-
-            PromptOnSecureDesktop:
-            - 0 = Consent prompting occurs on user desktop
-            - 1 = Force all UAC prompts to happen on user secure desktop
-
-            EnableInstallerDetection Value:
-            - 0 = Heuristically detection off for installing packages that require administrator
-            - 1 = Heuristically detection on for installing packages that require administrator
-
-            ValidateAdminCodeSignatures:
-            - 0 = Doesn't enforce cryptographic signatures on interactive applications that require administrator
-            - 1 = Enforces cryptographic signatures on interactive applications that require administrator
-
-            FilterAdministratorToken: 
-            - 0 = Built in admin accounts don't get UAC prompts
-            - 1 = Built in admin accounts get UAC prompts
-
-            For more information on `RegQueryValueExW`:
-            (https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regqueryvalueexw) - C++
-            (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Registry/fn.RegQueryValueExW.html) - Rust
-        */
-
-        let mut query: Vec<String> = Vec::new();
-
-        // Query EnableLUA value
-        let value: PCWSTR = w!("EnableLUA"); // Registry value we want to retrieve
-        let mut enable_lua: u32 = 0; // This will hold our data for our uac.
-        let mut size: u32 = std::mem::size_of::<u32>() as u32; // This is a buffer for our size arg
-        // Go check out the function to see what this data is for
-        registry_query(key, value, &mut enable_lua, &mut size);
-        query.push(value.to_string()?); // Query name
-
-        // Query ConsentPromptBehaviorAdmin Value
-        let value: PCWSTR = w!("ConsentPromptBehaviorAdmin");
-        let mut prompt_behavior: u32 = 0;
-        let mut size: u32 = std::mem::size_of::<u32>() as u32;
-        registry_query(key, value, &mut prompt_behavior, &mut size);
-        query.push(value.to_string()?);
-
-        // Query PromptOnSecureDesktop Value
-        let value: PCWSTR = w!("PromptOnSecureDesktop");
-        let mut prompt_secure_desktop: u32 = 0;
-        let mut size: u32 = std::mem::size_of::<u32>() as u32;
-        registry_query(key, value, &mut prompt_secure_desktop, &mut size);
-        query.push(value.to_string()?);
-
-        // Query EnableInstallerDetection Value
-        let value: PCWSTR = w!("EnableInstallerDetection");
-        let mut installer_detection: u32 = 0;
-        let mut size: u32 = std::mem::size_of::<u32>() as u32;
-        registry_query(key, value, &mut installer_detection, &mut size);
-        query.push(value.to_string()?);
-
-        // Query ValidateAdminCodeSignatures Value
-        let value: PCWSTR = w!("ValidateAdminCodeSignatures");
-        let mut admin_signature: u32 = 0;
-        let mut size: u32 = std::mem::size_of::<u32>() as u32;
-        registry_query(key, value, &mut admin_signature, &mut size);
-        query.push(value.to_string()?);
-
-        // Query FilterAdministratorToken Value
-        let value: PCWSTR = w!("FilterAdministratorToken");
-        let mut admin_token: u32 = 0;
-        let mut size: u32 = std::mem::size_of::<u32>() as u32;
-        registry_query(key, value, &mut admin_token, &mut size);
-        query.push(value.to_string()?);
-
-        // Query FilterAdministratorToken Value
-        let value: PCWSTR = w!("EnableVirtualization");
-        let mut virtualization: u32 = 0;
-        let mut size: u32 = std::mem::size_of::<u32>() as u32;
-        registry_query(key, value, &mut virtualization, &mut size);
-        query.push(value.to_string()?);
-
-        /*
-            Shugo: Closing the Registry Key
-
-            Always close the registry keys when you're done with them. Leaving keys
-            open can cause resource leaks.
-
-            For more information on `RegCloseKey`:
-            (https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regclosekey) - C++
-            (https://microsoft.github.io/windows-docs-rs/doc/windows/Win32/System/Registry/fn.RegCloseKey.html) - Rust
-        */
-        let _ = RegCloseKey(key);
-
-        let module_info: ModuleInfo = ModuleInfo {
-            registry_key: subkey.to_string()?,
-            queries: query
+    #[test]
+    fn keeps_unreadable_values_unknown() {
+        let source = FixtureSource::new()
+            .with_registry(RegistryHive::LocalMachine, UAC_KEY, "EnableLUA", RegistryValue::String("1".to_string()));
+
+        let info = scan_uac_with(&source).unwrap();
+        assert_eq!(info.lua_value, None);
+        assert!(info.module_info.unreadable["EnableLUA"].contains("expected a DWORD"));
+        assert!(!info.module_info.unreadable.contains_key("PromptOnSecureDesktop"));
+    }
+
+    #[test]
+    fn reads_uac_values_from_hive() {
+        use crate::common::hive::HiveFile;
+        use crate::common::hive::tests::{HiveBuilder, TestKey, dword};
+        use crate::common::offline::OfflineSource;
+        use crate::common::source::REG_DWORD;
+
+        let key = |name, children| TestKey { name, values: vec![], children };
+        let system = TestKey {
+            name: "System",
+            values: vec![("EnableLUA", REG_DWORD, dword(1)), ("ConsentPromptBehaviorAdmin", REG_DWORD, dword(2))],
+            children: vec![]
         };
-        
-        Ok(UacInfo {
-            lua_value: enable_lua,
-            prompt_level_value: prompt_behavior,
-            prompt_on_secure_desktop_value: prompt_secure_desktop,
-            installer_detection_value: installer_detection,
-            validate_admin_code_signatures_value: admin_signature,
-            filter_admin_token_value: admin_token,
-            enable_virtualization_value: virtualization,
-            module_info
-        })
+        let root = key("ROOT", vec![key("Microsoft", vec![key("Windows", vec![key("CurrentVersion", vec![key("Policies", vec![system])])])])]);
+        let hive = HiveFile::from_bytes(HiveBuilder::build(&root)).unwrap();
+        let source = OfflineSource::new().with_hive(RegistryHive::LocalMachine, "SOFTWARE", hive);
+
+        let info = scan_uac_with(&source).unwrap();
+        assert_eq!(info.lua_value, Some(1));
+        assert_eq!(info.prompt_level_value, Some(2));
+    }
+
+    #[test]
+    fn reads_uac_values_from_reg_file() {
+        use crate::common::offline::OfflineSource;
+        use crate::common::regfile::RegFile;
+
+        let reg = RegFile::parse(concat!(
+            "Windows Registry Editor Version 5.00\r\n",
+            "\r\n",
+            "[HKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Policies\\System]\r\n",
+            "\"EnableLUA\"=dword:00000000\r\n",
+            "\"PromptOnSecureDesktop\"=dword:00000001\r\n"
+        )).unwrap();
+        let source = OfflineSource::new().with_reg_file(reg);
+
+        let info = scan_uac_with(&source).unwrap();
+        assert_eq!(info.lua_value, Some(0));
+        assert_eq!(info.prompt_on_secure_desktop_value, Some(1));
     }
-}
 
-fn registry_query(key: HKEY, reg_value: PCWSTR, value_pointer: &mut u32, size_pointer: &mut u32) {
-    unsafe {
-        let _ = RegQueryValueExW(
-            key, // Our handle to the open registry key
-            reg_value, // Our registry value we want to grab
-            None, // This must be NULL
-            None, // This is also NULL
-            Some(value_pointer as *mut u32 as *mut u8), // A pointer to a buffer to recieve the values data
-            Some(size_pointer) // A pointer to a variable that specifies the size of a buffer in bytes
-        );
+    #[test]
+    fn missing_key_is_an_error() {
+        let error = scan_uac_with(&FixtureSource::new()).err().unwrap();
+        assert!(matches!(error, ShugoError::NotFound { .. }));
+        assert_eq!(error.context().module.as_deref(), Some("uac"));
     }
-}
\ No newline at end of file
+}
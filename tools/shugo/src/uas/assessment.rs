@@ -0,0 +1,61 @@
+//! Assessment for the UAS Module, turns the local account summary into findings.
+use super::scanner::UserAccountSummary;
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "uas";
+
+/// Assessing local user accounts
+pub fn assess_uas(info: &UserAccountSummary) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if info.guest_enabled {
+        findings.push(
+            Finding::new("UAS-001", MODULE, Severity::High, "Guest account is enabled")
+                .explanation("Anyone can sign in without a password and reach shared files and network resources")
+                .remediation("Disable the Guest account (net user Guest /active:no)")
+                .evidence("Guest enabled")
+        );
+    }
+
+    if info.admin_count > 1 {
+        let mut finding = Finding::new("UAS-002", MODULE, Severity::Medium, format!("{} administrator accounts detected", info.admin_count))
+            .explanation("Every administrator account is another password that gives full control of the machine")
+            .remediation("Use a standard account day to day and keep only the administrator accounts you need");
+        for account in info.accounts.iter().filter(|account| account.is_admin) {
+            finding = finding.evidence(account.username.clone());
+        }
+        findings.push(finding);
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uas::scanner::UserAccountInfo;
+
+    fn account(username: &str, is_admin: bool) -> UserAccountInfo {
+        UserAccountInfo {
+            username: username.to_string(),
+            account_type: String::new(),
+            is_enabled: true,
+            is_admin
+        }
+    }
+
+    #[test]
+    fn flags_guest_and_extra_admins() {
+        let summary = UserAccountSummary {
+            total_users: 3,
+            enabled_users: 3,
+            admin_count: 2,
+            guest_enabled: true,
+            accounts: vec![account("Administrator", true), account("alice", true), account("Guest", false)]
+        };
+        let findings = assess_uas(&summary);
+        assert_eq!(findings[0].id, "UAS-001");
+        assert_eq!(findings[1].id, "UAS-002");
+        assert_eq!(findings[1].evidence, vec!["Administrator", "alice"]);
+    }
+}
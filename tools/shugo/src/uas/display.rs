@@ -1,4 +1,8 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_uas;
 use super::scanner::UserAccountSummary;
+use crate::common::finding::display_findings;
 use crate::common::time::get_time;
 
 pub fn display_uas(info: UserAccountSummary, verbose: bool) {
@@ -12,7 +16,15 @@ pub fn display_uas(info: UserAccountSummary, verbose: bool) {
 
     if verbose {account_display(&info);}
 
-    display_assessment(&info);
+    display_findings(&assess_uas(&info), verbose);
+}
+
+/// JSON for UAS Module
+pub fn json_uas(info: &UserAccountSummary) -> Value {
+    json!({
+        "summary": info,
+        "findings": assess_uas(info)
+    })
 }
 
 fn display_scan_details() {
@@ -43,22 +55,3 @@ fn account_display(info: &UserAccountSummary) {
         println!();
     }
 }
-
-fn display_assessment(info: &UserAccountSummary) {
-    println!("Security Assessment:");
-
-    if !info.guest_enabled {
-        println!(" - Guest account is disabled (Recommended)");
-    } else {
-        println!(" - Guest account is enabled (Not Recommended)");
-    }
-
-    if info.admin_count > 1 {
-        println!(" - {} administrator acounts detected", info.admin_count);
-    } else if info.admin_count == 1 {
-        println!(" - Single administrator account (Recommended)");
-    }
-
-    println!(" - {} of {} accounts currently enabled", info.enabled_users, info.total_users);
-    println!()
-}
\ No newline at end of file
@@ -0,0 +1,61 @@
+//! Assessment for the Update Module, turns the pending update counts into findings.
+use super::scanner::UpdateSummary;
+use crate::common::finding::{Finding, Severity};
+
+const MODULE: &str = "updates";
+
+/// Assessing pending Windows updates
+pub fn assess_updates(update: &UpdateSummary) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if update.critical_count > 0 {
+        findings.push(
+            Finding::new("UPD-001", MODULE, Severity::High, format!("{} critical update(s) pending", update.critical_count))
+                .explanation("Critical updates fix widespread problems that can leave the system unstable or exposed")
+                .remediation("Install pending updates from Settings > Windows Update")
+                .evidence(format!("critical_count={}", update.critical_count))
+        );
+    }
+    if update.security_count > 0 {
+        findings.push(
+            Finding::new("UPD-002", MODULE, Severity::High, format!("{} security update(s) pending", update.security_count))
+                .explanation("Security updates patch vulnerabilities that are often public and actively exploited once released")
+                .remediation("Install pending updates from Settings > Windows Update and restart if asked")
+                .evidence(format!("security_count={}", update.security_count))
+        );
+    }
+    if update.definition_count > 0 {
+        findings.push(
+            Finding::new("UPD-003", MODULE, Severity::Low, format!("{} definition update(s) pending", update.definition_count))
+                .explanation("Definition updates keep Microsoft Defender able to recognise new threats")
+                .remediation("Install pending definition updates, they normally apply automatically")
+                .evidence(format!("definition_count={}", update.definition_count))
+        );
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_pending_security_updates() {
+        let summary = UpdateSummary {
+            total_count: 2,
+            critical_count: 0,
+            security_count: 2,
+            definition_count: 0,
+            feature_count: 0,
+            driver_count: 0,
+            other_count: 0,
+            update_list: vec![],
+            query: String::new()
+        };
+        let findings = assess_updates(&summary);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "UPD-002");
+        assert_eq!(findings[0].evidence, vec!["security_count=2"]);
+    }
+}
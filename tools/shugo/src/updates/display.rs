@@ -1,4 +1,8 @@
+use serde_json::{Value, json};
+
+use super::assessment::assess_updates;
 use super::scanner::UpdateSummary;
+use crate::common::finding::display_findings;
 use crate::common::time::get_time;
 
 /// Display for Update Module
@@ -57,9 +61,19 @@ pub fn display_updates(update: UpdateSummary, verbose: bool) {
         "Other Updates Needing Installed:"
     );
 
+    display_findings(&assess_updates(&update), verbose);
+
     if verbose {display_technical();}
 }
 
+/// JSON for Update Module
+pub fn json_updates(update: &UpdateSummary) -> Value {
+    json!({
+        "summary": update,
+        "findings": assess_updates(update)
+    })
+}
+
 fn display_scan_details(update: &UpdateSummary) {
     let (h, m, s) = get_time();
 
@@ -105,6 +119,7 @@ fn update_display(update: &UpdateSummary, verbose: bool, count: i32, class: &str
                 println!("   - Product: {}", info.product);
                 println!("   - Classification: {}", info.classification);
                 println!("   - Description: {}", info.description);
+                if let Some(age) = info.age_days {println!("   - Released: {} days ago", age);}
             }
             println!();
         }
@@ -137,6 +152,7 @@ fn other_updates(update: &UpdateSummary, verbose: bool, count: i32, header: &str
                 println!("   - Product: {}", info.product);
                 println!("   - Classification: {}", info.classification);
                 println!("   - Description: {}", info.description);
+                if let Some(age) = info.age_days {println!("   - Released: {} days ago", age);}
             }
             println!();
     }